@@ -6,10 +6,11 @@
 
 use SendableFrameTree;
 use compositor::CompositingReason;
-use euclid::{Point2D, Size2D};
+use euclid::{Point2D, Size2D, TypedRect};
 use gfx_traits::Epoch;
 use ipc_channel::ipc::IpcSender;
-use msg::constellation_msg::{Key, KeyModifiers, KeyState, PipelineId, TopLevelBrowsingContextId};
+use msg::constellation_msg::{AutocorrectWord, Key, KeyModifiers, KeyState, PipelineId, Suggestion, TextInputHints};
+use msg::constellation_msg::TopLevelBrowsingContextId;
 use net_traits::image::base::Image;
 use profile_traits::mem;
 use profile_traits::time;
@@ -17,6 +18,7 @@ use script_traits::{AnimationState, ConstellationMsg, EventResult, LoadData};
 use servo_url::ServoUrl;
 use std::fmt::{Debug, Error, Formatter};
 use std::sync::mpsc::{Receiver, Sender};
+use style_traits::CSSPixel;
 use style_traits::cursor::CursorKind;
 use style_traits::viewport::ViewportConstraints;
 use webrender;
@@ -134,6 +136,27 @@ pub enum EmbedderMsg {
     KeyEvent(Option<TopLevelBrowsingContextId>, Option<char>, Key, KeyState, KeyModifiers),
     /// Changes the cursor.
     SetCursor(CursorKind),
+    /// The bounds of the caret of the text control in which an input method is composing text,
+    /// relative to the viewport.
+    SetImeCaretRect(TopLevelBrowsingContextId, Option<TypedRect<f32, CSSPixel>>),
+    /// The composition in the focused text control was cancelled, so the input method should
+    /// drop the text it was composing.
+    CancelComposition(TopLevelBrowsingContextId),
+    /// The kind of virtual keyboard to show for the focused text control, if any.
+    SetTextInputHints(TopLevelBrowsingContextId, Option<TextInputHints>),
+    /// Whether to warn that Caps Lock is on while the user types in a password field.
+    SetCapsLockWarning(TopLevelBrowsingContextId, bool),
+    /// The options of the datalist of the focused input that match what the user typed.
+    SetSuggestions(TopLevelBrowsingContextId, Vec<Suggestion>),
+    /// Let the user pick a color for a color input, starting at the given color in `#rrggbb`
+    /// form, and reply with the picked color, or `None` if there is none.
+    PickColor(TopLevelBrowsingContextId, String, IpcSender<Option<String>>),
+    /// Tell which of the given words, typed into a text control in the given language, are
+    /// misspelled, with a flag for each word.
+    CheckSpelling(TopLevelBrowsingContextId, Vec<String>, String, IpcSender<Vec<bool>>),
+    /// Offer a word the user just finished typing to be corrected, and reply with the text to
+    /// replace it with, or `None` to keep it.
+    Autocorrect(TopLevelBrowsingContextId, AutocorrectWord, IpcSender<Option<String>>),
     /// A favicon was detected
     NewFavicon(TopLevelBrowsingContextId, ServoUrl),
     /// <head> tag finished parsing
@@ -233,6 +256,14 @@ impl Debug for EmbedderMsg {
             EmbedderMsg::AllowNavigation(..) => write!(f, "AllowNavigation"),
             EmbedderMsg::KeyEvent(..) => write!(f, "KeyEvent"),
             EmbedderMsg::SetCursor(..) => write!(f, "SetCursor"),
+            EmbedderMsg::SetImeCaretRect(..) => write!(f, "SetImeCaretRect"),
+            EmbedderMsg::CancelComposition(..) => write!(f, "CancelComposition"),
+            EmbedderMsg::SetTextInputHints(..) => write!(f, "SetTextInputHints"),
+            EmbedderMsg::SetCapsLockWarning(..) => write!(f, "SetCapsLockWarning"),
+            EmbedderMsg::SetSuggestions(..) => write!(f, "SetSuggestions"),
+            EmbedderMsg::PickColor(..) => write!(f, "PickColor"),
+            EmbedderMsg::CheckSpelling(..) => write!(f, "CheckSpelling"),
+            EmbedderMsg::Autocorrect(..) => write!(f, "Autocorrect"),
             EmbedderMsg::NewFavicon(..) => write!(f, "NewFavicon"),
             EmbedderMsg::HeadParsed(..) => write!(f, "HeadParsed"),
             EmbedderMsg::HistoryChanged(..) => write!(f, "HistoryChanged"),
@@ -6,17 +6,20 @@
 
 use compositor_thread::EventLoopWaker;
 use euclid::{Point2D, Size2D};
-use euclid::{TypedScale, TypedPoint2D, TypedSize2D};
+use euclid::{TypedScale, TypedPoint2D, TypedRect, TypedSize2D};
 use gleam::gl;
 use ipc_channel::ipc::IpcSender;
-use msg::constellation_msg::{Key, KeyModifiers, KeyState, TopLevelBrowsingContextId, TraversalDirection};
+use msg::constellation_msg::{AutocorrectWord, ImeEvent, ImeSurroundingText, Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{Suggestion, TextInputHints};
+use msg::constellation_msg::TopLevelBrowsingContextId;
+use msg::constellation_msg::TraversalDirection;
 use net_traits::net_error_list::NetError;
 use script_traits::{LoadData, MouseButton, TouchEventType, TouchId};
 use servo_geometry::DeviceIndependentPixel;
 use servo_url::ServoUrl;
 use std::fmt::{Debug, Error, Formatter};
 use std::rc::Rc;
-use style_traits::DevicePixel;
+use style_traits::{CSSPixel, DevicePixel};
 use style_traits::cursor::CursorKind;
 use webrender_api::{DeviceUintSize, DeviceUintRect, ScrollLocation};
 
@@ -73,6 +76,23 @@ pub enum WindowEvent {
     Quit,
     /// Sent when a key input state changes
     KeyEvent(Option<char>, Key, KeyState, KeyModifiers),
+    /// Sent when the input method editor composes or commits text
+    Ime(ImeEvent),
+    /// Sent when the input method editor needs up to the given numbers of characters before and
+    /// after the caret of the focused text control
+    GetImeSurroundingText(usize, usize, IpcSender<Option<ImeSurroundingText>>),
+    /// Sent when the virtual keyboard appears, disappears or changes size, with the area of the
+    /// viewport that it covers
+    VirtualKeyboardGeometryChange(TypedRect<f32, CSSPixel>),
+    /// Sent when the user asks to show the value of the focused password field, or to hide it
+    /// again
+    RevealPassword(bool),
+    /// Sent when the embedder fills in the focused text control on the user's behalf, as a
+    /// password manager does
+    Autofill(String),
+    /// Sent when the user picks one of the suggestions offered with `set_suggestions`, with its
+    /// value
+    ChooseSuggestion(String),
     /// Sent when Ctr+R/Apple+R is called to reload the current page.
     Reload(TopLevelBrowsingContextId),
     /// Create a new top level browsing context
@@ -93,6 +113,12 @@ impl Debug for WindowEvent {
             WindowEvent::Refresh => write!(f, "Refresh"),
             WindowEvent::Resize => write!(f, "Resize"),
             WindowEvent::KeyEvent(..) => write!(f, "Key"),
+            WindowEvent::Ime(..) => write!(f, "Ime"),
+            WindowEvent::GetImeSurroundingText(..) => write!(f, "GetImeSurroundingText"),
+            WindowEvent::VirtualKeyboardGeometryChange(..) => write!(f, "VirtualKeyboardGeometryChange"),
+            WindowEvent::RevealPassword(..) => write!(f, "RevealPassword"),
+            WindowEvent::Autofill(..) => write!(f, "Autofill"),
+            WindowEvent::ChooseSuggestion(..) => write!(f, "ChooseSuggestion"),
             WindowEvent::LoadUrl(..) => write!(f, "LoadUrl"),
             WindowEvent::MouseWindowEventClass(..) => write!(f, "Mouse"),
             WindowEvent::MouseWindowMoveEventClass(..) => write!(f, "MouseMove"),
@@ -172,6 +198,64 @@ pub trait WindowMethods {
     /// Sets the cursor to be used in the window.
     fn set_cursor(&self, cursor: CursorKind);
 
+    /// Called while an input method composes text in the focused text control, with the bounds
+    /// of its caret relative to the viewport, so that the candidate window of the input method
+    /// can be placed next to it.
+    fn set_ime_caret_rect(&self, _ctx: TopLevelBrowsingContextId, _rect: Option<TypedRect<f32, CSSPixel>>) {}
+
+    /// Called when the composition in the focused text control is cancelled, as by Escape, so
+    /// that the input method drops the text it was composing rather than sending it again.
+    fn cancel_composition(&self, _ctx: TopLevelBrowsingContextId) {}
+
+    /// Called when a text control gains the focus, with the kind of virtual keyboard that suits
+    /// it, and with `None` when the focus leaves text controls, so that ports with on-screen
+    /// keyboards can show or hide them.
+    fn set_text_input_hints(&self, _ctx: TopLevelBrowsingContextId, _hints: Option<TextInputHints>) {}
+
+    /// Called when Caps Lock is found to be on while the user types in a password field, and
+    /// when it is off again or the field loses the focus, so that ports can warn the user.
+    fn set_caps_lock_warning(&self, _ctx: TopLevelBrowsingContextId, _warn: bool) {}
+
+    /// Called as the user types in an input with a datalist, with the options that match what
+    /// they typed, so that ports can offer them in a dropdown. No suggestions hide the dropdown.
+    /// The one the user picks is sent back with `WindowEvent::ChooseSuggestion`.
+    fn set_suggestions(&self, _ctx: TopLevelBrowsingContextId, _suggestions: Vec<Suggestion>) {}
+
+    /// Called when a color input is activated, with its color in `#rrggbb` form, to let the
+    /// user pick another one. The picked color, or `None` if the user closes the picker without
+    /// one, is sent on `response_chan`. Ports without a color picker reply `None` at once.
+    fn pick_color(&self, _ctx: TopLevelBrowsingContextId, _color: String, response_chan: IpcSender<Option<String>>) {
+        if let Err(e) = response_chan.send(None) {
+            warn!("Failed to send pick_color() response: {}", e);
+        }
+    }
+
+    /// Called as the user types into a text control that is checked for spelling, to tell
+    /// which of the given words, in the language of the control, are misspelled. A flag for
+    /// each word is sent on `response_chan`. Ports without a spell checker flag none of them.
+    fn check_spelling(&self,
+                      _ctx: TopLevelBrowsingContextId,
+                      words: Vec<String>,
+                      _lang: String,
+                      response_chan: IpcSender<Vec<bool>>) {
+        if let Err(e) = response_chan.send(vec![false; words.len()]) {
+            warn!("Failed to send check_spelling() response: {}", e);
+        }
+    }
+
+    /// Called when the user finishes typing a word in a text control that is checked for
+    /// spelling, to offer it for correction. The text to replace it with, or `None` to keep it,
+    /// is sent on `response_chan`. The replacement is an edit like any other, which the user can
+    /// undo. Ports without autocorrection keep every word.
+    fn autocorrect(&self,
+                   _ctx: TopLevelBrowsingContextId,
+                   _word: AutocorrectWord,
+                   response_chan: IpcSender<Option<String>>) {
+        if let Err(e) = response_chan.send(None) {
+            warn!("Failed to send autocorrect() response: {}", e);
+        }
+    }
+
     /// Process a key event.
     fn handle_key(&self, ctx: Option<TopLevelBrowsingContextId>, ch: Option<char>, key: Key, mods: KeyModifiers);
 
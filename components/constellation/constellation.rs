@@ -102,7 +102,7 @@ use compositing::compositor_thread::{CompositorProxy, EmbedderMsg, EmbedderProxy
 use compositing::compositor_thread::Msg as ToCompositorMsg;
 use debugger;
 use devtools_traits::{ChromeToDevtoolsControlMsg, DevtoolsControlMsg};
-use euclid::{Size2D, TypedSize2D, TypedScale};
+use euclid::{Size2D, TypedRect, TypedSize2D, TypedScale};
 use event_loop::EventLoop;
 use gfx::font_cache_thread::FontCacheThread;
 use gfx_traits::Epoch;
@@ -113,7 +113,8 @@ use itertools::Itertools;
 use layout_traits::LayoutThreadFactory;
 use log::{Log, LogLevel, LogLevelFilter, LogMetadata, LogRecord};
 use msg::constellation_msg::{BrowsingContextId, TopLevelBrowsingContextId, PipelineId};
-use msg::constellation_msg::{Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor, clipboard_text_chunk};
+use msg::constellation_msg::{ImeEvent, ImeSurroundingText, Key, KeyModifiers, KeyState};
 use msg::constellation_msg::{PipelineNamespace, PipelineNamespaceId, TraversalDirection};
 use net_traits::{self, IpcSend, FetchResponseMsg, ResourceThreads};
 use net_traits::pub_domains::reg_host;
@@ -304,6 +305,15 @@ pub struct Constellation<Message, LTF, STF> {
     /// Means of accessing the clipboard
     clipboard_ctx: Option<ClipboardContext>,
 
+    /// The contents last put on the clipboard by content, in all the flavors they were offered
+    /// in. The system clipboard only holds their plain text, so the other flavors are only
+    /// offered for as long as it still does.
+    clipboard_contents: Option<ClipboardContents>,
+
+    /// The clipboard contents being read in chunks, in the flavor they are being read in, so
+    /// that the system clipboard isn't read again for every chunk.
+    clipboard_snapshot: Option<(ClipboardFlavor, String)>,
+
     /// Bits of state used to interact with the webdriver implementation
     webdriver: WebDriverData,
 
@@ -606,6 +616,8 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 } else {
                     None
                 },
+                clipboard_contents: None,
+                clipboard_snapshot: None,
                 webdriver: WebDriverData::new(),
                 scheduler_chan: TimerScheduler::start(),
                 document_states: HashMap::new(),
@@ -999,6 +1011,30 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 debug!("constellation got key event message");
                 self.handle_key_msg(ch, key, state, modifiers);
             }
+            FromCompositorMsg::ImeEvent(event) => {
+                debug!("constellation got input method event message");
+                self.handle_ime_msg(event);
+            }
+            FromCompositorMsg::GetImeSurroundingText(before, after, resp_chan) => {
+                debug!("constellation got surrounding text request");
+                self.handle_get_ime_surrounding_text(before, after, resp_chan);
+            }
+            FromCompositorMsg::RevealPassword(revealed) => {
+                debug!("constellation got password reveal");
+                self.handle_reveal_password(revealed);
+            }
+            FromCompositorMsg::Autofill(value) => {
+                debug!("constellation got autofill");
+                self.handle_autofill(value);
+            }
+            FromCompositorMsg::ChooseSuggestion(value) => {
+                debug!("constellation got suggestion choice");
+                self.handle_choose_suggestion(value);
+            }
+            FromCompositorMsg::VirtualKeyboardGeometryChange(rect) => {
+                debug!("constellation got virtual keyboard geometry message");
+                self.handle_virtual_keyboard_geometry_change(rect);
+            }
             // Load a new page from a typed url
             // If there is already a pending page (self.pending_changes), it will not be overridden;
             // However, if the id is not encompassed by another change, it will be.
@@ -1164,28 +1200,33 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
             FromScriptMsg::ForwardEvent(destination_pipeline_id, event) => {
                 self.forward_event(destination_pipeline_id, event);
             }
-            FromScriptMsg::GetClipboardContents(sender) => {
-                let contents = match self.clipboard_ctx {
-                    Some(ref mut ctx) => match ctx.get_contents() {
-                        Ok(c) => c,
-                        Err(e) => {
-                            warn!("Error getting clipboard contents ({}), defaulting to empty string", e);
-                            "".to_owned()
-                        },
-                    },
-                    None => "".to_owned(),
-                };
-                if let Err(e) = sender.send(contents.to_owned()) {
+            FromScriptMsg::GetClipboardFlavors(sender) => {
+                let flavors = self.clipboard_flavors();
+                if let Err(e) = sender.send(flavors) {
+                    warn!("Failed to send clipboard flavors ({})", e);
+                }
+            }
+            FromScriptMsg::GetClipboardContents(flavor, sender) => {
+                let contents = self.clipboard_contents(flavor);
+                if let Err(e) = sender.send(contents) {
                     warn!("Failed to send clipboard ({})", e);
                 }
             }
-            FromScriptMsg::SetClipboardContents(s) => {
-                if let Some(ref mut ctx) = self.clipboard_ctx {
-                    if let Err(e) = ctx.set_contents(s) {
-                        warn!("Error setting clipboard contents ({})", e);
-                    }
+            FromScriptMsg::GetClipboardLength(flavor, sender) => {
+                let length = self.clipboard_length(flavor);
+                if let Err(e) = sender.send(length) {
+                    warn!("Failed to send clipboard length ({})", e);
+                }
+            }
+            FromScriptMsg::GetClipboardChunk(flavor, start, max_length, sender) => {
+                let chunk = self.clipboard_chunk(flavor, start, max_length);
+                if let Err(e) = sender.send(chunk) {
+                    warn!("Failed to send clipboard chunk ({})", e);
                 }
             }
+            FromScriptMsg::SetClipboardContents(contents) => {
+                self.set_clipboard_contents(contents);
+            }
             FromScriptMsg::SetVisible(visible) => {
                 debug!("constellation got set visible messsage");
                 self.handle_set_visible_msg(source_pipeline_id, visible);
@@ -1229,6 +1270,15 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 debug!("constellation got Alert message");
                 self.handle_alert(source_top_ctx_id, message, sender);
             }
+            FromScriptMsg::PickColor(color, sender) => {
+                self.embedder_proxy.send(EmbedderMsg::PickColor(source_top_ctx_id, color, sender));
+            }
+            FromScriptMsg::CheckSpelling(words, lang, sender) => {
+                self.embedder_proxy.send(EmbedderMsg::CheckSpelling(source_top_ctx_id, words, lang, sender));
+            }
+            FromScriptMsg::Autocorrect(word, sender) => {
+                self.embedder_proxy.send(EmbedderMsg::Autocorrect(source_top_ctx_id, word, sender));
+            }
             FromScriptMsg::GetClientWindow(send) => {
                 self.embedder_proxy.send(EmbedderMsg::GetClientWindow(source_top_ctx_id, send));
             }
@@ -1267,6 +1317,26 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
                 self.embedder_proxy.send(event);
             }
 
+            FromScriptMsg::SetImeCaretRect(rect) => {
+                self.embedder_proxy.send(EmbedderMsg::SetImeCaretRect(source_top_ctx_id, rect));
+            }
+
+            FromScriptMsg::CancelComposition => {
+                self.embedder_proxy.send(EmbedderMsg::CancelComposition(source_top_ctx_id));
+            }
+
+            FromScriptMsg::SetTextInputHints(hints) => {
+                self.embedder_proxy.send(EmbedderMsg::SetTextInputHints(source_top_ctx_id, hints));
+            }
+
+            FromScriptMsg::SetCapsLockWarning(warn) => {
+                self.embedder_proxy.send(EmbedderMsg::SetCapsLockWarning(source_top_ctx_id, warn));
+            }
+
+            FromScriptMsg::SetSuggestions(suggestions) => {
+                self.embedder_proxy.send(EmbedderMsg::SetSuggestions(source_top_ctx_id, suggestions));
+            }
+
             FromScriptMsg::TouchEventProcessed(result) => {
                 self.compositor_proxy.send(ToCompositorMsg::TouchEventProcessed(result))
             }
@@ -2058,6 +2128,161 @@ impl<Message, LTF, STF> Constellation<Message, LTF, STF>
         }
     }
 
+    /// Send a message to the focused pipeline, which holds the focused text control that input
+    /// methods, autofill and the like are for. `what` names the message in debug output.
+    fn forward_to_focused_pipeline<F>(&mut self, msg: F, what: &str)
+        where F: FnOnce(PipelineId) -> ConstellationControlMsg
+    {
+        let pipeline_id = match self.focus_pipeline_id {
+            Some(pipeline_id) => pipeline_id,
+            None => return debug!("No focused pipeline to send {} to.", what),
+        };
+        let result = match self.pipelines.get(&pipeline_id) {
+            Some(pipeline) => pipeline.event_loop.send(msg(pipeline_id)),
+            None => return debug!("Pipeline {:?} got {} after closure.", pipeline_id, what),
+        };
+        if let Err(e) = result {
+            self.handle_send_error(pipeline_id, e);
+        }
+    }
+
+    fn handle_ime_msg(&mut self, event: ImeEvent) {
+        // Only the focused pipeline can have a text control that is composing text.
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::SendEvent(pipeline_id, CompositorEvent::ImeEvent(event))
+        }, "input method event");
+    }
+
+    fn handle_get_ime_surrounding_text(&mut self,
+                                       before: usize,
+                                       after: usize,
+                                       resp_chan: IpcSender<Option<ImeSurroundingText>>) {
+        if self.focus_pipeline_id.is_none() {
+            let _ = resp_chan.send(None);
+            return;
+        }
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::GetImeSurroundingText(pipeline_id, before, after, resp_chan)
+        }, "surrounding text request");
+    }
+
+    /// The password field to reveal or hide is the focused one, in the focused pipeline.
+    fn handle_reveal_password(&mut self, revealed: bool) {
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::RevealPassword(pipeline_id, revealed)
+        }, "password reveal");
+    }
+
+    /// The text control to fill in is the focused one, in the focused pipeline.
+    fn handle_autofill(&mut self, value: String) {
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::Autofill(pipeline_id, value)
+        }, "autofill");
+    }
+
+    /// The suggestion is for the focused input, in the focused pipeline.
+    fn handle_choose_suggestion(&mut self, value: String) {
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::ChooseSuggestion(pipeline_id, value)
+        }, "suggestion choice");
+    }
+
+    /// The page that the virtual keyboard types into is the one that is told where it is.
+    fn handle_virtual_keyboard_geometry_change(&mut self, rect: TypedRect<f32, CSSPixel>) {
+        self.forward_to_focused_pipeline(|pipeline_id| {
+            ConstellationControlMsg::VirtualKeyboardGeometryChange(pipeline_id, rect)
+        }, "virtual keyboard geometry");
+    }
+
+    /// The text on the system clipboard, or `None` if it can't be read.
+    fn system_clipboard_text(&mut self) -> Option<String> {
+        let ctx = match self.clipboard_ctx {
+            Some(ref mut ctx) => ctx,
+            None => return None,
+        };
+        match ctx.get_contents() {
+            Ok(text) => Some(text),
+            Err(e) => {
+                warn!("Error getting clipboard contents ({})", e);
+                None
+            },
+        }
+    }
+
+    /// The contents that content put on the clipboard, unless something else has been put on
+    /// it since, which is the case if the system clipboard holds some other text.
+    fn own_clipboard_contents(&self, text: &str) -> Option<&ClipboardContents> {
+        match self.clipboard_contents {
+            Some(ref contents) if contents.get(ClipboardFlavor::PlainText).unwrap_or("") == text => Some(contents),
+            _ => None,
+        }
+    }
+
+    fn clipboard_flavors(&mut self) -> Vec<ClipboardFlavor> {
+        let text = match self.system_clipboard_text() {
+            Some(text) => text,
+            None => return vec!(),
+        };
+        let mut flavors = vec!(ClipboardFlavor::PlainText);
+        if let Some(contents) = self.own_clipboard_contents(&text) {
+            flavors.extend(contents.flavors().into_iter().filter(|&f| f != ClipboardFlavor::PlainText));
+        }
+        flavors
+    }
+
+    fn clipboard_contents(&mut self, flavor: ClipboardFlavor) -> Option<String> {
+        let text = self.system_clipboard_text()?;
+        if flavor == ClipboardFlavor::PlainText {
+            return Some(text);
+        }
+        self.own_clipboard_contents(&text).and_then(|contents| contents.get(flavor)).map(ToOwned::to_owned)
+    }
+
+    /// The length of the clipboard contents in the given flavor, which are kept until they
+    /// have been read in chunks.
+    fn clipboard_length(&mut self, flavor: ClipboardFlavor) -> Option<usize> {
+        self.clipboard_snapshot = None;
+        let contents = self.clipboard_contents(flavor)?;
+        let length = contents.len();
+        self.clipboard_snapshot = Some((flavor, contents));
+        Some(length)
+    }
+
+    fn clipboard_chunk(&mut self, flavor: ClipboardFlavor, start: usize, max_length: usize) -> Option<String> {
+        let snapshot_chunk = match self.clipboard_snapshot {
+            Some((snapshot_flavor, ref contents)) if snapshot_flavor == flavor => {
+                let chunk = clipboard_text_chunk(contents, start, max_length);
+                Some((chunk.to_owned(), start + chunk.len() >= contents.len()))
+            },
+            _ => None,
+        };
+        match snapshot_chunk {
+            Some((chunk, is_last)) => {
+                if is_last {
+                    self.clipboard_snapshot = None;
+                }
+                Some(chunk)
+            },
+            None => {
+                let contents = self.clipboard_contents(flavor)?;
+                Some(clipboard_text_chunk(&contents, start, max_length).to_owned())
+            },
+        }
+    }
+
+    fn set_clipboard_contents(&mut self, contents: ClipboardContents) {
+        self.clipboard_contents = None;
+        self.clipboard_snapshot = None;
+        let result = match self.clipboard_ctx {
+            Some(ref mut ctx) => ctx.set_contents(contents.get(ClipboardFlavor::PlainText).unwrap_or("").to_owned()),
+            None => return,
+        };
+        match result {
+            Ok(()) => self.clipboard_contents = Some(contents),
+            Err(e) => warn!("Error setting clipboard contents ({})", e),
+        }
+    }
+
     fn handle_reload_msg(&mut self, top_level_browsing_context_id: TopLevelBrowsingContextId) {
         let browsing_context_id = BrowsingContextId::from(top_level_browsing_context_id);
         let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
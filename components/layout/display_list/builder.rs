@@ -10,7 +10,7 @@
 
 #![deny(unsafe_code)]
 
-use app_units::{Au, AU_PER_PX};
+use app_units::Au;
 use block::{BlockFlow, BlockStackingContextType};
 use canvas_traits::canvas::{CanvasMsg, FromLayoutMsg};
 use context::LayoutContext;
@@ -512,9 +512,6 @@ impl<'a> DisplayListBuildState<'a> {
     }
 }
 
-/// The logical width of an insertion point: at the moment, a one-pixel-wide line.
-const INSERTION_POINT_LOGICAL_WIDTH: Au = Au(1 * AU_PER_PX);
-
 pub trait FragmentDisplayListBuilding {
     fn collect_stacking_contexts_for_blocklike_fragment(
         &mut self,
@@ -678,11 +675,13 @@ pub trait FragmentDisplayListBuilding {
         clip: &Rect<Au>,
     );
 
-    /// Creates the display item for a text decoration: underline, overline, or line-through.
+    /// Creates the display item for a text decoration: underline, overline, or line-through, or
+    /// the mark under a misspelled word.
     fn build_display_list_for_text_decoration(
         &self,
         state: &mut DisplayListBuildState,
         color: &RGBA,
+        line_style: LineStyle,
         stacking_relative_box: &LogicalRect<Au>,
         clip: &Rect<Au>,
     );
@@ -1648,34 +1647,14 @@ impl FragmentDisplayListBuilding for Fragment {
         }
 
         // Draw a caret at the insertion point.
-        let insertion_point_index = match scanned_text_fragment_info.insertion_point {
-            Some(insertion_point_index) => insertion_point_index,
+        let insertion_point_bounds = match self.insertion_point_bounds(stacking_relative_border_box) {
+            Some(bounds) => bounds,
             None => return,
         };
-        let range = Range::new(
-            scanned_text_fragment_info.range.begin(),
-            insertion_point_index - scanned_text_fragment_info.range.begin(),
-        );
-        let advance = scanned_text_fragment_info.run.advance_for_range(&range);
-
-        let insertion_point_bounds;
-        let cursor;
-        if !self.style.writing_mode.is_vertical() {
-            insertion_point_bounds = rect(
-                stacking_relative_border_box.origin.x + advance,
-                stacking_relative_border_box.origin.y,
-                INSERTION_POINT_LOGICAL_WIDTH,
-                stacking_relative_border_box.size.height,
-            );
-            cursor = CursorKind::Text;
+        let cursor = if !self.style.writing_mode.is_vertical() {
+            CursorKind::Text
         } else {
-            insertion_point_bounds = rect(
-                stacking_relative_border_box.origin.x,
-                stacking_relative_border_box.origin.y + advance,
-                stacking_relative_border_box.size.width,
-                INSERTION_POINT_LOGICAL_WIDTH,
-            );
-            cursor = CursorKind::VerticalText;
+            CursorKind::VerticalText
         };
 
         let base = state.create_base_display_item(
@@ -2160,6 +2139,23 @@ impl FragmentDisplayListBuilding for Fragment {
             self.build_display_list_for_text_decoration(
                 state,
                 &text_color,
+                LineStyle::Solid,
+                &stacking_relative_box,
+                clip,
+            );
+        }
+
+        // Misspelled words of a text control are marked with a wavy line in the underline
+        // position, which is thick enough for the wave to show.
+        if text_fragment.misspelled() {
+            let mut stacking_relative_box = stacking_relative_content_box;
+            stacking_relative_box.start.b =
+                stacking_relative_content_box.start.b + metrics.ascent - metrics.underline_offset;
+            stacking_relative_box.size.block = metrics.underline_size * 3;
+            self.build_display_list_for_text_decoration(
+                state,
+                &RGBA::new(255, 0, 0, 255),
+                LineStyle::Wavy,
                 &stacking_relative_box,
                 clip,
             );
@@ -2172,6 +2168,7 @@ impl FragmentDisplayListBuilding for Fragment {
             self.build_display_list_for_text_decoration(
                 state,
                 &text_color,
+                LineStyle::Solid,
                 &stacking_relative_box,
                 clip,
             );
@@ -2207,6 +2204,7 @@ impl FragmentDisplayListBuilding for Fragment {
             self.build_display_list_for_text_decoration(
                 state,
                 &text_color,
+                LineStyle::Solid,
                 &stacking_relative_box,
                 clip,
             );
@@ -2224,6 +2222,7 @@ impl FragmentDisplayListBuilding for Fragment {
         &self,
         state: &mut DisplayListBuildState,
         color: &RGBA,
+        line_style: LineStyle,
         stacking_relative_box: &LogicalRect<Au>,
         clip: &Rect<Au>,
     ) {
@@ -2242,7 +2241,7 @@ impl FragmentDisplayListBuilding for Fragment {
         state.add_display_item(DisplayItem::Line(Box::new(LineDisplayItem {
             base: base,
             color: color.to_layout(),
-            style: LineStyle::Solid,
+            style: line_style,
         })));
     }
 
@@ -7,7 +7,7 @@
 #![deny(unsafe_code)]
 
 use ServoArc;
-use app_units::Au;
+use app_units::{Au, AU_PER_PX};
 use canvas_traits::canvas::CanvasMsg;
 use context::{LayoutContext, with_thread_local_font_context};
 use euclid::{Transform3D, Point2D, Vector2D, Rect, Size2D};
@@ -76,6 +76,9 @@ static FONT_SUPERSCRIPT_OFFSET_RATIO: f32 = 0.34;
 static DEFAULT_REPLACED_WIDTH: i32 = 300;
 static DEFAULT_REPLACED_HEIGHT: i32 = 150;
 
+/// The logical width of an insertion point: at the moment, a one-pixel-wide line.
+const INSERTION_POINT_LOGICAL_WIDTH: Au = Au(1 * AU_PER_PX);
+
 /// Fragments (`struct Fragment`) are the leaves of the layout tree. They cannot position
 /// themselves. In general, fragments do not have a simple correspondence with CSS fragments in the
 /// specification:
@@ -484,6 +487,9 @@ bitflags! {
         ///
         /// This handles cases like Foo<span>bar</span>
         const SUPPRESS_LINE_BREAK_BEFORE = 0x04;
+
+        /// Is this fragment a misspelled word in a text control?
+        const MISSPELLED = 0x08;
     }
 }
 
@@ -516,6 +522,10 @@ impl ScannedTextFragmentInfo {
     pub fn selected(&self) -> bool {
         self.flags.contains(ScannedTextFlags::SELECTED)
     }
+
+    pub fn misspelled(&self) -> bool {
+        self.flags.contains(ScannedTextFlags::MISSPELLED)
+    }
 }
 
 /// Describes how to split a fragment. This is used during line breaking as part of the return
@@ -565,6 +575,9 @@ pub struct UnscannedTextFragmentInfo {
 
     /// The selected text range.  An empty range represents the insertion point.
     pub selection: Option<Range<ByteIndex>>,
+
+    /// The ranges of the misspelled words in the text, which are underlined.
+    pub misspellings: Vec<Range<ByteIndex>>,
 }
 
 impl UnscannedTextFragmentInfo {
@@ -574,6 +587,7 @@ impl UnscannedTextFragmentInfo {
         UnscannedTextFragmentInfo {
             text: text,
             selection: selection,
+            misspellings: vec![],
         }
     }
 }
@@ -1374,6 +1388,35 @@ impl Fragment {
         &*self.selected_style
     }
 
+    /// The bounds of the caret at the insertion point, if this is the text fragment that
+    /// contains it, given the border box of the fragment.
+    pub fn insertion_point_bounds(&self, border_box: &Rect<Au>) -> Option<Rect<Au>> {
+        let scanned_text_fragment_info = match self.specific {
+            SpecificFragmentInfo::ScannedText(ref scanned_text_fragment_info) => {
+                scanned_text_fragment_info
+            },
+            _ => return None,
+        };
+        let insertion_point_index = scanned_text_fragment_info.insertion_point?;
+        let range = Range::new(
+            scanned_text_fragment_info.range.begin(),
+            insertion_point_index - scanned_text_fragment_info.range.begin(),
+        );
+        let advance = scanned_text_fragment_info.run.advance_for_range(&range);
+
+        Some(if !self.style.writing_mode.is_vertical() {
+            Rect::new(
+                Point2D::new(border_box.origin.x + advance, border_box.origin.y),
+                Size2D::new(INSERTION_POINT_LOGICAL_WIDTH, border_box.size.height),
+            )
+        } else {
+            Rect::new(
+                Point2D::new(border_box.origin.x, border_box.origin.y + advance),
+                Size2D::new(border_box.size.width, INSERTION_POINT_LOGICAL_WIDTH),
+            )
+        })
+    }
+
     pub fn white_space(&self) -> WhiteSpace {
         self.style().get_inheritedtext().white_space
     }
@@ -17,10 +17,10 @@ use msg::constellation_msg::PipelineId;
 use opaque_node::OpaqueNodeMethods;
 use script_layout_interface::{LayoutElementType, LayoutNodeType};
 use script_layout_interface::StyleData;
-use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
+use script_layout_interface::rpc::{CaretRectResponse, ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{NodeGeometryResponse, NodeScrollIdResponse};
 use script_layout_interface::rpc::{OffsetParentResponse, ResolvedStyleResponse, StyleResponse};
-use script_layout_interface::rpc::TextIndexResponse;
+use script_layout_interface::rpc::{TextIndexResponse, TextWrapPointsResponse};
 use script_layout_interface::wrapper_traits::{LayoutNode, ThreadSafeLayoutElement, ThreadSafeLayoutNode};
 use script_traits::LayoutMsg as ConstellationMsg;
 use script_traits::UntrustedNodeAddress;
@@ -57,6 +57,12 @@ pub struct LayoutThreadData {
     /// A queued response for the content boxes of a node.
     pub content_boxes_response: Vec<Rect<Au>>,
 
+    /// A queued response for the bounds of the caret in a text control.
+    pub caret_rect_response: Option<Rect<Au>>,
+
+    /// A queued response for the points where the lines of a text control wrap.
+    pub text_wrap_points_response: Option<Vec<(usize, usize)>>,
+
     /// A queued response for the client {top, left, width, height} of a node in pixels.
     pub client_rect_response: Rect<i32>,
 
@@ -118,6 +124,18 @@ impl LayoutRPC for LayoutRPCImpl {
         ContentBoxesResponse(rw_data.content_boxes_response.clone())
     }
 
+    fn caret_rect(&self) -> CaretRectResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        CaretRectResponse(rw_data.caret_rect_response)
+    }
+
+    fn text_wrap_points(&self) -> TextWrapPointsResponse {
+        let &LayoutRPCImpl(ref rw_data) = self;
+        let rw_data = rw_data.lock().unwrap();
+        TextWrapPointsResponse(rw_data.text_wrap_points_response.clone())
+    }
+
     fn nodes_from_point_response(&self) -> Vec<UntrustedNodeAddress> {
         let &LayoutRPCImpl(ref rw_data) = self;
         let rw_data = rw_data.lock().unwrap();
@@ -360,6 +378,109 @@ pub fn process_content_boxes_request<N: LayoutNode>(requested_node: N, layout_ro
     iterator.rects
 }
 
+struct CaretLocatingFragmentIterator {
+    node_address: OpaqueNode,
+    rect: Option<Rect<Au>>,
+}
+
+impl CaretLocatingFragmentIterator {
+    fn new(node_address: OpaqueNode) -> CaretLocatingFragmentIterator {
+        CaretLocatingFragmentIterator {
+            node_address: node_address,
+            rect: None,
+        }
+    }
+}
+
+impl FragmentBorderBoxIterator for CaretLocatingFragmentIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        if self.rect.is_none() {
+            self.rect = fragment.insertion_point_bounds(border_box);
+        }
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        fragment.contains_node(self.node_address)
+    }
+}
+
+pub fn process_caret_rect_request<N: LayoutNode>(requested_node: N, layout_root: &mut Flow)
+        -> Option<Rect<Au>> {
+    // FIXME: Like the content box queries, this is relative to the stacking context rather
+    // than the page.
+    let mut iterator = CaretLocatingFragmentIterator::new(requested_node.opaque());
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    iterator.rect
+}
+
+/// Finds where the lines of a text control's content wrap, from the rows its text fragments
+/// were laid out in. Each preserved newline ends a fragment, so the fragments of a line follow
+/// one another, and one that starts a row further down than the last starts a soft wrap.
+struct WrapPointLocatingFragmentIterator {
+    node_address: OpaqueNode,
+    /// The line the next fragment belongs to, and the byte offset of its text in that line.
+    line: usize,
+    index: usize,
+    /// The block position of the row the last fragment was laid out in.
+    row: Option<Au>,
+    wrap_points: Option<Vec<(usize, usize)>>,
+}
+
+impl WrapPointLocatingFragmentIterator {
+    fn new(node_address: OpaqueNode) -> WrapPointLocatingFragmentIterator {
+        WrapPointLocatingFragmentIterator {
+            node_address: node_address,
+            line: 0,
+            index: 0,
+            row: None,
+            wrap_points: None,
+        }
+    }
+}
+
+impl FragmentBorderBoxIterator for WrapPointLocatingFragmentIterator {
+    fn process(&mut self, fragment: &Fragment, _: i32, border_box: &Rect<Au>) {
+        let scanned_text_fragment_info = match fragment.specific {
+            SpecificFragmentInfo::ScannedText(ref scanned_text_fragment_info) => {
+                scanned_text_fragment_info
+            },
+            _ => return,
+        };
+        let row = if fragment.style.writing_mode.is_vertical() {
+            border_box.origin.x
+        } else {
+            border_box.origin.y
+        };
+
+        let wrap_points = self.wrap_points.get_or_insert(vec!());
+        if self.index > 0 && self.row.map_or(false, |last_row| last_row != row) {
+            wrap_points.push((self.line, self.index));
+        }
+        self.row = Some(row);
+
+        // Whitespace that hangs past the end of a row is still part of the line.
+        let range_end = scanned_text_fragment_info.range_end_including_stripped_whitespace;
+        self.index += (range_end - scanned_text_fragment_info.range.begin()).to_usize();
+        if scanned_text_fragment_info.requires_line_break_afterward_if_wrapping_on_newlines() {
+            self.line += 1;
+            self.index = 0;
+        }
+    }
+
+    fn should_process(&mut self, fragment: &Fragment) -> bool {
+        fragment.contains_node(self.node_address)
+    }
+}
+
+/// The points where the lines of the text control's content wrap, as the line and the byte
+/// offset in it, or `None` if none of its text has been laid out.
+pub fn process_text_wrap_points_request<N: LayoutNode>(requested_node: N, layout_root: &mut Flow)
+        -> Option<Vec<(usize, usize)>> {
+    let mut iterator = WrapPointLocatingFragmentIterator::new(requested_node.opaque());
+    sequential::iterate_through_flow_tree_fragment_border_boxes(layout_root, &mut iterator);
+    iterator.wrap_points
+}
+
 struct FragmentLocatingFragmentIterator {
     node_address: OpaqueNode,
     client_rect: Rect<i32>,
@@ -196,10 +196,12 @@ impl TextRunScanner {
                 let mut mapping = RunMapping::new(&run_info_list[..], fragment_index);
                 let text;
                 let selection;
+                let misspellings;
                 match in_fragment.specific {
                     SpecificFragmentInfo::UnscannedText(ref text_fragment_info) => {
                         text = &text_fragment_info.text;
                         selection = text_fragment_info.selection;
+                        misspellings = &text_fragment_info.misspellings;
                     }
                     _ => panic!("Expected an unscanned text fragment!"),
                 };
@@ -243,12 +245,17 @@ impl TextRunScanner {
                         Some(range) => range.contains(ByteIndex(byte_index as isize)),
                         None => false
                     };
+                    let misspelled = misspellings.iter().any(|range| {
+                        range.contains(ByteIndex(byte_index as isize))
+                    });
 
                     // Now, if necessary, flush the mapping we were building up.
                     let flush_run = run_info.font_index != font_index ||
                                     run_info.bidi_level != bidi_level ||
                                     !compatible_script;
-                    let new_mapping_needed = flush_run || mapping.selected != selected;
+                    let new_mapping_needed = flush_run ||
+                                             mapping.selected != selected ||
+                                             mapping.misspelled != misspelled;
 
                     if new_mapping_needed {
                         // We ignore empty mappings at the very start of a fragment.
@@ -276,6 +283,7 @@ impl TextRunScanner {
                         run_info.bidi_level = bidi_level;
                         run_info.script = script;
                         mapping.selected = selected;
+                        mapping.misspelled = misspelled;
                     }
 
                     // Consume this character.
@@ -393,6 +401,10 @@ impl TextRunScanner {
                     flags.insert(ScannedTextFlags::SELECTED);
                 }
 
+                if mapping.misspelled {
+                    flags.insert(ScannedTextFlags::MISSPELLED);
+                }
+
                 let insertion_point = if mapping.contains_insertion_point(scanned_run.insertion_point) {
                     scanned_run.insertion_point
                 } else {
@@ -487,6 +499,7 @@ fn split_first_fragment_at_newline_if_necessary(fragments: &mut LinkedList<Fragm
         let first_fragment = fragments.front_mut().unwrap();
         let string_before;
         let selection_before;
+        let misspellings_before;
         {
             if !first_fragment.white_space().preserve_newlines() {
                 return;
@@ -528,12 +541,23 @@ fn split_first_fragment_at_newline_if_necessary(fragments: &mut LinkedList<Fragm
                     unscanned_text_fragment_info.selection = None;
                 }
             };
+            // Words never span a newline, so each misspelling is in one fragment or the other.
+            let (before, after): (Vec<_>, Vec<_>) =
+                mem::replace(&mut unscanned_text_fragment_info.misspellings, vec![])
+                .into_iter()
+                .partition(|misspelling| misspelling.begin() < offset);
+            misspellings_before = before;
+            unscanned_text_fragment_info.misspellings = after.into_iter().map(|mut misspelling| {
+                misspelling.shift_by(-offset);
+                misspelling
+            }).collect();
         }
+        let mut info_before =
+            UnscannedTextFragmentInfo::new(string_before.into_boxed_str(), selection_before);
+        info_before.misspellings = misspellings_before;
         first_fragment.transform(
             first_fragment.border_box.size,
-            SpecificFragmentInfo::UnscannedText(Box::new(
-                UnscannedTextFragmentInfo::new(string_before.into_boxed_str(), selection_before)
-            ))
+            SpecificFragmentInfo::UnscannedText(Box::new(info_before))
         )
     };
 
@@ -598,6 +622,8 @@ struct RunMapping {
     text_run_index: usize,
     /// Is the text in this fragment selected?
     selected: bool,
+    /// Is the text in this fragment a misspelled word?
+    misspelled: bool,
 }
 
 impl RunMapping {
@@ -610,6 +636,7 @@ impl RunMapping {
             old_fragment_index: fragment_index,
             text_run_index: run_info_list.len(),
             selected: false,
+            misspelled: false,
         }
     }
 
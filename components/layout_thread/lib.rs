@@ -75,10 +75,12 @@ use layout::flow_ref::FlowRef;
 use layout::incremental::{LayoutDamageComputation, RelayoutMode, SpecialRestyleDamage};
 use layout::layout_debug;
 use layout::parallel;
-use layout::query::{LayoutRPCImpl, LayoutThreadData, process_content_box_request, process_content_boxes_request};
+use layout::query::{LayoutRPCImpl, LayoutThreadData, process_caret_rect_request};
+use layout::query::{process_content_box_request, process_content_boxes_request};
 use layout::query::{process_element_inner_text_query, process_node_geometry_request};
 use layout::query::{process_node_scroll_area_request, process_node_scroll_id_request};
 use layout::query::{process_offset_parent_query, process_resolved_style_request, process_style_query};
+use layout::query::process_text_wrap_points_request;
 use layout::sequential;
 use layout::traversal::{ComputeStackingRelativePositions, PreorderFlowTraversal, RecalcStyleAndConstructFlows};
 use layout::wrapper::LayoutNodeLayoutData;
@@ -517,6 +519,8 @@ impl LayoutThread {
                     display_list: None,
                     content_box_response: None,
                     content_boxes_response: Vec::new(),
+                    caret_rect_response: None,
+                    text_wrap_points_response: None,
                     client_rect_response: Rect::zero(),
                     scroll_id_response: None,
                     scroll_area_response: Rect::zero(),
@@ -1081,6 +1085,12 @@ impl LayoutThread {
                     ReflowGoal::ContentBoxQuery(_) => {
                         rw_data.content_box_response = None;
                     },
+                    ReflowGoal::CaretRectQuery(_) => {
+                        rw_data.caret_rect_response = None;
+                    },
+                    ReflowGoal::TextWrapPointsQuery(_) => {
+                        rw_data.text_wrap_points_response = None;
+                    },
                     ReflowGoal::ContentBoxesQuery(_) => {
                         rw_data.content_boxes_response = Vec::new();
                     },
@@ -1354,6 +1364,14 @@ impl LayoutThread {
                 let node = unsafe { ServoLayoutNode::new(&node) };
                 rw_data.content_box_response = process_content_box_request(node, root_flow);
             },
+            ReflowGoal::CaretRectQuery(node) => {
+                let node = unsafe { ServoLayoutNode::new(&node) };
+                rw_data.caret_rect_response = process_caret_rect_request(node, root_flow);
+            },
+            ReflowGoal::TextWrapPointsQuery(node) => {
+                let node = unsafe { ServoLayoutNode::new(&node) };
+                rw_data.text_wrap_points_response = process_text_wrap_points_request(node, root_flow);
+            },
             ReflowGoal::ContentBoxesQuery(node) => {
                 let node = unsafe { ServoLayoutNode::new(&node) };
                 rw_data.content_boxes_response = process_content_boxes_request(node, root_flow);
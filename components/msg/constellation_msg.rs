@@ -7,6 +7,7 @@
 
 use nonzero::NonZero;
 use std::cell::Cell;
+use std::cmp;
 use std::fmt;
 use webrender_api;
 
@@ -144,6 +145,12 @@ pub enum Key {
 
     NavigateBackward,
     NavigateForward,
+
+    /// A dead key, whose accent is the character sent along with the key event. It is combined
+    /// with the next character typed.
+    Dead,
+    /// The compose key, which starts a sequence of characters that combine into one.
+    Compose,
 }
 
 bitflags! {
@@ -157,6 +164,222 @@ bitflags! {
     }
 }
 
+/// A clause of the text being composed by an input method, as UTF-8 byte offsets into it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ImeClause {
+    pub start: usize,
+    pub end: usize,
+    /// Whether this is the clause the input method is currently converting.
+    pub selected: bool,
+}
+
+/// An update from the platform's input method editor to the focused text control.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum ImeEvent {
+    /// The text being composed changed, with the caret at the given UTF-8 byte offset in it.
+    Composition(String, Vec<ImeClause>, usize),
+    /// The composition ended with the given text, or with the text composed so far.
+    Commit(Option<String>),
+    /// The composition was abandoned.
+    Cancel,
+    /// Delete up to the given numbers of characters before and after the selection, or around
+    /// the text being composed.
+    DeleteSurroundingText(usize, usize),
+}
+
+/// The kind of virtual keyboard suited to the focused text control.
+///
+/// https://html.spec.whatwg.org/multipage/#input-modalities:-the-inputmode-attribute
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum InputMode {
+    /// The text control provides its own input, so no virtual keyboard should be shown.
+    None,
+    Text,
+    Decimal,
+    Numeric,
+    Tel,
+    Search,
+    Email,
+    Url,
+}
+
+/// The action that the enter key of a virtual keyboard is labelled with.
+///
+/// https://html.spec.whatwg.org/multipage/#input-modalities:-the-enterkeyhint-attribute
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum EnterKeyHint {
+    Enter,
+    Done,
+    Go,
+    Next,
+    Previous,
+    Search,
+    Send,
+}
+
+/// What the `autocomplete` attribute of a text control asks autofill providers, such as
+/// password managers, to fill it with.
+///
+/// https://html.spec.whatwg.org/multipage/#autofill-detail-tokens
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AutofillHint {
+    /// The autofill field name, such as `username`, `current-password` or `one-time-code`, or
+    /// `on` or `off` if the control only allows or forbids autofill.
+    pub field: String,
+    /// The section of the form that the control belongs to, with its `section-` prefix.
+    pub section: Option<String>,
+    /// `shipping` or `billing`, if the control is part of an address.
+    pub address_type: Option<String>,
+    /// `home`, `work`, `mobile`, `fax` or `pager`, if the control is for contact details.
+    pub contact_type: Option<String>,
+    /// Whether the control also accepts WebAuthn credentials.
+    pub webauthn: bool,
+}
+
+/// What a port with a virtual keyboard, or an autofill provider, needs to know about the
+/// focused text control.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct TextInputHints {
+    pub input_mode: InputMode,
+    /// The label of the enter key, which the port chooses if there is none.
+    pub enter_key_hint: Option<EnterKeyHint>,
+    /// What the control asks to be autofilled with, if its `autocomplete` attribute is valid.
+    pub autofill: Option<AutofillHint>,
+}
+
+/// An option of the datalist of the focused input that matches what the user typed, for the
+/// port to offer in a dropdown.
+///
+/// https://html.spec.whatwg.org/multipage/#attr-input-list
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub value: String,
+    /// The label to show with the value, which is empty if the option has none of its own.
+    pub label: String,
+}
+
+/// The text around the caret of the focused text control, for input methods that reconvert
+/// text or base their suggestions on it.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ImeSurroundingText {
+    pub text: String,
+    /// The UTF-8 byte offset of the caret in `text`.
+    pub cursor: usize,
+    /// The UTF-8 byte offset of the other end of the selection in `text`, which is `cursor` if
+    /// nothing is selected.
+    pub anchor: usize,
+}
+
+/// A word the user just finished typing in a text control, by typing a space or punctuation
+/// after it, which the embedder may correct.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct AutocorrectWord {
+    pub word: String,
+    /// The UTF-16 code unit offset of the start of the word in the value of the control, as the
+    /// DOM counts offsets.
+    pub start: usize,
+    /// The UTF-16 code unit offset of the end of the word in the value of the control.
+    pub end: usize,
+    /// The language of the control, as given by its `lang` attribute.
+    pub lang: String,
+}
+
+/// A format that clipboard contents can be offered in.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub enum ClipboardFlavor {
+    PlainText,
+    Html,
+    UriList,
+}
+
+impl ClipboardFlavor {
+    pub fn mime_type(&self) -> &'static str {
+        match *self {
+            ClipboardFlavor::PlainText => "text/plain",
+            ClipboardFlavor::Html => "text/html",
+            ClipboardFlavor::UriList => "text/uri-list",
+        }
+    }
+
+    /// The flavor with the given MIME type, ignoring case and any parameters.
+    pub fn from_mime_type(mime_type: &str) -> Option<ClipboardFlavor> {
+        let essence = mime_type.split(';').next().unwrap_or("").trim();
+        if essence.eq_ignore_ascii_case("text/plain") {
+            Some(ClipboardFlavor::PlainText)
+        } else if essence.eq_ignore_ascii_case("text/html") {
+            Some(ClipboardFlavor::Html)
+        } else if essence.eq_ignore_ascii_case("text/uri-list") {
+            Some(ClipboardFlavor::UriList)
+        } else {
+            None
+        }
+    }
+}
+
+/// Contents put on the clipboard, in each of the flavors they are offered in.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ClipboardContents {
+    /// The offered flavors, in the order they were added, each at most once.
+    flavors: Vec<(ClipboardFlavor, String)>,
+}
+
+impl ClipboardContents {
+    pub fn new() -> ClipboardContents {
+        Default::default()
+    }
+
+    pub fn from_plain_text(text: String) -> ClipboardContents {
+        let mut contents = ClipboardContents::new();
+        contents.set(ClipboardFlavor::PlainText, text);
+        contents
+    }
+
+    /// Offer `data` in the given flavor, replacing what was offered in it before.
+    pub fn set(&mut self, flavor: ClipboardFlavor, data: String) {
+        match self.flavors.iter_mut().find(|&&mut (f, _)| f == flavor) {
+            Some(&mut (_, ref mut old_data)) => *old_data = data,
+            None => self.flavors.push((flavor, data)),
+        }
+    }
+
+    pub fn get(&self, flavor: ClipboardFlavor) -> Option<&str> {
+        self.flavors.iter().find(|&&(f, _)| f == flavor).map(|&(_, ref data)| &**data)
+    }
+
+    /// The data offered in the given flavor, taken out of the contents without copying it.
+    pub fn into_data(self, flavor: ClipboardFlavor) -> Option<String> {
+        self.flavors.into_iter().find(|&(f, _)| f == flavor).map(|(_, data)| data)
+    }
+
+    pub fn flavors(&self) -> Vec<ClipboardFlavor> {
+        self.flavors.iter().map(|&(flavor, _)| flavor).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.flavors.is_empty()
+    }
+}
+
+/// The part of clipboard text that starts at the UTF-8 byte offset `start` and is at most
+/// `max_length` bytes long, for reading large clipboard contents in chunks. The chunk ends at a
+/// character boundary, and holds at least one character unless `start` is at the end of the
+/// text, so that a reader always makes progress.
+pub fn clipboard_text_chunk(text: &str, start: usize, max_length: usize) -> &str {
+    let mut start = cmp::min(start, text.len());
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let rest = &text[start..];
+    let mut end = cmp::min(max_length, rest.len());
+    while !rest.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == 0 {
+        end = rest.chars().next().map_or(0, char::len_utf8);
+    }
+    &rest[..end]
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
 pub enum TraversalDirection {
     Forward(usize),
@@ -2,45 +2,234 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
-use ipc_channel::ipc::channel;
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor, clipboard_text_chunk};
 use script_traits::{ScriptToConstellationChan, ScriptMsg};
 use std::borrow::ToOwned;
+use std::time::{Duration, Instant};
+#[cfg(feature = "test_clipboard")]
+use test_clipboard::TestableClipboardProvider;
+
+/// The largest chunk, in bytes, that clipboard contents are read in, so that huge contents
+/// don't have to be sent in a single message.
+pub const CLIPBOARD_CHUNK_SIZE: usize = 1 << 20;
 
 pub trait ClipboardProvider {
-    // blocking method to get the clipboard contents
-    fn clipboard_contents(&mut self) -> String;
-    // blocking method to set the clipboard contents
-    fn set_clipboard_contents(&mut self, String);
+    // ask for the flavors that the clipboard contents are offered in, which are sent to the
+    // given channel once they are known
+    fn request_clipboard_flavors(&mut self, IpcSender<Vec<ClipboardFlavor>>);
+    // ask for the clipboard contents in the given flavor, which are sent to the given channel
+    // once they are read, or None if they aren't offered in it; that can take a while, e.g.
+    // over a remote desktop connection, so this must not block
+    fn request_clipboard_contents(&mut self, ClipboardFlavor, IpcSender<Option<String>>);
+    // ask for the length in UTF-8 bytes of the clipboard contents in the given flavor, or None
+    // if they aren't offered in it
+    fn request_clipboard_length(&mut self, ClipboardFlavor, IpcSender<Option<usize>>);
+    // ask for the part of the clipboard contents in the given flavor that starts at the given
+    // byte offset and is at most the given number of bytes long, as cut by
+    // `clipboard_text_chunk`, or None if they aren't offered in it
+    fn request_clipboard_chunk(&mut self, ClipboardFlavor, usize, usize, IpcSender<Option<String>>);
+    // set the clipboard contents, in all the flavors they are offered in
+    fn set_clipboard_contents(&mut self, ClipboardContents);
+}
+
+/// The clipboard that text controls read and write: the system clipboard, through the
+/// constellation.
+#[cfg(not(feature = "test_clipboard"))]
+pub type TextControlClipboard = ScriptToConstellationChan;
+
+/// The clipboard that text controls read and write: the test clipboard while one is installed,
+/// and the system clipboard otherwise.
+#[cfg(feature = "test_clipboard")]
+pub type TextControlClipboard = TestableClipboardProvider;
+
+/// The clipboard for a text control whose script thread sends its messages to the
+/// constellation through `chan`.
+#[cfg(not(feature = "test_clipboard"))]
+pub fn text_control_clipboard(chan: ScriptToConstellationChan) -> TextControlClipboard {
+    chan
+}
+
+/// The clipboard for a text control whose script thread sends its messages to the
+/// constellation through `chan`.
+#[cfg(feature = "test_clipboard")]
+pub fn text_control_clipboard(chan: ScriptToConstellationChan) -> TextControlClipboard {
+    TestableClipboardProvider::new(chan)
 }
 
 impl ClipboardProvider for ScriptToConstellationChan {
-    fn clipboard_contents(&mut self) -> String {
-        let (tx, rx) = channel().unwrap();
-        self.send(ScriptMsg::GetClipboardContents(tx)).unwrap();
-        rx.recv().unwrap()
+    fn request_clipboard_flavors(&mut self, sender: IpcSender<Vec<ClipboardFlavor>>) {
+        self.send(ScriptMsg::GetClipboardFlavors(sender)).unwrap();
+    }
+    fn request_clipboard_contents(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<String>>) {
+        self.send(ScriptMsg::GetClipboardContents(flavor, sender)).unwrap();
+    }
+    fn request_clipboard_length(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<usize>>) {
+        self.send(ScriptMsg::GetClipboardLength(flavor, sender)).unwrap();
     }
-    fn set_clipboard_contents(&mut self, s: String) {
-        self.send(ScriptMsg::SetClipboardContents(s)).unwrap();
+    fn request_clipboard_chunk(&mut self, flavor: ClipboardFlavor, start: usize, max_length: usize,
+                               sender: IpcSender<Option<String>>) {
+        self.send(ScriptMsg::GetClipboardChunk(flavor, start, max_length, sender)).unwrap();
+    }
+    fn set_clipboard_contents(&mut self, contents: ClipboardContents) {
+        self.send(ScriptMsg::SetClipboardContents(contents)).unwrap();
     }
 }
 
+#[derive(Clone)]
 pub struct DummyClipboardContext {
-    content: String,
+    contents: ClipboardContents,
 }
 
 impl DummyClipboardContext {
     pub fn new(s: &str) -> DummyClipboardContext {
+        DummyClipboardContext::with_contents(ClipboardContents::from_plain_text(s.to_owned()))
+    }
+
+    pub fn with_contents(contents: ClipboardContents) -> DummyClipboardContext {
         DummyClipboardContext {
-            content: s.to_owned(),
+            contents: contents,
         }
     }
 }
 
 impl ClipboardProvider for DummyClipboardContext {
-    fn clipboard_contents(&mut self) -> String {
-        self.content.clone()
+    fn request_clipboard_flavors(&mut self, sender: IpcSender<Vec<ClipboardFlavor>>) {
+        let _ = sender.send(self.contents.flavors());
+    }
+    fn request_clipboard_contents(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<String>>) {
+        let _ = sender.send(self.contents.get(flavor).map(ToOwned::to_owned));
+    }
+    fn request_clipboard_length(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<usize>>) {
+        let _ = sender.send(self.contents.get(flavor).map(str::len));
+    }
+    fn request_clipboard_chunk(&mut self, flavor: ClipboardFlavor, start: usize, max_length: usize,
+                               sender: IpcSender<Option<String>>) {
+        let chunk = self.contents.get(flavor).map(|text| clipboard_text_chunk(text, start, max_length).to_owned());
+        let _ = sender.send(chunk);
+    }
+    fn set_clipboard_contents(&mut self, contents: ClipboardContents) {
+        self.contents = contents;
+    }
+}
+
+/// Reads clipboard contents in chunks: it asks for their length, then for each chunk once the
+/// previous one has arrived, and gathers them in a single string.
+///
+/// The contents are read in the first of the given flavors that they are offered in with
+/// anything in it. Contents in a fallback flavor are read without knowing their length, until a
+/// chunk comes back empty.
+pub struct ClipboardChunkReader<T: ClipboardProvider> {
+    clipboard_provider: T,
+    /// The flavors to read the contents in, in order of preference. The first one is the one
+    /// being read.
+    flavors: Vec<ClipboardFlavor>,
+    chunk_size: usize,
+    /// The length of the contents, if it is known.
+    length: Option<usize>,
+    contents: String,
+    /// How long to wait for each answer of the clipboard before the read is abandoned.
+    timeout: Option<Duration>,
+    /// When the request that the reader is waiting for an answer to was made.
+    requested_at: Instant,
+    /// Whether an answer took longer than the timeout, so that nothing was read.
+    timed_out: bool,
+}
+
+impl<T: ClipboardProvider> ClipboardChunkReader<T> {
+    pub fn new(clipboard_provider: T, flavors: Vec<ClipboardFlavor>, chunk_size: usize) -> ClipboardChunkReader<T> {
+        assert!(!flavors.is_empty());
+        ClipboardChunkReader {
+            clipboard_provider: clipboard_provider,
+            flavors: flavors,
+            chunk_size: chunk_size,
+            length: None,
+            contents: String::new(),
+            timeout: None,
+            requested_at: Instant::now(),
+            timed_out: false,
+        }
+    }
+
+    /// Abandon the read if the clipboard takes longer than `timeout` to answer one of its
+    /// requests. The time the whole read takes is not limited, so that huge contents can be
+    /// read from a slow clipboard.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
     }
-    fn set_clipboard_contents(&mut self, s: String) {
-        self.content = s;
+
+    /// Ask for the length of the contents in the preferred flavor, which is sent to `sender`.
+    pub fn request_length(&mut self, sender: IpcSender<Option<usize>>) {
+        self.requested_at = Instant::now();
+        self.clipboard_provider.request_clipboard_length(self.flavors[0], sender);
+    }
+
+    /// Whether the answer that just arrived took too long, in which case the read is
+    /// abandoned.
+    fn answer_timed_out(&mut self) -> bool {
+        if self.timeout.map_or(false, |timeout| self.requested_at.elapsed() >= timeout) {
+            self.timed_out = true;
+            self.length = Some(self.contents.len());
+        }
+        self.timed_out
+    }
+
+    /// Take the length that arrived, and return whether there is anything to read, possibly in
+    /// a fallback flavor.
+    pub fn receive_length(&mut self, length: Option<usize>) -> bool {
+        if self.answer_timed_out() {
+            return false;
+        }
+        match length {
+            Some(length) if length > 0 => {
+                self.length = Some(length);
+                self.contents.reserve(length);
+                true
+            },
+            _ if self.flavors.len() > 1 => {
+                self.flavors.remove(0);
+                true
+            },
+            _ => {
+                self.length = Some(0);
+                false
+            },
+        }
+    }
+
+    /// Ask for the next chunk, which is sent to `sender`, and return whether there was one
+    /// left to ask for.
+    pub fn request_next_chunk(&mut self, sender: IpcSender<Option<String>>) -> bool {
+        let start = self.contents.len();
+        if self.length.map_or(false, |length| start >= length) {
+            return false;
+        }
+        self.requested_at = Instant::now();
+        self.clipboard_provider.request_clipboard_chunk(self.flavors[0], start, self.chunk_size, sender);
+        true
+    }
+
+    /// Add a chunk that arrived, and return whether there are more to read. The read stops
+    /// once a chunk comes back empty, which happens early if the clipboard no longer offers the
+    /// flavor, or its contents got shorter.
+    pub fn receive_chunk(&mut self, chunk: Option<String>) -> bool {
+        if self.answer_timed_out() {
+            return false;
+        }
+        match chunk {
+            Some(ref chunk) if !chunk.is_empty() => self.contents.push_str(chunk),
+            _ => self.length = Some(self.contents.len()),
+        }
+        self.length.map_or(true, |length| self.contents.len() < length)
+    }
+
+    /// The contents read so far, in the flavor they were read in, or nothing if the read was
+    /// abandoned because the clipboard took too long to answer.
+    pub fn into_contents(self) -> ClipboardContents {
+        let mut contents = ClipboardContents::new();
+        if !self.timed_out && !self.contents.is_empty() {
+            contents.set(self.flavors[0], self.contents);
+        }
+        contents
     }
 }
@@ -0,0 +1,148 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Dead keys and compose sequences, which combine several key presses into one character.
+//!
+//! A dead key sends its accent along with a `Key::Dead` event, and the accent is combined with
+//! the next character typed, so that `´` then `e` gives `é`. The compose key starts a sequence
+//! of two characters, which are either an accent and a letter in any order, or one of a few
+//! pairs such as `o c` for `©`.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// The combining mark for the accent of a dead key.
+fn combining_mark(accent: char) -> Option<char> {
+    Some(match accent {
+        '`' | '\u{2CB}' => '\u{300}',
+        '\'' | '\u{B4}' | '\u{2CA}' => '\u{301}',
+        '^' | '\u{2C6}' => '\u{302}',
+        '~' | '\u{2DC}' => '\u{303}',
+        '\u{AF}' | '\u{2C9}' => '\u{304}',
+        '\u{2D8}' => '\u{306}',
+        '\u{2D9}' => '\u{307}',
+        '"' | '\u{A8}' => '\u{308}',
+        '\u{2DA}' => '\u{30A}',
+        '\u{2DD}' => '\u{30B}',
+        '\u{2C7}' => '\u{30C}',
+        ',' | '\u{B8}' => '\u{327}',
+        '\u{2DB}' => '\u{328}',
+        '\u{300}'...'\u{36F}' => accent,
+        _ => return None,
+    })
+}
+
+/// Compose pairs that are not an accent and a letter.
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let pair = |a, b| (first == a && second == b) || (first == b && second == a);
+    Some(if pair('o', 'c') {
+        '\u{A9}'
+    } else if pair('o', 'r') {
+        '\u{AE}'
+    } else if pair('s', 's') {
+        '\u{DF}'
+    } else if pair('a', 'e') {
+        '\u{E6}'
+    } else if pair('A', 'E') {
+        '\u{C6}'
+    } else if pair('<', '<') {
+        '\u{AB}'
+    } else if pair('>', '>') {
+        '\u{BB}'
+    } else if pair('=', 'e') || pair('C', '=') {
+        '\u{20AC}'
+    } else if pair('+', '-') {
+        '\u{B1}'
+    } else {
+        return None
+    })
+}
+
+/// The letter `base` with the accent of a dead key, if there is such a character.
+fn accented(accent: char, base: char) -> Option<char> {
+    let mark = combining_mark(accent)?;
+    let mut composed = [base, mark].iter().cloned().nfc();
+    match (composed.next(), composed.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// The characters pressed so far in an unfinished composition.
+#[derive(Clone, Copy, Debug, Eq, JSTraceable, MallocSizeOf, PartialEq)]
+enum Pending {
+    DeadKey(char),
+    Compose,
+    ComposeWith(char),
+}
+
+/// The state of a composition in a text control.
+#[derive(Clone, Debug, Default, JSTraceable, MallocSizeOf)]
+pub struct ComposeState {
+    pending: Option<Pending>,
+}
+
+impl ComposeState {
+    /// Whether a composition has started and is waiting for more characters.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Abandon the current composition, if any.
+    pub fn cancel(&mut self) {
+        self.pending = None;
+    }
+
+    /// Handle a dead key with the given accent, and return the text to insert, if any.
+    pub fn press_dead_key(&mut self, accent: char) -> Option<String> {
+        match self.pending.take() {
+            // Pressing a dead key twice types its accent.
+            Some(Pending::DeadKey(previous)) if previous == accent => Some(accent.to_string()),
+            Some(Pending::DeadKey(previous)) => {
+                self.pending = Some(Pending::DeadKey(accent));
+                Some(previous.to_string())
+            },
+            // Within a compose sequence, a dead key stands for its accent.
+            Some(pending @ Pending::Compose) | Some(pending @ Pending::ComposeWith(_)) => {
+                self.pending = Some(pending);
+                self.press_char(accent)
+            },
+            None => {
+                self.pending = Some(Pending::DeadKey(accent));
+                None
+            },
+        }
+    }
+
+    /// Handle the compose key.
+    pub fn press_compose(&mut self) {
+        self.pending = Some(Pending::Compose);
+    }
+
+    /// Handle a typed character, and return the text to insert. This is the character itself
+    /// unless a composition is pending, and nothing if the composition needs more characters or
+    /// is not a known sequence.
+    pub fn press_char(&mut self, c: char) -> Option<String> {
+        match self.pending.take() {
+            None => Some(c.to_string()),
+            Some(Pending::DeadKey(accent)) => Some(match accented(accent, c) {
+                Some(composed) => composed.to_string(),
+                // A space types the accent itself.
+                None if c == ' ' => accent.to_string(),
+                // Otherwise the accent did not apply, so type both.
+                None => format!("{}{}", accent, c),
+            }),
+            Some(Pending::Compose) => {
+                self.pending = Some(Pending::ComposeWith(c));
+                None
+            },
+            Some(Pending::ComposeWith(first)) => {
+                let composed = compose_pair(first, c)
+                    .or_else(|| accented(first, c))
+                    .or_else(|| accented(c, first));
+                // An unknown sequence types nothing.
+                composed.map(|composed| composed.to_string())
+            },
+        }
+    }
+}
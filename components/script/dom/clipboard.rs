@@ -0,0 +1,122 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use clipboard_provider::ClipboardProvider;
+use dom::bindings::codegen::Bindings::ClipboardBinding::{self, ClipboardMethods};
+use dom::bindings::codegen::Bindings::PermissionStatusBinding::{PermissionName, PermissionState};
+use dom::bindings::error::Error;
+use dom::bindings::refcounted::TrustedPromise;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::permissions::get_descriptor_permission_state;
+use dom::promise::Promise;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor};
+use std::rc::Rc;
+use task_source::TaskSource;
+
+// https://w3c.github.io/clipboard-apis/#clipboard-interface
+#[dom_struct]
+pub struct Clipboard {
+    eventtarget: EventTarget,
+}
+
+impl Clipboard {
+    fn new_inherited() -> Clipboard {
+        Clipboard {
+            eventtarget: EventTarget::new_inherited(),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<Clipboard> {
+        reflect_dom_object(Box::new(Clipboard::new_inherited()),
+                           window,
+                           ClipboardBinding::Wrap)
+    }
+}
+
+/// Whether script in the given global may read the clipboard without the user asking for it
+/// through the browser's own paste command.
+///
+/// https://w3c.github.io/clipboard-apis/#check-clipboard-read-permission
+pub fn may_read_clipboard(global: &GlobalScope) -> bool {
+    get_descriptor_permission_state(PermissionName::Clipboard_read, Some(global)) == PermissionState::Granted
+}
+
+/// Whether script in the given global may write to the clipboard without the user asking for
+/// it through the browser's own copy and cut commands.
+///
+/// https://w3c.github.io/clipboard-apis/#check-clipboard-write-permission
+pub fn may_write_clipboard(global: &GlobalScope) -> bool {
+    global.as_window().has_transient_activation() ||
+        get_descriptor_permission_state(PermissionName::Clipboard_write, Some(global)) == PermissionState::Granted
+}
+
+impl ClipboardMethods for Clipboard {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-readtext
+    #[allow(unrooted_must_root)]
+    fn ReadText(&self) -> Rc<Promise> {
+        let global = self.global();
+        // Step 1
+        let promise = Promise::new(&global);
+
+        // Step 2
+        if !may_read_clipboard(&global) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+
+        let (sender, receiver) = ipc::channel().unwrap();
+        let window = global.as_window();
+        let task_source = window.user_interaction_task_source();
+        let canceller = window.task_canceller();
+        let mut trusted_promise = Some(TrustedPromise::new(promise.clone()));
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let trusted_promise = match trusted_promise.take() {
+                Some(trusted_promise) => trusted_promise,
+                None => return,
+            };
+            let text: Option<String> = message.to().unwrap();
+            let _ = task_source.queue_with_canceller(
+                task!(resolve_clipboard_text: move || {
+                    let text = DOMString::from(text.unwrap_or_default());
+                    trusted_promise.root().resolve_native(&text);
+                }),
+                &canceller,
+            );
+        }));
+        global.script_to_constellation_chan().clone()
+              .request_clipboard_contents(ClipboardFlavor::PlainText, sender);
+
+        // Step 3
+        promise
+    }
+
+    // https://w3c.github.io/clipboard-apis/#dom-clipboard-writetext
+    #[allow(unrooted_must_root)]
+    fn WriteText(&self, data: DOMString) -> Rc<Promise> {
+        let global = self.global();
+        // Step 1
+        let promise = Promise::new(&global);
+
+        // Step 2
+        if !may_write_clipboard(&global) {
+            promise.reject_error(Error::NotAllowed);
+            return promise;
+        }
+
+        let contents = ClipboardContents::from_plain_text(String::from(data));
+        global.script_to_constellation_chan().clone().set_clipboard_contents(contents);
+        promise.resolve_native(&());
+
+        // Step 3
+        promise
+    }
+}
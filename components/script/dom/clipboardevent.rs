@@ -0,0 +1,68 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::ClipboardEventBinding;
+use dom::bindings::codegen::Bindings::ClipboardEventBinding::{ClipboardEventInit, ClipboardEventMethods};
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::reflect_dom_object;
+use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
+use dom::bindings::str::DOMString;
+use dom::datatransfer::DataTransfer;
+use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::window::Window;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+use std::default::Default;
+
+#[dom_struct]
+pub struct ClipboardEvent {
+    event: Event,
+    clipboard_data: MutNullableDom<DataTransfer>,
+}
+
+impl ClipboardEvent {
+    fn new_inherited() -> ClipboardEvent {
+        ClipboardEvent {
+            event: Event::new_inherited(),
+            clipboard_data: Default::default(),
+        }
+    }
+
+    pub fn new(window: &Window,
+               type_: Atom,
+               bubbles: EventBubbles,
+               cancelable: EventCancelable,
+               clipboard_data: Option<&DataTransfer>) -> DomRoot<ClipboardEvent> {
+        let ev = reflect_dom_object(Box::new(ClipboardEvent::new_inherited()),
+                                    window,
+                                    ClipboardEventBinding::Wrap);
+        ev.upcast::<Event>().init_event(type_, bool::from(bubbles), bool::from(cancelable));
+        ev.clipboard_data.set(clipboard_data);
+        ev
+    }
+
+    pub fn Constructor(window: &Window,
+                       type_: DOMString,
+                       init: &ClipboardEventInit) -> Fallible<DomRoot<ClipboardEvent>> {
+        Ok(ClipboardEvent::new(window,
+                               Atom::from(type_),
+                               EventBubbles::from(init.parent.bubbles),
+                               EventCancelable::from(init.parent.cancelable),
+                               init.clipboardData.r()))
+    }
+}
+
+impl ClipboardEventMethods for ClipboardEvent {
+    // https://w3c.github.io/clipboard-apis/#dom-clipboardevent-clipboarddata
+    fn GetClipboardData(&self) -> Option<DomRoot<DataTransfer>> {
+        self.clipboard_data.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.upcast::<Event>().IsTrusted()
+    }
+}
@@ -0,0 +1,567 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Editing of the content of `contenteditable` elements.
+//!
+//! The editing commands are those of `TextInput`, carried out on a model of the editing host's
+//! content in which each text node contributes its text and each `<br>` a line break. The
+//! changes `TextInput` makes to the model are then made to the DOM, trimming and removing the
+//! text nodes and `<br>` elements they cover, inserting into or splitting the text node at the
+//! caret, and inserting `<br>` elements for line breaks. The selection is kept as DOM
+//! positions between edits, so that it survives changes made to the content by the page.
+//!
+//! https://html.spec.whatwg.org/multipage/#editing-2
+
+use clipboard_provider::{TextControlClipboard, text_control_clipboard};
+use dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::TextBinding::TextMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::root::{Dom, DomRoot, RootedReference};
+use dom::bindings::str::DOMString;
+use dom::characterdata::CharacterData;
+use dom::element::Element;
+use dom::event::{Event, EventStatus};
+use dom::globalscope::GlobalScope;
+use dom::htmlbrelement::HTMLBRElement;
+use dom::htmlelement::HTMLElement;
+use dom::inputevent::InputEvent;
+use dom::keyboardevent::KeyboardEvent;
+use dom::node::{Node, document_from_node, window_from_node};
+use dom::text::Text;
+use std::cmp::{max, min};
+use std::ops::Range;
+use textinput::{Edit, KeyReaction, Lines, SelectionDirection, TextChange, TextInput};
+
+/// The state of the `contenteditable` attribute of an element.
+///
+/// https://html.spec.whatwg.org/multipage/#attr-contenteditable
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContentEditableState {
+    True,
+    False,
+    /// The element is editable if its parent is.
+    Inherit,
+}
+
+impl ContentEditableState {
+    /// The state for the value of the attribute, or for its absence if `value` is `None`.
+    pub fn from_attribute(value: Option<&str>) -> ContentEditableState {
+        match value {
+            Some(value) if value.is_empty() || value.eq_ignore_ascii_case("true") => ContentEditableState::True,
+            Some(value) if value.eq_ignore_ascii_case("false") => ContentEditableState::False,
+            _ => ContentEditableState::Inherit,
+        }
+    }
+
+    /// The state as the `contentEditable` IDL attribute gives it.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ContentEditableState::True => "true",
+            ContentEditableState::False => "false",
+            ContentEditableState::Inherit => "inherit",
+        }
+    }
+}
+
+/// The state of the `contenteditable` attribute of `element`, which only HTML elements have.
+pub fn content_editable_state(element: &Element) -> ContentEditableState {
+    if !element.is::<HTMLElement>() || !element.has_attribute(&local_name!("contenteditable")) {
+        return ContentEditableState::Inherit;
+    }
+    let value = element.get_string_attribute(&local_name!("contenteditable"));
+    ContentEditableState::from_attribute(Some(&value))
+}
+
+/// The editing host whose content `node` is part of, if any: the outermost of the elements
+/// around it that are made editable by `contenteditable`, up to the closest one that isn't,
+/// or the body if the document is in design mode and none is. (The whole document is editable
+/// in design mode, but only the content of the body is rendered.)
+///
+/// https://html.spec.whatwg.org/multipage/#editing-host
+pub fn editing_host(node: &Node) -> Option<DomRoot<HTMLElement>> {
+    let mut host = None;
+    for ancestor in node.inclusive_ancestors() {
+        let state = match ancestor.downcast::<Element>() {
+            Some(element) => content_editable_state(element),
+            None => continue,
+        };
+        match state {
+            ContentEditableState::True => host = DomRoot::downcast::<HTMLElement>(ancestor),
+            ContentEditableState::False => return host,
+            ContentEditableState::Inherit => (),
+        }
+    }
+
+    let document = node.owner_doc();
+    if !document.design_mode() {
+        return host;
+    }
+    match document.GetBody() {
+        Some(body) if body.upcast::<Node>().is_inclusive_ancestor_of(node) => Some(body),
+        _ => host,
+    }
+}
+
+/// Whether `element` is an editing host, rather than not editable or editable as part of the
+/// content of another one.
+pub fn is_editing_host(element: &HTMLElement) -> bool {
+    editing_host(element.upcast()).map_or(false, |host| &*host == element)
+}
+
+/// What a leaf of the model of an editing host's content is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LeafKind {
+    /// A text node, which contributes its text to the model.
+    Text,
+    /// A `<br>` element, which contributes a line break.
+    LineBreak,
+}
+
+/// How the leaves of the model are trimmed to delete some of the content.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Deletion {
+    /// Delete the text in the UTF-8 byte range from the text leaf at the given index.
+    Trim(usize, Range<usize>),
+    /// Remove the leaf at the given index, all of which was deleted.
+    Remove(usize),
+}
+
+/// Where the text of a change is inserted into the leaves of the model, once its deletions
+/// have been made.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InsertionPoint {
+    /// At the UTF-8 byte offset in the text leaf at the given index.
+    InText(usize, usize),
+    /// Before the leaf at the given index.
+    Before(usize),
+    /// After the leaf at the given index.
+    After(usize),
+    /// At the end of the editing host.
+    End,
+}
+
+/// Plan how to replace the UTF-8 byte range of the model with other text, given the kind and
+/// length of each of its leaves. Text is inserted into the text leaf that ends at the range,
+/// or else the one that starts there, so that it takes on the formatting of the text before
+/// the caret where there is any.
+pub fn plan_change(leaves: &[(LeafKind, usize)], range: Range<usize>) -> (Vec<Deletion>, InsertionPoint) {
+    let mut deletions = vec!();
+    let mut starts = Vec::with_capacity(leaves.len());
+    let mut offset = 0;
+    for (index, &(kind, len)) in leaves.iter().enumerate() {
+        starts.push(offset);
+        let deleted = max(range.start, offset)..min(range.end, offset + len);
+        if deleted.start < deleted.end {
+            if deleted.start == offset && deleted.end == offset + len {
+                deletions.push(Deletion::Remove(index));
+            } else {
+                debug_assert!(kind == LeafKind::Text);
+                deletions.push(Deletion::Trim(index, deleted.start - offset..deleted.end - offset));
+            }
+        }
+        offset += len;
+    }
+    let removed: Vec<bool> = (0..leaves.len()).map(|index| deletions.contains(&Deletion::Remove(index))).collect();
+
+    let text_before = leaves.iter().enumerate().position(|(index, &(kind, len))| {
+        kind == LeafKind::Text && starts[index] < range.start && range.start <= starts[index] + len
+    });
+    if let Some(index) = text_before {
+        return (deletions, InsertionPoint::InText(index, range.start - starts[index]));
+    }
+    let next = (0..leaves.len()).position(|index| starts[index] >= range.start && !removed[index]);
+    if let Some(index) = next {
+        let point = match leaves[index].0 {
+            LeafKind::Text => InsertionPoint::InText(index, 0),
+            LeafKind::LineBreak => InsertionPoint::Before(index),
+        };
+        return (deletions, point);
+    }
+    let previous = removed.iter().rev().position(|removed| !removed);
+    let point = match previous {
+        Some(position) => InsertionPoint::After(leaves.len() - 1 - position),
+        None => InsertionPoint::End,
+    };
+    (deletions, point)
+}
+
+/// The UTF-16 length of the first `offset` UTF-8 bytes of `text`, as the DOM counts offsets
+/// into a text node.
+fn utf16_offset(text: &str, offset: usize) -> u32 {
+    text[..offset].encode_utf16().count() as u32
+}
+
+/// The UTF-8 byte offset of the given offset in UTF-16 code units into `text`, moved back to a
+/// character boundary.
+fn utf8_offset(text: &str, utf16_offset: u32) -> usize {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        units += c.len_utf16() as u32;
+        if units > utf16_offset {
+            return offset;
+        }
+    }
+    text.len()
+}
+
+/// A text node or `<br>` element of an editing host's content.
+struct Leaf {
+    node: DomRoot<Node>,
+    kind: LeafKind,
+    /// The UTF-8 byte offset of the leaf in the model.
+    start: usize,
+    /// The text the leaf contributes to the model.
+    text: String,
+}
+
+/// A model of the content of an editing host, as a `TextInput` edits it.
+pub struct EditableContent {
+    host: DomRoot<HTMLElement>,
+    leaves: Vec<Leaf>,
+}
+
+impl EditableContent {
+    /// The model of the current content of `host`. Text in elements that are not editable, or
+    /// in other editing hosts, is left out.
+    pub fn collect(host: &HTMLElement) -> EditableContent {
+        let mut leaves = vec!();
+        let mut start = 0;
+        for node in host.upcast::<Node>().traverse_preorder() {
+            let (kind, text) = if let Some(text) = node.downcast::<Text>() {
+                (LeafKind::Text, String::from(text.upcast::<CharacterData>().data().clone()))
+            } else if node.is::<HTMLBRElement>() {
+                (LeafKind::LineBreak, "\n".to_owned())
+            } else {
+                continue;
+            };
+            if editing_host(&node).map_or(true, |node_host| &*node_host != host) {
+                continue;
+            }
+            let len = text.len();
+            leaves.push(Leaf {
+                node: node,
+                kind: kind,
+                start: start,
+                text: text,
+            });
+            start += len;
+        }
+        EditableContent {
+            host: DomRoot::from_ref(host),
+            leaves: leaves,
+        }
+    }
+
+    /// The content as `TextInput` holds it.
+    pub fn text(&self) -> DOMString {
+        let text: String = self.leaves.iter().map(|leaf| &*leaf.text).collect();
+        DOMString::from(text)
+    }
+
+    fn len(&self) -> usize {
+        self.leaves.last().map_or(0, |leaf| leaf.start + leaf.text.len())
+    }
+
+    /// The DOM position of the given UTF-8 byte offset into the model: a text node and an
+    /// offset into its data, or else a node and the index of the child the position is before.
+    pub fn position(&self, offset: usize) -> (DomRoot<Node>, u32) {
+        let text_leaf = self.leaves.iter().find(|leaf| {
+            leaf.kind == LeafKind::Text && leaf.start <= offset && offset <= leaf.start + leaf.text.len()
+        });
+        if let Some(leaf) = text_leaf {
+            return (leaf.node.clone(), utf16_offset(&leaf.text, offset - leaf.start));
+        }
+        match self.leaves.iter().find(|leaf| leaf.start >= offset) {
+            Some(leaf) => {
+                let parent = leaf.node.GetParentNode().unwrap();
+                (parent, leaf.node.index())
+            },
+            None => {
+                let host = DomRoot::from_ref(self.host.upcast::<Node>());
+                let children = host.children_count();
+                (host, children)
+            },
+        }
+    }
+
+    /// The UTF-8 byte offset into the model of a DOM position, if it is in the content.
+    pub fn offset(&self, node: &Node, offset: u32) -> Option<usize> {
+        if !self.host.upcast::<Node>().is_inclusive_ancestor_of(node) {
+            return None;
+        }
+        if let Some(leaf) = self.leaves.iter().find(|leaf| &*leaf.node == node) {
+            if leaf.kind == LeafKind::Text {
+                return Some(leaf.start + utf8_offset(&leaf.text, offset));
+            }
+        }
+        // The position is before the child at `offset`, so it is at the first leaf which is
+        // that child, inside it or after it.
+        let after = match node.children().nth(offset as usize) {
+            Some(child) => self.leaves.iter().find(|leaf| {
+                child.is_inclusive_ancestor_of(&leaf.node) || child.is_before(&leaf.node)
+            }),
+            None => self.leaves.iter().find(|leaf| {
+                !node.is_inclusive_ancestor_of(&leaf.node) && node.is_before(&leaf.node)
+            }),
+        };
+        Some(after.map_or(self.len(), |leaf| leaf.start))
+    }
+
+    /// Make a change to the model in the DOM, and merge the text nodes it leaves next to each
+    /// other.
+    pub fn apply(&self, change: &TextChange) {
+        let kinds: Vec<_> = self.leaves.iter().map(|leaf| (leaf.kind, leaf.text.len())).collect();
+        let (deletions, point) = plan_change(&kinds, change.range.clone());
+
+        // Trim from the end, so that the offsets of the text before stay valid.
+        for deletion in deletions.iter().rev() {
+            match *deletion {
+                Deletion::Trim(index, ref range) => {
+                    let leaf = &self.leaves[index];
+                    let start = utf16_offset(&leaf.text, range.start);
+                    let count = utf16_offset(&leaf.text, range.end) - start;
+                    let _ = leaf.node.downcast::<CharacterData>().unwrap().DeleteData(start, count);
+                },
+                Deletion::Remove(index) => self.leaves[index].node.remove_self(),
+            }
+        }
+
+        let mut lines = change.new_text.split('\n');
+        let first = lines.next().unwrap_or("");
+        let rest: Vec<&str> = lines.collect();
+        let (parent, before) = match point {
+            InsertionPoint::InText(index, offset) => {
+                let leaf = &self.leaves[index];
+                let text = leaf.node.downcast::<Text>().unwrap();
+                let offset = utf16_offset(&leaf.text, offset);
+                if rest.is_empty() {
+                    let _ = text.upcast::<CharacterData>().InsertData(offset, DOMString::from(first));
+                    self.normalize();
+                    return;
+                }
+                // The text after the caret moves to the last line.
+                let after = match text.SplitText(offset) {
+                    Ok(after) => after,
+                    Err(_) => return,
+                };
+                text.upcast::<CharacterData>().append_data(first);
+                let parent = after.upcast::<Node>().GetParentNode().unwrap();
+                (parent, Some(DomRoot::upcast::<Node>(after)))
+            },
+            InsertionPoint::Before(index) => {
+                let node = self.leaves[index].node.clone();
+                let parent = node.GetParentNode().unwrap();
+                self.insert_text(&parent, Some(&*node), first);
+                (parent, Some(node))
+            },
+            InsertionPoint::After(index) => {
+                let node = &self.leaves[index].node;
+                let parent = node.GetParentNode().unwrap();
+                let next = node.GetNextSibling();
+                self.insert_text(&parent, next.r(), first);
+                (parent, next)
+            },
+            InsertionPoint::End => {
+                let host = DomRoot::from_ref(self.host.upcast::<Node>());
+                self.insert_text(&host, None, first);
+                (host, None)
+            },
+        };
+        let document = document_from_node(&*self.host);
+        for line in rest {
+            let br = HTMLBRElement::new(local_name!("br"), None, &document);
+            let _ = parent.InsertBefore(br.upcast(), before.r());
+            self.insert_text(&parent, before.r(), line);
+        }
+        self.normalize();
+    }
+
+    fn insert_text(&self, parent: &Node, before: Option<&Node>, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let document = document_from_node(&*self.host);
+        let text = Text::new(DOMString::from(text), &document);
+        let _ = parent.InsertBefore(text.upcast(), before);
+    }
+
+    /// Merge adjacent text nodes and remove empty ones, as splitting and trimming leaves them.
+    fn normalize(&self) {
+        self.host.upcast::<Node>().Normalize();
+    }
+}
+
+/// The editing state of the editing host that the user last edited in a document: the
+/// selection, and a `TextInput` that carries out the editing commands on its content.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct Editor {
+    host: Dom<HTMLElement>,
+    /// The selection, from the position it was started at to the caret.
+    anchor: (Dom<Node>, u32),
+    focus: (Dom<Node>, u32),
+    textinput: TextInput<TextControlClipboard>,
+}
+
+impl Editor {
+    /// Editing state for `host`, with the caret at the given offset into its content.
+    #[allow(unrooted_must_root)]
+    fn new(host: &HTMLElement, content: &EditableContent, caret: usize) -> Editor {
+        let chan = window_from_node(host).upcast::<GlobalScope>().script_to_constellation_chan().clone();
+        let mut textinput = TextInput::new(Lines::Multiple,
+                                           content.text(),
+                                           text_control_clipboard(chan),
+                                           None,
+                                           None,
+                                           SelectionDirection::None);
+        textinput.set_defers_edits(true);
+        let (node, offset) = content.position(caret);
+        Editor {
+            host: Dom::from_ref(host),
+            anchor: (Dom::from_ref(&*node), offset),
+            focus: (Dom::from_ref(&*node), offset),
+            textinput: textinput,
+        }
+    }
+
+    /// Bring the model up to date with the content, which the page may have changed, and put
+    /// the selection where its DOM positions are now.
+    fn sync(&mut self, content: &EditableContent) {
+        self.textinput.set_content(content.text(), true);
+        let anchor = content.offset(&self.anchor.0, self.anchor.1);
+        let focus = content.offset(&self.focus.0, self.focus.1);
+        match (anchor, focus) {
+            (Some(anchor), Some(focus)) if anchor <= focus => {
+                self.textinput.set_selection_range(anchor as u32, focus as u32, SelectionDirection::Forward);
+            },
+            (Some(anchor), Some(focus)) => {
+                self.textinput.set_selection_range(focus as u32, anchor as u32, SelectionDirection::Backward);
+            },
+            // The selection was removed with the content around it.
+            _ => {
+                let end = content.len() as u32;
+                self.textinput.set_selection_range(end, end, SelectionDirection::None);
+            },
+        }
+    }
+
+    /// The position the selection was started at.
+    pub fn anchor(&self) -> (DomRoot<Node>, u32) {
+        (DomRoot::from_ref(&*self.anchor.0), self.anchor.1)
+    }
+
+    /// The position of the caret.
+    pub fn focus(&self) -> (DomRoot<Node>, u32) {
+        (DomRoot::from_ref(&*self.focus.0), self.focus.1)
+    }
+
+    /// Keep the selection of the model as DOM positions in the content.
+    fn store_selection(&mut self, content: &EditableContent) {
+        let anchor = self.textinput.selection_origin_or_edit_point();
+        let anchor = self.textinput.text_point_to_offset(&anchor);
+        let focus = self.textinput.text_point_to_offset(&self.textinput.edit_point);
+        let (node, offset) = content.position(anchor);
+        self.anchor = (Dom::from_ref(&*node), offset);
+        let (node, offset) = content.position(focus);
+        self.focus = (Dom::from_ref(&*node), offset);
+    }
+}
+
+/// Start editing `host` with the caret at the start of its content.
+#[allow(unrooted_must_root)]
+pub fn reset_selection(host: &HTMLElement) {
+    let content = EditableContent::collect(host);
+    *document_from_node(host).editor().borrow_mut() = Some(Editor::new(host, &content, 0));
+}
+
+/// Handle a `keydown` at an editing host as a text control does, making the edits it asks for
+/// to the host's content.
+#[allow(unrooted_must_root)]
+pub fn handle_keydown(host: &HTMLElement, event: &KeyboardEvent) {
+    let document = document_from_node(host);
+    let content = EditableContent::collect(host);
+    let reaction = {
+        let mut editor = document.editor().borrow_mut();
+        let is_host = editor.as_ref().map_or(false, |editor| &*editor.host == host);
+        if !is_host {
+            *editor = Some(Editor::new(host, &content, content.len()));
+        }
+        let editor = editor.as_mut().unwrap();
+        editor.sync(&content);
+        let reaction = editor.textinput.handle_keydown(event);
+        editor.store_selection(&content);
+        reaction
+    };
+    let event = event.upcast::<Event>();
+    match reaction {
+        KeyReaction::BeforeInput(edit) => {
+            perform_edit(host, edit);
+            event.mark_as_handled();
+        },
+        KeyReaction::CopyRequested => {
+            if let Some(editor) = document.editor().borrow_mut().as_mut() {
+                editor.textinput.copy_selection();
+            }
+            event.mark_as_handled();
+        },
+        KeyReaction::CutRequested => {
+            let copied = document.editor().borrow_mut().as_mut().map_or(false, |editor| {
+                editor.textinput.copy_selection()
+            });
+            if copied {
+                perform_edit(host, Edit::DeleteByCut);
+            }
+            event.mark_as_handled();
+        },
+        KeyReaction::RedrawSelection |
+        KeyReaction::ScrollCaretIntoView |
+        KeyReaction::SelectionCleared |
+        KeyReaction::Rejected => event.mark_as_handled(),
+        // TODO: pasting, and input methods, in editing hosts.
+        _ => (),
+    }
+}
+
+/// Make an edit to the content of an editing host, unless the page cancels the `beforeinput`
+/// event announcing it, and fire `input` if the content changed. Returns whether it did.
+#[allow(unrooted_must_root)]
+pub fn perform_edit(host: &HTMLElement, edit: Edit) -> bool {
+    let status = InputEvent::fire_for_edit(host.upcast(),
+                                           "beforeinput",
+                                           edit.input_type(),
+                                           edit.data().map(DOMString::from),
+                                           false,
+                                           None);
+    if status == EventStatus::Canceled {
+        return false;
+    }
+
+    // The page may have changed the content while handling `beforeinput`.
+    let document = document_from_node(host);
+    let content = EditableContent::collect(host);
+    let change = {
+        let mut editor = document.editor().borrow_mut();
+        let editor = match editor.as_mut() {
+            Some(editor) => editor,
+            None => return false,
+        };
+        if &*editor.host != host {
+            return false;
+        }
+        editor.sync(&content);
+        match editor.textinput.apply_edit(edit.clone()) {
+            KeyReaction::DispatchInput(Some(change)) => change,
+            _ => return false,
+        }
+    };
+    content.apply(&change);
+    if let Some(editor) = document.editor().borrow_mut().as_mut() {
+        editor.store_selection(&EditableContent::collect(host));
+    }
+    let data = edit.data().map(|_| change.new_text.clone());
+    InputEvent::fire_for_edit(host.upcast(), "input", edit.input_type(), data, false, None);
+    true
+}
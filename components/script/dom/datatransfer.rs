@@ -0,0 +1,157 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::cell::DomRefCell;
+use dom::bindings::codegen::Bindings::DataTransferBinding;
+use dom::bindings::codegen::Bindings::DataTransferBinding::DataTransferMethods;
+use dom::bindings::error::Fallible;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor};
+use std::cell::{Cell, RefMut};
+use std::rc::Rc;
+
+unsafe_no_jsmanaged_fields!(ClipboardContents);
+
+/// What script may do with the data of a `DataTransfer`.
+///
+/// https://html.spec.whatwg.org/multipage/#drag-data-store-mode
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum DataTransferMode {
+    ReadWrite,
+    ReadOnly,
+    Protected,
+}
+
+#[dom_struct]
+pub struct DataTransfer {
+    reflector_: Reflector,
+    mode: Cell<DataTransferMode>,
+    /// The string items of the drag data store, as format and data pairs in the order they
+    /// were added.
+    items: DomRefCell<Vec<(DOMString, DOMString)>>,
+    /// The contents the store was created with, which may be shared with the text control
+    /// they are pasted into. They only become the items once script reads or changes them,
+    /// so that huge contents aren't copied for nothing.
+    #[ignore_malloc_size_of = "Rc"]
+    initial_contents: DomRefCell<Option<Rc<ClipboardContents>>>,
+}
+
+impl DataTransfer {
+    fn new_inherited(mode: DataTransferMode, contents: Rc<ClipboardContents>) -> DataTransfer {
+        DataTransfer {
+            reflector_: Reflector::new(),
+            mode: Cell::new(mode),
+            items: DomRefCell::new(vec!()),
+            initial_contents: DomRefCell::new(Some(contents)),
+        }
+    }
+
+    pub fn new(window: &Window, mode: DataTransferMode, contents: Rc<ClipboardContents>) -> DomRoot<DataTransfer> {
+        reflect_dom_object(Box::new(DataTransfer::new_inherited(mode, contents)),
+                           window,
+                           DataTransferBinding::Wrap)
+    }
+
+    pub fn Constructor(window: &Window) -> Fallible<DomRoot<DataTransfer>> {
+        Ok(DataTransfer::new(window, DataTransferMode::ReadWrite, Rc::new(ClipboardContents::new())))
+    }
+
+    /// Set what script may do with the data. Contents that are protected can no longer be
+    /// read by script, so initial contents that it never read are let go of.
+    pub fn set_mode(&self, mode: DataTransferMode) {
+        self.mode.set(mode);
+        if mode == DataTransferMode::Protected {
+            self.initial_contents.borrow_mut().take();
+        }
+    }
+
+    /// The string items, made from the initial contents if they haven't been yet.
+    fn items(&self) -> RefMut<Vec<(DOMString, DOMString)>> {
+        let mut items = self.items.borrow_mut();
+        if let Some(contents) = self.initial_contents.borrow_mut().take() {
+            items.extend(contents.flavors().into_iter().map(|flavor| {
+                let data = contents.get(flavor).unwrap_or("");
+                (DOMString::from(flavor.mime_type()), DOMString::from(data))
+            }));
+        }
+        items
+    }
+
+    /// The data that can be put on the clipboard, in the flavors it supports.
+    pub fn clipboard_contents(&self) -> ClipboardContents {
+        let mut contents = ClipboardContents::new();
+        for &(ref format, ref data) in self.items().iter() {
+            if let Some(flavor) = ClipboardFlavor::from_mime_type(format) {
+                contents.set(flavor, String::from(data.clone()));
+            }
+        }
+        contents
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/#dom-datatransfer-getdata steps 3-5
+fn normalize_format(format: &str) -> DOMString {
+    let format = format.to_ascii_lowercase();
+    match &*format {
+        "text" => DOMString::from("text/plain"),
+        "url" => DOMString::from("text/uri-list"),
+        _ => DOMString::from(format),
+    }
+}
+
+impl DataTransferMethods for DataTransfer {
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-getdata
+    fn GetData(&self, format: DOMString) -> DOMString {
+        // Step 2
+        if self.mode.get() == DataTransferMode::Protected {
+            return DOMString::new();
+        }
+
+        // Steps 3-5
+        let format = normalize_format(&format);
+
+        // Steps 6-9
+        // TODO: only return the first URL of a text/uri-list when the format was "url".
+        self.items().iter()
+            .find(|&&(ref f, _)| *f == format)
+            .map_or_else(DOMString::new, |&(_, ref data)| data.clone())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-setdata
+    fn SetData(&self, format: DOMString, data: DOMString) {
+        // Step 2
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+
+        // Steps 3-5
+        let format = normalize_format(&format);
+
+        // Steps 6-7
+        let mut items = self.items();
+        items.retain(|&(ref f, _)| *f != format);
+        items.push((format, data));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-datatransfer-cleardata
+    fn ClearData(&self, format: Option<DOMString>) {
+        // Step 2
+        if self.mode.get() != DataTransferMode::ReadWrite {
+            return;
+        }
+
+        // Steps 3-4
+        match format {
+            Some(format) => {
+                let format = normalize_format(&format);
+                self.items().retain(|&(ref f, _)| *f != format);
+            },
+            None => self.items().clear(),
+        }
+    }
+}
@@ -30,6 +30,7 @@ use dom::bindings::xmlname::{namespace_from_domstring, validate_and_extract, xml
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
 use dom::closeevent::CloseEvent;
 use dom::comment::Comment;
+use dom::contenteditable::{self, Editor};
 use dom::cssstylesheet::CSSStyleSheet;
 use dom::customelementregistry::CustomElementDefinition;
 use dom::customevent::CustomEvent;
@@ -56,8 +57,10 @@ use dom::htmlheadelement::HTMLHeadElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmliframeelement::HTMLIFrameElement;
 use dom::htmlimageelement::HTMLImageElement;
+use dom::htmlinputelement::HTMLInputElement;
 use dom::htmlmetaelement::HTMLMetaElement;
 use dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
+use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::htmltitleelement::HTMLTitleElement;
 use dom::keyboardevent::KeyboardEvent;
 use dom::location::Location;
@@ -73,10 +76,12 @@ use dom::processinginstruction::ProcessingInstruction;
 use dom::progressevent::ProgressEvent;
 use dom::promise::Promise;
 use dom::range::Range;
+use dom::selection::Selection;
 use dom::servoparser::ServoParser;
 use dom::storageevent::StorageEvent;
 use dom::stylesheetlist::StyleSheetList;
 use dom::text::Text;
+use dom::textcontrol::{ExecCommand, TextControlElement};
 use dom::touch::Touch;
 use dom::touchevent::TouchEvent;
 use dom::touchlist::TouchList;
@@ -98,7 +103,8 @@ use js::jsapi::{JSContext, JSObject, JSRuntime};
 use js::jsapi::JS_GetRuntime;
 use metrics::{InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory, ProgressiveWebMetric};
 use mime::{Mime, TopLevel, SubLevel};
-use msg::constellation_msg::{BrowsingContextId, Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{BrowsingContextId, ImeEvent, ImeSurroundingText, Key, KeyModifiers, KeyState};
+use msg::constellation_msg::TextInputHints;
 use net_traits::{FetchResponseMsg, IpcSend, ReferrerPolicy};
 use net_traits::CookieSource::NonHTTP;
 use net_traits::CoreResourceMsg::{GetCookiesForUrl, SetCookiesForUrl};
@@ -269,6 +275,12 @@ pub struct Document {
     possibly_focused: MutNullableDom<Element>,
     /// The element that currently has the document focus context.
     focused: MutNullableDom<Element>,
+    /// The editing state of the editing host that was last edited in.
+    editor: DomRefCell<Option<Editor>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: MutNullableDom<Selection>,
+    /// <https://html.spec.whatwg.org/multipage/#designMode>
+    design_mode: Cell<bool>,
     /// The script element that is currently executing.
     current_script: MutNullableDom<HTMLScriptElement>,
     /// <https://html.spec.whatwg.org/multipage/#pending-parsing-blocking-script>
@@ -777,6 +789,16 @@ impl Document {
         self.focused.get()
     }
 
+    /// The editing state of the editing host that was last edited in.
+    pub fn editor(&self) -> &DomRefCell<Option<Editor>> {
+        &self.editor
+    }
+
+    /// Whether the whole document is editable.
+    pub fn design_mode(&self) -> bool {
+        self.design_mode.get()
+    }
+
     /// Initiate a new round of checking for elements requesting focus. The last element to call
     /// `request_focus` before `commit_focus_transaction` is called will receive focus.
     pub fn begin_focus_transaction(&self) {
@@ -796,7 +818,14 @@ impl Document {
         if self.focused == self.possibly_focused.get().r() {
             return
         }
+        let blurred_hints = self.focused.get().and_then(|elem| text_input_hints(&elem));
         if let Some(ref elem) = self.focused.get() {
+            commit_pending_composition(elem);
+            finish_spelling_check(elem);
+            if let Some(input) = elem.downcast::<HTMLInputElement>() {
+                input.end_password_entry();
+                input.hide_suggestions();
+            }
             let node = elem.upcast::<Node>();
             elem.set_focus_state(false);
             // FIXME: pass appropriate relatedTarget
@@ -816,6 +845,24 @@ impl Document {
                 self.send_to_constellation(ScriptMsg::Focus);
             }
         }
+
+        let focused_hints = self.focused.get().and_then(|elem| text_input_hints(&elem));
+        if focused_hints.is_some() || blurred_hints.is_some() {
+            self.send_to_constellation(ScriptMsg::SetTextInputHints(focused_hints));
+        }
+    }
+
+    /// Ask the embedder to show its virtual keyboard for the focused text control, if there is
+    /// one to type into.
+    pub fn show_virtual_keyboard(&self) {
+        if let Some(hints) = self.focused.get().and_then(|elem| text_input_hints(&elem)) {
+            self.send_to_constellation(ScriptMsg::SetTextInputHints(Some(hints)));
+        }
+    }
+
+    /// Ask the embedder to hide its virtual keyboard.
+    pub fn hide_virtual_keyboard(&self) {
+        self.send_to_constellation(ScriptMsg::SetTextInputHints(None));
     }
 
     /// Handles any updates when the document's title has changed.
@@ -909,6 +956,8 @@ impl Document {
         match mouse_event_type {
             MouseEventType::Click => el.authentic_click_activation(event),
             MouseEventType::MouseDown => {
+                // https://html.spec.whatwg.org/multipage/#activation-triggering-input-event
+                self.window.notify_activation();
                 if let Some(a) = activatable {
                     a.enter_formal_activation_state();
                 }
@@ -1121,6 +1170,11 @@ impl Document {
             TouchEventType::Cancel => "touchcancel",
         };
 
+        // https://html.spec.whatwg.org/multipage/#activation-triggering-input-event
+        if let TouchEventType::Up = event_type {
+            self.window.notify_activation();
+        }
+
         let el = node_address.and_then(|address| {
             let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
             node.inclusive_ancestors()
@@ -1230,8 +1284,13 @@ impl Document {
         let shift = modifiers.contains(KeyModifiers::SHIFT);
         let meta = modifiers.contains(KeyModifiers::SUPER);
 
-        let is_composing = false;
+        let is_composing = focused.as_ref().map_or(false, |focused| is_composing(focused));
         let is_repeating = state == KeyState::Repeated;
+        // https://html.spec.whatwg.org/multipage/#activation-triggering-input-event
+        if state == KeyState::Pressed && key != Key::Escape {
+            self.window.notify_activation();
+        }
+
         let ev_type = DOMString::from(match state {
                                           KeyState::Pressed | KeyState::Repeated => "keydown",
                                           KeyState::Released => "keyup",
@@ -1326,6 +1385,62 @@ impl Document {
         self.window.reflow(ReflowGoal::Full, ReflowReason::KeyEvent);
     }
 
+    /// The entry point for updates from the input method editor, which go to the focused text
+    /// control.
+    pub fn dispatch_ime_event(&self, event: ImeEvent) {
+        let focused = match self.get_focused_element() {
+            Some(focused) => focused,
+            None => return,
+        };
+        if let Some(input) = focused.downcast::<HTMLInputElement>() {
+            input.handle_ime_event(event);
+        } else if let Some(textarea) = focused.downcast::<HTMLTextAreaElement>() {
+            textarea.handle_ime_event(event);
+        } else {
+            return;
+        }
+        self.window.reflow(ReflowGoal::Full, ReflowReason::ImeEvent);
+    }
+
+    /// Show or hide the value of the focused password field, if any.
+    pub fn reveal_password(&self, revealed: bool) {
+        if let Some(input) = self.get_focused_element().and_then(DomRoot::downcast::<HTMLInputElement>) {
+            input.set_password_revealed(revealed);
+        }
+    }
+
+    /// Fill in the value of the focused text control, if any, on the user's behalf.
+    pub fn autofill(&self, value: DOMString) {
+        let focused = match self.get_focused_element() {
+            Some(focused) => focused,
+            None => return,
+        };
+        if let Some(input) = focused.downcast::<HTMLInputElement>() {
+            input.autofill(value);
+        } else if let Some(textarea) = focused.downcast::<HTMLTextAreaElement>() {
+            textarea.autofill(value);
+        }
+    }
+
+    /// Commit the suggestion that the user picked for the focused input, if any.
+    pub fn choose_suggestion(&self, value: DOMString) {
+        if let Some(input) = self.get_focused_element().and_then(DomRoot::downcast::<HTMLInputElement>) {
+            input.choose_suggestion(value);
+        }
+    }
+
+    /// The text around the caret of the focused text control, if there is one.
+    pub fn ime_surrounding_text(&self, before: usize, after: usize) -> Option<ImeSurroundingText> {
+        let focused = self.get_focused_element()?;
+        if let Some(input) = focused.downcast::<HTMLInputElement>() {
+            input.ime_surrounding_text(before, after)
+        } else if let Some(textarea) = focused.downcast::<HTMLTextAreaElement>() {
+            textarea.ime_surrounding_text(before, after)
+        } else {
+            None
+        }
+    }
+
     // https://dom.spec.whatwg.org/#converting-nodes-into-a-node
     pub fn node_from_nodes_and_strings(&self,
                                        mut nodes: Vec<NodeOrString>)
@@ -2083,6 +2198,55 @@ fn get_registrable_domain_suffix_of_or_is_equal_to(host_suffix_string: &str, ori
     Some(host)
 }
 
+/// Whether an input method is composing text in the given element.
+fn is_composing(element: &Element) -> bool {
+    if let Some(input) = element.downcast::<HTMLInputElement>() {
+        input.is_composing()
+    } else if let Some(textarea) = element.downcast::<HTMLTextAreaElement>() {
+        textarea.is_composing()
+    } else {
+        false
+    }
+}
+
+/// Commit the text an input method is composing in the given element, if any.
+fn commit_pending_composition(element: &Element) {
+    if let Some(input) = element.downcast::<HTMLInputElement>() {
+        input.commit_pending_composition();
+    } else if let Some(textarea) = element.downcast::<HTMLTextAreaElement>() {
+        textarea.commit_pending_composition();
+    }
+}
+
+/// Check the spelling of the word the user was typing in the given element, if it is a text
+/// control.
+fn finish_spelling_check(element: &Element) {
+    if let Some(input) = element.downcast::<HTMLInputElement>() {
+        input.finish_spelling_check();
+    } else if let Some(textarea) = element.downcast::<HTMLTextAreaElement>() {
+        textarea.finish_spelling_check();
+    }
+}
+
+/// The kind of virtual keyboard suited to the given element, if it is a text control.
+fn text_input_hints(element: &Element) -> Option<TextInputHints> {
+    if let Some(input) = element.downcast::<HTMLInputElement>() {
+        text_control_input_hints(input)
+    } else if let Some(textarea) = element.downcast::<HTMLTextAreaElement>() {
+        text_control_input_hints(textarea)
+    } else {
+        None
+    }
+}
+
+fn text_control_input_hints<E: TextControlElement>(control: &E) -> Option<TextInputHints> {
+    control.input_mode().map(|input_mode| TextInputHints {
+        input_mode: input_mode,
+        enter_key_hint: control.enter_key_hint(),
+        autofill: control.autofill_hint(),
+    })
+}
+
 /// <https://url.spec.whatwg.org/#network-scheme>
 fn url_has_network_scheme(url: &ServoUrl) -> bool {
     match url.scheme() {
@@ -2176,6 +2340,9 @@ impl Document {
             domcontentloaded_dispatched: Cell::new(domcontentloaded_dispatched),
             possibly_focused: Default::default(),
             focused: Default::default(),
+            editor: DomRefCell::new(None),
+            selection: MutNullableDom::new(None),
+            design_mode: Cell::new(false),
             current_script: Default::default(),
             pending_parsing_blocking_script: Default::default(),
             script_blocking_stylesheets_count: Cell::new(0u32),
@@ -2700,6 +2867,15 @@ impl DocumentMethods for Document {
         }
     }
 
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        if self.has_browsing_context {
+            Some(self.selection.or_init(|| Selection::new(self)))
+        } else {
+            None
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-hasfocus
     fn HasFocus(&self) -> bool {
         // Step 1-2.
@@ -2710,6 +2886,76 @@ impl DocumentMethods for Document {
         false
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn DesignMode(&self) -> DOMString {
+        DOMString::from(if self.design_mode.get() { "on" } else { "off" })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn SetDesignMode(&self, value: DOMString) {
+        match &*value.to_ascii_lowercase() {
+            // Step 1.
+            "on" if !self.design_mode.get() => {
+                self.design_mode.set(true);
+                // Put the caret at the start of the document.
+                match self.GetBody() {
+                    Some(body) => contenteditable::reset_selection(&body),
+                    None => *self.editor.borrow_mut() = None,
+                }
+            },
+            // Step 2.
+            "off" if self.design_mode.get() => {
+                self.design_mode.set(false);
+                *self.editor.borrow_mut() = None;
+            },
+            _ => (),
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    fn ExecCommand(&self, command_id: DOMString, _show_ui: bool, value: DOMString) -> bool {
+        let command = match ExecCommand::from_command_id(&command_id) {
+            Some(command) => command,
+            None => return false,
+        };
+        // Only text controls are editable so far.
+        let focused = match self.get_focused_element() {
+            Some(focused) => focused,
+            None => return false,
+        };
+        if let Some(input) = focused.downcast::<HTMLInputElement>() {
+            input.exec_command(command, value)
+        } else if let Some(textarea) = focused.downcast::<HTMLTextAreaElement>() {
+            textarea.exec_command(command, value)
+        } else {
+            false
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()
+    fn QueryCommandEnabled(&self, command_id: DOMString) -> bool {
+        let command = match ExecCommand::from_command_id(&command_id) {
+            Some(command) => command,
+            None => return false,
+        };
+        let focused = match self.get_focused_element() {
+            Some(focused) => focused,
+            None => return false,
+        };
+        if let Some(input) = focused.downcast::<HTMLInputElement>() {
+            input.exec_command_enabled(command)
+        } else if let Some(textarea) = focused.downcast::<HTMLTextAreaElement>() {
+            textarea.exec_command_enabled(command)
+        } else {
+            false
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()
+    fn QueryCommandSupported(&self, command_id: DOMString) -> bool {
+        ExecCommand::from_command_id(&command_id).is_some()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-domain
     fn Domain(&self) -> DOMString {
         // Step 1.
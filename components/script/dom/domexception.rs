@@ -35,6 +35,8 @@ pub enum DOMErrorName {
     TimeoutError = DOMExceptionConstants::TIMEOUT_ERR,
     InvalidNodeTypeError = DOMExceptionConstants::INVALID_NODE_TYPE_ERR,
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
+    // Names introduced after the legacy codes were frozen all have code 0.
+    NotAllowedError = 0,
 }
 
 #[dom_struct]
@@ -94,6 +96,8 @@ impl DOMExceptionMethods for DOMException {
             DOMErrorName::InvalidNodeTypeError =>
                 "The supplied node is incorrect or has an incorrect ancestor for this operation.",
             DOMErrorName::DataCloneError => "The object can not be cloned.",
+            DOMErrorName::NotAllowedError =>
+                "The request is not allowed by the user agent or the platform in the current context.",
         };
 
         DOMString::from(message)
@@ -29,6 +29,7 @@ use dom::bindings::str::DOMString;
 use dom::bindings::xmlname::{namespace_from_domstring, validate_and_extract, xml_name_type};
 use dom::bindings::xmlname::XMLName::InvalidXMLName;
 use dom::characterdata::CharacterData;
+use dom::contenteditable::is_editing_host;
 use dom::create::create_element;
 use dom::customelementregistry::{CallbackReaction, CustomElementDefinition, CustomElementReaction};
 use dom::document::{Document, LayoutDocumentHelpers};
@@ -1104,7 +1105,7 @@ impl Element {
             NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLTextAreaElement)) => {
                 true
             }
-            _ => false,
+            _ => self.downcast::<HTMLElement>().map_or(false, is_editing_host),
         }
     }
 
@@ -2687,6 +2688,7 @@ impl<'a> SelectorsElement for DomRoot<Element> {
             NonTSPseudoClass::Indeterminate |
             NonTSPseudoClass::ReadWrite |
             NonTSPseudoClass::PlaceholderShown |
+            NonTSPseudoClass::Autofill |
             NonTSPseudoClass::Target =>
                 Element::state(self).contains(pseudo_class.state_flag()),
         }
@@ -2967,6 +2969,17 @@ impl Element {
         }
     }
 
+    pub fn autofill_state(&self) -> bool {
+        self.state.get().contains(ElementState::IN_AUTOFILL_STATE)
+    }
+
+    pub fn set_autofill_state(&self, value: bool) {
+        if self.autofill_state() != value {
+            self.set_state(ElementState::IN_AUTOFILL_STATE, value);
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
     pub fn target_state(&self) -> bool {
         self.state.get().contains(ElementState::IN_TARGET_STATE)
     }
@@ -174,6 +174,11 @@ impl HTMLButtonElement {
             value: FormDatumValue::String(self.Value())
         })
     }
+
+    /// <https://html.spec.whatwg.org/multipage/#concept-submit-button>
+    pub fn is_submit_button(&self) -> bool {
+        self.button_type.get() == ButtonType::Submit
+    }
 }
 
 impl VirtualMethods for HTMLButtonElement {
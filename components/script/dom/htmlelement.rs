@@ -5,6 +5,7 @@
 use dom::activation::{ActivationSource, synthetic_click_activation};
 use dom::attr::Attr;
 use dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use dom::bindings::codegen::Bindings::HTMLElementBinding;
 use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
@@ -15,11 +16,13 @@ use dom::bindings::inheritance::{ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use dom::bindings::inheritance::Castable;
 use dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::contenteditable::{self, content_editable_state, editing_host, is_editing_host};
 use dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration, CSSStyleOwner};
 use dom::document::{Document, FocusType};
 use dom::documentfragment::DocumentFragment;
 use dom::domstringmap::DOMStringMap;
 use dom::element::{AttributeMutation, Element};
+use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::htmlbodyelement::HTMLBodyElement;
 use dom::htmlbrelement::HTMLBRElement;
@@ -27,6 +30,8 @@ use dom::htmlframesetelement::HTMLFrameSetElement;
 use dom::htmlhtmlelement::HTMLHtmlElement;
 use dom::htmlinputelement::{HTMLInputElement, InputType};
 use dom::htmllabelelement::HTMLLabelElement;
+use dom::htmltextareaelement::HTMLTextAreaElement;
+use dom::keyboardevent::KeyboardEvent;
 use dom::node::{Node, NodeFlags};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
@@ -107,7 +112,9 @@ impl HTMLElement {
                     } else {
                         node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, false);
                     }
-                    //TODO set SEQUENTIALLY_FOCUSABLE flag if editing host
+                    if is_editing_host(self) {
+                        node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, true);
+                    }
                     //TODO set SEQUENTIALLY_FOCUSABLE flag if "sorting interface th elements"
                 },
             }
@@ -350,6 +357,41 @@ impl HTMLElementMethods for HTMLElement {
         document.commit_focus_transaction(FocusType::Element);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn ContentEditable(&self) -> DOMString {
+        DOMString::from(content_editable_state(self.upcast()).as_str())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn SetContentEditable(&self, value: DOMString) -> ErrorResult {
+        let element = self.upcast::<Element>();
+        if value.eq_ignore_ascii_case("inherit") {
+            element.remove_attribute(&ns!(), &local_name!("contenteditable"));
+        } else if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+            let value = DOMString::from(value.to_ascii_lowercase());
+            element.set_string_attribute(&local_name!("contenteditable"), value);
+        } else {
+            return Err(Error::Syntax);
+        }
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-iscontenteditable
+    fn IsContentEditable(&self) -> bool {
+        editing_host(self.upcast()).is_some()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-spellcheck
+    fn Spellcheck(&self) -> bool {
+        self.spellcheck_enabled()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-spellcheck
+    fn SetSpellcheck(&self, value: bool) {
+        let value = DOMString::from(if value { "true" } else { "false" });
+        self.upcast::<Element>().set_string_attribute(&local_name!("spellcheck"), value);
+    }
+
     // https://drafts.csswg.org/cssom-view/#dom-htmlelement-offsetparent
     fn GetOffsetParent(&self) -> Option<DomRoot<Element>> {
         if self.is::<HTMLBodyElement>() || self.is::<HTMLHtmlElement>() {
@@ -547,6 +589,27 @@ fn to_camel_case(name: &str) -> Option<DOMString> {
 }
 
 impl HTMLElement {
+    // https://html.spec.whatwg.org/multipage/#concept-spellcheck-default
+    pub fn spellcheck_enabled(&self) -> bool {
+        for node in self.upcast::<Node>().inclusive_ancestors() {
+            let attr = match node.downcast::<Element>() {
+                Some(element) => element.get_attribute(&ns!(), &local_name!("spellcheck")),
+                None => continue,
+            };
+            if let Some(attr) = attr {
+                let value = attr.value();
+                if value.is_empty() || value.eq_ignore_ascii_case("true") {
+                    return true;
+                }
+                if value.eq_ignore_ascii_case("false") {
+                    return false;
+                }
+            }
+        }
+        // The default behaviour is for elements to be checked.
+        true
+    }
+
     pub fn set_custom_attr(&self, name: DOMString, value: DOMString) -> ErrorResult {
         if name.chars()
                .skip_while(|&ch| ch != '\u{2d}')
@@ -679,6 +742,7 @@ impl VirtualMethods for HTMLElement {
                                                       // FIXME(ajeffrey): Convert directly from AttrValue to DOMString
                                                       DOMString::from(&**attr.value()));
             },
+            (&local_name!("contenteditable"), _) => self.update_sequentially_focusable_status(),
             _ => {}
         }
     }
@@ -702,4 +766,26 @@ impl VirtualMethods for HTMLElement {
             },
         }
     }
+
+    fn handle_event(&self, event: &Event) {
+        if let Some(s) = self.super_type() {
+            s.handle_event(event);
+        }
+        // Text controls do their own editing, and are not part of an editing host's content.
+        if event.DefaultPrevented() || self.is::<HTMLInputElement>() || self.is::<HTMLTextAreaElement>() {
+            return;
+        }
+
+        // In design mode, keys go to the body when nothing is focused.
+        if event.type_() == atom!("keydown") {
+            let host = editing_host(self.upcast());
+            if let (Some(host), Some(event)) = (host, event.downcast::<KeyboardEvent>()) {
+                contenteditable::handle_keydown(&host, event);
+            }
+        } else if event.type_() == atom!("click") {
+            if let Some(host) = editing_host(self.upcast()) {
+                document_from_node(self).request_focus(host.upcast());
+            }
+        }
+    }
 }
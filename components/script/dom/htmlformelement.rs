@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use dom::activation::{ActivationSource, synthetic_click_activation};
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::BlobBinding::BlobMethods;
 use dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
@@ -39,6 +40,7 @@ use dom::htmlselectelement::HTMLSelectElement;
 use dom::htmltextareaelement::HTMLTextAreaElement;
 use dom::node::{Node, NodeFlags, UnbindContext, VecPreOrderInsertionHelper};
 use dom::node::{document_from_node, window_from_node};
+use dom::validation::report_constraint_problems;
 use dom::validitystate::ValidationFlags;
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
@@ -459,12 +461,17 @@ impl HTMLFormElement {
     /// <https://html.spec.whatwg.org/multipage/#interactively-validate-the-constraints>
     fn interactive_validation(&self) -> Result<(), ()> {
         // Step 1-3
-        let _unhandled_invalid_controls = match self.static_validation() {
+        let unhandled_invalid_controls = match self.static_validation() {
             Ok(()) => return Ok(()),
             Err(err) => err
         };
-        // TODO: Report the problems with the constraints of at least one of
-        //       the elements given in unhandled invalid controls to the user
+        // Report the problems with the constraints of at least one of
+        // the elements given in unhandled invalid controls to the user
+        let first_invalid = unhandled_invalid_controls.first()
+            .and_then(|field| field.as_event_target().downcast::<Element>());
+        if let Some(validatable) = first_invalid.and_then(|el| el.as_maybe_validatable()) {
+            report_constraint_problems(validatable);
+        }
         // Step 4
         Err(())
     }
@@ -555,8 +562,16 @@ impl HTMLFormElement {
                             data_set.push(FormDatum {
                                 ty: textarea.Type(),
                                 name: name,
-                                value: FormDatumValue::String(textarea.Value())
+                                value: FormDatumValue::String(textarea.value())
                             });
+                            let dirname = textarea.DirName();
+                            if !dirname.is_empty() {
+                                data_set.push(FormDatum {
+                                    ty: textarea.Type(),
+                                    name: dirname,
+                                    value: FormDatumValue::String(DOMString::from(textarea.directionality())),
+                                });
+                            }
                         }
                     }
                     _ => ()
@@ -564,8 +579,6 @@ impl HTMLFormElement {
             }
         }
         data_set
-        // TODO: Handle `dirnames` (needs directionality support)
-        //       https://html.spec.whatwg.org/multipage/#the-directionality
     }
 
     /// <https://html.spec.whatwg.org/multipage/#constructing-the-form-data-set>
@@ -606,7 +619,11 @@ impl HTMLFormElement {
         // Step 4
         for datum in &mut ret {
             match &*datum.ty {
-                "file" | "textarea" => (), // TODO
+                "file" => (), // TODO
+                "textarea" => {
+                    // The value already has CRLF line breaks.
+                    datum.name = clean_crlf(&datum.name);
+                },
                 _ => {
                     datum.name = clean_crlf(&datum.name);
                     datum.value = FormDatumValue::String(clean_crlf(match datum.value {
@@ -620,6 +637,39 @@ impl HTMLFormElement {
         ret
     }
 
+    /// Click the default button of this form, as submitting it implicitly does, unless it is
+    /// disabled. Returns whether the form has a default button, which is the first submit
+    /// button of the form in tree order, whether it is an input or a button element.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#implicit-submission
+    pub fn activate_default_button(&self, ctrl_key: bool, shift_key: bool, alt_key: bool, meta_key: bool) -> bool {
+        let doc = document_from_node(self);
+        let default_button = doc.upcast::<Node>().query_selector_iter(DOMString::from("input, button")).unwrap()
+            .filter_map(DomRoot::downcast::<Element>)
+            .find(|element| {
+                if let Some(input) = element.downcast::<HTMLInputElement>() {
+                    input.form_owner().r() == Some(self) && match input.input_type() {
+                        InputType::Submit | InputType::Image => true,
+                        _ => false
+                    }
+                } else if let Some(button) = element.downcast::<HTMLButtonElement>() {
+                    button.form_owner().r() == Some(self) && button.is_submit_button()
+                } else {
+                    false
+                }
+            });
+        let button = match default_button {
+            Some(button) => button,
+            None => return false,
+        };
+        // A default button that is disabled, or that does nothing when clicked, keeps the form
+        // from being submitted at all.
+        if button.as_maybe_activatable().map_or(false, |a| a.is_instance_activatable()) {
+            synthetic_click_activation(&button, ctrl_key, shift_key, alt_key, meta_key, ActivationSource::NotFromClick);
+        }
+        true
+    }
+
     pub fn reset(&self, _reset_method_flag: ResetFrom) {
         // https://html.spec.whatwg.org/multipage/#locked-for-reset
         if self.marked_for_reset.get() {
@@ -651,7 +701,7 @@ impl HTMLFormElement {
                     child.downcast::<HTMLSelectElement>().unwrap().reset();
                 }
                 NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLTextAreaElement)) => {
-                    child.downcast::<HTMLTextAreaElement>().unwrap().reset(true);
+                    child.downcast::<HTMLTextAreaElement>().unwrap().reset();
                 }
                 NodeTypeId::Element(ElementTypeId::HTMLElement(HTMLElementTypeId::HTMLOutputElement)) => {
                     // Unimplemented
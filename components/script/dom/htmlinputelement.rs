@@ -3,7 +3,8 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 use caseless::compatibility_caseless_match_str;
-use dom::activation::{Activatable, ActivationSource, synthetic_click_activation};
+use clipboard_provider::{TextControlClipboard, text_control_clipboard};
+use dom::activation::Activatable;
 use dom::attr::Attr;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
@@ -11,55 +12,81 @@ use dom::bindings::codegen::Bindings::FileListBinding::FileListMethods;
 use dom::bindings::codegen::Bindings::HTMLFormElementBinding::SelectionMode;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding;
 use dom::bindings::codegen::Bindings::HTMLInputElementBinding::HTMLInputElementMethods;
+use dom::bindings::codegen::Bindings::HTMLOptionElementBinding::HTMLOptionElementMethods;
 use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
+use dom::bindings::codegen::Bindings::MouseEventBinding::MouseEventMethods;
 use dom::bindings::error::{Error, ErrorResult};
 use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
 use dom::bindings::root::{Dom, DomRoot, LayoutDom, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element, LayoutElementHelpers, RawLayoutElementHelpers};
-use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::event::Event;
 use dom::eventtarget::EventTarget;
 use dom::file::File;
 use dom::filelist::FileList;
 use dom::globalscope::GlobalScope;
+use dom::htmldatalistelement::HTMLDataListElement;
 use dom::htmlelement::HTMLElement;
 use dom::htmlfieldsetelement::HTMLFieldSetElement;
 use dom::htmlformelement::{FormControl, FormDatum, FormDatumValue, FormSubmitter, HTMLFormElement};
 use dom::htmlformelement::{ResetFrom, SubmittedFrom};
+use dom::htmloptionelement::HTMLOptionElement;
 use dom::keyboardevent::KeyboardEvent;
 use dom::mouseevent::MouseEvent;
 use dom::node::{Node, NodeDamage, UnbindContext};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
-use dom::textcontrol::{TextControlElement, TextControlSelection};
-use dom::validation::Validatable;
-use dom::validitystate::ValidationFlags;
+use dom::textcontrol::TextControlElement;
+use dom::textcontrol::text_control_directionality;
+use dom::validation::{VALUE_MISSING_MESSAGE, Validatable, check_validity, report_validity};
+use dom::validitystate::{ValidationFlags, ValidityState};
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use input_pattern::CompiledPattern;
 use ipc_channel::ipc::channel;
+use ipc_channel::router::ROUTER;
+use js::jsapi::{ClippedTime, DateGetMsecSinceEpoch, JSContext, JSObject, JS_ObjectIsDate, NewDateObject};
 use mime_guess;
+use msg::constellation_msg::{KeyModifiers, Suggestion};
+use msg::constellation_msg::{InputMode, Key};
 use net_traits::{CoreResourceMsg, IpcSend};
 use net_traits::blob_url_store::get_blob_origin;
 use net_traits::filemanager_thread::{FileManagerThreadMsg, FilterPattern};
 use script_layout_interface::rpc::TextIndexResponse;
-use script_traits::ScriptToConstellationChan;
+use script_traits::ScriptMsg;
+use segmented_input::{EMPTY_DATE, EMPTY_MONTH, EMPTY_WEEK, SegmentReaction, SegmentedInput, TimePrecision};
+use segmented_input::{date_from_milliseconds, date_in_milliseconds, local_date_and_time_display};
+use segmented_input::{local_date_and_time_from_milliseconds, local_date_and_time_in_milliseconds};
+use segmented_input::{local_date_and_time_value, month_from_months, month_in_months, time_display};
+use segmented_input::{time_from_milliseconds, time_in_milliseconds, time_value, week_from_milliseconds};
+use segmented_input::week_in_milliseconds;
 use servo_atoms::Atom;
+use servo_config::prefs::PREFS;
+use servo_url::ServoUrl;
 use std::borrow::ToOwned;
 use std::cell::Cell;
+use std::f64;
+use std::i32;
 use std::mem;
 use std::ops::Range;
+use std::ptr::NonNull;
 use style::attr::AttrValue;
 use style::element_state::ElementState;
 use style::str::split_commas;
-use textinput::{Direction, SelectionDirection, TextInput};
-use textinput::KeyReaction::{DispatchInput, Nothing, RedrawSelection, TriggerDefaultAction};
+use task_source::TaskSource;
+use textinput::{CharFilter, Direction, EscapeOutcome, KeyHook, KeyReaction, PasteSanitization, SelectionDirection};
+use textinput::{TextDirection, TextInput};
+use textinput::KeyReaction::{BeforeInput, CompositionCancelled, CopyRequested, CutRequested, DispatchInput};
+use textinput::KeyReaction::{Escape, FocusNavigation, ImplicitSubmission, Nothing, PasteRequested, RedrawSelection};
+use textinput::KeyReaction::{Rejected, ScrollCaretIntoView, SelectionCleared, StepRequested, TriggerDefaultAction};
 use textinput::Lines::Single;
+use time;
 
 const DEFAULT_SUBMIT_VALUE: &'static str = "Submit";
 const DEFAULT_RESET_VALUE: &'static str = "Reset";
-const PASSWORD_REPLACEMENT_CHAR: char = '●';
 
 #[derive(Clone, Copy, JSTraceable, PartialEq)]
 #[allow(dead_code)]
@@ -111,6 +138,105 @@ impl InputType {
         self.is_textual() || *self == InputType::Password
     }
 
+    // Whether an input of this type takes its suggestions from the datalist of its `list`
+    // attribute.
+    // https://html.spec.whatwg.org/multipage/#attr-input-list
+    fn has_list(&self) -> bool {
+        match *self {
+            InputType::Hidden | InputType::Password | InputType::Checkbox | InputType::Radio
+            | InputType::File | InputType::Submit | InputType::Image | InputType::Reset
+            | InputType::Button => false,
+            _ => true,
+        }
+    }
+
+    // Whether an input of this type keeps a form without a submit button from being submitted
+    // implicitly, when the form has another field like it.
+    // https://html.spec.whatwg.org/multipage/#field-that-blocks-implicit-submission
+    fn blocks_implicit_submission(&self) -> bool {
+        match *self {
+            InputType::Text | InputType::Search | InputType::Url | InputType::Tel
+            | InputType::Email | InputType::Password | InputType::Date
+            | InputType::Month | InputType::Week | InputType::Time
+            | InputType::DatetimeLocal | InputType::Number => true,
+            _ => false,
+        }
+    }
+
+    // Whether the value of an input of this type is free text, which `maxlength` and
+    // `minlength` limit.
+    fn limits_length(&self) -> bool {
+        match *self {
+            InputType::Text | InputType::Search | InputType::Url | InputType::Tel
+            | InputType::Email | InputType::Password => true,
+            _ => false,
+        }
+    }
+
+    // Whether the value of an input of this type is a number, which `valueAsNumber` gets and
+    // sets.
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasnumber
+    fn has_value_as_number(&self) -> bool {
+        match *self {
+            InputType::Date | InputType::DatetimeLocal | InputType::Month | InputType::Number
+            | InputType::Range | InputType::Time | InputType::Week => true,
+            _ => false,
+        }
+    }
+
+    // Whether the value of an input of this type is a point in time, which `valueAsDate` gets
+    // and sets.
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasdate
+    fn has_value_as_date(&self) -> bool {
+        match *self {
+            InputType::Date | InputType::Month | InputType::Time | InputType::Week => true,
+            _ => false,
+        }
+    }
+
+    // Whether the value of an input of this type is edited a field at a time, with a
+    // `SegmentedInput`.
+    fn is_segmented(&self) -> bool {
+        match *self {
+            InputType::Date | InputType::DatetimeLocal | InputType::Month | InputType::Time
+            | InputType::Week => true,
+            _ => false,
+        }
+    }
+
+    // The filter restricting the characters the user can type or paste into an input of this
+    // type, shown as given, so that the value sanitization algorithm does not have to discard
+    // them afterwards.
+    fn char_filter(&self, display: ValueDisplay) -> Option<CharFilter> {
+        match *self {
+            InputType::Number if display == ValueDisplay::DecimalComma => Some(decimal_comma_number_char_filter),
+            InputType::Number => Some(number_char_filter),
+            InputType::Email => Some(email_char_filter),
+            InputType::Tel if PREFS.get("dom.input.tel.restrict_characters.enabled").as_boolean().unwrap_or(false) => {
+                Some(tel_char_filter)
+            }
+            _ => None,
+        }
+    }
+
+    // How text pasted into an input of this type is cleaned up. A URL is trimmed as it would be
+    // by the value sanitization algorithm, which is not run on the user's edits.
+    fn paste_sanitization(&self) -> PasteSanitization {
+        PasteSanitization {
+            strip_control_characters: true,
+            isolate_bidi_controls: true,
+            trim_whitespace: *self == InputType::Url,
+        }
+    }
+
+    // The handler for keys that an input of this type uses for something other than editing.
+    fn key_hook(&self) -> Option<KeyHook> {
+        match *self {
+            InputType::Number => Some(number_key_hook),
+            _ => None,
+        }
+    }
+
     fn to_str(&self) -> &str {
         match *self {
             InputType::Button => "button",
@@ -139,6 +265,469 @@ impl InputType {
     }
 }
 
+// https://html.spec.whatwg.org/multipage/#valid-floating-point-number
+fn number_char_filter(c: char) -> Option<char> {
+    match c {
+        '0'...'9' | '.' | '-' | '+' | 'e' | 'E' => Some(c),
+        // Full-width digits, as typed by some input methods.
+        '\u{FF10}'...'\u{FF19}' => ::std::char::from_u32(c as u32 - 0xFF10 + '0' as u32),
+        _ => None,
+    }
+}
+
+/// Whether Caps Lock is on, going by the case of a letter typed with or without Shift, or
+/// `None` if the key typed no letter.
+pub fn caps_lock_state(printable: Option<char>, shift: bool) -> Option<bool> {
+    let c = printable?;
+    if c.is_uppercase() {
+        Some(!shift)
+    } else if c.is_lowercase() && c.to_uppercase().next() != Some(c) {
+        Some(shift)
+    } else {
+        None
+    }
+}
+
+/// The options of a datalist that match what the user typed: those whose value or label
+/// contains it, ignoring case, other than one whose value the user typed in full. Nothing typed
+/// matches every option.
+pub fn matching_suggestions(options: Vec<Suggestion>, typed: &str) -> Vec<Suggestion> {
+    let typed = typed.to_lowercase();
+    options.into_iter().filter(|option| {
+        let value = option.value.to_lowercase();
+        value != typed && (value.contains(&typed) || option.label.to_lowercase().contains(&typed))
+    }).collect()
+}
+
+/// The characters of a telephone number, for embedders that want `<input type=tel>` to
+/// reject anything else. The value itself has no syntax, so this is opt-in.
+pub fn tel_char_filter(c: char) -> Option<char> {
+    match c {
+        '0'...'9' | '+' | '-' | '(' | ')' | '.' | ' ' | '*' | '#' => Some(c),
+        // Full-width digits, as typed by some input methods.
+        '\u{FF10}'...'\u{FF19}' => ::std::char::from_u32(c as u32 - 0xFF10 + '0' as u32),
+        _ => None,
+    }
+}
+
+// The up and down arrow keys step the value, ten steps at a time with Shift and a hundred
+// with Control, as there is no other line for the caret to move to.
+fn number_key_hook(key: Key, mods: KeyModifiers) -> Option<KeyReaction> {
+    let direction = match key {
+        Key::Up => 1,
+        Key::Down => -1,
+        _ => return None,
+    };
+    let steps = if mods.contains(KeyModifiers::CONTROL) {
+        100
+    } else if mods.contains(KeyModifiers::SHIFT) {
+        10
+    } else {
+        1
+    };
+    Some(KeyReaction::StepRequested(direction * steps))
+}
+
+/// The number of steps that a key moves the thumb of a range control whose range is `span`
+/// wide, or `None` if the key is not one for moving the thumb. The arrow keys move it a step,
+/// Page Up and Page Down a tenth of the range, and Home and End to the ends of the range.
+///
+/// https://html.spec.whatwg.org/multipage/#range-state-(type=range)
+pub fn range_steps_for_key(key: Key, span: f64, step: f64) -> Option<i32> {
+    let page = (span / step / 10.0).round().max(1.0) as i32;
+    match key {
+        Key::Up | Key::Right => Some(1),
+        Key::Down | Key::Left => Some(-1),
+        Key::PageUp => Some(page),
+        Key::PageDown => Some(-page),
+        Key::End => Some(i32::MAX),
+        Key::Home => Some(-i32::MAX),
+        _ => None,
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/#rules-for-parsing-floating-point-number-values
+///
+/// Unlike `DOMString::is_valid_floating_point_number_string`, the number is not rounded.
+pub fn parse_number(value: &str) -> Option<f64> {
+    if value.ends_with('.') || value.starts_with('+') || value.contains(' ') {
+        return None;
+    }
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Some(number),
+        _ => None,
+    }
+}
+
+/// The number of digits after the decimal point in the shortest representation of `number`.
+fn decimal_places(number: f64) -> i32 {
+    let string = number.to_string();
+    string.find('.').map_or(0, |point| (string.len() - point - 1) as i32)
+}
+
+/// The value a number input is stepped to from `value` by the given number of steps of size
+/// `step` up, or down if `steps` is negative, or `None` if it stays where it is. The value
+/// stays a whole number of steps from `base`, and between `min` and `max`.
+///
+/// https://html.spec.whatwg.org/multipage/#dom-input-stepup
+pub fn step_number(value: f64, base: f64, step: f64, min: Option<f64>, max: Option<f64>, steps: i32) -> Option<f64> {
+    // Snap to a whole number of steps, which the division may have missed by a rounding error.
+    let whole_steps = |number: f64| {
+        let position = (number - base) / step;
+        if (position - position.round()).abs() < 1e-9 { position.round() } else { position }
+    };
+
+    // Step 5: a value between steps goes to the step in the direction of stepping first.
+    let position = whole_steps(value);
+    let position = if steps > 0 { position.floor() } else { position.ceil() };
+    let mut new_position = position + steps as f64;
+
+    // Step 7.
+    if let Some(min) = min {
+        if base + new_position * step < min {
+            new_position = whole_steps(min).ceil();
+        }
+    }
+    if let Some(max) = max {
+        if base + new_position * step > max {
+            new_position = whole_steps(max).floor();
+        }
+    }
+
+    // Keep only as many decimal places as the step and the base have, so that rounding
+    // errors don't show in the value.
+    let scale = 10f64.powi(decimal_places(step).max(decimal_places(base)));
+    let new_value = ((base + new_position * step) * scale).round() / scale;
+
+    // Step 6: a value beyond the range doesn't go further away from it.
+    if (steps > 0 && new_value <= value) || (steps < 0 && new_value >= value) {
+        return None;
+    }
+    Some(new_value)
+}
+
+/// The step and range of an input whose value the step attribute applies to, as numbers that
+/// `step_number` takes: the numbers of a number or range input, and the milliseconds of a date
+/// or time input.
+struct StepRange {
+    /// The allowed value step, or `None` if the step attribute is `any`.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step
+    step: Option<f64>,
+    /// The step that the type has when the step attribute does not give a valid one.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step-default
+    default_step: f64,
+    /// https://html.spec.whatwg.org/multipage/#concept-input-min-zero
+    base: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+/// How the step attribute of an input type counts its steps, or `None` for a type that the step
+/// attribute does not apply to.
+///
+/// https://html.spec.whatwg.org/multipage/#concept-input-step-scale
+pub fn step_scale(input_type: InputType) -> Option<StepScale> {
+    let (default_step, scale, default_base) = match input_type {
+        InputType::Number | InputType::Range => (1.0, 1.0, 0.0),
+        InputType::Date => (1.0, 86400000.0, 0.0),
+        InputType::Month => (1.0, 1.0, 0.0),
+        // Weeks are counted from the Monday that starts the week of 1970-01-01.
+        InputType::Week => (1.0, 604800000.0, -259200000.0),
+        InputType::Time | InputType::DatetimeLocal => (60.0, 1000.0, 0.0),
+        _ => return None,
+    };
+    Some(StepScale {
+        default_step: default_step,
+        scale: scale,
+        default_base: default_base,
+    })
+}
+
+/// The units that the step attribute of an input type is given in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StepScale {
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step-default
+    pub default_step: f64,
+    /// The number of units of the value in a step of 1.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step-scale
+    pub scale: f64,
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step-default-base
+    pub default_base: f64,
+}
+
+/// The constraints on the range and step of a time input that the given value does not meet,
+/// given its min, max, step and value attributes. An empty value meets them all.
+///
+/// https://html.spec.whatwg.org/multipage/#time-state-(type=time)
+pub fn time_validity(value: &str, min: &str, max: &str, step: &str, default_value: &str) -> ValidationFlags {
+    milliseconds_validity(time_in_milliseconds, true, value, min, max, step, default_value)
+}
+
+/// The constraints on the range and step of a datetime-local input that the given value does
+/// not meet, like `time_validity`.
+///
+/// https://html.spec.whatwg.org/multipage/#local-date-and-time-state-(type=datetime-local)
+pub fn local_date_and_time_validity(value: &str,
+                                    min: &str,
+                                    max: &str,
+                                    step: &str,
+                                    default_value: &str) -> ValidationFlags {
+    milliseconds_validity(local_date_and_time_in_milliseconds, false, value, min, max, step, default_value)
+}
+
+// The constraints on the range and step of an input whose values are parsed as numbers of
+// milliseconds, with a default step of a minute. An input whose range may be reversed takes
+// a min after its max as a range that wraps around.
+fn milliseconds_validity(parse: fn(&str) -> Option<f64>,
+                         reversible: bool,
+                         value: &str,
+                         min: &str,
+                         max: &str,
+                         step: &str,
+                         default_value: &str) -> ValidationFlags {
+    let mut flags = ValidationFlags::empty();
+    let value = match parse(value) {
+        Some(value) => value,
+        None => return flags,
+    };
+    let (min, max) = (parse(min), parse(max));
+
+    match (min, max) {
+        // https://html.spec.whatwg.org/multipage/#has-a-reversed-range
+        (Some(min), Some(max)) if reversible && min > max => {
+            if value > max && value < min {
+                flags |= ValidationFlags::RANGE_UNDERFLOW | ValidationFlags::RANGE_OVERFLOW;
+            }
+        },
+        _ => {
+            if min.map_or(false, |min| value < min) {
+                flags |= ValidationFlags::RANGE_UNDERFLOW;
+            }
+            if max.map_or(false, |max| value > max) {
+                flags |= ValidationFlags::RANGE_OVERFLOW;
+            }
+        },
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-step
+    if step.eq_ignore_ascii_case("any") {
+        return flags;
+    }
+    let step = match parse_number(step) {
+        Some(step) if step > 0.0 => step * 1000.0,
+        _ => 60000.0,
+    };
+    // https://html.spec.whatwg.org/multipage/#concept-input-min-zero
+    let base = min.or_else(|| parse(default_value)).unwrap_or(0.0);
+    if (value - base) % step != 0.0 {
+        flags |= ValidationFlags::STEP_MISMATCH;
+    }
+    flags
+}
+
+// https://html.spec.whatwg.org/multipage/#valid-simple-colour
+fn is_valid_simple_color(value: &str) -> bool {
+    value.len() == 7 && value.starts_with('#') && value[1..].chars().all(|c| c.is_digit(16))
+}
+
+// The decimal separator is typed as a comma in some languages.
+fn decimal_comma_number_char_filter(c: char) -> Option<char> {
+    if c == ',' { Some(c) } else { number_char_filter(c) }
+}
+
+/// The languages that write numbers with a decimal comma, by their primary language subtag.
+/// Regional differences, as in de-CH, are not taken into account.
+static DECIMAL_COMMA_LANGUAGES: &'static [&'static str] = &[
+    "af", "az", "be", "bg", "bs", "ca", "cs", "da", "de", "el", "es", "et", "eu", "fi", "fr",
+    "gl", "hr", "hu", "hy", "id", "is", "it", "ka", "kk", "ky", "lt", "lv", "mk", "mn", "nb",
+    "nl", "nn", "no", "pl", "pt", "ro", "ru", "sk", "sl", "sq", "sr", "sv", "tr", "uk", "uz",
+    "vi",
+];
+
+// Which fields of a time are shown in the given language, for the fields that the given step,
+// in seconds, can change, and whether it is shown on a twelve hour clock.
+fn time_format(lang: &str, step: &str) -> (TimePrecision, bool) {
+    let precision = match parse_number(step) {
+        Some(step) if step > 0.0 && step % 60.0 != 0.0 && step % 1.0 == 0.0 => TimePrecision::Second,
+        Some(step) if step > 0.0 && step % 1.0 != 0.0 => TimePrecision::Millisecond,
+        _ => TimePrecision::Minute,
+    };
+    let language = lang.split(|c| c == '-' || c == '_').next().unwrap_or("").to_ascii_lowercase();
+    (precision, TWELVE_HOUR_LANGUAGES.contains(&&*language))
+}
+
+// The languages whose times are shown on a twelve hour clock, by their primary language subtag.
+static TWELVE_HOUR_LANGUAGES: &'static [&'static str] = &[
+    "ar", "bn", "en", "hi", "ko", "ml", "mr", "pa", "ta", "te", "ur",
+];
+
+/// How the value of an input is shown to the user, and edited, where that differs from the
+/// value itself. The content of the input's `TextInput` is the value as shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueDisplay {
+    /// The value is shown as it is.
+    AsIs,
+    /// The value is a number, shown with a comma as its decimal separator. Either a comma or
+    /// a dot may be typed.
+    DecimalComma,
+    /// The value is a date, edited a field at a time with a `SegmentedInput`, and shown with
+    /// placeholders for the fields that have no value yet.
+    Date,
+    /// The value is a month, edited like a date without its day.
+    Month,
+    /// The value is a week, edited like a date with the week of its year in place of its month
+    /// and day.
+    Week,
+    /// The value is a time, edited like a date. Its seconds and milliseconds are shown when
+    /// they are part of the value or `precision` calls for them, and its hour is shown on a
+    /// twelve hour clock, followed by AM or PM, if `twelve_hour`.
+    Time { precision: TimePrecision, twelve_hour: bool },
+    /// The value is a local date and time, edited like a date followed by a time, and shown
+    /// with the time's fields as for `Time`.
+    LocalDateAndTime { precision: TimePrecision, twelve_hour: bool },
+}
+
+impl ValueDisplay {
+    /// How a number is shown in the given language.
+    pub fn for_number(lang: &str) -> ValueDisplay {
+        let language = lang.split(|c| c == '-' || c == '_').next().unwrap_or("").to_ascii_lowercase();
+        if DECIMAL_COMMA_LANGUAGES.contains(&&*language) {
+            ValueDisplay::DecimalComma
+        } else {
+            ValueDisplay::AsIs
+        }
+    }
+
+    /// How a time is shown in the given language, with the fields that the given step, in
+    /// seconds, can change.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#concept-input-step-default
+    pub fn for_time(lang: &str, step: &str) -> ValueDisplay {
+        let (precision, twelve_hour) = time_format(lang, step);
+        ValueDisplay::Time { precision: precision, twelve_hour: twelve_hour }
+    }
+
+    /// How a local date and time is shown, with its time shown as `for_time` shows it.
+    pub fn for_local_date_and_time(lang: &str, step: &str) -> ValueDisplay {
+        let (precision, twelve_hour) = time_format(lang, step);
+        ValueDisplay::LocalDateAndTime { precision: precision, twelve_hour: twelve_hour }
+    }
+
+    /// The value as it is shown.
+    pub fn to_display(&self, value: &str) -> String {
+        match *self {
+            ValueDisplay::AsIs => value.to_owned(),
+            ValueDisplay::DecimalComma => value.replace('.', ","),
+            ValueDisplay::Date if value.is_empty() => EMPTY_DATE.to_owned(),
+            ValueDisplay::Date => value.to_owned(),
+            ValueDisplay::Month if value.is_empty() => EMPTY_MONTH.to_owned(),
+            ValueDisplay::Week if value.is_empty() => EMPTY_WEEK.to_owned(),
+            ValueDisplay::Month | ValueDisplay::Week => value.to_owned(),
+            ValueDisplay::Time { precision, twelve_hour } => time_display(value, precision, twelve_hour),
+            ValueDisplay::LocalDateAndTime { precision, twelve_hour } => {
+                local_date_and_time_display(value, precision, twelve_hour)
+            },
+        }
+    }
+
+    /// The value that is shown as given.
+    pub fn to_value(&self, display: &str) -> String {
+        match *self {
+            ValueDisplay::AsIs => display.to_owned(),
+            ValueDisplay::DecimalComma => display.replace(',', "."),
+            // A date is only a value once all its fields are filled in.
+            ValueDisplay::Date if DOMString::from(display).is_valid_date_string() => display.to_owned(),
+            ValueDisplay::Date => String::new(),
+            ValueDisplay::Month if DOMString::from(display).is_valid_month_string() => display.to_owned(),
+            ValueDisplay::Week if DOMString::from(display).is_valid_week_string() => display.to_owned(),
+            ValueDisplay::Month | ValueDisplay::Week => String::new(),
+            // Any time, however it is shown, so that changing how times are shown keeps it.
+            ValueDisplay::Time { .. } => time_value(display),
+            ValueDisplay::LocalDateAndTime { .. } => local_date_and_time_value(display),
+        }
+    }
+}
+
+/// The value of an email input, with the spaces around its address, or around each of its
+/// addresses if it accepts several, removed.
+///
+/// https://html.spec.whatwg.org/multipage/#e-mail-state-(type=email):value-sanitization-algorithm
+pub fn sanitize_email(value: &str, multiple: bool) -> String {
+    let trim = |address: &str| address.trim_matches(|c| char::is_ascii_whitespace(&c)).to_owned();
+    if multiple {
+        value.split(',').map(trim).collect::<Vec<_>>().join(",")
+    } else {
+        trim(&value.replace(|c| c == '\n' || c == '\r', ""))
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/#valid-e-mail-address
+pub fn is_valid_email_address(address: &str) -> bool {
+    let at = match address.find('@') {
+        Some(at) => at,
+        None => return false,
+    };
+    let (local, domain) = (&address[..at], &address[at + 1..]);
+    let is_label = |label: &str| {
+        !label.is_empty() && label.len() <= 63 && !label.starts_with('-') && !label.ends_with('-') &&
+            label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    !local.is_empty() &&
+        local.chars().all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c)) &&
+        domain.split('.').all(is_label)
+}
+
+/// The type mismatch of an email input whose value is not an address, or not a list of
+/// addresses if it accepts several. An empty value is not a mismatch.
+///
+/// https://html.spec.whatwg.org/multipage/#e-mail-state-(type=email)
+pub fn email_validity(value: &str, multiple: bool) -> ValidationFlags {
+    let valid = value.is_empty() || if multiple {
+        value.split(',').all(is_valid_email_address)
+    } else {
+        is_valid_email_address(value)
+    };
+    if valid { ValidationFlags::empty() } else { ValidationFlags::TYPE_MISMATCH }
+}
+
+/// The type mismatch of a URL input whose value is not an absolute URL. An empty value is not
+/// a mismatch.
+///
+/// https://html.spec.whatwg.org/multipage/#url-state-(type=url)
+pub fn url_validity(value: &str) -> ValidationFlags {
+    if value.is_empty() || ServoUrl::parse(value).is_ok() {
+        ValidationFlags::empty()
+    } else {
+        ValidationFlags::TYPE_MISMATCH
+    }
+}
+
+/// The pattern mismatch of a value that `pattern` does not match as a whole, or of any of its
+/// comma-separated values if it is an email input that accepts several. An invalid pattern
+/// sets no constraint, and an empty value is not a mismatch.
+///
+/// https://html.spec.whatwg.org/multipage/#the-pattern-attribute
+pub fn pattern_validity(pattern: &str, value: &str, multiple: bool) -> ValidationFlags {
+    compiled_pattern_validity(&CompiledPattern::new(pattern), value, multiple)
+}
+
+fn compiled_pattern_validity(pattern: &CompiledPattern, value: &str, multiple: bool) -> ValidationFlags {
+    let matches = value.is_empty() || if multiple {
+        value.split(',').all(|value| pattern.matches(value))
+    } else {
+        pattern.matches(value)
+    };
+    if matches { ValidationFlags::empty() } else { ValidationFlags::PATTERN_MISMATCH }
+}
+
+// https://html.spec.whatwg.org/multipage/#valid-e-mail-address
+fn email_char_filter(c: char) -> Option<char> {
+    if c.is_whitespace() { None } else { Some(c) }
+}
+
 impl<'a> From<&'a Atom> for InputType {
     fn from(value: &Atom) -> InputType {
         match value.to_ascii_lowercase() {
@@ -193,10 +782,21 @@ pub struct HTMLInputElement {
     maxlength: Cell<i32>,
     minlength: Cell<i32>,
     #[ignore_malloc_size_of = "#7193"]
-    textinput: DomRefCell<TextInput<ScriptToConstellationChan>>,
+    textinput: DomRefCell<TextInput<TextControlClipboard>>,
+    /// The field a date is being edited in, with the content of `textinput` as it is shown.
+    segmented_input: DomRefCell<SegmentedInput>,
     activation_state: DomRefCell<InputActivationState>,
     // https://html.spec.whatwg.org/multipage/#concept-input-value-dirty-flag
     value_dirty: Cell<bool>,
+    /// Whether the value was last changed by a user edit, rather than by a script.
+    edited_by_user: Cell<bool>,
+    /// Whether the embedder was told to warn that Caps Lock is on.
+    caps_lock_warning: Cell<bool>,
+    /// Whether the embedder was given suggestions from the datalist to show.
+    suggestions_shown: Cell<bool>,
+    /// The `pattern` attribute as it was last compiled.
+    #[ignore_malloc_size_of = "Regex"]
+    compiled_pattern: DomRefCell<Option<CompiledPattern>>,
 
     filelist: MutNullableDom<FileList>,
     form_owner: MutNullableDom<HTMLFormElement>,
@@ -236,6 +836,14 @@ static DEFAULT_MIN_LENGTH: i32 = -1;
 impl HTMLInputElement {
     fn new_inherited(local_name: LocalName, prefix: Option<Prefix>, document: &Document) -> HTMLInputElement {
         let chan = document.window().upcast::<GlobalScope>().script_to_constellation_chan().clone();
+        let mut textinput = TextInput::new(Single,
+                                           DOMString::new(),
+                                           text_control_clipboard(chan),
+                                           None,
+                                           None,
+                                           SelectionDirection::None);
+        textinput.set_paste_sanitization(InputType::default().paste_sanitization());
+        textinput.set_defers_edits(true);
         HTMLInputElement {
             htmlelement:
                 HTMLElement::new_inherited_with_state(ElementState::IN_ENABLED_STATE |
@@ -247,14 +855,14 @@ impl HTMLInputElement {
             maxlength: Cell::new(DEFAULT_MAX_LENGTH),
             minlength: Cell::new(DEFAULT_MIN_LENGTH),
             size: Cell::new(DEFAULT_INPUT_SIZE),
-            textinput: DomRefCell::new(TextInput::new(Single,
-                                                      DOMString::new(),
-                                                      chan,
-                                                      None,
-                                                      None,
-                                                      SelectionDirection::None)),
+            textinput: DomRefCell::new(textinput),
+            segmented_input: DomRefCell::new(SegmentedInput::new(time::now().tm_year as u32 + 1900)),
             activation_state: DomRefCell::new(InputActivationState::new()),
             value_dirty: Cell::new(false),
+            edited_by_user: Cell::new(false),
+            caps_lock_warning: Cell::new(false),
+            suggestions_shown: Cell::new(false),
+            compiled_pattern: DomRefCell::new(None),
             filelist: MutNullableDom::new(None),
             form_owner: Default::default(),
         }
@@ -308,6 +916,8 @@ pub trait LayoutHTMLInputElementHelpers {
     #[allow(unsafe_code)]
     unsafe fn selection_for_layout(self) -> Option<Range<usize>>;
     #[allow(unsafe_code)]
+    unsafe fn misspellings_for_layout(self) -> Vec<Range<usize>>;
+    #[allow(unsafe_code)]
     unsafe fn checked_state_for_layout(self) -> bool;
     #[allow(unsafe_code)]
     unsafe fn indeterminate_state_for_layout(self) -> bool;
@@ -336,22 +946,13 @@ impl LayoutHTMLInputElementHelpers for LayoutDom<HTMLInputElement> {
             InputType::Button => get_raw_attr_value(self, ""),
             InputType::Submit => get_raw_attr_value(self, DEFAULT_SUBMIT_VALUE),
             InputType::Reset => get_raw_attr_value(self, DEFAULT_RESET_VALUE),
-            InputType::Password => {
-                let text = get_raw_textinput_value(self);
-                if !text.is_empty() {
-                    text.chars().map(|_| PASSWORD_REPLACEMENT_CHAR).collect()
-                } else {
-                    String::from((*self.unsafe_get()).placeholder.borrow_for_layout().clone())
-                }
+            _ if (*self.unsafe_get()).textinput.borrow_for_layout().shows_placeholder() => {
+                String::from((*self.unsafe_get()).placeholder.borrow_for_layout().clone())
             },
-            _ => {
-                let text = get_raw_textinput_value(self);
-                if !text.is_empty() {
-                    String::from(text)
-                } else {
-                    String::from((*self.unsafe_get()).placeholder.borrow_for_layout().clone())
-                }
+            InputType::Password => {
+                String::from((*self.unsafe_get()).textinput.borrow_for_layout().masked_content())
             },
+            _ => String::from(get_raw_textinput_value(self)),
         }
     }
 
@@ -371,22 +972,23 @@ impl LayoutHTMLInputElementHelpers for LayoutDom<HTMLInputElement> {
         let textinput = (*self.unsafe_get()).textinput.borrow_for_layout();
 
         match (*self.unsafe_get()).input_type() {
-            InputType::Password => {
-                let text = get_raw_textinput_value(self);
-                let sel = textinput.sorted_selection_offsets_range();
-
-                // Translate indices from the raw value to indices in the replacement value.
-                let char_start = text[.. sel.start].chars().count();
-                let char_end = char_start + text[sel].chars().count();
-
-                let bytes_per_char = PASSWORD_REPLACEMENT_CHAR.len_utf8();
-                Some(char_start * bytes_per_char .. char_end * bytes_per_char)
-            }
+            InputType::Password => Some(textinput.masked_selection_offsets_range()),
             input_type if input_type.is_textual() => Some(textinput.sorted_selection_offsets_range()),
             _ => None
         }
     }
 
+    #[allow(unrooted_must_root)]
+    #[allow(unsafe_code)]
+    unsafe fn misspellings_for_layout(self) -> Vec<Range<usize>> {
+        let textinput = (*self.unsafe_get()).textinput.borrow_for_layout();
+        match (*self.unsafe_get()).input_type() {
+            _ if textinput.shows_placeholder() => vec!(),
+            InputType::Password => vec!(),
+            _ => textinput.misspellings().to_vec(),
+        }
+    }
+
     #[allow(unrooted_must_root)]
     #[allow(unsafe_code)]
     unsafe fn checked_state_for_layout(self) -> bool {
@@ -401,6 +1003,12 @@ impl LayoutHTMLInputElementHelpers for LayoutDom<HTMLInputElement> {
 }
 
 impl TextControlElement for HTMLInputElement {
+    type Buffer = Vec<DOMString>;
+
+    fn textinput(&self) -> &DomRefCell<TextInput<TextControlClipboard>> {
+        &self.textinput
+    }
+
     // https://html.spec.whatwg.org/multipage/#concept-input-apply
     fn selection_api_applies(&self) -> bool {
         match self.input_type() {
@@ -438,9 +1046,51 @@ impl TextControlElement for HTMLInputElement {
         }
     }
 
+    fn takes_text_input(&self) -> bool {
+        self.input_type().is_textual_or_password()
+    }
+
     fn set_dirty_value_flag(&self, value: bool) {
         self.value_dirty.set(value)
     }
+
+    fn set_edited_by_user(&self, edited: bool) {
+        self.edited_by_user.set(edited);
+        self.upcast::<Element>().set_autofill_state(false);
+    }
+
+    fn update_placeholder_shown_state(&self) {
+        if !self.input_type().is_textual_or_password() {
+            return
+        }
+
+        let has_placeholder = !self.placeholder.borrow().is_empty();
+        let shows_placeholder = self.textinput.borrow().shows_placeholder();
+        let el = self.upcast::<Element>();
+
+        el.set_placeholder_shown_state(has_placeholder && shows_placeholder);
+    }
+
+    // Only free text is checked; the other types hold values such as addresses and numbers, and
+    // a password must not be sent anywhere.
+    fn spellcheck_enabled(&self) -> bool {
+        match self.input_type() {
+            InputType::Text | InputType::Search => self.upcast::<HTMLElement>().spellcheck_enabled(),
+            _ => false,
+        }
+    }
+
+    // The keyboard suited to the type.
+    fn default_input_mode(&self) -> InputMode {
+        match self.input_type() {
+            InputType::Email => InputMode::Email,
+            InputType::Number => InputMode::Decimal,
+            InputType::Search => InputMode::Search,
+            InputType::Tel => InputMode::Tel,
+            InputType::Url => InputMode::Url,
+            _ => InputMode::Text,
+        }
+    }
 }
 
 impl HTMLInputElementMethods for HTMLInputElement {
@@ -520,7 +1170,10 @@ impl HTMLInputElementMethods for HTMLInputElement {
     // https://html.spec.whatwg.org/multipage/#dom-input-value
     fn Value(&self) -> DOMString {
         match self.value_mode() {
-            ValueMode::Value => self.textinput.borrow().get_content(),
+            ValueMode::Value => {
+                let content = self.textinput.borrow().get_content();
+                DOMString::from(self.value_display().to_value(&content))
+            }
             ValueMode::Default => {
                 self.upcast::<Element>()
                     .get_attribute(&ns!(), &local_name!("value"))
@@ -561,6 +1214,59 @@ impl HTMLInputElementMethods for HTMLInputElement {
     // https://html.spec.whatwg.org/multipage/#dom-input-defaultvalue
     make_setter!(SetDefaultValue, "value");
 
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasdate
+    #[allow(unsafe_code)]
+    unsafe fn GetValueAsDate(&self, cx: *mut JSContext) -> Option<NonNull<JSObject>> {
+        let milliseconds = self.convert_string_to_date(&self.Value())?;
+        NonNull::new(NewDateObject(cx, ClippedTime { t: milliseconds }))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasdate
+    #[allow(unsafe_code)]
+    unsafe fn SetValueAsDate(&self, cx: *mut JSContext, value: *mut JSObject) -> ErrorResult {
+        if !self.input_type().has_value_as_date() {
+            return Err(Error::InvalidState);
+        }
+        let milliseconds = if value.is_null() {
+            f64::NAN
+        } else {
+            rooted!(in(cx) let value = value);
+            let mut is_date = false;
+            if !JS_ObjectIsDate(cx, value.handle(), &mut is_date) {
+                return Err(Error::JSFailed);
+            }
+            if !is_date {
+                return Err(Error::Type("Value is not a Date".to_owned()));
+            }
+            let mut milliseconds = f64::NAN;
+            if !DateGetMsecSinceEpoch(cx, value.handle(), &mut milliseconds) {
+                return Err(Error::JSFailed);
+            }
+            milliseconds
+        };
+        // A null or invalid Date empties the value.
+        let value = if milliseconds.is_nan() { None } else { self.convert_date_to_string(milliseconds) };
+        self.update_text_contents(DOMString::from(value.unwrap_or_default()), true)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasnumber
+    fn ValueAsNumber(&self) -> f64 {
+        self.convert_string_to_number(&self.Value()).unwrap_or(f64::NAN)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-input-valueasnumber
+    fn SetValueAsNumber(&self, value: f64) -> ErrorResult {
+        if value.is_infinite() {
+            return Err(Error::Type("Value is infinite".to_owned()));
+        }
+        if !self.input_type().has_value_as_number() {
+            return Err(Error::InvalidState);
+        }
+        // NaN empties the value.
+        let value = if value.is_nan() { None } else { self.convert_number_to_string(value) };
+        self.update_text_contents(DOMString::from(value.unwrap_or_default()), true)
+    }
+
     // https://html.spec.whatwg.org/multipage/#attr-fe-name
     make_getter!(Name, "name");
 
@@ -606,6 +1312,11 @@ impl HTMLInputElementMethods for HTMLInputElement {
     // https://html.spec.whatwg.org/multipage/#attr-fs-formnovalidate
     make_bool_setter!(SetFormNoValidate, "formnovalidate");
 
+    // https://html.spec.whatwg.org/multipage/#dom-input-list
+    fn GetList(&self) -> Option<DomRoot<HTMLElement>> {
+        self.list().map(DomRoot::upcast)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-input-max
     make_getter!(Max, "max");
 
@@ -660,6 +1371,16 @@ impl HTMLInputElementMethods for HTMLInputElement {
     // https://html.spec.whatwg.org/multipage/#dom-input-step
     make_setter!(SetStep, "step");
 
+    // https://html.spec.whatwg.org/multipage/#dom-input-stepup
+    fn StepUp(&self, n: i32) -> ErrorResult {
+        self.step_by_script(n)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-input-stepdown
+    fn StepDown(&self, n: i32) -> ErrorResult {
+        self.step_by_script(n.checked_neg().unwrap_or(i32::MAX))
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-input-indeterminate
     fn Indeterminate(&self) -> bool {
         self.upcast::<Element>().state().contains(ElementState::IN_INDETERMINATE_STATE)
@@ -670,6 +1391,27 @@ impl HTMLInputElementMethods for HTMLInputElement {
         self.upcast::<Element>().set_state(ElementState::IN_INDETERMINATE_STATE, val)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
+    fn Validity(&self) -> DomRoot<ValidityState> {
+        let window = window_from_node(self);
+        ValidityState::new(&window, self.upcast())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn ValidationMessage(&self) -> DOMString {
+        self.validation_message()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        check_validity(self.upcast())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        report_validity(self.upcast())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-lfe-labels
     fn Labels(&self) -> DomRoot<NodeList> {
         if self.input_type() == InputType::Hidden {
@@ -859,11 +1601,36 @@ impl HTMLInputElement {
         }
 
         // Step 3.9
-        vec![FormDatum {
+        let mut datums = vec![FormDatum {
             ty: ty.clone(),
             name: name,
             value: FormDatumValue::String(self.Value())
-        }]
+        }];
+
+        // Step 3.10
+        let dirname = self.DirName();
+        if !dirname.is_empty() && self.dirname_applies() {
+            datums.push(FormDatum {
+                ty: ty,
+                name: dirname,
+                value: FormDatumValue::String(DOMString::from(self.directionality())),
+            });
+        }
+        datums
+    }
+
+    // https://html.spec.whatwg.org/multipage/#attr-input-dirname
+    fn dirname_applies(&self) -> bool {
+        match self.input_type() {
+            InputType::Text | InputType::Search | InputType::Tel | InputType::Url | InputType::Email |
+            InputType::Password => true,
+            _ => false,
+        }
+    }
+
+    /// The directionality of the control, which `dir=auto` takes from its value.
+    pub fn directionality(&self) -> TextDirection {
+        text_control_directionality(self.upcast(), &self.textinput.borrow())
     }
 
     // https://html.spec.whatwg.org/multipage/#radio-button-group
@@ -890,6 +1657,438 @@ impl HTMLInputElement {
         //TODO: dispatch change event
     }
 
+    // The number that a value or an attribute of this input's type is, in the units of its
+    // `step_range`, or `None` if it is not a valid value.
+    // https://html.spec.whatwg.org/multipage/#concept-input-value-string-number
+    fn convert_string_to_number(&self, value: &str) -> Option<f64> {
+        match self.input_type() {
+            InputType::Number | InputType::Range => parse_number(value),
+            InputType::Date => date_in_milliseconds(value),
+            InputType::Month => month_in_months(value),
+            InputType::Week => week_in_milliseconds(value),
+            InputType::Time => time_in_milliseconds(value),
+            InputType::DatetimeLocal => local_date_and_time_in_milliseconds(value),
+            _ => None,
+        }
+    }
+
+    // The value of this input's type that a number in the units of its `step_range` is, or
+    // `None` if there is none.
+    // https://html.spec.whatwg.org/multipage/#concept-input-value-number-string
+    fn convert_number_to_string(&self, number: f64) -> Option<String> {
+        let value = match self.input_type() {
+            InputType::Number | InputType::Range => number.to_string(),
+            InputType::Date => date_from_milliseconds(number),
+            InputType::Month => month_from_months(number),
+            InputType::Week => week_from_milliseconds(number),
+            InputType::Time => time_from_milliseconds(number),
+            InputType::DatetimeLocal => local_date_and_time_from_milliseconds(number),
+            _ => return None,
+        };
+        if value.is_empty() { None } else { Some(value) }
+    }
+
+    // The number of milliseconds after the Unix epoch of the Date that a value of this input's
+    // type is, or `None` if it is not a valid value.
+    // https://html.spec.whatwg.org/multipage/#concept-input-value-string-date
+    fn convert_string_to_date(&self, value: &str) -> Option<f64> {
+        match self.input_type() {
+            InputType::Date | InputType::Time | InputType::Week => self.convert_string_to_number(value),
+            // The first day of the month.
+            InputType::Month => date_in_milliseconds(&format!("{}-01", value)),
+            _ => None,
+        }
+    }
+
+    // The value of this input's type that a Date, as a number of milliseconds after the Unix
+    // epoch, is in UTC, or `None` if there is none.
+    // https://html.spec.whatwg.org/multipage/#concept-input-value-date-string
+    fn convert_date_to_string(&self, milliseconds: f64) -> Option<String> {
+        match self.input_type() {
+            InputType::Date | InputType::Week => self.convert_number_to_string(milliseconds),
+            InputType::Month => {
+                let date = date_from_milliseconds(milliseconds);
+                date.rfind('-').map(|dash| date[..dash].to_owned())
+            },
+            // The time of day of the Date.
+            InputType::Time => {
+                let milliseconds = milliseconds - (milliseconds / 86400000.0).floor() * 86400000.0;
+                self.convert_number_to_string(milliseconds)
+            },
+            _ => None,
+        }
+    }
+
+    // The step and range of this input, or `None` if the step attribute does not apply to its
+    // type.
+    fn step_range(&self) -> Option<StepRange> {
+        let StepScale { default_step, scale, default_base } = step_scale(self.input_type())?;
+        let element = self.upcast::<Element>();
+        let attribute = |name: &LocalName| self.convert_string_to_number(&element.get_string_attribute(name));
+        let step_attribute = element.get_string_attribute(&local_name!("step"));
+        let step = if step_attribute.eq_ignore_ascii_case("any") {
+            None
+        } else {
+            match parse_number(&step_attribute) {
+                Some(step) if step > 0.0 => Some(step * scale),
+                _ => Some(default_step * scale),
+            }
+        };
+        let (min, max) = match self.input_type() {
+            // https://html.spec.whatwg.org/multipage/#range-state-(type=range):concept-input-min-default
+            InputType::Range => {
+                let min = attribute(&local_name!("min")).unwrap_or(0.0);
+                let max = attribute(&local_name!("max")).unwrap_or(100.0);
+                (Some(min), Some(if max < min { min } else { max }))
+            },
+            _ => (attribute(&local_name!("min")), attribute(&local_name!("max"))),
+        };
+        Some(StepRange {
+            step: step,
+            default_step: default_step * scale,
+            base: attribute(&local_name!("min")).or_else(|| attribute(&local_name!("value"))).unwrap_or(default_base),
+            min: min,
+            max: max,
+        })
+    }
+
+    // The value that this input steps to by the given number of steps, for `stepUp`, `stepDown`
+    // and the arrow keys, or `None` if it stays as it is. A step of `any` leaves the steps to
+    // `default_step`.
+    // https://html.spec.whatwg.org/multipage/#dom-input-stepup
+    fn stepped_value(&self, range: &StepRange, steps: i32) -> Option<String> {
+        let step = range.step.unwrap_or(range.default_step);
+        // Step 3.
+        if let (Some(min), Some(max)) = (range.min, range.max) {
+            if min > max {
+                return None;
+            }
+            // Step 4: there is no value between them that is a whole number of steps.
+            if range.base + ((min - range.base) / step).ceil() * step > max {
+                return None;
+            }
+        }
+        // Step 5.
+        let value = self.convert_string_to_number(&self.Value()).unwrap_or(0.0);
+        let new_value = step_number(value, range.base, step, range.min, range.max, steps)?;
+        self.convert_number_to_string(new_value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-input-stepup
+    fn step_by_script(&self, steps: i32) -> ErrorResult {
+        // Steps 1-2.
+        let range = match self.step_range() {
+            Some(range) => range,
+            None => return Err(Error::InvalidState),
+        };
+        if range.step.is_none() {
+            return Err(Error::InvalidState);
+        }
+        // Step 9: the value is set as the value IDL attribute does, without firing any event.
+        match self.stepped_value(&range, steps) {
+            Some(value) => self.update_text_contents(DOMString::from(value), true),
+            None => Ok(()),
+        }
+    }
+
+    // Step the value of a number or range input by the given number of steps, as the user asked
+    // with the keys, and fire input and change if it changed.
+    // https://html.spec.whatwg.org/multipage/#the-input-element:dom-input-stepup-2
+    fn step_by_user(&self, steps: i32) -> bool {
+        if !self.is_mutable() {
+            return false;
+        }
+        // A step of "any" has no allowed value step, so the arrow keys use the default.
+        let new_value = match self.step_range().and_then(|range| self.stepped_value(&range, steps)) {
+            Some(new_value) => new_value,
+            None => return false,
+        };
+
+        let new_content = DOMString::from(self.value_display().to_display(&new_value));
+        self.textinput.borrow_mut().set_content(new_content, true);
+        self.value_dirty.set(true);
+        self.update_placeholder_shown_state();
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        let target = self.upcast::<EventTarget>();
+        target.fire_bubbling_event(atom!("input"));
+        target.fire_bubbling_event(atom!("change"));
+        true
+    }
+
+    // An email input that accepts several addresses moves and selects by word an address at a
+    // time.
+    fn update_list_separator(&self) {
+        let multiple = self.input_type() == InputType::Email && self.Multiple();
+        self.textinput.borrow_mut().set_list_separator(if multiple { Some(',') } else { None });
+    }
+
+    /// Show the value of a password field as it was typed, for an embedder button that reveals
+    /// it, or hide it again. The value is hidden again when the field loses the focus.
+    pub fn set_password_revealed(&self, revealed: bool) {
+        if self.input_type() != InputType::Password {
+            return;
+        }
+        self.textinput.borrow_mut().set_obscured(!revealed);
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// Hide the value of a password field that was revealed, and withdraw the warning about
+    /// Caps Lock, as the field has lost the focus.
+    pub fn end_password_entry(&self) {
+        self.set_password_revealed(false);
+        self.set_caps_lock_warning(false);
+    }
+
+    // Tell the embedder whether Caps Lock is on, as far as the keys typed into a password field
+    // show it.
+    fn update_caps_lock_warning(&self, event: &KeyboardEvent) {
+        if let Some(caps_lock) = caps_lock_state(event.printable(), event.ShiftKey()) {
+            self.set_caps_lock_warning(caps_lock);
+        }
+    }
+
+    fn set_caps_lock_warning(&self, warn: bool) {
+        if self.caps_lock_warning.get() == warn {
+            return;
+        }
+        self.caps_lock_warning.set(warn);
+        let msg = ScriptMsg::SetCapsLockWarning(warn);
+        window_from_node(self).upcast::<GlobalScope>().script_to_constellation_chan().send(msg).unwrap();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-list
+    fn list(&self) -> Option<DomRoot<HTMLDataListElement>> {
+        if !self.input_type().has_list() {
+            return None;
+        }
+        let id = self.upcast::<Element>().get_attribute(&ns!(), &local_name!("list"))?;
+        document_from_node(self).get_element_by_id(&Atom::from(&**id.value()))
+            .and_then(DomRoot::downcast::<HTMLDataListElement>)
+    }
+
+    /// Offer the embedder the options of the datalist that match what the user typed, or tell it
+    /// to hide the ones it offered if none match.
+    fn update_suggestions(&self) {
+        let datalist = match self.list() {
+            Some(datalist) => datalist,
+            None => return self.hide_suggestions(),
+        };
+        if !self.input_type().limits_length() {
+            return self.hide_suggestions();
+        }
+        let options = datalist.upcast::<Node>()
+            .traverse_preorder()
+            .filter_map(DomRoot::downcast::<HTMLOptionElement>)
+            .filter(|option| !option.Disabled())
+            .map(|option| {
+                let value = String::from(option.Value());
+                let label = String::from(option.Label());
+                Suggestion { label: if label == value { String::new() } else { label }, value: value }
+            })
+            .filter(|suggestion| !suggestion.value.is_empty())
+            .collect();
+        let suggestions = matching_suggestions(options, &self.textinput.borrow().get_content());
+        if suggestions.is_empty() {
+            return self.hide_suggestions();
+        }
+        self.send_suggestions(suggestions);
+    }
+
+    /// Tell the embedder to hide the suggestions it offered, if any, as the input lost the focus
+    /// or none match any more.
+    pub fn hide_suggestions(&self) {
+        if self.suggestions_shown.get() {
+            self.send_suggestions(vec![]);
+        }
+    }
+
+    fn send_suggestions(&self, suggestions: Vec<Suggestion>) {
+        self.suggestions_shown.set(!suggestions.is_empty());
+        let msg = ScriptMsg::SetSuggestions(suggestions);
+        window_from_node(self).upcast::<GlobalScope>().script_to_constellation_chan().send(msg).unwrap();
+    }
+
+    /// Put the suggestion the user picked in place of the value, through the same edit as
+    /// typing, and fire change as the user committed it.
+    pub fn choose_suggestion(&self, value: DOMString) {
+        self.hide_suggestions();
+        if !self.input_type().limits_length() || !self.is_mutable() {
+            return;
+        }
+        if let DispatchInput(_) = self.selection().replace_value(value) {
+            self.value_dirty.set(true);
+            self.update_placeholder_shown_state();
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+            self.upcast::<EventTarget>().fire_bubbling_event(atom!("change"));
+        }
+    }
+
+    /// Fill in the value on the user's behalf, as a password manager does, as far as `maxlength`
+    /// allows, and fire input and change. The input matches `:autofill` until its value is
+    /// changed otherwise.
+    pub fn autofill(&self, value: DOMString) {
+        if !self.input_type().limits_length() || !self.is_mutable() {
+            return;
+        }
+        self.selection().abandon_composition_for_value(&value);
+        self.textinput.borrow_mut().set_content_within_max_length(value);
+        self.sanitize_value();
+        self.value_dirty.set(true);
+        self.set_edited_by_user(true);
+        self.upcast::<Element>().set_autofill_state(true);
+        self.update_placeholder_shown_state();
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        let target = self.upcast::<EventTarget>();
+        target.fire_bubbling_event(atom!("input"));
+        target.fire_bubbling_event(atom!("change"));
+    }
+
+    // Move the thumb of a range control with the keys of `range_steps_for_key`, stepping as
+    // `stepUp` and `stepDown` do, and fire input and change when the value changes. No key
+    // edits the value as text.
+    fn handle_range_keydown(&self, event: &KeyboardEvent) {
+        let key = match event.get_key() {
+            Some(key) => key,
+            None => return,
+        };
+        let range = match self.step_range() {
+            Some(range) => range,
+            None => return,
+        };
+        let span = range.max.unwrap_or(0.0) - range.min.unwrap_or(0.0);
+        let steps = match range_steps_for_key(key, span, range.step.unwrap_or(range.default_step)) {
+            Some(steps) => steps,
+            None => return,
+        };
+        event.upcast::<Event>().mark_as_handled();
+        self.step_by_user(steps);
+    }
+
+    // Let the user pick a color with the embedder's color picker, starting at the current value.
+    // The color they pick is applied by `finish_color_pick` once the picker closes.
+    // https://html.spec.whatwg.org/multipage/#color-state-(type=color)
+    fn pick_color(&self) {
+        let (chan, recv) = channel().expect("Error initializing channel");
+        let window = window_from_node(self);
+        let task_source = window.user_interaction_task_source();
+        let canceller = window.task_canceller();
+        let trusted = Trusted::new(self);
+        ROUTER.add_route(recv.to_opaque(), Box::new(move |message| {
+            let color: Option<String> = message.to().unwrap();
+            let input = trusted.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(finish_color_pick: move || {
+                    input.root().finish_color_pick(color);
+                }),
+                &canceller,
+            );
+        }));
+        let msg = ScriptMsg::PickColor(self.Value().into(), chan);
+        window.upcast::<GlobalScope>().script_to_constellation_chan().send(msg).unwrap();
+    }
+
+    // Apply the color that the user picked with `pick_color`, and fire input and change if it
+    // is another one.
+    fn finish_color_pick(&self, color: Option<String>) {
+        // The picker was closed without a color, or the embedder has none.
+        let color = match color {
+            Some(ref color) if is_valid_simple_color(color) => color.to_ascii_lowercase(),
+            _ => return,
+        };
+        // The page may have changed the type of the input, or disabled it, in the meantime.
+        if self.input_type() != InputType::Color || !self.is_mutable() || *self.Value() == color {
+            return;
+        }
+        self.textinput.borrow_mut().set_content(DOMString::from(color), true);
+        self.value_dirty.set(true);
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        let target = self.upcast::<EventTarget>();
+        target.fire_bubbling_event(atom!("input"));
+        target.fire_bubbling_event(atom!("change"));
+    }
+
+    // Edit a date or time a field at a time, keeping the content of the text input as the
+    // fields are shown, and fire input and change when the value changes.
+    fn handle_segmented_keydown(&self, event: &KeyboardEvent) {
+        let key = match event.get_key() {
+            Some(key) => key,
+            None => return,
+        };
+        // The language may have changed how the value is shown since it was.
+        self.update_segmented_display();
+        let content = self.textinput.borrow().get_content();
+        let reaction = self.segmented_input.borrow_mut().handle_keydown_aux(&content,
+                                                                            event.printable(),
+                                                                            key,
+                                                                            event.get_key_modifiers());
+        let event = event.upcast::<Event>();
+        match reaction {
+            SegmentReaction::Changed(_) if !self.is_mutable() => event.mark_as_handled(),
+            SegmentReaction::Changed(content) => {
+                let old_value = self.Value();
+                self.textinput.borrow_mut().set_content(DOMString::from(content), true);
+                self.value_dirty.set(true);
+                self.select_focused_segment();
+                self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                if self.Value() != old_value {
+                    let target = self.upcast::<EventTarget>();
+                    target.fire_bubbling_event(atom!("input"));
+                    target.fire_bubbling_event(atom!("change"));
+                }
+                event.mark_as_handled();
+            },
+            SegmentReaction::SegmentFocused => {
+                self.select_focused_segment();
+                self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                event.mark_as_handled();
+            },
+            SegmentReaction::Rejected => event.mark_as_handled(),
+            SegmentReaction::FocusNavigation(_) | SegmentReaction::Nothing => (),
+        }
+    }
+
+    // Show a complete date or time as its type and attributes now show it. The fields of a
+    // value that is not complete yet are kept as they are.
+    fn update_segmented_display(&self) {
+        let display = self.value_display();
+        let value = display.to_value(&self.textinput.borrow().get_content());
+        if value.is_empty() {
+            return;
+        }
+        let content = DOMString::from(display.to_display(&value));
+        if *self.textinput.borrow().single_line_content() != content {
+            self.textinput.borrow_mut().set_content(content, true);
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    // Select the field of a date or time that is being edited, to show which it is.
+    fn select_focused_segment(&self) {
+        let mut textinput = self.textinput.borrow_mut();
+        let range = self.segmented_input.borrow().focused_range(&textinput.get_content());
+        textinput.set_selection_range(range.start as u32, range.end as u32, SelectionDirection::Forward);
+    }
+
+    // How the value is shown in the control, which for numbers depends on the language.
+    fn value_display(&self) -> ValueDisplay {
+        match self.input_type() {
+            InputType::Number => ValueDisplay::for_number(&self.upcast::<Element>().get_lang()),
+            InputType::Date => ValueDisplay::Date,
+            InputType::Month => ValueDisplay::Month,
+            InputType::Week => ValueDisplay::Week,
+            InputType::Time => {
+                let element = self.upcast::<Element>();
+                ValueDisplay::for_time(&element.get_lang(), &element.get_string_attribute(&local_name!("step")))
+            },
+            InputType::DatetimeLocal => {
+                let element = self.upcast::<Element>();
+                let step = element.get_string_attribute(&local_name!("step"));
+                ValueDisplay::for_local_date_and_time(&element.get_lang(), &step)
+            },
+            _ => ValueDisplay::AsIs,
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#concept-fe-mutable
     fn is_mutable(&self) -> bool {
         // https://html.spec.whatwg.org/multipage/#the-input-element:concept-fe-mutable
@@ -908,24 +2107,22 @@ impl HTMLInputElement {
             _ => ()
         }
 
-        self.update_text_contents(self.DefaultValue(), true)
-            .expect("Failed to reset input value to default.");
+        if self.value_mode() == ValueMode::Value {
+            let value = DOMString::from(self.value_display().to_display(&self.DefaultValue()));
+            self.selection().reset(value);
+            self.sanitize_value();
+            // Sanitization may have shortened the value; the caret stays at its end.
+            self.textinput.borrow_mut().clear_selection_to_limit(Direction::Forward, true);
+            self.set_edited_by_user(false);
+            self.update_placeholder_shown_state();
+        } else {
+            self.update_text_contents(self.DefaultValue(), true)
+                .expect("Failed to reset input value to default.");
+        }
         self.value_dirty.set(false);
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 
-    fn update_placeholder_shown_state(&self) {
-        if !self.input_type().is_textual_or_password() {
-            return
-        }
-
-        let has_placeholder = !self.placeholder.borrow().is_empty();
-        let has_value = !self.textinput.borrow().is_empty();
-        let el = self.upcast::<Element>();
-
-        el.set_placeholder_shown_state(has_placeholder && !has_value);
-    }
-
     // https://html.spec.whatwg.org/multipage/#file-upload-state-(type=file)
     // Select files by invoking UI or by passed in argument
     fn select_files(&self, opt_test_paths: Option<Vec<DOMString>>) {
@@ -1001,61 +2198,35 @@ impl HTMLInputElement {
                 content.strip_newlines();
                 content.strip_leading_and_trailing_ascii_whitespace();
             }
-            InputType::Date => {
-                let mut textinput = self.textinput.borrow_mut();
-                if !textinput.single_line_content().is_valid_date_string() {
-                    textinput.single_line_content_mut().clear();
-                }
-            }
-            InputType::Month => {
+            InputType::Email => {
+                let multiple = self.Multiple();
                 let mut textinput = self.textinput.borrow_mut();
-                if !textinput.single_line_content().is_valid_month_string() {
-                    textinput.single_line_content_mut().clear();
-                }
+                let value = sanitize_email(textinput.single_line_content(), multiple);
+                *textinput.single_line_content_mut() = DOMString::from(value);
             }
-            InputType::Week => {
+            InputType::Date | InputType::Time | InputType::DatetimeLocal | InputType::Month | InputType::Week => {
+                let display = self.value_display();
                 let mut textinput = self.textinput.borrow_mut();
-                if !textinput.single_line_content().is_valid_week_string() {
-                    textinput.single_line_content_mut().clear();
-                }
+                let value = display.to_value(textinput.single_line_content());
+                *textinput.single_line_content_mut() = DOMString::from(display.to_display(&value));
             }
             InputType::Color => {
                 let mut textinput = self.textinput.borrow_mut();
 
-                let is_valid = {
-                    let content = textinput.single_line_content();
-                    let mut chars = content.chars();
-                    if content.len() == 7 && chars.next() == Some('#') {
-                        chars.all(|c| c.is_digit(16))
-                    } else {
-                        false
-                    }
-                };
-
-                if is_valid {
+                if is_valid_simple_color(textinput.single_line_content()) {
                     let content = textinput.single_line_content_mut();
                     content.make_ascii_lowercase();
                 } else {
                     textinput.set_content("#000000".into(), true);
                 }
             }
-            InputType::Time => {
-                let mut textinput = self.textinput.borrow_mut();
-
-                if !textinput.single_line_content().is_valid_time_string() {
-                    textinput.single_line_content_mut().clear();
-                }
-            }
-            InputType::DatetimeLocal => {
-                let mut textinput = self.textinput.borrow_mut();
-                if textinput.single_line_content_mut()
-                    .convert_valid_normalized_local_date_and_time_string().is_err() {
-                        textinput.single_line_content_mut().clear();
-                }
-            }
             InputType::Number => {
+                let display = self.value_display();
                 let mut textinput = self.textinput.borrow_mut();
-                if !textinput.single_line_content().is_valid_floating_point_number_string() {
+                let value = DOMString::from(display.to_value(textinput.single_line_content()));
+                if value.is_valid_floating_point_number_string() {
+                    *textinput.single_line_content_mut() = DOMString::from(display.to_display(&value));
+                } else {
                     textinput.single_line_content_mut().clear();
                 }
             }
@@ -1070,18 +2241,61 @@ impl HTMLInputElement {
         }
     }
 
-    #[allow(unrooted_must_root)]
-    fn selection(&self) -> TextControlSelection<Self> {
-        TextControlSelection::new(&self, &self.textinput)
+    // https://html.spec.whatwg.org/multipage/#the-required-attribute
+    fn value_missing_validity(&self) -> ValidationFlags {
+        // The value is already sanitized for its type, so an email or URL of only
+        // whitespace is missing too.
+        let applies = match self.input_type() {
+            InputType::Text | InputType::Search | InputType::Url | InputType::Tel
+            | InputType::Email | InputType::Password | InputType::Date | InputType::Month
+            | InputType::Week | InputType::Time | InputType::DatetimeLocal | InputType::Number => true,
+            _ => false,
+        };
+        if applies && self.Required() && self.is_mutable() && self.Value().is_empty() {
+            ValidationFlags::VALUE_MISSING
+        } else {
+            ValidationFlags::empty()
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-pattern-attribute
+    fn pattern_validity(&self) -> ValidationFlags {
+        let multiple = match self.input_type() {
+            InputType::Text | InputType::Search | InputType::Url | InputType::Tel
+            | InputType::Password => false,
+            InputType::Email => self.Multiple(),
+            _ => return ValidationFlags::empty(),
+        };
+        let pattern = match self.upcast::<Element>().get_attribute(&ns!(), &local_name!("pattern")) {
+            Some(pattern) => pattern,
+            None => return ValidationFlags::empty(),
+        };
+        // The pattern is only compiled again when the attribute changes.
+        let mut compiled = self.compiled_pattern.borrow_mut();
+        if compiled.as_ref().map_or(true, |compiled| compiled.source() != &**pattern.value()) {
+            *compiled = Some(CompiledPattern::new(&pattern.value()));
+        }
+        compiled_pattern_validity(compiled.as_ref().unwrap(), &self.Value(), multiple)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-maxlength-and-minlength-attributes
+    fn length_validity(&self) -> ValidationFlags {
+        if !self.input_type().limits_length() {
+            return ValidationFlags::empty();
+        }
+        self.selection().length_validity(self.value_dirty.get() && self.edited_by_user.get())
     }
 
     fn update_text_contents(&self, value: DOMString, update_text_cursor: bool) -> ErrorResult {
         match self.value_mode() {
             ValueMode::Value => {
+                let value = DOMString::from(self.value_display().to_display(&value));
+                self.selection().abandon_composition_for_value(&value);
                 // Steps 1-2.
                 let old_value = mem::replace(self.textinput.borrow_mut().single_line_content_mut(), value);
                 // Step 3.
                 self.value_dirty.set(true);
+                self.set_edited_by_user(false);
                 // Step 4.
                 if update_text_cursor {
                     self.sanitize_value();
@@ -1166,6 +2380,13 @@ impl VirtualMethods for HTMLInputElement {
                         let previously_selectable = self.selection_api_applies();
 
                         self.input_type.set(new_type);
+                        self.textinput.borrow_mut().set_obscured(new_type == InputType::Password);
+                        self.textinput.borrow_mut().set_char_filter(new_type.char_filter(self.value_display()));
+                        self.textinput.borrow_mut().set_key_hook(new_type.key_hook());
+                        self.textinput.borrow_mut().set_paste_sanitization(new_type.paste_sanitization());
+                        self.textinput.borrow_mut().set_clears_on_escape(new_type == InputType::Search);
+                        self.update_list_separator();
+                        self.set_caps_lock_warning(false);
 
                         if new_type.is_textual() {
                             let read_write = !(self.ReadOnly() || el.disabled_state());
@@ -1205,6 +2426,14 @@ impl VirtualMethods for HTMLInputElement {
                                 self.SetValue(DOMString::from(""))
                                     .expect("Failed to set input value on type change to ValueMode::Filename.");
                             }
+
+                            // The value is kept, but may be shown differently by the new type.
+                            (&ValueMode::Value, _, ValueMode::Value) => {
+                                let content = DOMString::from(self.value_display().to_display(&old_idl_value));
+                                if *self.textinput.borrow().single_line_content() != content {
+                                    self.textinput.borrow_mut().set_content(content, true);
+                                }
+                            }
                             _ => {}
                         }
 
@@ -1228,11 +2457,25 @@ impl VirtualMethods for HTMLInputElement {
                                 self,
                                 self.radio_group_name().as_ref());
                         }
+                        let previously_selectable = self.selection_api_applies();
                         self.input_type.set(InputType::default());
+                        self.textinput.borrow_mut().set_obscured(false);
+                        self.textinput.borrow_mut().set_char_filter(None);
+                        self.textinput.borrow_mut().set_key_hook(None);
+                        self.textinput.borrow_mut().set_paste_sanitization(InputType::default().paste_sanitization());
+                        self.textinput.borrow_mut().set_clears_on_escape(false);
+                        self.update_list_separator();
+                        self.set_caps_lock_warning(false);
                         let el = self.upcast::<Element>();
 
                         let read_write = !(self.ReadOnly() || el.disabled_state());
                         el.set_read_write_state(read_write);
+
+                        // https://html.spec.whatwg.org/multipage/#input-type-change
+                        // Steps 7-9, as the input goes back to the text type.
+                        if !previously_selectable {
+                            self.textinput.borrow_mut().clear_selection_to_limit(Direction::Backward, true);
+                        }
                     }
                 }
 
@@ -1245,6 +2488,14 @@ impl VirtualMethods for HTMLInputElement {
                 self.sanitize_value();
                 self.update_placeholder_shown_state();
             },
+            &local_name!("multiple") if self.input_type() == InputType::Email => {
+                self.update_list_separator();
+                self.sanitize_value();
+                self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+            },
+            &local_name!("step") if self.input_type().is_segmented() => {
+                self.update_segmented_display();
+            },
             &local_name!("name") if self.input_type() == InputType::Radio => {
                 self.radio_group_updated(
                     mutation.new_value(attr).as_ref().map(|name| name.as_atom()));
@@ -1284,15 +2535,15 @@ impl VirtualMethods for HTMLInputElement {
                 }
                 self.update_placeholder_shown_state();
             },
-            &local_name!("readonly") if self.input_type().is_textual() => {
-                let el = self.upcast::<Element>();
-                match mutation {
-                    AttributeMutation::Set(_) => {
-                        el.set_read_write_state(false);
-                    },
-                    AttributeMutation::Removed => {
-                        el.set_read_write_state(!el.disabled_state());
-                    }
+            &local_name!("readonly") => {
+                let read_only = match mutation {
+                    AttributeMutation::Set(_) => true,
+                    AttributeMutation::Removed => false,
+                };
+                self.textinput.borrow_mut().set_read_only(read_only);
+                if self.input_type().is_textual() {
+                    let el = self.upcast::<Element>();
+                    el.set_read_write_state(!read_only && !el.disabled_state());
                 }
             },
             &local_name!("form") => {
@@ -1346,8 +2597,8 @@ impl VirtualMethods for HTMLInputElement {
 
             document_from_node(self).request_focus(self.upcast());
             if self.input_type().is_textual_or_password() &&
-                // Check if we display a placeholder. Layout doesn't know about this.
-                !self.textinput.borrow().is_empty() {
+                // Layout has no text to find the index in while it shows the placeholder.
+                !self.textinput.borrow().shows_placeholder() {
                     if let Some(mouse_event) = event.downcast::<MouseEvent>() {
                         // dispatch_key_event (document.rs) triggers a click event when releasing
                         // the space key. There's no nice way to catch this so let's use this for
@@ -1359,7 +2610,18 @@ impl VirtualMethods for HTMLInputElement {
                                 point_in_target
                             );
                             if let Some(i) = index {
-                                self.textinput.borrow_mut().set_edit_point_index(i as usize);
+                                let original_selection_state = self.textinput.borrow().selection_state();
+                                if mouse_event.ShiftKey() {
+                                    self.textinput.borrow_mut().extend_selection_to_index(i as usize);
+                                } else {
+                                    self.textinput.borrow_mut().set_edit_point_index(i as usize);
+                                }
+                                if self.input_type().is_segmented() {
+                                    let content = self.textinput.borrow().get_content();
+                                    self.segmented_input.borrow_mut().focus_at(&content, i as usize);
+                                    self.select_focused_segment();
+                                }
+                                self.selection().selection_changed_by_user(original_selection_state);
                                 // trigger redraw
                                 self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                                 event.PreventDefault();
@@ -1370,39 +2632,96 @@ impl VirtualMethods for HTMLInputElement {
         } else if event.type_() == atom!("keydown") && !event.DefaultPrevented() &&
             self.input_type().is_textual_or_password() {
                 if let Some(keyevent) = event.downcast::<KeyboardEvent>() {
+                    if self.input_type().is_segmented() {
+                        return self.handle_segmented_keydown(keyevent);
+                    }
+                    if self.input_type() == InputType::Range {
+                        return self.handle_range_keydown(keyevent);
+                    }
+                    if self.input_type() == InputType::Password {
+                        self.update_caps_lock_warning(keyevent);
+                    }
                     // This can't be inlined, as holding on to textinput.borrow_mut()
                     // during self.implicit_submission will cause a panic.
-                    let action = self.textinput.borrow_mut().handle_keydown(keyevent);
+                    let (action, original_selection_state) = {
+                        let mut textinput = self.textinput.borrow_mut();
+                        let original_selection_state = textinput.selection_state();
+                        textinput.set_language(&self.upcast::<Element>().get_lang());
+                        // The decimal separator that may be typed depends on the language too.
+                        textinput.set_char_filter(self.input_type().char_filter(self.value_display()));
+                        (textinput.handle_keydown(keyevent), original_selection_state)
+                    };
+                    let action = self.selection().perform_edit(action);
                     match action {
-                        TriggerDefaultAction => {
+                        ImplicitSubmission => {
+                            // Search fields also tell the page that a search was asked for, as
+                            // they do in other browsers, whether or not there is a form.
+                            if self.input_type() == InputType::Search {
+                                self.upcast::<EventTarget>().fire_bubbling_event(atom!("search"));
+                            }
                             self.implicit_submission(keyevent.CtrlKey(),
                                                      keyevent.ShiftKey(),
                                                      keyevent.AltKey(),
                                                      keyevent.MetaKey());
                         },
-                        DispatchInput => {
+                        DispatchInput(_) => {
+                            self.value_dirty.set(true);
+                            self.update_placeholder_shown_state();
+                            self.update_suggestions();
+                            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            event.mark_as_handled();
+                        }
+                        Escape(EscapeOutcome::ContentCleared(_)) => {
+                            self.selection().end_cleared_content();
                             self.value_dirty.set(true);
                             self.update_placeholder_shown_state();
                             self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                             event.mark_as_handled();
                         }
-                        RedrawSelection => {
+                        RedrawSelection | SelectionCleared | Escape(EscapeOutcome::SelectionCleared) => {
+                            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            event.mark_as_handled();
+                        }
+                        ScrollCaretIntoView => {
+                            // TODO: scroll the caret into view once layout reports where it is.
+                            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            event.mark_as_handled();
+                        }
+                        CopyRequested => {
+                            self.selection().copy();
+                            event.mark_as_handled();
+                        }
+                        CutRequested => {
+                            if self.selection().cut() {
+                                self.value_dirty.set(true);
+                                self.update_placeholder_shown_state();
+                                self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                            }
+                            event.mark_as_handled();
+                        }
+                        PasteRequested => {
+                            self.selection().request_paste();
+                            event.mark_as_handled();
+                        }
+                        StepRequested(steps) => {
+                            self.step_by_user(steps);
+                            event.mark_as_handled();
+                        }
+                        CompositionCancelled(change) => {
+                            self.selection().end_cancelled_composition(change);
+                            self.update_placeholder_shown_state();
                             self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                             event.mark_as_handled();
                         }
-                        Nothing => (),
+                        // The key was meant for the control, even though it did nothing.
+                        Rejected | Escape(EscapeOutcome::CompositionCancelled) => event.mark_as_handled(),
+                        // There are no popups to close yet, so an Escape the control had no use
+                        // for is left to the page. Edits were carried out by perform_edit, and only
+                        // multiline controls have an action of their own for Return.
+                        BeforeInput(_) | FocusNavigation(_) | Escape(EscapeOutcome::Nothing) | Nothing |
+                        TriggerDefaultAction => (),
                     }
-                }
-        } else if event.type_() == atom!("keypress") && !event.DefaultPrevented() &&
-            self.input_type().is_textual_or_password() {
-                if event.IsTrusted() {
-                    let window = window_from_node(self);
-                    let _ = window.user_interaction_task_source()
-                                  .queue_event(&self.upcast(),
-                                               atom!("input"),
-                                               EventBubbles::Bubbles,
-                                               EventCancelable::NotCancelable,
-                                               &window);
+                    self.selection().selection_changed_by_user(original_selection_state);
                 }
             }
     }
@@ -1427,9 +2746,34 @@ impl Validatable for HTMLInputElement {
         // https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation
         true
     }
-    fn validate(&self, _validate_flags: ValidationFlags) -> bool {
+    fn validate(&self, validate_flags: ValidationFlags) -> bool {
         // call stub methods defined in validityState.rs file here according to the flags set in validate_flags
-        true
+        // No flags asks for every constraint, as when the form is submitted.
+        let flags = if validate_flags.is_empty() { ValidationFlags::all() } else { validate_flags };
+        let element = self.upcast::<Element>();
+        let attribute = |name: &LocalName| element.get_string_attribute(name);
+        let range_and_step = |validity: fn(&str, &str, &str, &str, &str) -> ValidationFlags| {
+            validity(&self.Value(),
+                     &attribute(&local_name!("min")),
+                     &attribute(&local_name!("max")),
+                     &attribute(&local_name!("step")),
+                     &attribute(&local_name!("value")))
+        };
+        let failed = match self.input_type() {
+            InputType::Time => range_and_step(time_validity),
+            InputType::DatetimeLocal => range_and_step(local_date_and_time_validity),
+            InputType::Email => email_validity(&self.Value(), self.Multiple()),
+            InputType::Url => url_validity(&self.Value()),
+            _ => ValidationFlags::empty(),
+        };
+        !flags.intersects(failed | self.value_missing_validity() | self.length_validity() | self.pattern_validity())
+    }
+
+    fn validation_message(&self) -> DOMString {
+        if !self.value_missing_validity().is_empty() {
+            return DOMString::from(VALUE_MISSING_MESSAGE);
+        }
+        self.selection().length_validation_message(self.length_validity()).unwrap_or_default()
     }
 }
 
@@ -1444,8 +2788,9 @@ impl Activatable for HTMLInputElement {
             // https://html.spec.whatwg.org/multipage/#reset-button-state-%28type=reset%29:activation-behaviour-2
             // https://html.spec.whatwg.org/multipage/#checkbox-state-%28type=checkbox%29:activation-behaviour-2
             // https://html.spec.whatwg.org/multipage/#radio-button-state-%28type=radio%29:activation-behaviour-2
+            // https://html.spec.whatwg.org/multipage/#color-state-(type=color):activation-behaviour
             InputType::Submit | InputType::Reset | InputType::File
-            | InputType::Checkbox | InputType::Radio => self.is_mutable(),
+            | InputType::Checkbox | InputType::Radio | InputType::Color => self.is_mutable(),
             _ => false
         }
     }
@@ -1581,6 +2926,7 @@ impl Activatable for HTMLInputElement {
                 target.fire_bubbling_event(atom!("change"));
             },
             InputType::File => self.select_files(None),
+            InputType::Color => self.pick_color(),
             _ => ()
         }
     }
@@ -1599,43 +2945,19 @@ impl Activatable for HTMLInputElement {
         if self.upcast::<Element>().click_in_progress() {
             return;
         }
-        let submit_button;
-        submit_button = node.query_selector_iter(DOMString::from("input[type=submit]")).unwrap()
+        if form.activate_default_button(ctrl_key, shift_key, alt_key, meta_key) {
+            return;
+        }
+        let inputs = node.query_selector_iter(DOMString::from("input")).unwrap()
             .filter_map(DomRoot::downcast::<HTMLInputElement>)
-            .find(|r| r.form_owner() == owner);
-        match submit_button {
-            Some(ref button) => {
-                if button.is_instance_activatable() {
-                    synthetic_click_activation(button.as_element(),
-                                               ctrl_key,
-                                               shift_key,
-                                               alt_key,
-                                               meta_key,
-                                               ActivationSource::NotFromClick)
-                }
-            }
-            None => {
-                let inputs = node.query_selector_iter(DOMString::from("input")).unwrap()
-                    .filter_map(DomRoot::downcast::<HTMLInputElement>)
-                    .filter(|input| {
-                        input.form_owner() == owner && match input.input_type() {
-                            InputType::Text | InputType::Search | InputType::Url | InputType::Tel
-                            | InputType::Email | InputType::Password | InputType::Date
-                            | InputType::Month | InputType::Week | InputType::Time
-                            | InputType::DatetimeLocal | InputType::Number
-                              => true,
-                            _ => false
-                        }
-                    });
+            .filter(|input| input.form_owner() == owner && input.input_type().blocks_implicit_submission());
 
-                if inputs.skip(1).next().is_some() {
-                    // lazily test for > 1 submission-blocking inputs
-                    return;
-                }
-                form.submit(SubmittedFrom::NotFromForm,
-                            FormSubmitter::FormElement(&form));
-            }
+        if inputs.skip(1).next().is_some() {
+            // lazily test for > 1 submission-blocking inputs
+            return;
         }
+        form.submit(SubmittedFrom::NotFromForm,
+                    FormSubmitter::FormElement(&form));
     }
 }
 
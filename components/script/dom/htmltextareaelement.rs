@@ -2,12 +2,15 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
+use clipboard_provider::{TextControlClipboard, text_control_clipboard};
 use dom::attr::Attr;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
 use dom::bindings::codegen::Bindings::HTMLFormElementBinding::SelectionMode;
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding;
 use dom::bindings::codegen::Bindings::HTMLTextAreaElementBinding::HTMLTextAreaElementMethods;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventMethods;
 use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::error::ErrorResult;
 use dom::bindings::inheritance::Castable;
@@ -16,36 +19,47 @@ use dom::bindings::str::DOMString;
 use dom::document::Document;
 use dom::element::{AttributeMutation, Element};
 use dom::element::RawLayoutElementHelpers;
-use dom::event::{Event, EventBubbles, EventCancelable};
+use dom::event::Event;
+use dom::eventtarget::EventTarget;
 use dom::globalscope::GlobalScope;
 use dom::htmlelement::HTMLElement;
 use dom::htmlfieldsetelement::HTMLFieldSetElement;
-use dom::htmlformelement::{FormControl, HTMLFormElement};
+use dom::htmlformelement::{FormControl, FormSubmitter, HTMLFormElement, SubmittedFrom};
 use dom::keyboardevent::KeyboardEvent;
-use dom::node::{ChildrenMutation, Node, NodeDamage, UnbindContext};
+use dom::node::{ChildrenMutation, CloneChildrenFlag, Node, NodeDamage, UnbindContext};
 use dom::node::{document_from_node, window_from_node};
 use dom::nodelist::NodeList;
-use dom::textcontrol::{TextControlElement, TextControlSelection};
-use dom::validation::Validatable;
+use dom::textcontrol::TextControlElement;
+use dom::textcontrol::text_control_directionality;
+use dom::validation::{VALUE_MISSING_MESSAGE, Validatable, check_validity, report_validity};
+use dom::validitystate::{ValidationFlags, ValidityState};
 use dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
-use script_traits::ScriptToConstellationChan;
+use msg::constellation_msg::EnterKeyHint;
+use servo_config::prefs::PREFS;
 use std::cell::Cell;
 use std::default::Default;
 use std::ops::Range;
 use style::attr::AttrValue;
 use style::element_state::ElementState;
-use textinput::{Direction, KeyReaction, Lines, SelectionDirection, TextInput};
+use text_buffer::GapBuffer;
+use textinput::{Direction, EscapeOutcome, KeyReaction, Lines, PasteSanitization, SelectionDirection};
+use textinput::{TextDirection, TextInput, TextPoint};
 
 #[dom_struct]
 pub struct HTMLTextAreaElement {
     htmlelement: HTMLElement,
     #[ignore_malloc_size_of = "#7193"]
-    textinput: DomRefCell<TextInput<ScriptToConstellationChan>>,
+    textinput: DomRefCell<TextInput<TextControlClipboard, GapBuffer>>,
     placeholder: DomRefCell<DOMString>,
+    // Where the rendered text wraps, as reported by layout, for the wrap=hard transformation.
+    // `None` until layout reports them again after the content changes.
+    wrap_points: DomRefCell<Option<Vec<TextPoint>>>,
     // https://html.spec.whatwg.org/multipage/#concept-textarea-dirty
     value_dirty: Cell<bool>,
+    /// Whether the value was last changed by a user edit, rather than by a script.
+    edited_by_user: Cell<bool>,
     form_owner: MutNullableDom<HTMLFormElement>,
 }
 
@@ -55,6 +69,8 @@ pub trait LayoutHTMLTextAreaElementHelpers {
     #[allow(unsafe_code)]
     unsafe fn selection_for_layout(self) -> Option<Range<usize>>;
     #[allow(unsafe_code)]
+    unsafe fn misspellings_for_layout(self) -> Vec<Range<usize>>;
+    #[allow(unsafe_code)]
     fn get_cols(self) -> u32;
     #[allow(unsafe_code)]
     fn get_rows(self) -> u32;
@@ -64,14 +80,14 @@ impl LayoutHTMLTextAreaElementHelpers for LayoutDom<HTMLTextAreaElement> {
     #[allow(unrooted_must_root)]
     #[allow(unsafe_code)]
     unsafe fn value_for_layout(self) -> String {
-        let text = (*self.unsafe_get()).textinput.borrow_for_layout().get_content();
-        if text.is_empty() {
+        let textinput = (*self.unsafe_get()).textinput.borrow_for_layout();
+        if textinput.shows_placeholder() {
             (*self.unsafe_get()).placeholder
                 .borrow_for_layout()
                 .replace("\r\n", "\n")
                 .replace("\r", "\n")
         } else {
-            text.into()
+            textinput.lines().collect::<Vec<_>>().join("\n")
         }
     }
 
@@ -85,6 +101,16 @@ impl LayoutHTMLTextAreaElementHelpers for LayoutDom<HTMLTextAreaElement> {
         Some(textinput.sorted_selection_offsets_range())
     }
 
+    #[allow(unrooted_must_root)]
+    #[allow(unsafe_code)]
+    unsafe fn misspellings_for_layout(self) -> Vec<Range<usize>> {
+        let textinput = (*self.unsafe_get()).textinput.borrow_for_layout();
+        if textinput.shows_placeholder() {
+            return vec!();
+        }
+        textinput.misspellings().to_vec()
+    }
+
     #[allow(unsafe_code)]
     fn get_cols(self) -> u32 {
         unsafe {
@@ -109,21 +135,42 @@ static DEFAULT_COLS: u32 = 20;
 
 // https://html.spec.whatwg.org/multipage/#attr-textarea-rows-value
 static DEFAULT_ROWS: u32 = 2;
+static DEFAULT_MAX_LENGTH: i32 = -1;
+static DEFAULT_MIN_LENGTH: i32 = -1;
+
+// The limit that a `maxlength` or `minlength` attribute sets, if it is valid.
+fn length_limit(value: &AttrValue) -> Option<usize> {
+    match *value {
+        AttrValue::Int(_, limit) if limit >= 0 => Some(limit as usize),
+        _ => None,
+    }
+}
 
 impl HTMLTextAreaElement {
     fn new_inherited(local_name: LocalName,
                      prefix: Option<Prefix>,
                      document: &Document) -> HTMLTextAreaElement {
         let chan = document.window().upcast::<GlobalScope>().script_to_constellation_chan().clone();
+        let mut textinput: TextInput<_, GapBuffer> = TextInput::with_buffer(
+            Lines::Multiple, DOMString::new(), text_control_clipboard(chan), None, None, SelectionDirection::None);
+        textinput.set_visible_rows(Some(DEFAULT_ROWS as usize));
+        textinput.set_inserts_tabs(PREFS.get("dom.textarea.tab_insertion.enabled").as_boolean().unwrap_or(false));
+        textinput.set_paste_sanitization(PasteSanitization {
+            strip_control_characters: true,
+            isolate_bidi_controls: false,
+            trim_whitespace: false,
+        });
+        textinput.set_defers_edits(true);
         HTMLTextAreaElement {
             htmlelement:
                 HTMLElement::new_inherited_with_state(ElementState::IN_ENABLED_STATE |
                                                       ElementState::IN_READ_WRITE_STATE,
                                                       local_name, prefix, document),
             placeholder: DomRefCell::new(DOMString::new()),
-            textinput: DomRefCell::new(TextInput::new(
-                    Lines::Multiple, DOMString::new(), chan, None, None, SelectionDirection::None)),
+            textinput: DomRefCell::new(textinput),
+            wrap_points: DomRefCell::new(None),
             value_dirty: Cell::new(false),
+            edited_by_user: Cell::new(false),
             form_owner: Default::default(),
         }
     }
@@ -137,15 +184,34 @@ impl HTMLTextAreaElement {
                            HTMLTextAreaElementBinding::Wrap)
     }
 
-    fn update_placeholder_shown_state(&self) {
-        let has_placeholder = !self.placeholder.borrow().is_empty();
-        let has_value = !self.textinput.borrow().is_empty();
-        let el = self.upcast::<Element>();
-        el.set_placeholder_shown_state(has_placeholder && !has_value);
+    /// The directionality of the control, which `dir=auto` takes from its value.
+    pub fn directionality(&self) -> TextDirection {
+        text_control_directionality(self.upcast(), &self.textinput.borrow())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#attr-textarea-required
+    fn value_missing_validity(&self) -> ValidationFlags {
+        let mutable = !(self.upcast::<Element>().disabled_state() || self.ReadOnly());
+        if self.Required() && mutable && self.textinput.borrow().is_empty() {
+            ValidationFlags::VALUE_MISSING
+        } else {
+            ValidationFlags::empty()
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#limiting-user-input-length:-the-maxlength-attribute
+    fn length_validity(&self) -> ValidationFlags {
+        self.selection().length_validity(self.value_dirty.get() && self.edited_by_user.get())
     }
 }
 
 impl TextControlElement for HTMLTextAreaElement {
+    type Buffer = GapBuffer;
+
+    fn textinput(&self) -> &DomRefCell<TextInput<TextControlClipboard, GapBuffer>> {
+        &self.textinput
+    }
+
     fn selection_api_applies(&self) -> bool {
         true
     }
@@ -154,8 +220,30 @@ impl TextControlElement for HTMLTextAreaElement {
         true
     }
 
+    fn takes_text_input(&self) -> bool {
+        true
+    }
+
     fn set_dirty_value_flag(&self, value: bool) {
-        self.value_dirty.set(value)
+        self.value_dirty.set(value);
+        // Every edit goes through here, and the text no longer wraps where it did.
+        self.clear_wrap_points();
+    }
+
+    fn set_edited_by_user(&self, edited: bool) {
+        self.edited_by_user.set(edited);
+        self.upcast::<Element>().set_autofill_state(false);
+    }
+
+    fn update_placeholder_shown_state(&self) {
+        let has_placeholder = !self.placeholder.borrow().is_empty();
+        let shows_placeholder = self.textinput.borrow().shows_placeholder();
+        let el = self.upcast::<Element>();
+        el.set_placeholder_shown_state(has_placeholder && shows_placeholder);
+    }
+
+    fn spellcheck_enabled(&self) -> bool {
+        self.upcast::<HTMLElement>().spellcheck_enabled()
     }
 }
 
@@ -169,6 +257,12 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
     // https://html.spec.whatwg.org/multipage/#dom-textarea-cols
     make_limited_uint_setter!(SetCols, "cols", DEFAULT_COLS);
 
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-dirname
+    make_getter!(DirName, "dirname");
+
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-dirname
+    make_setter!(SetDirName, "dirname");
+
     // https://html.spec.whatwg.org/multipage/#dom-fe-disabled
     make_bool_getter!(Disabled, "disabled");
 
@@ -180,6 +274,18 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
         self.form_owner()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-maxlength
+    make_int_getter!(MaxLength, "maxlength", DEFAULT_MAX_LENGTH);
+
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-maxlength
+    make_limited_int_setter!(SetMaxLength, "maxlength", DEFAULT_MAX_LENGTH);
+
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-minlength
+    make_int_getter!(MinLength, "minlength", DEFAULT_MIN_LENGTH);
+
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-minlength
+    make_limited_int_setter!(SetMinLength, "minlength", DEFAULT_MIN_LENGTH);
+
     // https://html.spec.whatwg.org/multipage/#attr-fe-name
     make_getter!(Name, "name");
 
@@ -223,23 +329,19 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea-defaultvalue
     fn DefaultValue(&self) -> DOMString {
-        self.upcast::<Node>().GetTextContent().unwrap()
+        self.upcast::<Node>().child_text_content()
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea-defaultvalue
     fn SetDefaultValue(&self, value: DOMString) {
+        // Replacing the children updates the raw value in children_changed, unless the value is
+        // dirty.
         self.upcast::<Node>().SetTextContent(Some(value));
-
-        // if the element's dirty value flag is false, then the element's
-        // raw value must be set to the value of the element's textContent IDL attribute
-        if !self.value_dirty.get() {
-            self.reset(false);
-        }
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea-value
     fn Value(&self) -> DOMString {
-        self.textinput.borrow().get_content()
+        self.api_value()
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea-value
@@ -247,6 +349,32 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
         self.update_text_contents(value, true);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-textarea-textlength
+    fn TextLength(&self) -> u32 {
+        self.textinput.borrow().utf16_len() as u32
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
+    fn Validity(&self) -> DomRoot<ValidityState> {
+        let window = window_from_node(self);
+        ValidityState::new(&window, self.upcast())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn ValidationMessage(&self) -> DOMString {
+        self.validation_message()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        check_validity(self.upcast())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        report_validity(self.upcast())
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-lfe-labels
     fn Labels(&self) -> DomRoot<NodeList> {
         self.upcast::<HTMLElement>().labels()
@@ -306,36 +434,136 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 
 
 impl HTMLTextAreaElement {
-    pub fn reset(&self,  update_text_cursor: bool) {
-        // https://html.spec.whatwg.org/multipage/#the-textarea-element:concept-form-reset-control
-        self.update_text_contents(self.DefaultValue(), update_text_cursor);
-        self.value_dirty.set(false);
+    // https://html.spec.whatwg.org/multipage/#the-textarea-element:concept-form-reset-control
+    pub fn reset(&self) {
+        self.selection().reset(self.DefaultValue());
+        self.set_dirty_value_flag(false);
+        self.set_edited_by_user(false);
+        self.update_placeholder_shown_state();
+    }
+
+    /// Show the default value as it changes, while the user hasn't changed the value, keeping
+    /// the caret where it is.
+    fn update_to_default_value(&self) {
+        self.update_text_contents(self.DefaultValue(), false);
+        self.set_dirty_value_flag(false);
+    }
+
+    /// Fill in the value on the user's behalf, as form autofill does, as far as `maxlength`
+    /// allows, and fire input and change. The textarea matches `:autofill` until its value is
+    /// changed otherwise.
+    pub fn autofill(&self, value: DOMString) {
+        if self.upcast::<Element>().disabled_state() || self.ReadOnly() {
+            return;
+        }
+        self.selection().abandon_composition_for_value(&value);
+        self.textinput.borrow_mut().set_content_within_max_length(value);
+        self.set_dirty_value_flag(true);
+        self.set_edited_by_user(true);
+        self.upcast::<Element>().set_autofill_state(true);
+        self.update_placeholder_shown_state();
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        let target = self.upcast::<EventTarget>();
+        target.fire_bubbling_event(atom!("input"));
+        target.fire_bubbling_event(atom!("change"));
     }
 
-    #[allow(unrooted_must_root)]
-    fn selection(&self) -> TextControlSelection<Self> {
-        TextControlSelection::new(&self, &self.textinput)
+    // https://html.spec.whatwg.org/multipage/#concept-textarea-api-value
+    pub fn api_value(&self) -> DOMString {
+        self.textinput.borrow().get_content()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-fe-value
+    // The value used for form submission, in which line breaks are CRLF pairs.
+    pub fn value(&self) -> DOMString {
+        if self.Wrap().eq_ignore_ascii_case("hard") {
+            // https://html.spec.whatwg.org/multipage/#textarea-wrapping-transformation
+            if self.wrap_points().is_none() {
+                let node = self.upcast::<Node>();
+                let wrap_points = window_from_node(self).text_wrap_points_query(node.to_trusted_node_address());
+                if let Some(wrap_points) = wrap_points {
+                    self.set_wrap_points(wrap_points.into_iter().map(|(line, index)| {
+                        TextPoint { line: line, index: index }
+                    }).collect());
+                }
+            }
+            let textinput = self.textinput.borrow();
+            // Without a rendering, the text is wrapped as it is laid out by default, `cols`
+            // characters wide.
+            let wrap_points = self.wrap_points().unwrap_or_else(|| {
+                textinput.soft_wrap_points(self.Cols() as usize)
+            });
+            textinput.get_content_with_hard_wraps(&wrap_points)
+        } else {
+            self.textinput.borrow().get_content_with_crlf()
+        }
+    }
+
+    /// Record where the rendered text wraps, so that the wrap=hard transformation can turn these
+    /// points into line breaks when the form is submitted. The editing buffer is not affected.
+    pub fn set_wrap_points(&self, wrap_points: Vec<TextPoint>) {
+        *self.wrap_points.borrow_mut() = Some(wrap_points);
+    }
+
+    /// Where the rendered text wraps, if layout has reported it since the content last changed.
+    pub fn wrap_points(&self) -> Option<Vec<TextPoint>> {
+        self.wrap_points.borrow().clone()
+    }
+
+    fn clear_wrap_points(&self) {
+        *self.wrap_points.borrow_mut() = None;
+    }
+
+    /// The action that Return performs instead of inserting a newline, if it is enabled and
+    /// `enterkeyhint` names one that this control can carry out.
+    fn enter_key_action(&self) -> Option<EnterKeyHint> {
+        if !PREFS.get("dom.textarea.enterkeyhint_actions.enabled").as_boolean().unwrap_or(false) {
+            return None;
+        }
+        match self.enter_key_hint() {
+            Some(EnterKeyHint::Done) => Some(EnterKeyHint::Done),
+            Some(hint @ EnterKeyHint::Go) |
+            Some(hint @ EnterKeyHint::Search) |
+            Some(hint @ EnterKeyHint::Send) if self.form_owner().is_some() => Some(hint),
+            _ => None,
+        }
+    }
+
+    /// Carry out the action of the enter key, submitting the form as pressing Return in one of
+    /// its fields does.
+    fn perform_enter_key_action(&self, action: EnterKeyHint, event: &KeyboardEvent) {
+        match action {
+            EnterKeyHint::Done => self.upcast::<HTMLElement>().Blur(),
+            _ => {
+                let form = match self.form_owner() {
+                    Some(form) => form,
+                    None => return,
+                };
+                if !form.activate_default_button(event.CtrlKey(), event.ShiftKey(), event.AltKey(), event.MetaKey()) {
+                    form.submit(SubmittedFrom::NotFromForm, FormSubmitter::FormElement(&form));
+                }
+            },
+        }
     }
 
     // Helper function to check if text_cursor is to be updated or not
     fn update_text_contents(&self, value: DOMString, update_text_cursor: bool) {
+        self.selection().abandon_composition_for_value(&value);
         let mut textinput = self.textinput.borrow_mut();
 
         // Step 1
         let old_value = textinput.get_content();
-        let old_selection = textinput.selection_origin;
 
         // Step 2
         textinput.set_content(value, update_text_cursor);
 
         // Step 3
-        self.value_dirty.set(true);
+        self.set_dirty_value_flag(true);
+        self.set_edited_by_user(false);
 
+        // Step 4; setting the same value keeps the selection.
         if old_value != textinput.get_content() {
-            // Step 4
             textinput.clear_selection_to_limit(Direction::Forward, update_text_cursor);
-        } else {
-            textinput.selection_origin = old_selection;
         }
 
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
@@ -386,12 +614,30 @@ impl VirtualMethods for HTMLTextAreaElement {
                 match mutation {
                     AttributeMutation::Set(_) => {
                         el.set_read_write_state(false);
+                        self.textinput.borrow_mut().set_read_only(true);
                     },
                     AttributeMutation::Removed => {
                         el.set_read_write_state(!el.disabled_state());
+                        self.textinput.borrow_mut().set_read_only(false);
                     }
                 }
             },
+            local_name!("cols") | local_name!("wrap") => {
+                // The text is laid out anew, and may wrap elsewhere.
+                self.clear_wrap_points();
+            },
+            local_name!("rows") => {
+                let rows = mutation.new_value(attr).map_or(DEFAULT_ROWS, |value| value.as_uint());
+                self.textinput.borrow_mut().set_visible_rows(Some(rows as usize));
+            },
+            local_name!("maxlength") => {
+                let limit = mutation.new_value(attr).and_then(|value| length_limit(&value));
+                self.textinput.borrow_mut().max_length = limit;
+            },
+            local_name!("minlength") => {
+                let limit = mutation.new_value(attr).and_then(|value| length_limit(&value));
+                self.textinput.borrow_mut().min_length = limit;
+            },
             local_name!("form") => {
                 self.form_attribute_mutated(mutation);
             },
@@ -411,6 +657,8 @@ impl VirtualMethods for HTMLTextAreaElement {
         match *name {
             local_name!("cols") => AttrValue::from_limited_u32(value.into(), DEFAULT_COLS),
             local_name!("rows") => AttrValue::from_limited_u32(value.into(), DEFAULT_ROWS),
+            local_name!("maxlength") => AttrValue::from_limited_i32(value.into(), DEFAULT_MAX_LENGTH),
+            local_name!("minlength") => AttrValue::from_limited_i32(value.into(), DEFAULT_MIN_LENGTH),
             _ => self.super_type().unwrap().parse_plain_attribute(name, value),
         }
     }
@@ -431,11 +679,24 @@ impl VirtualMethods for HTMLTextAreaElement {
         if let Some(ref s) = self.super_type() {
             s.children_changed(mutation);
         }
+        // https://html.spec.whatwg.org/multipage/#the-textarea-element:child-text-content
         if !self.value_dirty.get() {
-            self.reset(false);
+            self.update_to_default_value();
         }
     }
 
+    // https://html.spec.whatwg.org/multipage/#the-textarea-element:concept-node-clone-ext
+    fn cloning_steps(&self, copy: &Node, maybe_doc: Option<&Document>,
+                     clone_children: CloneChildrenFlag) {
+        if let Some(ref s) = self.super_type() {
+            s.cloning_steps(copy, maybe_doc, clone_children);
+        }
+        // A dirty copy keeps this value when its children are cloned after this.
+        let copy = copy.downcast::<HTMLTextAreaElement>().unwrap();
+        copy.value_dirty.set(self.value_dirty.get());
+        copy.textinput.borrow_mut().set_content(self.textinput.borrow().get_content(), true);
+    }
+
     // copied and modified from htmlinputelement.rs
     fn handle_event(&self, event: &Event) {
         if let Some(s) = self.super_type() {
@@ -450,31 +711,73 @@ impl VirtualMethods for HTMLTextAreaElement {
             if let Some(kevent) = event.downcast::<KeyboardEvent>() {
                 // This can't be inlined, as holding on to textinput.borrow_mut()
                 // during self.implicit_submission will cause a panic.
-                let action = self.textinput.borrow_mut().handle_keydown(kevent);
+                let enter_key_action = self.enter_key_action();
+                let (action, original_selection_state) = {
+                    let mut textinput = self.textinput.borrow_mut();
+                    let original_selection_state = textinput.selection_state();
+                    textinput.set_language(&self.upcast::<Element>().get_lang());
+                    textinput.set_enter_triggers_action(enter_key_action.is_some());
+                    (textinput.handle_keydown(kevent), original_selection_state)
+                };
+                let action = self.selection().perform_edit(action);
                 match action {
-                    KeyReaction::TriggerDefaultAction => (),
-                    KeyReaction::DispatchInput => {
-                        self.value_dirty.set(true);
+                    KeyReaction::TriggerDefaultAction => {
+                        if let Some(enter_key_action) = enter_key_action {
+                            self.perform_enter_key_action(enter_key_action, kevent);
+                        }
+                    },
+                    KeyReaction::DispatchInput(_) | KeyReaction::Escape(EscapeOutcome::ContentCleared(_)) => {
+                        self.set_dirty_value_flag(true);
                         self.update_placeholder_shown_state();
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     }
-                    KeyReaction::RedrawSelection => {
+                    KeyReaction::RedrawSelection |
+                    KeyReaction::SelectionCleared |
+                    KeyReaction::Escape(EscapeOutcome::SelectionCleared) => {
+                        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                        event.mark_as_handled();
+                    }
+                    KeyReaction::ScrollCaretIntoView => {
+                        // TODO: scroll the caret into view once layout reports where it is.
+                        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                        event.mark_as_handled();
+                    }
+                    KeyReaction::CopyRequested => {
+                        self.selection().copy();
+                        event.mark_as_handled();
+                    }
+                    KeyReaction::CutRequested => {
+                        if self.selection().cut() {
+                            self.set_dirty_value_flag(true);
+                            self.update_placeholder_shown_state();
+                            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+                        }
+                        event.mark_as_handled();
+                    }
+                    KeyReaction::PasteRequested => {
+                        self.selection().request_paste();
+                        event.mark_as_handled();
+                    }
+                    KeyReaction::CompositionCancelled(change) => {
+                        self.selection().end_cancelled_composition(change);
+                        self.update_placeholder_shown_state();
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     }
+                    // The key was meant for the control, even though it did nothing.
+                    KeyReaction::Rejected |
+                    KeyReaction::Escape(EscapeOutcome::CompositionCancelled) => event.mark_as_handled(),
+                    // Edits were carried out by perform_edit, and textareas don't step or submit
+                    // their form when Return is pressed.
+                    KeyReaction::BeforeInput(_) |
+                    KeyReaction::ImplicitSubmission |
+                    KeyReaction::StepRequested(_) |
+                    KeyReaction::FocusNavigation(_) |
+                    KeyReaction::Escape(EscapeOutcome::Nothing) |
                     KeyReaction::Nothing => (),
                 }
-            }
-        } else if event.type_() == atom!("keypress") && !event.DefaultPrevented() {
-            if event.IsTrusted() {
-                let window = window_from_node(self);
-                let _ = window.user_interaction_task_source()
-                              .queue_event(&self.upcast(),
-                                           atom!("input"),
-                                           EventBubbles::Bubbles,
-                                           EventCancelable::NotCancelable,
-                                           &window);
+                self.selection().selection_changed_by_user(original_selection_state);
             }
         }
     }
@@ -483,7 +786,7 @@ impl VirtualMethods for HTMLTextAreaElement {
         self.super_type().unwrap().pop();
 
         // https://html.spec.whatwg.org/multipage/#the-textarea-element:stack-of-open-elements
-        self.reset(false);
+        self.update_to_default_value();
     }
 }
 
@@ -502,4 +805,17 @@ impl FormControl for HTMLTextAreaElement {
 }
 
 
-impl Validatable for HTMLTextAreaElement {}
+impl Validatable for HTMLTextAreaElement {
+    fn validate(&self, validate_flags: ValidationFlags) -> bool {
+        // No flags asks for every constraint, as when the form is submitted.
+        let flags = if validate_flags.is_empty() { ValidationFlags::all() } else { validate_flags };
+        !flags.intersects(self.value_missing_validity() | self.length_validity())
+    }
+
+    fn validation_message(&self) -> DOMString {
+        if !self.value_missing_validity().is_empty() {
+            return DOMString::from(VALUE_MISSING_MESSAGE);
+        }
+        self.selection().length_validation_message(self.length_validity()).unwrap_or_default()
+    }
+}
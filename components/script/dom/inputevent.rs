@@ -5,9 +5,13 @@
 use dom::bindings::codegen::Bindings::InputEventBinding::{self, InputEventMethods};
 use dom::bindings::codegen::Bindings::UIEventBinding::UIEventBinding::UIEventMethods;
 use dom::bindings::error::Fallible;
+use dom::bindings::inheritance::Castable;
 use dom::bindings::reflector::reflect_dom_object;
-use dom::bindings::root::{DomRoot, RootedReference};
+use dom::bindings::root::{DomRoot, MutNullableDom, RootedReference};
 use dom::bindings::str::DOMString;
+use dom::datatransfer::DataTransfer;
+use dom::event::{Event, EventStatus};
+use dom::node::{Node, window_from_node};
 use dom::uievent::UIEvent;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -17,6 +21,8 @@ pub struct InputEvent {
     uievent: UIEvent,
     data: Option<DOMString>,
     is_composing: bool,
+    input_type: DOMString,
+    data_transfer: MutNullableDom<DataTransfer>,
 }
 
 impl InputEvent {
@@ -27,11 +33,15 @@ impl InputEvent {
                view: Option<&Window>,
                detail: i32,
                data: Option<DOMString>,
-               is_composing: bool) -> DomRoot<InputEvent> {
+               is_composing: bool,
+               input_type: DOMString,
+               data_transfer: Option<&DataTransfer>) -> DomRoot<InputEvent> {
         let ev = reflect_dom_object(Box::new(InputEvent {
                                         uievent: UIEvent::new_inherited(),
                                         data: data,
                                         is_composing: is_composing,
+                                        input_type: input_type,
+                                        data_transfer: MutNullableDom::new(data_transfer),
                                     }),
                                     window,
                                     InputEventBinding::Wrap);
@@ -50,9 +60,35 @@ impl InputEvent {
                                     init.parent.view.r(),
                                     init.parent.detail,
                                     init.data.clone(),
-                                    init.isComposing);
+                                    init.isComposing,
+                                    init.inputType.clone(),
+                                    init.dataTransfer.r());
         Ok(event)
     }
+
+    /// Fire a `beforeinput` or `input` event at `target` for an edit of its content, of which
+    /// only `beforeinput` can be cancelled.
+    ///
+    /// https://w3c.github.io/input-events/#events-inputevents
+    pub fn fire_for_edit(target: &Node,
+                         type_: &str,
+                         input_type: &str,
+                         data: Option<DOMString>,
+                         is_composing: bool,
+                         data_transfer: Option<&DataTransfer>) -> EventStatus {
+        let window = window_from_node(target);
+        let event = InputEvent::new(&window,
+                                    DOMString::from(type_),
+                                    true,
+                                    type_ == "beforeinput",
+                                    Some(&window),
+                                    0,
+                                    data,
+                                    is_composing,
+                                    DOMString::from(input_type),
+                                    data_transfer);
+        event.upcast::<Event>().fire(target.upcast())
+    }
 }
 
 impl InputEventMethods for InputEvent {
@@ -66,6 +102,16 @@ impl InputEventMethods for InputEvent {
         self.is_composing
     }
 
+    // https://w3c.github.io/input-events/#dom-inputevent-inputtype
+    fn InputType(&self) -> DOMString {
+        self.input_type.clone()
+    }
+
+    // https://w3c.github.io/input-events/#dom-inputevent-datatransfer
+    fn GetDataTransfer(&self) -> Option<DomRoot<DataTransfer>> {
+        self.data_transfer.get()
+    }
+
     // https://dom.spec.whatwg.org/#dom-event-istrusted
     fn IsTrusted(&self) -> bool {
         self.uievent.IsTrusted()
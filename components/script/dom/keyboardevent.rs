@@ -160,6 +160,11 @@ impl KeyboardEvent {
 
 // https://w3c.github.io/uievents-key/#key-value-tables
 pub fn key_value(ch: Option<char>, key: Key, mods: KeyModifiers) -> Cow<'static, str> {
+    // The character sent with a dead key is the accent it adds, not the key value.
+    if key == Key::Dead {
+        return Cow::from("Dead");
+    }
+
     if let Some(ch) = ch {
         return Cow::from(format!("{}", ch));
     }
@@ -335,10 +340,12 @@ pub fn key_value(ch: Option<char>, key: Key, mods: KeyModifiers) -> Cow<'static,
         Key::Menu => "ContextMenu",
         Key::NavigateForward => "BrowserForward",
         Key::NavigateBackward => "BrowserBack",
+        Key::Dead => "Dead",
+        Key::Compose => "Compose",
     })
 }
 
-fn key_from_string(key_string: &str, location: u32) -> Option<Key> {
+pub fn key_from_string(key_string: &str, location: u32) -> Option<Key> {
     match key_string {
         " " => Some(Key::Space),
         "\"" => Some(Key::Apostrophe),
@@ -507,6 +514,8 @@ fn key_from_string(key_string: &str, location: u32) -> Option<Key> {
         "ContextMenu" => Some(Key::Menu),
         "BrowserForward" => Some(Key::NavigateForward),
         "BrowserBack" => Some(Key::NavigateBackward),
+        "Dead" => Some(Key::Dead),
+        "Compose" => Some(Key::Compose),
         _ => None
     }
 }
@@ -635,6 +644,9 @@ fn code_value(key: Key) -> &'static str {
 
         Key::NavigateForward => "BrowserForward",
         Key::NavigateBackward => "BrowserBackward",
+
+        // The physical key depends on the keyboard layout.
+        Key::Dead | Key::Compose => "Unidentified",
     }
 }
 
@@ -234,10 +234,13 @@ pub mod canvaspattern;
 pub mod canvasrenderingcontext2d;
 pub mod characterdata;
 pub mod client;
+pub mod clipboard;
+pub mod clipboardevent;
 pub mod closeevent;
 pub mod comment;
 pub mod compositionevent;
 pub mod console;
+pub mod contenteditable;
 mod create;
 pub mod crypto;
 pub mod css;
@@ -259,6 +262,7 @@ pub mod csssupportsrule;
 pub mod cssviewportrule;
 pub mod customelementregistry;
 pub mod customevent;
+pub mod datatransfer;
 pub mod dedicatedworkerglobalscope;
 pub mod dissimilaroriginlocation;
 pub mod dissimilaroriginwindow;
@@ -418,6 +422,7 @@ pub mod range;
 pub mod request;
 pub mod response;
 pub mod screen;
+pub mod selection;
 pub mod serviceworker;
 pub mod serviceworkercontainer;
 pub mod serviceworkerglobalscope;
@@ -435,6 +440,7 @@ pub mod testbinding;
 pub mod testbindingiterable;
 pub mod testbindingpairiterable;
 pub mod testbindingproxy;
+pub mod testclipboard;
 pub mod testrunner;
 pub mod testworklet;
 pub mod testworkletglobalscope;
@@ -455,6 +461,7 @@ pub mod userscripts;
 pub mod validation;
 pub mod validitystate;
 pub mod values;
+pub mod virtualkeyboard;
 pub mod virtualmethods;
 pub mod vr;
 pub mod vrdisplay;
@@ -9,6 +9,7 @@ use dom::bindings::reflector::{Reflector, DomObject, reflect_dom_object};
 use dom::bindings::root::{DomRoot, MutNullableDom};
 use dom::bindings::str::DOMString;
 use dom::bluetooth::Bluetooth;
+use dom::clipboard::Clipboard;
 use dom::gamepadlist::GamepadList;
 use dom::mimetypearray::MimeTypeArray;
 use dom::navigatorinfo;
@@ -16,6 +17,7 @@ use dom::permissions::Permissions;
 use dom::pluginarray::PluginArray;
 use dom::promise::Promise;
 use dom::serviceworkercontainer::ServiceWorkerContainer;
+use dom::virtualkeyboard::VirtualKeyboard;
 use dom::vr::VR;
 use dom::window::Window;
 use dom_struct::dom_struct;
@@ -31,6 +33,8 @@ pub struct Navigator {
     vr: MutNullableDom<VR>,
     gamepads: MutNullableDom<GamepadList>,
     permissions: MutNullableDom<Permissions>,
+    clipboard: MutNullableDom<Clipboard>,
+    virtual_keyboard: MutNullableDom<VirtualKeyboard>,
 }
 
 impl Navigator {
@@ -44,6 +48,8 @@ impl Navigator {
             vr: Default::default(),
             gamepads: Default::default(),
             permissions: Default::default(),
+            clipboard: Default::default(),
+            virtual_keyboard: Default::default(),
         }
     }
 
@@ -143,6 +149,16 @@ impl NavigatorMethods for Navigator {
         self.permissions.or_init(|| Permissions::new(&self.global()))
     }
 
+    // https://w3c.github.io/clipboard-apis/#dom-navigator-clipboard
+    fn Clipboard(&self) -> DomRoot<Clipboard> {
+        self.clipboard.or_init(|| Clipboard::new(self.global().as_window()))
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-navigator-virtualkeyboard
+    fn VirtualKeyboard(&self) -> DomRoot<VirtualKeyboard> {
+        self.virtual_keyboard.or_init(|| VirtualKeyboard::new(self.global().as_window()))
+    }
+
     // https://w3c.github.io/webvr/spec/1.1/#navigator-getvrdisplays-attribute
     #[allow(unrooted_must_root)]
     fn GetVRDisplays(&self) -> Rc<Promise> {
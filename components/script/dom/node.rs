@@ -1024,6 +1024,7 @@ pub trait LayoutNodeHelpers {
 
     fn text_content(&self) -> String;
     fn selection(&self) -> Option<Range<usize>>;
+    fn misspellings(&self) -> Vec<Range<usize>>;
     fn image_url(&self) -> Option<ServoUrl>;
     fn canvas_data(&self) -> Option<HTMLCanvasData>;
     fn svg_data(&self) -> Option<SVGSVGData>;
@@ -1159,6 +1160,19 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
         None
     }
 
+    #[allow(unsafe_code)]
+    fn misspellings(&self) -> Vec<Range<usize>> {
+        if let Some(area) = self.downcast::<HTMLTextAreaElement>() {
+            return unsafe { area.misspellings_for_layout() };
+        }
+
+        if let Some(input) = self.downcast::<HTMLInputElement>() {
+            return unsafe { input.misspellings_for_layout() };
+        }
+
+        vec!()
+    }
+
     #[allow(unsafe_code)]
     fn image_url(&self) -> Option<ServoUrl> {
         unsafe {
@@ -977,9 +977,9 @@ impl PartialEq for BoundaryPoint {
 }
 
 // https://dom.spec.whatwg.org/#concept-range-bp-position
-fn bp_position(a_node: &Node, a_offset: u32,
-               b_node: &Node, b_offset: u32)
-               -> Option<Ordering> {
+pub fn bp_position(a_node: &Node, a_offset: u32,
+                   b_node: &Node, b_offset: u32)
+                   -> Option<Ordering> {
     if a_node as *const Node == b_node as *const Node {
         // Step 1.
         return Some(a_offset.cmp(&b_offset));
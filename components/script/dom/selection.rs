@@ -0,0 +1,137 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use dom::bindings::codegen::Bindings::SelectionBinding;
+use dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::{Dom, DomRoot};
+use dom::bindings::str::DOMString;
+use dom::document::Document;
+use dom::htmlinputelement::HTMLInputElement;
+use dom::htmltextareaelement::HTMLTextAreaElement;
+use dom::node::Node;
+use dom::range::{Range, bp_position};
+use dom_struct::dom_struct;
+use std::cmp::Ordering;
+
+/// A position in the document, as a node and an offset into it.
+type Position = (DomRoot<Node>, u32);
+
+/// The selection of a document, which is the selection of the editing host that was last
+/// edited in, as kept by its `Editor`.
+#[dom_struct]
+pub struct Selection {
+    reflector_: Reflector,
+    document: Dom<Document>,
+}
+
+impl Selection {
+    fn new_inherited(document: &Document) -> Selection {
+        Selection {
+            reflector_: Reflector::new(),
+            document: Dom::from_ref(document),
+        }
+    }
+
+    pub fn new(document: &Document) -> DomRoot<Selection> {
+        reflect_dom_object(Box::new(Selection::new_inherited(document)),
+                           document.window(),
+                           SelectionBinding::Wrap)
+    }
+
+    /// The anchor and focus of the selection, or None if the document has no selection.
+    fn ends(&self) -> Option<(Position, Position)> {
+        // The selection inside an input or textarea belongs to the control, and the page only
+        // sees it through the control's own selection APIs. While the control is focused, the
+        // selection of the document is collapsed just before it, as it is in other browsers.
+        if let Some(focused) = self.document.get_focused_element() {
+            if focused.is::<HTMLInputElement>() || focused.is::<HTMLTextAreaElement>() {
+                let node = focused.upcast::<Node>();
+                return node.GetParentNode().map(|parent| {
+                    let index = node.index();
+                    ((parent.clone(), index), (parent, index))
+                });
+            }
+        }
+        let editor = self.document.editor().borrow();
+        let editor = match *editor {
+            Some(ref editor) => editor,
+            None => return None,
+        };
+        let (anchor, focus) = (editor.anchor(), editor.focus());
+        // The page may have taken the selected content out of the document since it was last
+        // edited.
+        if !anchor.0.is_in_doc() || !focus.0.is_in_doc() {
+            return None;
+        }
+        Some((anchor, focus))
+    }
+}
+
+impl SelectionMethods for Selection {
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    fn GetAnchorNode(&self) -> Option<DomRoot<Node>> {
+        self.ends().map(|(anchor, _)| anchor.0)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchoroffset
+    fn AnchorOffset(&self) -> u32 {
+        self.ends().map_or(0, |(anchor, _)| anchor.1)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    fn GetFocusNode(&self) -> Option<DomRoot<Node>> {
+        self.ends().map(|(_, focus)| focus.0)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusoffset
+    fn FocusOffset(&self) -> u32 {
+        self.ends().map_or(0, |(_, focus)| focus.1)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    fn IsCollapsed(&self) -> bool {
+        self.ends().map_or(true, |(anchor, focus)| anchor == focus)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-rangecount
+    fn RangeCount(&self) -> u32 {
+        if self.ends().is_some() { 1 } else { 0 }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-type
+    fn Type(&self) -> DOMString {
+        DOMString::from(match self.ends() {
+            None => "None",
+            Some((anchor, focus)) => if anchor == focus { "Caret" } else { "Range" },
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    fn GetRangeAt(&self, index: u32) -> Fallible<DomRoot<Range>> {
+        let (anchor, focus) = match self.ends() {
+            Some(ends) if index == 0 => ends,
+            _ => return Err(Error::IndexSize),
+        };
+        // The range is made anew from the ends each time, so changing it does not change the
+        // selection.
+        let (start, end) = match bp_position(&anchor.0, anchor.1, &focus.0, focus.1) {
+            Some(Ordering::Greater) => (focus, anchor),
+            _ => (anchor, focus),
+        };
+        Ok(Range::new(&self.document, &start.0, start.1, &end.0, end.1))
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-stringifier
+    fn Stringifier(&self) -> DOMString {
+        match self.GetRangeAt(0) {
+            Ok(range) => range.Stringifier(),
+            Err(_) => DOMString::new(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+// check-tidy: no specs after this line
+
+use dom::bindings::codegen::Bindings::TestClipboardBinding::{self, TestClipboardMethods};
+use dom::bindings::error::{Error, Fallible};
+use dom::bindings::reflector::{Reflector, reflect_dom_object};
+use dom::bindings::root::DomRoot;
+use dom::bindings::str::DOMString;
+use dom::window::Window;
+use dom_struct::dom_struct;
+#[cfg(feature = "test_clipboard")]
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor};
+#[cfg(feature = "test_clipboard")]
+use test_clipboard;
+
+/// Sets and inspects the test clipboard, which is installed in place of the system clipboard
+/// the first time it is used.
+#[dom_struct]
+pub struct TestClipboard {
+    reflector_: Reflector,
+}
+
+impl TestClipboard {
+    fn new_inherited() -> TestClipboard {
+        TestClipboard {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<TestClipboard> {
+        reflect_dom_object(Box::new(TestClipboard::new_inherited()),
+                           window,
+                           TestClipboardBinding::Wrap)
+    }
+}
+
+#[cfg(feature = "test_clipboard")]
+fn flavor(format: &str) -> Fallible<ClipboardFlavor> {
+    ClipboardFlavor::from_mime_type(format).ok_or(Error::NotSupported)
+}
+
+#[cfg(feature = "test_clipboard")]
+impl TestClipboardMethods for TestClipboard {
+    fn GetData(&self, format: DOMString) -> Fallible<Option<DOMString>> {
+        let flavor = flavor(&format)?;
+        Ok(test_clipboard::install_if_needed().contents().get(flavor).map(DOMString::from))
+    }
+
+    fn SetData(&self, format: DOMString, data: DOMString) -> Fallible<()> {
+        let flavor = flavor(&format)?;
+        let clipboard = test_clipboard::install_if_needed();
+        let mut contents = clipboard.contents();
+        contents.set(flavor, String::from(data));
+        clipboard.set_contents(contents);
+        Ok(())
+    }
+
+    fn Clear(&self) -> Fallible<()> {
+        test_clipboard::install_if_needed().set_contents(ClipboardContents::new());
+        Ok(())
+    }
+
+    fn TakeLog(&self) -> Fallible<Vec<DOMString>> {
+        let log = test_clipboard::install_if_needed().take_log();
+        Ok(log.iter().map(|call| DOMString::from(call.to_string())).collect())
+    }
+}
+
+/// Without the `test_clipboard` feature, there is no test clipboard to use.
+#[cfg(not(feature = "test_clipboard"))]
+impl TestClipboardMethods for TestClipboard {
+    fn GetData(&self, _format: DOMString) -> Fallible<Option<DOMString>> {
+        Err(Error::NotSupported)
+    }
+
+    fn SetData(&self, _format: DOMString, _data: DOMString) -> Fallible<()> {
+        Err(Error::NotSupported)
+    }
+
+    fn Clear(&self) -> Fallible<()> {
+        Err(Error::NotSupported)
+    }
+
+    fn TakeLog(&self) -> Fallible<Vec<DOMString>> {
+        Err(Error::NotSupported)
+    }
+}
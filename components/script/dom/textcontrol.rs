@@ -3,34 +3,209 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 //! This is an abstraction used by `HTMLInputElement` and `HTMLTextAreaElement` to implement the
-//! text control selection DOM API.
+//! text control selection DOM API, and text composition by input methods.
 //!
 //! https://html.spec.whatwg.org/multipage/#textFieldSelection
+//! https://w3c.github.io/uievents/#events-compositionevents
 
+use clipboard_provider::TextControlClipboard;
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::HTMLFormElementBinding::SelectionMode;
+use dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use dom::bindings::conversions::DerivedFrom;
 use dom::bindings::error::{Error, ErrorResult};
+use dom::bindings::inheritance::Castable;
+use dom::bindings::refcounted::Trusted;
+use dom::bindings::root::RootedReference;
 use dom::bindings::str::DOMString;
-use dom::event::{EventBubbles, EventCancelable};
+use dom::clipboardevent::ClipboardEvent;
+use dom::compositionevent::CompositionEvent;
+use dom::datatransfer::{DataTransfer, DataTransferMode};
+use dom::element::Element;
+use dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use dom::eventtarget::EventTarget;
+use dom::globalscope::GlobalScope;
+use dom::inputevent::InputEvent;
 use dom::node::{Node, NodeDamage, window_from_node};
-use script_traits::ScriptToConstellationChan;
-use textinput::{SelectionDirection, SelectionState, TextInput};
+use dom::validitystate::ValidationFlags;
+use html5ever::LocalName;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use msg::constellation_msg::{AutocorrectWord, AutofillHint, ClipboardContents, EnterKeyHint, ImeEvent};
+use msg::constellation_msg::{ImeSurroundingText, InputMode};
+use script_traits::ScriptMsg;
+use servo_atoms::Atom;
+use servo_config::prefs::PREFS;
+use spellcheck_provider::SpellCheckProvider;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use style::str::split_html_space_chars;
+use task_source::TaskSource;
+use text_buffer::TextBuffer;
+use textinput::{CompositionClause, Edit, KeyReaction, SelectionDirection, SelectionState, TextChange, TextDirection};
+use textinput::{SpellingRequest, TextInput, WordCorrection, first_strong_direction};
 
 pub trait TextControlElement: DerivedFrom<EventTarget> + DerivedFrom<Node> {
+    /// The text buffer that the control keeps its value in.
+    type Buffer: 'static + TextBuffer;
+    /// The editing state of the control, for tasks that only hold on to the element.
+    fn textinput(&self) -> &DomRefCell<TextInput<TextControlClipboard, Self::Buffer>>;
     fn selection_api_applies(&self) -> bool;
     fn has_selectable_text(&self) -> bool;
+    /// Whether the user types text into the control, so that input methods, virtual keyboards
+    /// and editing commands apply to it.
+    fn takes_text_input(&self) -> bool;
     fn set_dirty_value_flag(&self, value: bool);
+    /// Record whether the value was last changed by a user edit, rather than by a script, as
+    /// only the user is held to the length constraints. Either way, the value is no longer the
+    /// one that was autofilled.
+    fn set_edited_by_user(&self, edited: bool);
+    /// Show the placeholder if the value is empty, and hide it otherwise.
+    fn update_placeholder_shown_state(&self);
+    /// Whether the spelling of the value is checked, as the `spellcheck` attribute says.
+    fn spellcheck_enabled(&self) -> bool;
+
+    /// The kind of virtual keyboard to show when the `inputmode` attribute names none.
+    fn default_input_mode(&self) -> InputMode {
+        InputMode::Text
+    }
+
+    #[allow(unrooted_must_root)]
+    fn selection(&self) -> TextControlSelection<Self, Self::Buffer> {
+        TextControlSelection::new(self, self.textinput())
+    }
+
+    /// Insert the clipboard contents that a paste asked for with `request_paste`.
+    fn finish_paste(&self, contents: ClipboardContents) {
+        if !self.textinput().borrow_mut().take_paste_request() {
+            return;
+        }
+        if let KeyReaction::DispatchInput(_) = self.selection().paste(contents) {
+            self.set_dirty_value_flag(true);
+            self.update_placeholder_shown_state();
+            self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    /// Whether `document.execCommand` can carry out the command while this control has the
+    /// focus.
+    fn exec_command_enabled(&self, command: ExecCommand) -> bool {
+        self.takes_text_input() && self.selection().exec_command_enabled(command)
+    }
+
+    /// Carry out a command of `document.execCommand` while this control has the focus, and
+    /// return whether it was enabled.
+    fn exec_command(&self, command: ExecCommand, value: DOMString) -> bool {
+        if !self.exec_command_enabled(command) {
+            return false;
+        }
+        if let KeyReaction::DispatchInput(_) = self.selection().exec_command(command, value) {
+            self.set_dirty_value_flag(true);
+            self.update_placeholder_shown_state();
+        }
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        true
+    }
+
+    /// Whether an input method is composing text in this control.
+    fn is_composing(&self) -> bool {
+        self.textinput().borrow().is_composing()
+    }
+
+    /// The kind of virtual keyboard to show while this control has the focus, if it takes text.
+    fn input_mode(&self) -> Option<InputMode> {
+        if !self.takes_text_input() {
+            return None;
+        }
+        let element = self.upcast::<Node>().downcast::<Element>()?;
+        let attribute = element.get_string_attribute(&local_name!("inputmode"));
+        Some(parse_input_mode(&attribute).unwrap_or_else(|| self.default_input_mode()))
+    }
+
+    /// The label of the enter key of a virtual keyboard, if the page chose one.
+    fn enter_key_hint(&self) -> Option<EnterKeyHint> {
+        let element = self.upcast::<Node>().downcast::<Element>()?;
+        parse_enter_key_hint(&element.get_string_attribute(&LocalName::from("enterkeyhint")))
+    }
+
+    /// What the `autocomplete` attribute asks autofill providers to fill this control with.
+    fn autofill_hint(&self) -> Option<AutofillHint> {
+        let element = self.upcast::<Node>().downcast::<Element>()?;
+        parse_autocomplete(&element.get_string_attribute(&local_name!("autocomplete")))
+    }
+
+    /// Check the spelling of the word left at the caret too, as this control is losing the focus.
+    fn finish_spelling_check(&self) {
+        self.selection().check_spelling(true);
+    }
+
+    /// Commit the text an input method is composing, as this control is losing the focus.
+    fn commit_pending_composition(&self) {
+        if !self.is_composing() {
+            return;
+        }
+        self.selection().commit_pending_composition();
+        self.update_placeholder_shown_state();
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// The text around the caret, for the input method editor.
+    fn ime_surrounding_text(&self, before: usize, after: usize) -> Option<ImeSurroundingText> {
+        if !self.takes_text_input() {
+            return None;
+        }
+        self.textinput().borrow().surrounding_text(before, after)
+    }
+
+    /// Apply an update from the input method editor while this control has the focus.
+    fn handle_ime_event(&self, event: ImeEvent) {
+        if !self.takes_text_input() {
+            return;
+        }
+        // Starting or ending a composition shows or hides the placeholder, even if the content
+        // does not change.
+        self.selection().apply_ime_event(event);
+        self.update_placeholder_shown_state();
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        window_from_node(self).report_ime_caret_rect(self.upcast::<Node>().to_trusted_node_address());
+    }
+}
+
+/// A command of `document.execCommand` that the focused text control carries out.
+///
+/// https://w3c.github.io/editing/docs/execCommand/#commands
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExecCommand {
+    Delete,
+    ForwardDelete,
+    InsertText,
+    Redo,
+    SelectAll,
+    Undo,
 }
 
-pub struct TextControlSelection<'a, E: TextControlElement> {
+impl ExecCommand {
+    /// The command with the given id, which is matched ASCII case-insensitively.
+    pub fn from_command_id(command_id: &str) -> Option<ExecCommand> {
+        match &*command_id.to_ascii_lowercase() {
+            "delete" => Some(ExecCommand::Delete),
+            "forwarddelete" => Some(ExecCommand::ForwardDelete),
+            "inserttext" => Some(ExecCommand::InsertText),
+            "redo" => Some(ExecCommand::Redo),
+            "selectall" => Some(ExecCommand::SelectAll),
+            "undo" => Some(ExecCommand::Undo),
+            _ => None,
+        }
+    }
+}
+
+pub struct TextControlSelection<'a, E: TextControlElement, B: 'a + TextBuffer = Vec<DOMString>> {
     element: &'a E,
-    textinput: &'a DomRefCell<TextInput<ScriptToConstellationChan>>,
+    textinput: &'a DomRefCell<TextInput<TextControlClipboard, B>>,
 }
 
-impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
-    pub fn new(element: &'a E, textinput: &'a DomRefCell<TextInput<ScriptToConstellationChan>>) -> Self {
+impl<'a, E: TextControlElement, B: TextBuffer> TextControlSelection<'a, E, B> {
+    pub fn new(element: &'a E, textinput: &'a DomRefCell<TextInput<TextControlClipboard, B>>) -> Self {
         TextControlSelection { element, textinput }
     }
 
@@ -42,7 +217,42 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
         }
 
         // Step 2
-        self.set_range(Some(0), Some(u32::max_value()), None, None);
+        let original_selection_state = {
+            let mut textinput = self.textinput.borrow_mut();
+            let original_selection_state = textinput.selection_state();
+            textinput.select_all();
+            textinput.selection_direction = SelectionDirection::None;
+            original_selection_state
+        };
+        if self.textinput.borrow().selection_state() != original_selection_state {
+            self.queue_select_event();
+        }
+        self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// Queue a `select` event if the user selected something new, e.g. with the keyboard or the
+    /// mouse, given the selection state before they did.
+    ///
+    /// https://w3c.github.io/uievents/#event-type-select
+    pub fn selection_changed_by_user(&self, original_selection_state: SelectionState) {
+        let selected = {
+            let textinput = self.textinput.borrow();
+            textinput.selection_state() != original_selection_state &&
+                textinput.selection_start() != textinput.selection_end()
+        };
+        if selected {
+            self.queue_select_event();
+        }
+    }
+
+    fn queue_select_event(&self) {
+        let window = window_from_node(self.element);
+        window.user_interaction_task_source().queue_event(
+            &self.element.upcast::<EventTarget>(),
+            atom!("select"),
+            EventBubbles::Bubbles,
+            EventCancelable::NotCancelable,
+            &window);
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-textarea/input-selectionstart
@@ -155,6 +365,7 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
 
         // Step 2
         self.element.set_dirty_value_flag(true);
+        self.element.set_edited_by_user(false);
 
         // Step 3
         let mut start = start.unwrap_or_else(|| self.start());
@@ -169,7 +380,7 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
         // change the selection state in order to replace the text in the range.
         let original_selection_state = self.textinput.borrow().selection_state();
 
-        let content_length = self.textinput.borrow().len() as u32;
+        let content_length = self.textinput.borrow().utf16_len() as u32;
 
         // Step 5
         if start > content_length {
@@ -190,14 +401,15 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
         // Step 11
         // Must come before the textinput.replace_selection() call, as replacement gets moved in
         // that call.
-        let new_length = replacement.len() as u32;
+        let new_length = replacement.chars().map(char::len_utf16).sum::<usize>() as u32;
 
         {
             let mut textinput = self.textinput.borrow_mut();
 
             // Steps 9-10
-            textinput.set_selection_range(start, end, SelectionDirection::None);
-            textinput.replace_selection(replacement);
+            // The maxlength attribute only limits what the user types, not what script inserts.
+            textinput.set_selection_range_utf16(start, end, SelectionDirection::None);
+            textinput.replace_selection_ignoring_max_length(replacement);
         }
 
         // Step 12
@@ -248,12 +460,549 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
         Ok(())
     }
 
+    /// Apply an update from the input method editor, firing the composition and input events
+    /// that go with it. Returns whether the content changed.
+    ///
+    /// https://w3c.github.io/uievents/#events-composition-event-order
+    pub fn apply_ime_event(&self, event: ImeEvent) -> bool {
+        if self.textinput.borrow().is_read_only() {
+            return false;
+        }
+
+        match event {
+            ImeEvent::Composition(text, clauses, caret) => {
+                self.start_composition();
+                self.fire_composition_event("compositionupdate", DOMString::from(&*text));
+                let clauses = clauses.iter().map(|clause| CompositionClause {
+                    start: clause.start,
+                    end: clause.end,
+                    selected: clause.selected,
+                }).collect();
+                let change = self.textinput.borrow_mut().set_composition(DOMString::from(text), clauses, caret);
+                match change {
+                    Some(change) => {
+                        self.fire_input_event("insertCompositionText", Some(change.new_text), None);
+                        true
+                    },
+                    None => false,
+                }
+            },
+            ImeEvent::Commit(text) => {
+                // Input methods may commit text without composing it first.
+                let started = self.start_composition();
+                let composed = self.textinput.borrow().composition().map(|c| c.text().clone());
+                let text = text.map(DOMString::from).or(composed.clone()).unwrap_or_default();
+                if Some(&text) != composed.as_ref() {
+                    self.fire_composition_event("compositionupdate", text.clone());
+                }
+                let changed = {
+                    let mut textinput = self.textinput.borrow_mut();
+                    let emptied = started && textinput.set_composition(DOMString::new(), vec!(), 0).is_some();
+                    textinput.commit_composition(Some(text.clone())).is_some() || emptied
+                };
+                if changed {
+                    self.fire_input_event("insertCompositionText", Some(text.clone()), None);
+                }
+                self.fire_composition_event("compositionend", text);
+                changed
+            },
+            ImeEvent::Cancel => {
+                if !self.textinput.borrow().is_composing() {
+                    return false;
+                }
+                let changed = self.textinput.borrow_mut().cancel_composition().is_some();
+                if changed {
+                    self.fire_input_event("deleteCompositionText", None, None);
+                }
+                self.fire_composition_event("compositionend", DOMString::new());
+                changed
+            },
+            ImeEvent::DeleteSurroundingText(before, after) => {
+                let changed = self.textinput.borrow_mut().delete_surrounding_text(before, after).is_some();
+                if changed {
+                    self.fire_input_event("deleteContent", None, None);
+                }
+                changed
+            },
+        }
+    }
+
+    /// Commit the text being composed, as it stands, because the control lost the focus and the
+    /// input method won't be composing in it any more. The page gets a last `input` event, now
+    /// that the text is no longer being composed, and `compositionend`.
+    pub fn commit_pending_composition(&self) {
+        let composed = match self.textinput.borrow().composition() {
+            Some(composition) => composition.text().clone(),
+            None => return,
+        };
+        let change = self.textinput.borrow_mut().commit_composition(None);
+        let text = change.map_or(composed, |change| change.new_text);
+        self.fire_input_event("insertCompositionText", Some(text.clone()), None);
+        self.fire_composition_event("compositionend", text);
+    }
+
+    /// Tell the page that Escape cleared the content, as it does in a search field.
+    pub fn end_cleared_content(&self) {
+        self.fire_input_event("deleteContent", None, None);
+    }
+
+    /// Finish a composition that the user cancelled with Escape, once `TextInput` has put back
+    /// the text it replaced with the given change.
+    pub fn end_cancelled_composition(&self, change: Option<TextChange>) {
+        // The input method would otherwise put the text back with its next update.
+        let window = window_from_node(self.element);
+        window.upcast::<GlobalScope>().script_to_constellation_chan().send(ScriptMsg::CancelComposition).unwrap();
+        if change.is_some() {
+            self.fire_input_event("deleteCompositionText", None, None);
+        }
+        self.fire_composition_event("compositionend", DOMString::new());
+    }
+
+    /// End the composition without committing or cancelling it, because script is about to
+    /// replace the content with `value`. Setting the content the control already holds keeps
+    /// the composition, as pages commonly do that on every `input` event.
+    pub fn abandon_composition_for_value(&self, value: &DOMString) {
+        let abandoned = {
+            let mut textinput = self.textinput.borrow_mut();
+            if textinput.get_content() == *value {
+                return;
+            }
+            textinput.abandon_composition()
+        };
+        if abandoned.is_some() {
+            self.fire_composition_event("compositionend", DOMString::new());
+        }
+    }
+
+    /// Put back the default value, as a form reset does, ending the composition if there is one.
+    /// No `input` event is fired, as the user didn't change the value.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#concept-form-reset-control
+    pub fn reset(&self, default_value: DOMString) {
+        let abandoned = self.textinput.borrow_mut().abandon_composition();
+        if abandoned.is_some() {
+            self.fire_composition_event("compositionend", DOMString::new());
+        }
+        self.textinput.borrow_mut().reset_content(default_value);
+        self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// Ask for the clipboard contents that the last `KeyReaction::PasteRequested` is waiting
+    /// for. They are handed to the element's `finish_paste` in a task once they arrive, which
+    /// can take a while, so that reading the clipboard doesn't hold up the script thread. They
+    /// are read in chunks, so that huge contents aren't sent in a single message.
+    pub fn request_paste(&self) where E: 'static {
+        let (length_sender, length_receiver) = ipc::channel().unwrap();
+        let (chunk_sender, chunk_receiver) = ipc::channel().unwrap();
+        let window = window_from_node(self.element);
+        let task_source = window.user_interaction_task_source();
+        let canceller = window.task_canceller();
+        let trusted = Trusted::new(self.element);
+        let mut reader = self.textinput.borrow().paste_reader();
+        reader.request_length(length_sender);
+        // The reader and the channel for its chunks, until the contents have all been read.
+        let read = Arc::new(Mutex::new(Some((reader, chunk_sender))));
+
+        let length_read = read.clone();
+        ROUTER.add_route(length_receiver.to_opaque(), Box::new(move |message| {
+            if let Some((ref mut reader, ref chunk_sender)) = *length_read.lock().unwrap() {
+                if !(reader.receive_length(message.to().unwrap()) && reader.request_next_chunk(chunk_sender.clone())) {
+                    // There is nothing to read, so the paste is finished right away.
+                    let _ = chunk_sender.send(None);
+                }
+            }
+        }));
+        ROUTER.add_route(chunk_receiver.to_opaque(), Box::new(move |message| {
+            let mut read = read.lock().unwrap();
+            match *read {
+                Some((ref mut reader, ref chunk_sender)) => {
+                    if reader.receive_chunk(message.to().unwrap()) && reader.request_next_chunk(chunk_sender.clone()) {
+                        return;
+                    }
+                },
+                None => return,
+            }
+            let (reader, _) = read.take().unwrap();
+            let contents = reader.into_contents();
+            let element = trusted.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(finish_paste: move || {
+                    element.root().finish_paste(contents);
+                }),
+                &canceller,
+            );
+        }));
+    }
+
+    /// Copy the selection, unless the page handles the `copy` event. A page that cancels it
+    /// puts whatever it set on the event's `clipboardData` on the clipboard instead.
+    ///
+    /// https://w3c.github.io/clipboard-apis/#copy-action
+    pub fn copy(&self) {
+        if let Some(contents) = self.fire_clipboard_write_event(atom!("copy")) {
+            if !contents.is_empty() {
+                self.textinput.borrow_mut().write_clipboard(contents);
+            }
+            return;
+        }
+        self.textinput.borrow_mut().copy_selection();
+    }
+
+    /// Move the selection to the clipboard, unless the page handles the `cut` event, as with
+    /// `copy`. Returns whether the content changed.
+    ///
+    /// https://w3c.github.io/clipboard-apis/#cut-action
+    pub fn cut(&self) -> bool {
+        if let Some(contents) = self.fire_clipboard_write_event(atom!("cut")) {
+            if !contents.is_empty() {
+                self.textinput.borrow_mut().write_clipboard(contents);
+            }
+            return false;
+        }
+        if !self.textinput.borrow_mut().copy_selection() {
+            return false;
+        }
+        match self.perform_edit(KeyReaction::BeforeInput(Edit::DeleteByCut)) {
+            KeyReaction::DispatchInput(change) => change.is_some(),
+            _ => false,
+        }
+    }
+
+    /// Insert the clipboard contents that a paste was waiting for, unless the page handles the
+    /// `paste` event, which carries them in its `clipboardData`.
+    ///
+    /// https://w3c.github.io/clipboard-apis/#paste-action
+    pub fn paste(&self, contents: ClipboardContents) -> KeyReaction {
+        // The contents are shared with the events that carry them, rather than copied for each.
+        let contents = Rc::new(contents);
+        let data_transfer = DataTransfer::new(&window_from_node(self.element),
+                                              DataTransferMode::ReadOnly,
+                                              contents.clone());
+        if self.fire_clipboard_event(atom!("paste"), &data_transfer) == EventStatus::Canceled {
+            return KeyReaction::Nothing;
+        }
+        let reaction = self.textinput.borrow_mut().paste(contents.clone());
+        self.perform_edit_with_contents(reaction, Some(contents))
+    }
+
+    /// Whether `document.execCommand` can carry out the command in this control.
+    ///
+    /// https://w3c.github.io/editing/docs/execCommand/#enabled
+    pub fn exec_command_enabled(&self, command: ExecCommand) -> bool {
+        let textinput = self.textinput.borrow();
+        match command {
+            ExecCommand::SelectAll => true,
+            _ if textinput.is_read_only() => false,
+            ExecCommand::Undo => textinput.can_undo(),
+            ExecCommand::Redo => textinput.can_redo(),
+            ExecCommand::Delete | ExecCommand::ForwardDelete | ExecCommand::InsertText => true,
+        }
+    }
+
+    /// Carry out a command of `document.execCommand`, firing `beforeinput` and `input` as for
+    /// the same edit made with the keyboard.
+    ///
+    /// https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    pub fn exec_command(&self, command: ExecCommand, value: DOMString) -> KeyReaction {
+        let edit = match command {
+            ExecCommand::SelectAll => {
+                self.dom_select();
+                return KeyReaction::RedrawSelection;
+            },
+            ExecCommand::Delete => Edit::DeleteBackward,
+            ExecCommand::ForwardDelete => Edit::DeleteForward,
+            ExecCommand::InsertText => Edit::InsertText(String::from(value)),
+            ExecCommand::Redo => Edit::HistoryRedo,
+            ExecCommand::Undo => Edit::HistoryUndo,
+        };
+        let reaction = self.textinput.borrow_mut().request_edit(edit);
+        self.perform_edit(reaction)
+    }
+
+    /// Replace the whole value with text the user picked, such as a suggestion, firing
+    /// `beforeinput` and `input` as for an edit made with the keyboard.
+    pub fn replace_value(&self, text: DOMString) -> KeyReaction {
+        let reaction = self.textinput.borrow_mut().request_edit(Edit::InsertReplacementText(String::from(text)));
+        self.perform_edit(reaction)
+    }
+
+    /// Carry out the edit that a `KeyReaction::BeforeInput` deferred, unless the page cancels
+    /// the `beforeinput` event announcing it, and fire `input` if the content changed. Other
+    /// reactions are returned as they are.
+    ///
+    /// https://w3c.github.io/input-events/#event-type-beforeinput
+    pub fn perform_edit(&self, reaction: KeyReaction) -> KeyReaction {
+        self.perform_edit_with_contents(reaction, None)
+    }
+
+    /// Perform an edit, as `perform_edit` does, whose events carry `contents` in their
+    /// `dataTransfer`, e.g. those of a paste.
+    fn perform_edit_with_contents(&self, reaction: KeyReaction, contents: Option<Rc<ClipboardContents>>)
+                                  -> KeyReaction {
+        let edit = match reaction {
+            KeyReaction::BeforeInput(edit) => edit,
+            reaction => return reaction,
+        };
+        let window = window_from_node(self.element);
+        let data_transfer = contents.map(|contents| {
+            DataTransfer::new(&window, DataTransferMode::ReadOnly, contents)
+        });
+        if self.fire_before_input_event(&edit, data_transfer.r()) == EventStatus::Canceled {
+            return KeyReaction::Nothing;
+        }
+        let reaction = self.textinput.borrow_mut().apply_edit(edit.clone());
+        if let KeyReaction::DispatchInput(Some(ref change)) = reaction {
+            // The text that was actually inserted, after filtering and truncation.
+            let data = edit.data().map(|_| change.new_text.clone());
+            self.fire_input_event(edit.input_type(), data, data_transfer.r());
+            if let Edit::InsertText(_) = edit {
+                self.autocorrect(change);
+            }
+        }
+        reaction
+    }
+
+    /// Offer the word that typing the given change finished, if any, to the embedder to correct.
+    /// Its answer is taken in a task, so that the script thread isn't held up waiting for it.
+    fn autocorrect(&self, change: &TextChange) {
+        if !PREFS.get("dom.autocorrect.enabled").as_boolean().unwrap_or(false) ||
+           !self.element.spellcheck_enabled() {
+            return;
+        }
+        let (range, word) = match self.textinput.borrow().word_finished_by(change) {
+            Some(finished) => finished,
+            None => return,
+        };
+        let request = {
+            let textinput = self.textinput.borrow();
+            let lang = self.element.upcast::<Node>().downcast::<Element>().map(Element::get_lang);
+            AutocorrectWord {
+                word: word.clone(),
+                start: textinput.text_point_to_utf16_offset(&textinput.offset_to_text_point(range.start)),
+                end: textinput.text_point_to_utf16_offset(&textinput.offset_to_text_point(range.end)),
+                lang: lang.unwrap_or_default(),
+            }
+        };
+        let (sender, receiver) = ipc::channel().unwrap();
+        let window = window_from_node(self.element);
+        let task_source = window.user_interaction_task_source();
+        let canceller = window.task_canceller();
+        let trusted = Trusted::new(self.element);
+        let mut offered = Some((range, word));
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let (range, word) = match offered.take() {
+                Some(offered) => offered,
+                None => return,
+            };
+            let correction = match message.to::<Option<String>>() {
+                Ok(Some(correction)) => correction,
+                _ => return,
+            };
+            if correction == word {
+                return;
+            }
+            let correction = WordCorrection {
+                range: range,
+                word: word,
+                correction: correction,
+            };
+            let element = trusted.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(correct_word: move || {
+                    element.root().selection().correct_word(correction);
+                }),
+                &canceller,
+            );
+        }));
+        let mut provider = window.upcast::<GlobalScope>().script_to_constellation_chan().clone();
+        provider.request_autocorrect(request, sender);
+    }
+
+    /// Replace a word with the correction the embedder offered for it, as an edit of its own,
+    /// announced as `insertReplacementText` with `beforeinput` and `input` events, which the
+    /// user can undo. Nothing is replaced if the word is no longer where it was, e.g. because
+    /// the user kept typing before the correction arrived, nor while a composition is in
+    /// progress, which the edit would end.
+    fn correct_word(&self, correction: WordCorrection) {
+        {
+            let textinput = self.textinput.borrow();
+            let content = textinput.get_content();
+            if textinput.is_composing() || content.get(correction.range.clone()) != Some(&*correction.word) {
+                return;
+            }
+        }
+        let reaction = self.textinput.borrow_mut().request_edit(Edit::InsertCorrection(correction));
+        if let KeyReaction::DispatchInput(_) = self.perform_edit(reaction) {
+            self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    fn fire_before_input_event(&self, edit: &Edit, data_transfer: Option<&DataTransfer>) -> EventStatus {
+        let is_composing = self.textinput.borrow().is_composing();
+        InputEvent::fire_for_edit(self.element.upcast(),
+                                  "beforeinput",
+                                  edit.input_type(),
+                                  edit.data().map(DOMString::from),
+                                  is_composing,
+                                  data_transfer)
+    }
+
+    /// Fire a `copy` or `cut` event with an empty `clipboardData` for the page to fill, and
+    /// return what it filled it with if it cancelled the event.
+    fn fire_clipboard_write_event(&self, type_: Atom) -> Option<ClipboardContents> {
+        let data_transfer = DataTransfer::new(&window_from_node(self.element),
+                                              DataTransferMode::ReadWrite,
+                                              Rc::new(ClipboardContents::new()));
+        match self.fire_clipboard_event(type_, &data_transfer) {
+            EventStatus::Canceled => Some(data_transfer.clipboard_contents()),
+            EventStatus::NotCanceled => None,
+        }
+    }
+
+    fn fire_clipboard_event(&self, type_: Atom, data_transfer: &DataTransfer) -> EventStatus {
+        let window = window_from_node(self.element);
+        let event = ClipboardEvent::new(&window,
+                                        type_,
+                                        EventBubbles::Bubbles,
+                                        EventCancelable::Cancelable,
+                                        Some(data_transfer));
+        let status = event.upcast::<Event>().fire(self.element.upcast());
+        // Script that kept hold of the data can't read or change it once the event is over.
+        data_transfer.set_mode(DataTransferMode::Protected);
+        status
+    }
+
+    /// Fire `compositionstart` if no composition is in progress, and return whether it did.
+    fn start_composition(&self) -> bool {
+        if self.textinput.borrow().is_composing() {
+            return false;
+        }
+        let selected = self.textinput.borrow().get_selection_text().unwrap_or_default();
+        // The input method can't be told to stop composing, so as in other browsers, cancelling
+        // this event has no effect.
+        self.fire_composition_event("compositionstart", DOMString::from(selected));
+        true
+    }
+
+    /// Fire the `input` event that follows a change of content, with the text that was
+    /// inserted, if any.
+    ///
+    /// https://w3c.github.io/input-events/#event-type-input
+    fn fire_input_event(&self, input_type: &str, data: Option<DOMString>, data_transfer: Option<&DataTransfer>) {
+        self.element.set_dirty_value_flag(true);
+        self.element.set_edited_by_user(true);
+        self.check_spelling(false);
+        let is_composing = self.textinput.borrow().is_composing();
+        InputEvent::fire_for_edit(self.element.upcast(), "input", input_type, data, is_composing, data_transfer);
+    }
+
+    /// Send the text edited since the last check to the embedder's spell checker, which layout
+    /// underlines the misspelled words of once it answers, in a task, so that checking doesn't
+    /// hold up the script thread. The word at the caret is left until the user has finished
+    /// typing it, unless `include_word_at_caret` is set.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#spelling-and-grammar-checking
+    pub fn check_spelling(&self, include_word_at_caret: bool) {
+        if !PREFS.get("dom.spellcheck.enabled").as_boolean().unwrap_or(false) {
+            return;
+        }
+        if !self.element.spellcheck_enabled() {
+            self.textinput.borrow_mut().clear_misspellings();
+            self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+            return;
+        }
+        let request = match self.textinput.borrow_mut().spelling_request(include_word_at_caret) {
+            Some(request) => request,
+            None => return,
+        };
+        let words = request.texts.clone();
+        let lang = self.element.upcast::<Node>().downcast::<Element>().map(Element::get_lang);
+        let (sender, receiver) = ipc::channel().unwrap();
+        let window = window_from_node(self.element);
+        let task_source = window.user_interaction_task_source();
+        let canceller = window.task_canceller();
+        let trusted = Trusted::new(self.element);
+        let mut request = Some(request);
+        ROUTER.add_route(receiver.to_opaque(), Box::new(move |message| {
+            let request = match request.take() {
+                Some(request) => request,
+                None => return,
+            };
+            let misspelled: Vec<bool> = message.to().unwrap_or_default();
+            let element = trusted.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(receive_misspellings: move || {
+                    element.root().selection().receive_misspellings(request, misspelled, include_word_at_caret);
+                }),
+                &canceller,
+            );
+        }));
+        let mut provider = window.upcast::<GlobalScope>().script_to_constellation_chan().clone();
+        provider.request_spell_check(words, lang.unwrap_or_default(), sender);
+    }
+
+    /// Underline the misspelled words that the spell checker found for a request of
+    /// `check_spelling`, or check again if the content changed while it was out.
+    fn receive_misspellings(&self, request: SpellingRequest, misspelled: Vec<bool>, include_word_at_caret: bool) {
+        if !self.textinput.borrow_mut().receive_misspellings(request, misspelled) {
+            return self.check_spelling(include_word_at_caret);
+        }
+        self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    fn fire_composition_event(&self, type_: &str, data: DOMString) {
+        let window = window_from_node(self.element);
+        let cancelable = type_ == "compositionstart";
+        let event = CompositionEvent::new(&window,
+                                          DOMString::from(type_),
+                                          true,
+                                          cancelable,
+                                          Some(&window),
+                                          0,
+                                          data);
+        event.upcast::<Event>().fire(self.element.upcast());
+    }
+
+    /// The length constraints that the value does not meet. They only apply to a value last
+    /// changed by a user edit, with `edited_by_user`, so that scripts can set any value.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#limiting-user-input-length:-the-maxlength-attribute
+    pub fn length_validity(&self, edited_by_user: bool) -> ValidationFlags {
+        let textinput = self.textinput.borrow();
+        let mut failed = ValidationFlags::empty();
+        if edited_by_user && textinput.is_too_long() {
+            failed.insert(ValidationFlags::TOO_LONG);
+        }
+        if edited_by_user && textinput.is_too_short() {
+            failed.insert(ValidationFlags::TOO_SHORT);
+        }
+        failed
+    }
+
+    /// The message telling the user how to meet the length constraints in `failed`, if any.
+    pub fn length_validation_message(&self, failed: ValidationFlags) -> Option<DOMString> {
+        let textinput = self.textinput.borrow();
+        let length = textinput.length();
+        let message = match (textinput.max_length, textinput.min_length) {
+            (Some(max_length), _) if failed.contains(ValidationFlags::TOO_LONG) => {
+                format!("Please shorten this text to {} characters or less (you are currently using {} characters).",
+                        max_length, length)
+            }
+            (_, Some(min_length)) if failed.contains(ValidationFlags::TOO_SHORT) => {
+                format!("Please use at least {} characters (you are currently using {} characters).",
+                        min_length, length)
+            }
+            _ => return None,
+        };
+        Some(DOMString::from(message))
+    }
+
     fn start(&self) -> u32 {
-        self.textinput.borrow().selection_start_offset() as u32
+        self.textinput.borrow().selection_start_utf16_offset() as u32
     }
 
     fn end(&self) -> u32 {
-        self.textinput.borrow().selection_end_offset() as u32
+        self.textinput.borrow().selection_end_utf16_offset() as u32
     }
 
     fn direction(&self) -> SelectionDirection {
@@ -278,19 +1027,130 @@ impl<'a, E: TextControlElement> TextControlSelection<'a, E> {
         let end = end.unwrap_or(0);
 
         // Steps 3-5
-        textinput.set_selection_range(start, end, direction.unwrap_or(SelectionDirection::None));
+        textinput.set_selection_range_utf16(start, end, direction.unwrap_or(SelectionDirection::None));
 
         // Step 6
         if textinput.selection_state() != original_selection_state {
-            let window = window_from_node(self.element);
-            window.user_interaction_task_source().queue_event(
-                &self.element.upcast::<EventTarget>(),
-                atom!("select"),
-                EventBubbles::Bubbles,
-                EventCancelable::NotCancelable,
-                &window);
+            self.queue_select_event();
         }
 
         self.element.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 }
+
+/// The directionality that a `dir` attribute gives an element, or `None` if the attribute is
+/// missing or invalid, or is `auto` and the text has no strong direction.
+fn dir_attribute_directionality<F>(element: &Element, auto_text: F) -> Option<TextDirection>
+    where F: FnOnce() -> Option<TextDirection>
+{
+    match &*element.get_string_attribute(&local_name!("dir")).to_ascii_lowercase() {
+        "ltr" => Some(TextDirection::Ltr),
+        "rtl" => Some(TextDirection::Rtl),
+        "auto" => Some(auto_text().unwrap_or(TextDirection::Ltr)),
+        _ => None,
+    }
+}
+
+/// The directionality of a text control: that of its `dir` attribute, taken from its value if
+/// the attribute is `auto`, or else that of its nearest ancestor with a `dir` attribute.
+///
+/// https://html.spec.whatwg.org/multipage/#the-directionality
+pub fn text_control_directionality<B: TextBuffer>(element: &Element,
+                                                  textinput: &TextInput<TextControlClipboard, B>)
+                                                  -> TextDirection {
+    if let Some(direction) = dir_attribute_directionality(element, || textinput.first_strong_direction()) {
+        return direction;
+    }
+    element.upcast::<Node>().ancestors().filter_map(|ancestor| {
+        // TODO: skip the text of descendants that have a `dir` attribute of their own, and of
+        // `bdi`, `script` and `style` elements.
+        let text = || ancestor.GetTextContent().and_then(|text| first_strong_direction(&text));
+        ancestor.downcast::<Element>().and_then(|element| dir_attribute_directionality(element, text))
+    }).next().unwrap_or(TextDirection::Ltr)
+}
+
+/// Parse the value of an `inputmode` attribute, which is `None` for an invalid value.
+///
+/// https://html.spec.whatwg.org/multipage/#attr-inputmode
+pub fn parse_input_mode(value: &str) -> Option<InputMode> {
+    let modes = [
+        ("none", InputMode::None),
+        ("text", InputMode::Text),
+        ("decimal", InputMode::Decimal),
+        ("numeric", InputMode::Numeric),
+        ("tel", InputMode::Tel),
+        ("search", InputMode::Search),
+        ("email", InputMode::Email),
+        ("url", InputMode::Url),
+    ];
+    modes.iter().find(|&&(keyword, _)| value.eq_ignore_ascii_case(keyword)).map(|&(_, mode)| mode)
+}
+
+/// Parse the value of an `autocomplete` attribute, which is `None` for an invalid value.
+///
+/// https://html.spec.whatwg.org/multipage/#autofill-processing-model
+pub fn parse_autocomplete(value: &str) -> Option<AutofillHint> {
+    const FIELDS: &'static [&'static str] = &[
+        "name", "honorific-prefix", "given-name", "additional-name", "family-name",
+        "honorific-suffix", "nickname", "username", "new-password", "current-password",
+        "one-time-code", "organization-title", "organization", "street-address", "address-line1",
+        "address-line2", "address-line3", "address-level4", "address-level3", "address-level2",
+        "address-level1", "country", "country-name", "postal-code", "cc-name", "cc-given-name",
+        "cc-additional-name", "cc-family-name", "cc-number", "cc-exp", "cc-exp-month",
+        "cc-exp-year", "cc-csc", "cc-type", "transaction-currency", "transaction-amount",
+        "language", "bday", "bday-day", "bday-month", "bday-year", "sex", "url", "photo",
+    ];
+    const CONTACT_FIELDS: &'static [&'static str] = &[
+        "tel", "tel-country-code", "tel-national", "tel-area-code", "tel-local",
+        "tel-local-prefix", "tel-local-suffix", "tel-extension", "email", "impp",
+    ];
+    const CONTACT_TYPES: &'static [&'static str] = &["home", "work", "mobile", "fax", "pager"];
+
+    let mut tokens: Vec<String> = split_html_space_chars(value).map(|token| token.to_ascii_lowercase()).collect();
+    let webauthn = tokens.last().map_or(false, |token| token == "webauthn");
+    if webauthn {
+        tokens.pop();
+    }
+    let mut hint = AutofillHint {
+        field: tokens.pop()?,
+        section: None,
+        address_type: None,
+        contact_type: None,
+        webauthn: webauthn,
+    };
+
+    // `on` and `off` stand alone.
+    if hint.field == "on" || hint.field == "off" {
+        return if tokens.is_empty() && !webauthn { Some(hint) } else { None };
+    }
+    let is_contact_field = CONTACT_FIELDS.contains(&&*hint.field);
+    if !is_contact_field && !FIELDS.contains(&&*hint.field) {
+        return None;
+    }
+    if is_contact_field && tokens.last().map_or(false, |token| CONTACT_TYPES.contains(&&**token)) {
+        hint.contact_type = tokens.pop();
+    }
+    if tokens.last().map_or(false, |token| token == "shipping" || token == "billing") {
+        hint.address_type = tokens.pop();
+    }
+    if tokens.last().map_or(false, |token| token.starts_with("section-")) {
+        hint.section = tokens.pop();
+    }
+    if tokens.is_empty() { Some(hint) } else { None }
+}
+
+/// Parse the value of an `enterkeyhint` attribute, which is `None` for an invalid value.
+///
+/// https://html.spec.whatwg.org/multipage/#attr-enterkeyhint
+pub fn parse_enter_key_hint(value: &str) -> Option<EnterKeyHint> {
+    let hints = [
+        ("enter", EnterKeyHint::Enter),
+        ("done", EnterKeyHint::Done),
+        ("go", EnterKeyHint::Go),
+        ("next", EnterKeyHint::Next),
+        ("previous", EnterKeyHint::Previous),
+        ("search", EnterKeyHint::Search),
+        ("send", EnterKeyHint::Send),
+    ];
+    hints.iter().find(|&&(keyword, _)| value.eq_ignore_ascii_case(keyword)).map(|&(_, hint)| hint)
+}
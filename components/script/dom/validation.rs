@@ -1,9 +1,63 @@
 /* This Source Code Form is subject to the terms of the Mozilla Public
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+use dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::str::DOMString;
+use dom::element::Element;
+use dom::eventtarget::EventTarget;
 use dom::validitystate::ValidationFlags;
 
 pub trait Validatable {
     fn is_instance_validatable(&self) -> bool { true }
     fn validate(&self, _validate_flags: ValidationFlags) -> bool { true }
+    /// The message telling the user how to satisfy the constraints that the element does not.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn validation_message(&self) -> DOMString { DOMString::new() }
+}
+
+/// The message of a required control that has no value.
+pub static VALUE_MISSING_MESSAGE: &'static str = "Please fill out this field.";
+
+/// Tell the user about the constraints that `validatable` does not satisfy.
+// TODO: Show the problems to the user, rather than only logging them.
+pub fn report_constraint_problems(validatable: &Validatable) {
+    warn!("Invalid form control: {}", validatable.validation_message());
+}
+
+/// Whether `element` satisfies its constraints, firing `invalid` at it if it does not. The
+/// error tells whether that event was canceled.
+///
+/// https://html.spec.whatwg.org/multipage/#check-validity-steps
+fn check_validity_firing_invalid(element: &Element) -> Result<(), bool> {
+    let validatable = match element.as_maybe_validatable() {
+        Some(validatable) => validatable,
+        None => return Ok(()),
+    };
+    if !validatable.is_instance_validatable() || validatable.validate(ValidationFlags::empty()) {
+        return Ok(());
+    }
+    let event = element.upcast::<EventTarget>().fire_cancelable_event(atom!("invalid"));
+    Err(event.DefaultPrevented())
+}
+
+/// https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+pub fn check_validity(element: &Element) -> bool {
+    check_validity_firing_invalid(element).is_ok()
+}
+
+/// https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+pub fn report_validity(element: &Element) -> bool {
+    match check_validity_firing_invalid(element) {
+        Ok(()) => true,
+        Err(canceled) => {
+            if !canceled {
+                if let Some(validatable) = element.as_maybe_validatable() {
+                    report_constraint_problems(validatable);
+                }
+            }
+            false
+        },
+    }
 }
@@ -0,0 +1,86 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use dom::bindings::codegen::Bindings::VirtualKeyboardBinding::{self, VirtualKeyboardMethods};
+use dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use dom::bindings::inheritance::Castable;
+use dom::bindings::reflector::{DomObject, reflect_dom_object};
+use dom::bindings::root::{DomRoot, MutDom};
+use dom::domrect::DOMRect;
+use dom::eventtarget::EventTarget;
+use dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::TypedRect;
+use std::cell::Cell;
+use style_traits::CSSPixel;
+
+// https://w3c.github.io/virtual-keyboard/#the-virtualkeyboard-interface
+#[dom_struct]
+pub struct VirtualKeyboard {
+    eventtarget: EventTarget,
+    bounding_rect: MutDom<DOMRect>,
+    overlays_content: Cell<bool>,
+}
+
+impl VirtualKeyboard {
+    fn new_inherited(bounding_rect: &DOMRect) -> VirtualKeyboard {
+        VirtualKeyboard {
+            eventtarget: EventTarget::new_inherited(),
+            bounding_rect: MutDom::new(bounding_rect),
+            overlays_content: Cell::new(false),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<VirtualKeyboard> {
+        let bounding_rect = DOMRect::new(window.upcast(), 0., 0., 0., 0.);
+        reflect_dom_object(Box::new(VirtualKeyboard::new_inherited(&bounding_rect)),
+                           window,
+                           VirtualKeyboardBinding::Wrap)
+    }
+
+    /// Record the area of the viewport that the virtual keyboard now covers, as reported by
+    /// the embedder, and let the page know.
+    ///
+    /// https://w3c.github.io/virtual-keyboard/#dfn-set-the-virtual-keyboard-bounding-rect
+    pub fn set_geometry(&self, rect: TypedRect<f32, CSSPixel>) {
+        let bounding_rect = DOMRect::new(&self.global(),
+                                         rect.origin.x as f64,
+                                         rect.origin.y as f64,
+                                         rect.size.width as f64,
+                                         rect.size.height as f64);
+        self.bounding_rect.set(&bounding_rect);
+        self.upcast::<EventTarget>().fire_event(atom!("geometrychange"));
+    }
+}
+
+impl VirtualKeyboardMethods for VirtualKeyboard {
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-show
+    fn Show(&self) {
+        self.global().as_window().Document().show_virtual_keyboard();
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-hide
+    fn Hide(&self) {
+        self.global().as_window().Document().hide_virtual_keyboard();
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-boundingrect
+    fn BoundingRect(&self) -> DomRoot<DOMRect> {
+        self.bounding_rect.get()
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-overlayscontent
+    fn OverlaysContent(&self) -> bool {
+        self.overlays_content.get()
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-overlayscontent
+    fn SetOverlaysContent(&self, overlays_content: bool) {
+        // TODO: let the embedder know, so that it stops resizing the viewport for the keyboard.
+        self.overlays_content.set(overlays_content);
+    }
+
+    // https://w3c.github.io/virtual-keyboard/#dom-virtualkeyboard-ongeometrychange
+    event_handler!(geometrychange, GetOngeometrychange, SetOngeometrychange);
+}
@@ -11,6 +11,7 @@ use devtools_traits::{ScriptToDevtoolsControlMsg, TimelineMarker, TimelineMarker
 use dom::bindings::cell::DomRefCell;
 use dom::bindings::codegen::Bindings::DocumentBinding::{DocumentMethods, DocumentReadyState};
 use dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use dom::bindings::codegen::Bindings::NavigatorBinding::NavigatorMethods;
 use dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use dom::bindings::codegen::Bindings::WindowBinding::{self, FrameRequestCallback, WindowMethods};
@@ -42,13 +43,15 @@ use dom::node::{Node, NodeDamage, document_from_node, from_untrusted_node_addres
 use dom::performance::Performance;
 use dom::promise::Promise;
 use dom::screen::Screen;
+use dom::selection::Selection;
 use dom::storage::Storage;
+use dom::testclipboard::TestClipboard;
 use dom::testrunner::TestRunner;
 use dom::windowproxy::WindowProxy;
 use dom::worklet::Worklet;
 use dom::workletglobalscope::WorkletGlobalScopeType;
 use dom_struct::dom_struct;
-use euclid::{Point2D, Vector2D, Rect, Size2D};
+use euclid::{Point2D, Vector2D, Rect, Size2D, TypedPoint2D, TypedRect, TypedSize2D};
 use fetch;
 use ipc_channel::ipc::{self, IpcSender};
 use ipc_channel::router::ROUTER;
@@ -68,8 +71,9 @@ use profile_traits::time::ProfilerChan as TimeProfilerChan;
 use script_layout_interface::{TrustedNodeAddress, PendingImageState};
 use script_layout_interface::message::{Msg, Reflow, ReflowGoal, ScriptReflow};
 use script_layout_interface::reporter::CSSErrorReporter;
-use script_layout_interface::rpc::{ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
+use script_layout_interface::rpc::{CaretRectResponse, ContentBoxResponse, ContentBoxesResponse, LayoutRPC};
 use script_layout_interface::rpc::{NodeScrollIdResponse, ResolvedStyleResponse, TextIndexResponse};
+use script_layout_interface::rpc::TextWrapPointsResponse;
 use script_runtime::{CommonScriptMsg, ScriptChan, ScriptPort, ScriptThreadEventCategory, Runtime};
 use script_thread::{ImageCacheMsg, MainThreadScriptChan, MainThreadScriptMsg};
 use script_thread::{ScriptThread, SendableMainThreadScriptChan};
@@ -80,6 +84,7 @@ use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
 use selectors::attr::CaseSensitivity;
 use servo_arc;
 use servo_config::opts;
+use servo_config::prefs::PREFS;
 use servo_geometry::{f32_rect_to_au_rect, MaxRect};
 use servo_url::{Host, MutableOrigin, ImmutableOrigin, ServoUrl};
 use std::borrow::ToOwned;
@@ -102,7 +107,7 @@ use style::properties::{ComputedValues, PropertyId};
 use style::selector_parser::PseudoElement;
 use style::str::HTML_SPACE_CHARACTERS;
 use style::stylesheets::CssRuleType;
-use style_traits::ParsingMode;
+use style_traits::{CSSPixel, ParsingMode};
 use task::TaskCanceller;
 use task_source::dom_manipulation::DOMManipulationTaskSource;
 use task_source::file_reading::FileReadingTaskSource;
@@ -133,6 +138,7 @@ pub enum ReflowReason {
     RefreshTick,
     FirstLoad,
     KeyEvent,
+    ImeEvent,
     MouseEvent,
     Query,
     Timer,
@@ -256,6 +262,7 @@ pub struct Window {
     media_query_lists: WeakMediaQueryListVec,
 
     test_runner: MutNullableDom<TestRunner>,
+    test_clipboard: MutNullableDom<TestClipboard>,
 
     /// A handle for communicating messages to the webvr thread, if available.
     #[ignore_malloc_size_of = "channels are hard"]
@@ -268,6 +275,11 @@ pub struct Window {
     /// A map for storing the previous permission state read results.
     permission_state_invocation_results: DomRefCell<HashMap<String, PermissionState>>,
 
+    /// When the user last activated this window, with a mouse button, key or touch, in
+    /// `time::precise_time_ns` units.
+    /// https://html.spec.whatwg.org/multipage/#last-activation-timestamp
+    last_activation: Cell<Option<u64>>,
+
     /// All of the elements that have an outstanding image request that was
     /// initiated by layout during a reflow. They are stored in the script thread
     /// to ensure that the element can be marked dirty when the image data becomes
@@ -419,6 +431,19 @@ impl Window {
         &self.permission_state_invocation_results
     }
 
+    /// https://html.spec.whatwg.org/multipage/#activation-notification
+    pub fn notify_activation(&self) {
+        self.last_activation.set(Some(time::precise_time_ns()));
+    }
+
+    /// https://html.spec.whatwg.org/multipage/#transient-activation
+    pub fn has_transient_activation(&self) -> bool {
+        let timeout_ms = PREFS.get("dom.user_activation.transient_timeout_ms").as_u64().unwrap_or(5000);
+        self.last_activation.get().map_or(false, |last_activation| {
+            time::precise_time_ns() - last_activation <= timeout_ms * 1_000_000
+        })
+    }
+
     pub fn pending_image_notification(&self, response: PendingImageResponse) {
         //XXXjdm could be more efficient to send the responses to the layout thread,
         //       rather than making the layout thread talk to the image cache to
@@ -829,6 +854,11 @@ impl WindowMethods for Window {
         }
     }
 
+    // https://w3c.github.io/selection-api/#dom-window-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        self.Document().GetSelection()
+    }
+
     // https://drafts.csswg.org/cssom/#dom-window-getcomputedstyle
     fn GetComputedStyle(&self,
                         element: &Element,
@@ -1030,6 +1060,10 @@ impl WindowMethods for Window {
     fn TestRunner(&self) -> DomRoot<TestRunner> {
         self.test_runner.or_init(|| TestRunner::new(self.upcast()))
     }
+
+    fn TestClipboard(&self) -> DomRoot<TestClipboard> {
+        self.test_clipboard.or_init(|| TestClipboard::new(self))
+    }
 }
 
 impl Window {
@@ -1384,6 +1418,44 @@ impl Window {
         rects
     }
 
+    /// The bounds of the caret of the given text control, relative to the viewport.
+    pub fn caret_rect_query(&self, node: TrustedNodeAddress) -> Option<Rect<Au>> {
+        if !self.reflow(ReflowGoal::CaretRectQuery(node), ReflowReason::Query) {
+            return None;
+        }
+        let CaretRectResponse(rect) = self.layout_rpc.caret_rect();
+        let scroll_offset = self.current_viewport.get().origin.to_vector();
+        rect.map(|rect| rect.translate(&-scroll_offset))
+    }
+
+    /// The points where the lines of the given text control's content wrap, as a line and the
+    /// byte offset in it, or `None` if its text isn't rendered.
+    pub fn text_wrap_points_query(&self, node: TrustedNodeAddress) -> Option<Vec<(usize, usize)>> {
+        if !self.reflow(ReflowGoal::TextWrapPointsQuery(node), ReflowReason::Query) {
+            return None;
+        }
+        let TextWrapPointsResponse(wrap_points) = self.layout_rpc.text_wrap_points();
+        wrap_points
+    }
+
+    /// Tell the embedder where the caret of the given text control is, so that it can place the
+    /// candidate window of an input method next to it.
+    pub fn report_ime_caret_rect(&self, node: TrustedNodeAddress) {
+        let rect = self.caret_rect_query(node).map(|rect| {
+            TypedRect::new(TypedPoint2D::new(rect.origin.x.to_f32_px(), rect.origin.y.to_f32_px()),
+                           TypedSize2D::new(rect.size.width.to_f32_px(), rect.size.height.to_f32_px()))
+        });
+        self.send_to_constellation(ScriptMsg::SetImeCaretRect(rect));
+    }
+
+    /// Let the page know which area of the viewport the embedder's virtual keyboard covers.
+    pub fn virtual_keyboard_geometry_changed(&self, rect: TypedRect<f32, CSSPixel>) {
+        if !PREFS.get("dom.virtualkeyboard.enabled").as_boolean().unwrap_or(false) {
+            return;
+        }
+        self.Navigator().VirtualKeyboard().set_geometry(rect);
+    }
+
     pub fn client_rect_query(&self, node_geometry_request: TrustedNodeAddress) -> Rect<i32> {
         if !self.reflow(ReflowGoal::NodeGeometryQuery(node_geometry_request), ReflowReason::Query) {
             return Rect::zero();
@@ -1817,9 +1889,11 @@ impl Window {
             scroll_offsets: Default::default(),
             media_query_lists: WeakMediaQueryListVec::new(),
             test_runner: Default::default(),
+            test_clipboard: Default::default(),
             webgl_chan,
             webvr_chan,
             permission_state_invocation_results: Default::default(),
+            last_activation: Default::default(),
             pending_layout_images: Default::default(),
             unminified_js_dir: Default::default(),
             test_worklet: Default::default(),
@@ -1861,6 +1935,8 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
     debug_msg.push_str(match *reflow_goal {
         ReflowGoal::Full => "\tFull",
         ReflowGoal::ContentBoxQuery(_n) => "\tContentBoxQuery",
+        ReflowGoal::CaretRectQuery(_n) => "\tCaretRectQuery",
+        ReflowGoal::TextWrapPointsQuery(_n) => "\tTextWrapPointsQuery",
         ReflowGoal::ContentBoxesQuery(_n) => "\tContentBoxesQuery",
         ReflowGoal::NodesFromPointQuery(..) => "\tNodesFromPointQuery",
         ReflowGoal::NodeGeometryQuery(_n) => "\tNodeGeometryQuery",
@@ -1879,6 +1955,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
         ReflowReason::RefreshTick => "\tRefreshTick",
         ReflowReason::FirstLoad => "\tFirstLoad",
         ReflowReason::KeyEvent => "\tKeyEvent",
+        ReflowReason::ImeEvent => "\tImeEvent",
         ReflowReason::MouseEvent => "\tMouseEvent",
         ReflowReason::Query => "\tQuery",
         ReflowReason::Timer => "\tTimer",
@@ -0,0 +1,443 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! The `pattern` attribute of input elements, which holds an ECMAScript regular expression
+//! compiled with the `u` flag. The expression is parsed with the ECMAScript grammar and
+//! translated for `Regex`, so that syntax only `Regex` knows is rejected, and so that the
+//! character classes keep their ECMAScript meanings: `\d`, `\w` and `\b` are ASCII only, and
+//! `.` stops at every line terminator.
+//!
+//! https://html.spec.whatwg.org/multipage/#the-pattern-attribute
+
+use regex::Regex;
+use std::cmp::{max, min};
+use std::fmt::Write;
+
+/// Why a pattern can't be compiled.
+#[derive(Debug, PartialEq)]
+enum PatternError {
+    /// The pattern is not a valid ECMAScript regular expression.
+    Invalid,
+    /// The pattern is valid, but uses lookaround, backreferences or property escapes, which
+    /// `Regex` can't match.
+    Unsupported,
+}
+
+/// Ranges of code points, inclusive at both ends.
+type Ranges = Vec<(u32, u32)>;
+
+const DIGIT: &[(u32, u32)] = &[(0x30, 0x39)];
+const WORD: &[(u32, u32)] = &[(0x30, 0x39), (0x41, 0x5A), (0x5F, 0x5F), (0x61, 0x7A)];
+/// https://tc39.github.io/ecma262/#prod-WhiteSpace and
+/// https://tc39.github.io/ecma262/#prod-LineTerminator
+const SPACE: &[(u32, u32)] = &[
+    (0x9, 0xD), (0x20, 0x20), (0xA0, 0xA0), (0x1680, 0x1680), (0x2000, 0x200A), (0x2028, 0x2029),
+    (0x202F, 0x202F), (0x205F, 0x205F), (0x3000, 0x3000), (0xFEFF, 0xFEFF),
+];
+const LINE_TERMINATORS: &[(u32, u32)] = &[(0xA, 0xA), (0xD, 0xD), (0x2028, 0x2029)];
+
+/// A `Regex` that matches nothing: no position is both a word boundary and not one.
+const NOTHING: &str = "(?:\\b\\B)";
+
+/// The `pattern` attribute value of an input, compiled once for all the checks of its
+/// validity until the attribute changes.
+pub struct CompiledPattern {
+    source: String,
+    /// `None` if the pattern can't be compiled, in which case it sets no constraint.
+    regex: Option<Regex>,
+}
+
+unsafe_no_jsmanaged_fields!(CompiledPattern);
+
+impl CompiledPattern {
+    pub fn new(source: &str) -> CompiledPattern {
+        let regex = match compile(source) {
+            Ok(regex) => Some(regex),
+            Err(PatternError::Invalid) => None,
+            Err(PatternError::Unsupported) => {
+                warn!("The pattern {:?} sets no constraint, as it uses lookaround, backreferences or \
+                       property escapes, which are not supported yet.", source);
+                None
+            },
+        };
+        CompiledPattern {
+            source: source.to_owned(),
+            regex: regex,
+        }
+    }
+
+    /// The attribute value that the pattern was compiled from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches the whole of `value`. A pattern that can't be compiled
+    /// matches anything.
+    pub fn matches(&self, value: &str) -> bool {
+        self.regex.as_ref().map_or(true, |regex| regex.is_match(value))
+    }
+}
+
+/// Compile a pattern as the spec does, as `^(?:pattern)$` with the `u` flag.
+fn compile(pattern: &str) -> Result<Regex, PatternError> {
+    let mut parser = Parser {
+        chars: pattern.chars().collect(),
+        position: 0,
+        output: String::from("^(?:"),
+    };
+    parser.disjunction()?;
+    // Only an unmatched `)` stops the disjunction before the end.
+    if parser.position < parser.chars.len() {
+        return Err(PatternError::Invalid);
+    }
+    parser.output.push_str(")$");
+    Regex::new(&parser.output).map_err(|_| PatternError::Invalid)
+}
+
+/// The ranges of the code points that are in none of the given sorted ranges.
+fn complement(ranges: &[(u32, u32)]) -> Ranges {
+    let mut result = vec![];
+    let mut next = 0;
+    for &(start, end) in ranges {
+        if start > next {
+            result.push((next, start - 1));
+        }
+        next = end + 1;
+    }
+    if next <= 0x10FFFF {
+        result.push((next, 0x10FFFF));
+    }
+    result
+}
+
+/// The ranges without the surrogate code points, which are not characters that a value can
+/// hold.
+fn without_surrogates(ranges: &[(u32, u32)]) -> Ranges {
+    let mut result = vec![];
+    for &(start, end) in ranges {
+        if start < 0xD800 {
+            result.push((start, min(end, 0xD7FF)));
+        }
+        if end > 0xDFFF {
+            result.push((max(start, 0xE000), end));
+        }
+    }
+    result
+}
+
+fn single(code_point: u32) -> Result<Ranges, PatternError> {
+    Ok(vec![(code_point, code_point)])
+}
+
+/// The code point that the ranges hold, if they hold only one.
+fn single_code_point(ranges: &[(u32, u32)]) -> Option<u32> {
+    match ranges.first() {
+        Some(&(start, end)) if ranges.len() == 1 && start == end => Some(start),
+        _ => None,
+    }
+}
+
+/// A recursive descent parser for the ECMAScript pattern grammar, with the `u` flag, that
+/// writes out the `Regex` matching the same strings as it goes.
+///
+/// https://tc39.github.io/ecma262/#sec-patterns
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    output: String,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).cloned()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        if c.is_some() {
+            self.position += 1;
+        }
+        c
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn disjunction(&mut self) -> Result<(), PatternError> {
+        self.alternative()?;
+        while self.eat('|') {
+            self.output.push('|');
+            self.alternative()?;
+        }
+        Ok(())
+    }
+
+    fn alternative(&mut self) -> Result<(), PatternError> {
+        loop {
+            match self.peek() {
+                None | Some('|') | Some(')') => return Ok(()),
+                Some(_) => self.term()?,
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<(), PatternError> {
+        let quantifiable = self.atom()?;
+        match self.peek() {
+            Some('*') | Some('+') | Some('?') | Some('{') if !quantifiable => Err(PatternError::Invalid),
+            Some('*') | Some('+') | Some('?') | Some('{') => self.quantifier(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Parse an atom or an assertion, and tell whether it may be quantified.
+    fn atom(&mut self) -> Result<bool, PatternError> {
+        match self.next() {
+            Some(c) if c == '^' || c == '$' => {
+                self.output.push(c);
+                return Ok(false);
+            },
+            Some('\\') => return self.atom_escape(),
+            Some('.') => self.push_class(true, LINE_TERMINATORS),
+            Some('(') => self.group()?,
+            Some('[') => self.class()?,
+            // A `{` that starts no quantifier, and a lone `}` or `]`, are only allowed without
+            // the `u` flag.
+            Some('*') | Some('+') | Some('?') | Some('{') | Some('}') | Some(']') | None => {
+                return Err(PatternError::Invalid)
+            },
+            Some(c) => self.push_code_point(c as u32),
+        }
+        Ok(true)
+    }
+
+    fn atom_escape(&mut self) -> Result<bool, PatternError> {
+        match self.peek() {
+            Some('b') => {
+                self.position += 1;
+                self.output.push_str("(?-u:\\b)");
+                Ok(false)
+            },
+            Some('B') => {
+                self.position += 1;
+                self.output.push_str("(?-u:\\B)");
+                Ok(false)
+            },
+            Some(c) if c >= '1' && c <= '9' => Err(PatternError::Unsupported),
+            Some('k') => Err(PatternError::Unsupported),
+            _ => {
+                let ranges = self.character_escape(false)?;
+                self.push_class(false, &ranges);
+                Ok(true)
+            },
+        }
+    }
+
+    /// Parse the escape after a `\`, outside or inside a class, as the code points it matches.
+    fn character_escape(&mut self, in_class: bool) -> Result<Ranges, PatternError> {
+        match self.next() {
+            Some('d') => Ok(DIGIT.to_vec()),
+            Some('D') => Ok(complement(DIGIT)),
+            Some('w') => Ok(WORD.to_vec()),
+            Some('W') => Ok(complement(WORD)),
+            Some('s') => Ok(SPACE.to_vec()),
+            Some('S') => Ok(complement(SPACE)),
+            Some('p') | Some('P') => Err(PatternError::Unsupported),
+            Some('f') => single(0xC),
+            Some('n') => single(0xA),
+            Some('r') => single(0xD),
+            Some('t') => single(0x9),
+            Some('v') => single(0xB),
+            Some('b') if in_class => single(0x8),
+            Some('-') if in_class => single('-' as u32),
+            Some('c') => match self.next() {
+                Some(letter) if letter.is_ascii_alphabetic() => single(letter as u32 % 32),
+                _ => Err(PatternError::Invalid),
+            },
+            Some('0') if !self.peek().map_or(false, |c| c.is_ascii_digit()) => single(0),
+            Some('x') => self.hex(2).map_or(Err(PatternError::Invalid), single),
+            Some('u') => self.unicode_escape().and_then(single),
+            Some(c) if "^$\\.*+?()[]{}|/".contains(c) => single(c as u32),
+            _ => Err(PatternError::Invalid),
+        }
+    }
+
+    fn hex(&mut self, digits: usize) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..digits {
+            let digit = self.peek()?.to_digit(16)?;
+            self.position += 1;
+            value = value * 16 + digit;
+        }
+        Some(value)
+    }
+
+    fn unicode_escape(&mut self) -> Result<u32, PatternError> {
+        if self.eat('{') {
+            let mut value: u32 = 0;
+            let mut digits = 0;
+            while let Some(digit) = self.peek().and_then(|c| c.to_digit(16)) {
+                self.position += 1;
+                digits += 1;
+                value = value.saturating_mul(16).saturating_add(digit);
+            }
+            if digits == 0 || value > 0x10FFFF || !self.eat('}') {
+                return Err(PatternError::Invalid);
+            }
+            return Ok(value);
+        }
+        let lead = self.hex(4).ok_or(PatternError::Invalid)?;
+        // A surrogate pair written as two escapes is the code point they encode.
+        if lead >= 0xD800 && lead <= 0xDBFF && self.peek() == Some('\\') && self.peek_at(1) == Some('u') {
+            let start = self.position;
+            self.position += 2;
+            match self.hex(4) {
+                Some(trail) if trail >= 0xDC00 && trail <= 0xDFFF => {
+                    return Ok(0x10000 + ((lead - 0xD800) << 10) + (trail - 0xDC00));
+                },
+                _ => self.position = start,
+            }
+        }
+        Ok(lead)
+    }
+
+    fn group(&mut self) -> Result<(), PatternError> {
+        if self.eat('?') {
+            match self.next() {
+                Some(':') => self.output.push_str("(?:"),
+                Some('=') | Some('!') => return Err(PatternError::Unsupported),
+                Some('<') if self.peek() == Some('=') || self.peek() == Some('!') => {
+                    return Err(PatternError::Unsupported)
+                },
+                // Without backreferences, a named group is as good as any other.
+                Some('<') => {
+                    self.group_name()?;
+                    self.output.push('(');
+                },
+                _ => return Err(PatternError::Invalid),
+            }
+        } else {
+            self.output.push('(');
+        }
+        self.disjunction()?;
+        if !self.eat(')') {
+            return Err(PatternError::Invalid);
+        }
+        self.output.push(')');
+        Ok(())
+    }
+
+    fn group_name(&mut self) -> Result<(), PatternError> {
+        let mut empty = true;
+        loop {
+            match self.next() {
+                Some('>') if !empty => return Ok(()),
+                Some(c) if c == '$' || c == '_' || c.is_alphabetic() || (!empty && c.is_numeric()) => {
+                    empty = false
+                },
+                _ => return Err(PatternError::Invalid),
+            }
+        }
+    }
+
+    fn quantifier(&mut self) -> Result<(), PatternError> {
+        match self.next() {
+            Some('{') => {
+                let least = self.decimal().ok_or(PatternError::Invalid)?;
+                let most = if self.eat(',') { self.decimal() } else { Some(least) };
+                if !self.eat('}') || most.map_or(false, |most| most < least) {
+                    return Err(PatternError::Invalid);
+                }
+                let written = match most {
+                    Some(most) if most == least => write!(self.output, "{{{}}}", least),
+                    Some(most) => write!(self.output, "{{{},{}}}", least, most),
+                    None => write!(self.output, "{{{},}}", least),
+                };
+                written.unwrap();
+            },
+            Some(c) => self.output.push(c),
+            None => unreachable!(),
+        }
+        if self.eat('?') {
+            self.output.push('?');
+        }
+        Ok(())
+    }
+
+    fn decimal(&mut self) -> Option<u32> {
+        let mut value: Option<u32> = None;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            self.position += 1;
+            value = Some(value.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        }
+        value
+    }
+
+    fn class(&mut self) -> Result<(), PatternError> {
+        let negated = self.eat('^');
+        let mut ranges = vec![];
+        while let Some(start) = self.class_atom()? {
+            if self.peek() == Some('-') && self.peek_at(1).map_or(false, |c| c != ']') {
+                self.position += 1;
+                let end = self.class_atom()?.ok_or(PatternError::Invalid)?;
+                // Only single characters, not class escapes, can bound a range.
+                match (single_code_point(&start), single_code_point(&end)) {
+                    (Some(start), Some(end)) if start <= end => ranges.push((start, end)),
+                    _ => return Err(PatternError::Invalid),
+                }
+            } else {
+                ranges.extend(start);
+            }
+        }
+        self.push_class(negated, &ranges);
+        Ok(())
+    }
+
+    /// Parse a character or escape in a class, or the `]` that closes it as `None`.
+    fn class_atom(&mut self) -> Result<Option<Ranges>, PatternError> {
+        match self.next() {
+            None => Err(PatternError::Invalid),
+            Some(']') => Ok(None),
+            Some('\\') => self.character_escape(true).map(Some),
+            Some(c) => single(c as u32).map(Some),
+        }
+    }
+
+    fn push_code_point(&mut self, code_point: u32) {
+        match ::std::char::from_u32(code_point) {
+            Some(c) if c.is_ascii_alphanumeric() => self.output.push(c),
+            _ => write!(self.output, "\\x{{{:X}}}", code_point).unwrap(),
+        }
+    }
+
+    fn push_class(&mut self, negated: bool, ranges: &[(u32, u32)]) {
+        let ranges = without_surrogates(ranges);
+        // An empty class matches nothing, and its negation any character.
+        if ranges.is_empty() {
+            return self.output.push_str(if negated { "(?s:.)" } else { NOTHING });
+        }
+        if let (false, Some(code_point)) = (negated, single_code_point(&ranges)) {
+            return self.push_code_point(code_point);
+        }
+        self.output.push_str(if negated { "[^" } else { "[" });
+        for &(start, end) in &ranges {
+            self.push_code_point(start);
+            if end != start {
+                self.output.push('-');
+                self.push_code_point(end);
+            }
+        }
+        self.output.push(']');
+    }
+}
@@ -0,0 +1,110 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Scripted sequences of key presses, so that tests can drive a text control without building
+//! each `Key` and `KeyModifiers` by hand:
+//!
+//! ```ignore
+//! let reactions = KeySequence::new()
+//!     .type_text("hello world")
+//!     .press_times("Control+Shift+ArrowLeft", 2)
+//!     .press("Backspace")
+//!     .send_to(&mut textinput);
+//! ```
+
+use clipboard_provider::ClipboardProvider;
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventConstants;
+use dom::keyboardevent::key_from_string;
+use keybindings::parse_key_combination;
+use msg::constellation_msg::{Key, KeyModifiers};
+use text_buffer::TextBuffer;
+use textinput::{KeyReaction, TextInput};
+
+/// A single key press, as passed to `TextInput::handle_keydown_aux`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyPress {
+    pub printable: Option<char>,
+    pub key: Key,
+    pub modifiers: KeyModifiers,
+}
+
+/// A builder for a sequence of key presses.
+#[derive(Clone, Debug, Default)]
+pub struct KeySequence {
+    presses: Vec<KeyPress>,
+}
+
+impl KeySequence {
+    pub fn new() -> KeySequence {
+        KeySequence::default()
+    }
+
+    /// Type each character of `text`. Line breaks and tabs press Enter and Tab, upper case
+    /// letters are typed with Shift held, and characters without a key of their own are sent
+    /// as an unidentified key.
+    pub fn type_text(mut self, text: &str) -> KeySequence {
+        for c in text.chars() {
+            let press = match c {
+                '\n' => KeyPress { printable: None, key: Key::Enter, modifiers: KeyModifiers::empty() },
+                '\t' => KeyPress { printable: None, key: Key::Tab, modifiers: KeyModifiers::empty() },
+                _ => {
+                    let name = c.to_string();
+                    let key = key_from_string(&name, KeyboardEventConstants::DOM_KEY_LOCATION_STANDARD);
+                    KeyPress {
+                        printable: Some(c),
+                        key: key.unwrap_or(Key::World1),
+                        modifiers: if c.is_uppercase() { KeyModifiers::SHIFT } else { KeyModifiers::empty() },
+                    }
+                },
+            };
+            self.presses.push(press);
+        }
+        self
+    }
+
+    /// Press a dead key that adds the given accent to the next character.
+    pub fn dead_key(mut self, accent: char) -> KeySequence {
+        self.presses.push(KeyPress { printable: Some(accent), key: Key::Dead, modifiers: KeyModifiers::empty() });
+        self
+    }
+
+    /// Press a `Modifier+...+Key` combination, written as in the `dom.textinput.keybindings`
+    /// preference, e.g. `Control+Shift+ArrowLeft`. Panics if the combination is malformed.
+    pub fn press(self, combination: &str) -> KeySequence {
+        self.press_times(combination, 1)
+    }
+
+    /// Press a `Modifier+...+Key` combination `times` times.
+    pub fn press_times(self, combination: &str, times: usize) -> KeySequence {
+        let (key, modifiers) = parse_key_combination(combination)
+            .unwrap_or_else(|| panic!("Malformed key combination {:?}", combination));
+        self.press_key_times(key, modifiers, times)
+    }
+
+    /// Press a key with the given modifiers held, without any printable character.
+    pub fn press_key(self, key: Key, modifiers: KeyModifiers) -> KeySequence {
+        self.press_key_times(key, modifiers, 1)
+    }
+
+    /// Press a key with the given modifiers held `times` times.
+    pub fn press_key_times(mut self, key: Key, modifiers: KeyModifiers, times: usize) -> KeySequence {
+        for _ in 0..times {
+            self.presses.push(KeyPress { printable: None, key: key, modifiers: modifiers });
+        }
+        self
+    }
+
+    /// The key presses in this sequence.
+    pub fn presses(&self) -> &[KeyPress] {
+        &self.presses
+    }
+
+    /// Send the key presses to `textinput` in order, and return its reaction to each of them.
+    pub fn send_to<T: ClipboardProvider, B: TextBuffer>(&self, textinput: &mut TextInput<T, B>)
+                                                        -> Vec<KeyReaction> {
+        self.presses.iter()
+            .map(|press| textinput.handle_keydown_aux(press.printable, press.key, press.modifiers))
+            .collect()
+    }
+}
@@ -0,0 +1,329 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Mapping of keyboard shortcuts to the editing commands understood by text input controls.
+//!
+//! Each platform has a default table, which can be amended through the
+//! `dom.textinput.keybindings` preference. The preference holds a whitespace-separated list of
+//! `Modifier+...+Key=command` entries, where the key is a DOM key value (e.g. `ArrowLeft`,
+//! `Backspace` or `u`) and the command is one of the names accepted by
+//! `EditCommand::from_name`, or `none` to remove an existing binding. For example:
+//!
+//! ```text
+//! Control+k=delete-to-line-start Alt+ArrowLeft=none
+//! ```
+
+use dom::bindings::codegen::Bindings::KeyboardEventBinding::KeyboardEventConstants;
+use dom::keyboardevent::key_from_string;
+use msg::constellation_msg::{Key, KeyModifiers};
+use servo_config::prefs::PREFS;
+
+/// A named editing operation that a key combination can trigger.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EditCommand {
+    MoveCharBackward,
+    MoveCharForward,
+    MoveWordBackward,
+    MoveWordForward,
+    MoveLineStart,
+    MoveLineEnd,
+    MoveUp,
+    MoveDown,
+    MovePageUp,
+    MovePageDown,
+    MoveDocumentStart,
+    MoveDocumentEnd,
+    SelectAll,
+    Copy,
+    Cut,
+    Paste,
+    DeleteBackward,
+    DeleteForward,
+    DeleteToLineStart,
+    InsertLineBreak,
+    InsertTab,
+    Cancel,
+}
+
+impl EditCommand {
+    /// Look up a command by the name used in the `dom.textinput.keybindings` preference.
+    pub fn from_name(name: &str) -> Option<EditCommand> {
+        Some(match name {
+            "move-char-backward" => EditCommand::MoveCharBackward,
+            "move-char-forward" => EditCommand::MoveCharForward,
+            "move-word-backward" => EditCommand::MoveWordBackward,
+            "move-word-forward" => EditCommand::MoveWordForward,
+            "move-line-start" => EditCommand::MoveLineStart,
+            "move-line-end" => EditCommand::MoveLineEnd,
+            "move-up" => EditCommand::MoveUp,
+            "move-down" => EditCommand::MoveDown,
+            "move-page-up" => EditCommand::MovePageUp,
+            "move-page-down" => EditCommand::MovePageDown,
+            "move-document-start" => EditCommand::MoveDocumentStart,
+            "move-document-end" => EditCommand::MoveDocumentEnd,
+            "select-all" => EditCommand::SelectAll,
+            "copy" => EditCommand::Copy,
+            "cut" => EditCommand::Cut,
+            "paste" => EditCommand::Paste,
+            "delete-backward" => EditCommand::DeleteBackward,
+            "delete-forward" => EditCommand::DeleteForward,
+            "delete-to-line-start" => EditCommand::DeleteToLineStart,
+            "insert-line-break" => EditCommand::InsertLineBreak,
+            "insert-tab" => EditCommand::InsertTab,
+            "cancel" => EditCommand::Cancel,
+            _ => return None,
+        })
+    }
+}
+
+/// The platform whose conventions a default keybinding table follows.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Platform {
+    Mac,
+    Other,
+}
+
+impl Platform {
+    /// The platform Servo was built for.
+    #[cfg(target_os = "macos")]
+    pub fn current() -> Platform {
+        Platform::Mac
+    }
+
+    /// The platform Servo was built for.
+    #[cfg(not(target_os = "macos"))]
+    pub fn current() -> Platform {
+        Platform::Other
+    }
+
+    /// The standard control modifier, i.e. cmd on Mac OS or ctrl on other platforms.
+    pub fn control_modifier(self) -> KeyModifiers {
+        match self {
+            Platform::Mac => KeyModifiers::SUPER,
+            Platform::Other => KeyModifiers::CONTROL,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct KeyBinding {
+    key: Key,
+    modifiers: KeyModifiers,
+    command: EditCommand,
+}
+
+/// A table of key combinations and the editing commands they trigger.
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    bindings: Vec<KeyBinding>,
+}
+
+unsafe_no_jsmanaged_fields!(KeyBindings);
+
+impl KeyBindings {
+    /// A table without any bindings.
+    pub fn empty() -> KeyBindings {
+        KeyBindings {
+            bindings: vec!(),
+        }
+    }
+
+    /// The default bindings for the given platform.
+    pub fn for_platform(platform: Platform) -> KeyBindings {
+        let none = KeyModifiers::empty();
+        let control = platform.control_modifier();
+        let control_alt = KeyModifiers::CONTROL | KeyModifiers::ALT;
+
+        let mut bindings = KeyBindings::empty();
+        bindings.bind(Key::B, control_alt, Some(EditCommand::MoveWordBackward));
+        bindings.bind(Key::F, control_alt, Some(EditCommand::MoveWordForward));
+        bindings.bind(Key::A, control_alt, Some(EditCommand::MoveLineStart));
+        bindings.bind(Key::E, control_alt, Some(EditCommand::MoveLineEnd));
+        bindings.bind(Key::A, control, Some(EditCommand::SelectAll));
+        bindings.bind(Key::C, control, Some(EditCommand::Copy));
+        bindings.bind(Key::X, control, Some(EditCommand::Cut));
+        bindings.bind(Key::V, control, Some(EditCommand::Paste));
+        bindings.bind(Key::U, KeyModifiers::CONTROL, Some(EditCommand::DeleteToLineStart));
+        bindings.bind(Key::Delete, none, Some(EditCommand::DeleteForward));
+        bindings.bind(Key::Backspace, none, Some(EditCommand::DeleteBackward));
+        bindings.bind(Key::Left, KeyModifiers::ALT, Some(EditCommand::MoveWordBackward));
+        bindings.bind(Key::Right, KeyModifiers::ALT, Some(EditCommand::MoveWordForward));
+        bindings.bind(Key::Left, none, Some(EditCommand::MoveCharBackward));
+        bindings.bind(Key::Right, none, Some(EditCommand::MoveCharForward));
+        bindings.bind(Key::Up, none, Some(EditCommand::MoveUp));
+        bindings.bind(Key::Down, none, Some(EditCommand::MoveDown));
+        bindings.bind(Key::PageUp, none, Some(EditCommand::MovePageUp));
+        bindings.bind(Key::PageDown, none, Some(EditCommand::MovePageDown));
+        bindings.bind(Key::Enter, none, Some(EditCommand::InsertLineBreak));
+        bindings.bind(Key::KpEnter, none, Some(EditCommand::InsertLineBreak));
+        bindings.bind(Key::Tab, none, Some(EditCommand::InsertTab));
+        bindings.bind(Key::Escape, none, Some(EditCommand::Cancel));
+
+        match platform {
+            Platform::Mac => {
+                bindings.bind(Key::A, KeyModifiers::CONTROL, Some(EditCommand::MoveLineStart));
+                bindings.bind(Key::E, KeyModifiers::CONTROL, Some(EditCommand::MoveLineEnd));
+                bindings.bind(Key::Left, KeyModifiers::SUPER, Some(EditCommand::MoveLineStart));
+                bindings.bind(Key::Right, KeyModifiers::SUPER, Some(EditCommand::MoveLineEnd));
+                bindings.bind(Key::Up, KeyModifiers::SUPER, Some(EditCommand::MoveDocumentStart));
+                bindings.bind(Key::Down, KeyModifiers::SUPER, Some(EditCommand::MoveDocumentEnd));
+                bindings.bind(Key::Home, none, Some(EditCommand::MoveDocumentStart));
+                bindings.bind(Key::End, none, Some(EditCommand::MoveDocumentEnd));
+            },
+            Platform::Other => {
+                bindings.bind(Key::Left, KeyModifiers::CONTROL, Some(EditCommand::MoveWordBackward));
+                bindings.bind(Key::Right, KeyModifiers::CONTROL, Some(EditCommand::MoveWordForward));
+                bindings.bind(Key::Home, none, Some(EditCommand::MoveLineStart));
+                bindings.bind(Key::End, none, Some(EditCommand::MoveLineEnd));
+                // The IBM CUA clipboard shortcuts, which predate Ctrl+C/X/V.
+                bindings.bind(Key::Insert, KeyModifiers::CONTROL, Some(EditCommand::Copy));
+                bindings.bind(Key::Delete, KeyModifiers::SHIFT, Some(EditCommand::Cut));
+                bindings.bind(Key::Insert, KeyModifiers::SHIFT, Some(EditCommand::Paste));
+            },
+        }
+        bindings
+    }
+
+    /// The default bindings for the current platform, amended by the
+    /// `dom.textinput.keybindings` preference.
+    pub fn from_prefs() -> KeyBindings {
+        let mut bindings = KeyBindings::for_platform(Platform::current());
+        if let Some(overrides) = PREFS.get("dom.textinput.keybindings").as_string() {
+            bindings.apply_overrides(overrides);
+        }
+        bindings
+    }
+
+    /// Bind a key combination to a command, replacing any existing binding for it. Passing
+    /// `None` as the command removes the binding.
+    pub fn bind(&mut self, key: Key, modifiers: KeyModifiers, command: Option<EditCommand>) {
+        self.bindings.retain(|b| b.key != key || b.modifiers != modifiers);
+        if let Some(command) = command {
+            self.bindings.push(KeyBinding {
+                key: key,
+                modifiers: modifiers,
+                command: command,
+            });
+        }
+    }
+
+    /// Apply a whitespace-separated list of `Modifier+Key=command` entries, as found in the
+    /// `dom.textinput.keybindings` preference. Malformed entries are ignored.
+    pub fn apply_overrides(&mut self, overrides: &str) {
+        for entry in overrides.split_whitespace() {
+            match parse_binding(entry) {
+                Some((key, modifiers, command)) => self.bind(key, modifiers, command),
+                None => warn!("Ignoring malformed keybinding {:?}", entry),
+            }
+        }
+    }
+
+    /// The command bound to the given key combination, if any.
+    ///
+    /// Shift is used to extend the selection, so a combination including it falls back to the
+    /// binding without it unless it has been bound explicitly.
+    pub fn lookup(&self, key: Key, modifiers: KeyModifiers) -> Option<EditCommand> {
+        self.find(key, modifiers).or_else(|| {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                self.find(key, modifiers - KeyModifiers::SHIFT)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// The command triggered by a key event, if any. This is `lookup`, except that a key event
+    /// that types a character with AltGr triggers nothing, and that keypad keys which do not type
+    /// anything, as when NumLock is off, act as the navigation keys printed on them.
+    pub fn lookup_event(&self, printable: Option<char>, key: Key, modifiers: KeyModifiers)
+                        -> Option<EditCommand> {
+        if is_altgr_character(printable, key, modifiers) {
+            return None;
+        }
+        let key = match printable {
+            None => keypad_navigation_key(key).unwrap_or(key),
+            Some(_) => key,
+        };
+        self.lookup(key, modifiers)
+    }
+
+    fn find(&self, key: Key, modifiers: KeyModifiers) -> Option<EditCommand> {
+        self.bindings.iter()
+            .find(|b| b.key == key && b.modifiers == modifiers)
+            .map(|b| b.command)
+    }
+}
+
+/// Whether a key event types a character with AltGr, which many layouts report as Ctrl+Alt.
+/// This is the case when the character is not the one the key types by itself, e.g. `@` from
+/// AltGr+Q on a German keyboard, as opposed to `b` from a Ctrl+Alt+B shortcut.
+fn is_altgr_character(printable: Option<char>, key: Key, modifiers: KeyModifiers) -> bool {
+    if !modifiers.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+        return false;
+    }
+    match printable {
+        Some(c) if !c.is_control() => {
+            let own_key = key_from_string(&c.to_string(), KeyboardEventConstants::DOM_KEY_LOCATION_STANDARD);
+            own_key != Some(key)
+        },
+        _ => false,
+    }
+}
+
+/// The navigation key that a keypad key stands for when NumLock is off.
+fn keypad_navigation_key(key: Key) -> Option<Key> {
+    Some(match key {
+        Key::Kp0 => Key::Insert,
+        Key::Kp1 => Key::End,
+        Key::Kp2 => Key::Down,
+        Key::Kp3 => Key::PageDown,
+        Key::Kp4 => Key::Left,
+        Key::Kp6 => Key::Right,
+        Key::Kp7 => Key::Home,
+        Key::Kp8 => Key::Up,
+        Key::Kp9 => Key::PageUp,
+        Key::KpDecimal => Key::Delete,
+        _ => return None,
+    })
+}
+
+/// Parse a single `Modifier+...+Key=command` entry.
+fn parse_binding(entry: &str) -> Option<(Key, KeyModifiers, Option<EditCommand>)> {
+    let mut parts = entry.splitn(2, '=');
+    let combination = parts.next()?;
+    let command = match parts.next()? {
+        "none" => None,
+        name => Some(EditCommand::from_name(name)?),
+    };
+    let (key, modifiers) = parse_key_combination(combination)?;
+    Some((key, modifiers, command))
+}
+
+/// Parse a `Modifier+...+Key` combination such as `Control+Shift+ArrowLeft`, where the key is
+/// a DOM key value.
+pub fn parse_key_combination(combination: &str) -> Option<(Key, KeyModifiers)> {
+    // The key itself may be "+", in which case it is the last character of the combination.
+    let (modifier_names, key_name) = if combination == "+" || combination.ends_with("++") {
+        (&combination[..combination.len() - 1], "+")
+    } else {
+        match combination.rfind('+') {
+            Some(index) => (&combination[..index], &combination[index + 1..]),
+            None => ("", combination),
+        }
+    };
+
+    let mut modifiers = KeyModifiers::empty();
+    for name in modifier_names.split('+').filter(|name| !name.is_empty()) {
+        modifiers = modifiers | match name {
+            "Shift" => KeyModifiers::SHIFT,
+            "Control" | "Ctrl" => KeyModifiers::CONTROL,
+            "Alt" => KeyModifiers::ALT,
+            "Super" | "Meta" | "Cmd" => KeyModifiers::SUPER,
+            _ => return None,
+        };
+    }
+
+    let key = key_from_string(key_name, KeyboardEventConstants::DOM_KEY_LOCATION_STANDARD)?;
+    Some((key, modifiers))
+}
@@ -96,7 +96,10 @@ extern crate swapper;
 extern crate time;
 #[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
 extern crate tinyfiledialogs;
+extern crate unicode_bidi;
+extern crate unicode_normalization;
 extern crate unicode_segmentation;
+extern crate unicode_width;
 extern crate url;
 extern crate utf8;
 extern crate uuid;
@@ -109,11 +112,15 @@ mod task;
 
 mod body;
 pub mod clipboard_provider;
+pub mod compose;
 mod devtools;
 pub mod document_loader;
 #[macro_use]
 mod dom;
 pub mod fetch;
+mod input_pattern;
+pub mod key_sequence;
+pub mod keybindings;
 mod layout_image;
 mod mem;
 mod microtask;
@@ -121,15 +128,21 @@ mod network_listener;
 pub mod script_runtime;
 #[allow(unsafe_code)]
 pub mod script_thread;
+pub mod segmented_input;
 mod serviceworker_manager;
 mod serviceworkerjob;
+pub mod spellcheck_provider;
 mod stylesheet_loader;
 mod task_source;
 pub mod test;
+#[cfg(feature = "test_clipboard")]
+pub mod test_clipboard;
+pub mod text_buffer;
 pub mod textinput;
 mod timers;
 mod unpremultiplytable;
 mod webdriver_handlers;
+pub mod word_segmentation;
 
 /// A module with everything layout can use from script.
 ///
@@ -61,7 +61,7 @@ use dom::windowproxy::WindowProxy;
 use dom::worker::TrustedWorkerAddress;
 use dom::worklet::WorkletThreadPool;
 use dom::workletglobalscope::WorkletGlobalScopeInit;
-use euclid::{Point2D, Vector2D, Rect};
+use euclid::{Point2D, Vector2D, Rect, TypedRect};
 use fetch::FetchCanceller;
 use hyper::header::{ContentType, HttpDate, Headers, LastModified};
 use hyper::header::ReferrerPolicy as ReferrerPolicyHeader;
@@ -77,7 +77,8 @@ use malloc_size_of::MallocSizeOfOps;
 use mem::malloc_size_of_including_self;
 use metrics::{MAX_TASK_NS, PaintTimeMetrics};
 use microtask::{MicrotaskQueue, Microtask};
-use msg::constellation_msg::{BrowsingContextId, PipelineId, PipelineNamespace, TopLevelBrowsingContextId};
+use msg::constellation_msg::{BrowsingContextId, ImeSurroundingText, PipelineId, PipelineNamespace};
+use msg::constellation_msg::TopLevelBrowsingContextId;
 use net_traits::{FetchMetadata, FetchResponseListener, FetchResponseMsg};
 use net_traits::{Metadata, NetworkError, ReferrerPolicy, ResourceThreads};
 use net_traits::image_cache::{ImageCache, PendingImageResponse};
@@ -96,7 +97,7 @@ use script_traits::{ProgressiveWebMetricType, Painter, ScriptMsg, ScriptThreadFa
 use script_traits::{ScriptToConstellationChan, TimerEvent, TimerSchedulerMsg};
 use script_traits::{TimerSource, TouchEventType, TouchId, UntrustedNodeAddress};
 use script_traits::{UpdatePipelineIdReason, WindowSizeData, WindowSizeType};
-use script_traits::CompositorEvent::{KeyEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent, TouchEvent};
+use script_traits::CompositorEvent::{ImeEvent, KeyEvent, MouseButtonEvent, MouseMoveEvent, ResizeEvent, TouchEvent};
 use script_traits::webdriver_msg::WebDriverScriptCommand;
 use serviceworkerjob::{Job, JobQueue};
 use servo_atoms::Atom;
@@ -114,6 +115,7 @@ use std::sync::Arc;
 use std::sync::mpsc::{Receiver, Select, Sender, channel};
 use std::thread;
 use style::thread_state::{self, ThreadState};
+use style_traits::CSSPixel;
 use task_source::dom_manipulation::DOMManipulationTaskSource;
 use task_source::file_reading::FileReadingTaskSource;
 use task_source::history_traversal::HistoryTraversalTaskSource;
@@ -1163,6 +1165,11 @@ impl ScriptThread {
                     Viewport(id, ..) => Some(id),
                     SetScrollState(id, ..) => Some(id),
                     GetTitle(id) => Some(id),
+                    GetImeSurroundingText(id, ..) => Some(id),
+                    VirtualKeyboardGeometryChange(id, ..) => Some(id),
+                    RevealPassword(id, ..) => Some(id),
+                    Autofill(id, ..) => Some(id),
+                    ChooseSuggestion(id, ..) => Some(id),
                     SetDocumentActivity(id, ..) => Some(id),
                     ChangeFrameVisibilityStatus(id, ..) => Some(id),
                     NotifyVisibilityChange(id, ..) => Some(id),
@@ -1279,6 +1286,16 @@ impl ScriptThread {
                 self.handle_resize_inactive_msg(id, new_size),
             ConstellationControlMsg::GetTitle(pipeline_id) =>
                 self.handle_get_title_msg(pipeline_id),
+            ConstellationControlMsg::GetImeSurroundingText(pipeline_id, before, after, sender) =>
+                self.handle_get_ime_surrounding_text_msg(pipeline_id, before, after, sender),
+            ConstellationControlMsg::VirtualKeyboardGeometryChange(pipeline_id, rect) =>
+                self.handle_virtual_keyboard_geometry_change_msg(pipeline_id, rect),
+            ConstellationControlMsg::RevealPassword(pipeline_id, revealed) =>
+                self.handle_reveal_password_msg(pipeline_id, revealed),
+            ConstellationControlMsg::Autofill(pipeline_id, value) =>
+                self.handle_autofill_msg(pipeline_id, value),
+            ConstellationControlMsg::ChooseSuggestion(pipeline_id, value) =>
+                self.handle_choose_suggestion_msg(pipeline_id, value),
             ConstellationControlMsg::SetDocumentActivity(pipeline_id, activity) =>
                 self.handle_set_document_activity_msg(pipeline_id, activity),
             ConstellationControlMsg::ChangeFrameVisibilityStatus(pipeline_id, visible) =>
@@ -1814,6 +1831,45 @@ impl ScriptThread {
         document.send_title_to_constellation();
     }
 
+    fn handle_get_ime_surrounding_text_msg(&self,
+                                           pipeline_id: PipelineId,
+                                           before: usize,
+                                           after: usize,
+                                           sender: IpcSender<Option<ImeSurroundingText>>) {
+        let text = self.documents.borrow().find_document(pipeline_id)
+                       .and_then(|document| document.ime_surrounding_text(before, after));
+        let _ = sender.send(text);
+    }
+
+    fn handle_virtual_keyboard_geometry_change_msg(&self, pipeline_id: PipelineId, rect: TypedRect<f32, CSSPixel>) {
+        let window = match { self.documents.borrow().find_window(pipeline_id) } {
+            Some(window) => window,
+            None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
+        };
+        window.virtual_keyboard_geometry_changed(rect);
+    }
+
+    fn handle_reveal_password_msg(&self, pipeline_id: PipelineId, revealed: bool) {
+        match { self.documents.borrow().find_document(pipeline_id) } {
+            Some(document) => document.reveal_password(revealed),
+            None => warn!("Message sent to closed pipeline {}.", pipeline_id),
+        }
+    }
+
+    fn handle_autofill_msg(&self, pipeline_id: PipelineId, value: String) {
+        match { self.documents.borrow().find_document(pipeline_id) } {
+            Some(document) => document.autofill(DOMString::from(value)),
+            None => warn!("Message sent to closed pipeline {}.", pipeline_id),
+        }
+    }
+
+    fn handle_choose_suggestion_msg(&self, pipeline_id: PipelineId, value: String) {
+        match { self.documents.borrow().find_document(pipeline_id) } {
+            Some(document) => document.choose_suggestion(DOMString::from(value)),
+            None => warn!("Message sent to closed pipeline {}.", pipeline_id),
+        }
+    }
+
     /// Handles a request to exit a pipeline and shut down layout.
     fn handle_exit_pipeline_msg(&self, id: PipelineId, discard_bc: DiscardBrowsingContext) {
         debug!("Exiting pipeline {}.", id);
@@ -2352,6 +2408,14 @@ impl ScriptThread {
                 };
                 document.dispatch_key_event(ch, key, state, modifiers);
             }
+
+            ImeEvent(event) => {
+                let document = match { self.documents.borrow().find_document(pipeline_id) } {
+                    Some(document) => document,
+                    None => return warn!("Message sent to closed pipeline {}.", pipeline_id),
+                };
+                document.dispatch_ime_event(event);
+            }
         }
     }
 
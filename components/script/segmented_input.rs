@@ -0,0 +1,740 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Editing of inputs whose value is made of numeric fields, such as the year, month and day of
+//! a date or the hour and minute of a time, one field at a time. The user moves between the
+//! fields with the arrow keys and Tab, and types digits over the focused field or steps it up
+//! and down, rather than editing the value as free text with a `TextInput`.
+//!
+//! The fields are kept as the owner shows them, e.g. `2018-mm-05` or `09:mm PM`, with a
+//! placeholder in place of each field that has no value yet, and are read back from that text
+//! for every key. Which fields there are follows from the text too: the fields of a date are
+//! separated by dashes, and those of a time by colons, with its milliseconds after a dot and
+//! whether it is AM or PM after a space. A month has only the year and the month of a date, and
+//! a week has a year and a week, which is shown after a `W`. A date and a time, as of a datetime-local input, are
+//! separated by a space.
+
+use msg::constellation_msg::{Key, KeyModifiers};
+use std::cmp::min;
+use std::ops::Range;
+use textinput::Direction;
+
+/// How a date is shown while none of its fields have a value.
+pub const EMPTY_DATE: &'static str = "yyyy-mm-dd";
+
+/// How a month is shown while none of its fields have a value.
+pub const EMPTY_MONTH: &'static str = "yyyy-mm";
+
+/// How a week is shown while none of its fields have a value.
+pub const EMPTY_WEEK: &'static str = "yyyy-Www";
+
+/// The largest year of a date input.
+///
+/// https://html.spec.whatwg.org/multipage/#date-state-(type=date)
+const MAX_YEAR: u32 = 275760;
+
+/// A field of the value of a segmented input.
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum SegmentKind {
+    Year,
+    Month,
+    Day,
+    /// The week of a year, shown after a `W`.
+    Week,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    /// Whether a time on a twelve hour clock is AM, shown as `AM` and taken as 0, or PM, shown
+    /// as `PM` and taken as 1.
+    Meridiem,
+}
+
+impl SegmentKind {
+    /// What is shown in place of the field while it has no value.
+    fn placeholder(&self) -> &'static str {
+        match *self {
+            SegmentKind::Year => "yyyy",
+            SegmentKind::Month => "mm",
+            SegmentKind::Day => "dd",
+            SegmentKind::Week => "ww",
+            SegmentKind::Hour => "hh",
+            SegmentKind::Minute => "mm",
+            SegmentKind::Second => "ss",
+            SegmentKind::Millisecond => "sss",
+            SegmentKind::Meridiem => "--",
+        }
+    }
+
+    /// The number of digits the field is shown with, after which typing moves on to the next
+    /// field.
+    fn width(&self) -> usize {
+        match *self {
+            SegmentKind::Year => 4,
+            SegmentKind::Millisecond => 3,
+            _ => 2,
+        }
+    }
+
+    /// The value of the field as shown, or `None` for its placeholder.
+    fn parse(&self, shown: &str) -> Option<u32> {
+        match *self {
+            SegmentKind::Meridiem => match shown {
+                "AM" => Some(0),
+                "PM" => Some(1),
+                _ => None,
+            },
+            _ if shown.is_empty() || !shown.chars().all(|c| c.is_digit(10)) => None,
+            _ => shown.parse().ok(),
+        }
+    }
+
+    /// How the given value of the field is shown.
+    fn format(&self, value: Option<u32>) -> String {
+        match (*self, value) {
+            (SegmentKind::Meridiem, Some(0)) => "AM".to_owned(),
+            (SegmentKind::Meridiem, Some(_)) => "PM".to_owned(),
+            (kind, Some(value)) => format!("{:01$}", value, kind.width()),
+            (kind, None) => kind.placeholder().to_owned(),
+        }
+    }
+}
+
+/// The fields shown in `display`, each with its byte range. A date comes first, and is followed
+/// by a space if there is a time after it.
+fn layout(display: &str) -> Vec<(SegmentKind, Range<usize>)> {
+    let mut fields = vec!();
+    let first_word = display.find(' ').unwrap_or(display.len());
+    let time_start = if display[..first_word].contains(':') {
+        0
+    } else {
+        push_fields(&mut fields, display, 0..first_word, false);
+        if !display.contains(':') {
+            return fields;
+        }
+        min(first_word + 1, display.len())
+    };
+    push_fields(&mut fields, display, time_start..display.len(), true);
+    fields
+}
+
+/// Add the fields of the date or time shown in the given range of `display` to `fields`.
+fn push_fields(fields: &mut Vec<(SegmentKind, Range<usize>)>, display: &str, range: Range<usize>, time: bool) {
+    let is_separator = |c: char| if time { c == ':' || c == '.' || c == ' ' } else { c == '-' };
+    let mut count = 0;
+    let mut start = range.start;
+    let mut separator = None;
+    let ends = display[range.clone()].char_indices()
+        .filter(|&(_, c)| is_separator(c))
+        .map(|(index, c)| Some((range.start + index, c)))
+        .chain(Some(None));
+    for end in ends {
+        let kind = match separator {
+            _ if !time && display[start..].starts_with('W') => {
+                start += 1;
+                SegmentKind::Week
+            },
+            _ if !time => match count {
+                0 => SegmentKind::Year,
+                1 => SegmentKind::Month,
+                _ => SegmentKind::Day,
+            },
+            None => SegmentKind::Hour,
+            Some(':') if count == 1 => SegmentKind::Minute,
+            Some(':') => SegmentKind::Second,
+            Some('.') => SegmentKind::Millisecond,
+            Some(_) => SegmentKind::Meridiem,
+        };
+        count += 1;
+        match end {
+            Some((index, c)) => {
+                fields.push((kind, start..index));
+                separator = Some(c);
+                start = index + c.len_utf8();
+            },
+            None => fields.push((kind, start..range.end)),
+        }
+    }
+}
+
+/// The value of the field of the given kind, if there is one.
+fn field(layout: &[(SegmentKind, Range<usize>)], fields: &[Option<u32>], kind: SegmentKind) -> Option<u32> {
+    layout.iter().position(|&(segment, _)| segment == kind).and_then(|index| fields[index])
+}
+
+/// What a key did to a segmented input.
+#[derive(Debug, PartialEq)]
+pub enum SegmentReaction {
+    /// The fields changed, and are now shown as given.
+    Changed(String),
+    /// Another field was focused.
+    SegmentFocused,
+    /// Tab was pressed with the first or last field focused. The owner may move the focus out
+    /// of the control in this direction.
+    FocusNavigation(Direction),
+    /// The key was meant for the input, which had no use for it.
+    Rejected,
+    Nothing,
+}
+
+/// The state of a segmented input between key presses: which field is focused, and how much
+/// of it has been typed.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct SegmentedInput {
+    /// The index of the focused field.
+    focused: usize,
+    /// How many digits have been typed into the focused field since it was focused. The next
+    /// digit replaces the field when this is zero, and is appended to it otherwise.
+    typed_digits: usize,
+    /// The year that stepping an empty year field starts at.
+    reference_year: u32,
+}
+
+impl SegmentedInput {
+    /// A segmented input with its first field focused. Stepping the year of a date before it
+    /// has a value starts at `reference_year`, usually the current year.
+    pub fn new(reference_year: u32) -> SegmentedInput {
+        SegmentedInput {
+            focused: 0,
+            typed_digits: 0,
+            reference_year: reference_year,
+        }
+    }
+
+    /// The index of the focused field.
+    pub fn focused(&self) -> usize {
+        self.focused
+    }
+
+    /// Focus the field with the given index, without anything typed into it yet.
+    pub fn focus(&mut self, index: usize) {
+        self.focused = index;
+        self.typed_digits = 0;
+    }
+
+    /// Focus the field at the given byte offset in `display`, as when it is clicked.
+    pub fn focus_at(&mut self, display: &str, offset: usize) {
+        let layout = layout(display);
+        let index = layout.iter().position(|&(_, ref range)| offset <= range.end).unwrap_or(layout.len() - 1);
+        self.focus(index);
+    }
+
+    /// The byte range of the focused field in `display`, which the owner shows selected.
+    pub fn focused_range(&self, display: &str) -> Range<usize> {
+        let layout = layout(display);
+        layout[min(self.focused, layout.len() - 1)].1.clone()
+    }
+
+    /// The range of values of the field with the given index, given the other fields.
+    fn range(&self, layout: &[(SegmentKind, Range<usize>)], fields: &[Option<u32>], index: usize) -> (u32, u32) {
+        match layout[index].0 {
+            SegmentKind::Year => (1, MAX_YEAR),
+            SegmentKind::Month => (1, 12),
+            SegmentKind::Day => {
+                let year = field(layout, fields, SegmentKind::Year);
+                (1, days_in_month(year, field(layout, fields, SegmentKind::Month)))
+            },
+            SegmentKind::Week => (1, weeks_in_year(field(layout, fields, SegmentKind::Year))),
+            SegmentKind::Hour if layout.iter().any(|&(kind, _)| kind == SegmentKind::Meridiem) => (1, 12),
+            SegmentKind::Hour => (0, 23),
+            SegmentKind::Minute | SegmentKind::Second => (0, 59),
+            SegmentKind::Millisecond => (0, 999),
+            SegmentKind::Meridiem => (0, 1),
+        }
+    }
+
+    /// Handle a key pressed with the input focused, given how its fields are shown.
+    pub fn handle_keydown_aux(&mut self,
+                              display: &str,
+                              printable: Option<char>,
+                              key: Key,
+                              mods: KeyModifiers) -> SegmentReaction {
+        let layout = layout(display);
+        let mut fields: Vec<_> = layout.iter().map(|&(kind, ref range)| kind.parse(&display[range.clone()])).collect();
+        if self.focused >= fields.len() {
+            self.focus(fields.len() - 1);
+        }
+        match key {
+            Key::Left => return self.move_focus(Direction::Backward, fields.len()),
+            Key::Right => return self.move_focus(Direction::Forward, fields.len()),
+            Key::Tab => {
+                let direction = if mods.contains(KeyModifiers::SHIFT) {
+                    Direction::Backward
+                } else {
+                    Direction::Forward
+                };
+                return match self.move_focus(direction, fields.len()) {
+                    SegmentReaction::Nothing => SegmentReaction::FocusNavigation(direction),
+                    reaction => reaction,
+                };
+            },
+            Key::Up => self.step(&layout, &mut fields, true),
+            Key::Down => self.step(&layout, &mut fields, false),
+            Key::Backspace | Key::Delete => {
+                if fields[self.focused].is_none() {
+                    return SegmentReaction::Rejected;
+                }
+                fields[self.focused] = None;
+                self.typed_digits = 0;
+            },
+            _ => match printable {
+                Some(c) if layout[self.focused].0 == SegmentKind::Meridiem => {
+                    fields[self.focused] = match c.to_ascii_lowercase() {
+                        'a' => Some(0),
+                        'p' => Some(1),
+                        _ => return SegmentReaction::Rejected,
+                    };
+                    self.typed_digits = 0;
+                },
+                Some(c) if c.is_digit(10) => self.type_digit(&layout, &mut fields, c.to_digit(10).unwrap()),
+                // A separator finishes the field, so that e.g. "3/" is a whole month.
+                Some(c) if c == '-' || c == '/' || c == '.' || c == ' ' || c == ':' => {
+                    if self.typed_digits == 0 {
+                        return SegmentReaction::Nothing;
+                    }
+                    return self.move_focus(Direction::Forward, fields.len());
+                },
+                Some(_) => return SegmentReaction::Rejected,
+                None => return SegmentReaction::Nothing,
+            },
+        }
+
+        // A day that the month no longer has becomes its last.
+        for index in 0..fields.len() {
+            let (_, max) = self.range(&layout, &fields, index);
+            fields[index] = fields[index].map(|value| min(value, max));
+        }
+
+        let mut shown = String::new();
+        let mut end = 0;
+        for (&(kind, ref range), &value) in layout.iter().zip(&fields) {
+            shown.push_str(&display[end..range.start]);
+            shown.push_str(&kind.format(value));
+            end = range.end;
+        }
+        SegmentReaction::Changed(shown)
+    }
+
+    fn move_focus(&mut self, direction: Direction, count: usize) -> SegmentReaction {
+        let index = match direction {
+            Direction::Backward if self.focused > 0 => self.focused - 1,
+            Direction::Forward if self.focused + 1 < count => self.focused + 1,
+            _ => return SegmentReaction::Nothing,
+        };
+        self.focus(index);
+        SegmentReaction::SegmentFocused
+    }
+
+    /// Step the focused field up or down by one, wrapping around at the ends of its range.
+    fn step(&mut self, layout: &[(SegmentKind, Range<usize>)], fields: &mut [Option<u32>], up: bool) {
+        let (min, max) = self.range(layout, fields, self.focused);
+        let kind = layout[self.focused].0;
+        fields[self.focused] = Some(match fields[self.focused] {
+            None if kind == SegmentKind::Year => self.reference_year,
+            None if up => min,
+            None => max,
+            Some(value) if up => if value >= max { min } else { value + 1 },
+            Some(value) => if value <= min { max } else { value - 1 },
+        });
+        self.typed_digits = 0;
+    }
+
+    /// Type a digit into the focused field, moving on to the next field once it is full.
+    fn type_digit(&mut self, layout: &[(SegmentKind, Range<usize>)], fields: &mut [Option<u32>], digit: u32) {
+        let (min, max) = self.range(layout, fields, self.focused);
+        let kind = layout[self.focused].0;
+        let value = match fields[self.focused] {
+            Some(value) if self.typed_digits > 0 => value * 10 + digit,
+            _ => digit,
+        };
+        self.typed_digits += 1;
+        // Zero is kept while it may be the first digit of a number in range.
+        fields[self.focused] = Some(if value > max { max } else { value });
+        if self.typed_digits < kind.width() && value * 10 <= max {
+            return;
+        }
+        if value < min {
+            fields[self.focused] = Some(min);
+        }
+        if self.move_focus(Direction::Forward, fields.len()) == SegmentReaction::Nothing {
+            // The last field starts over with the next digit.
+            self.typed_digits = 0;
+        }
+    }
+}
+
+/// Which fields of a time are shown, beyond its hour and minute.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TimePrecision {
+    Minute,
+    Second,
+    Millisecond,
+}
+
+/// The hour, minute, second and millisecond of a valid time string, with whether its seconds
+/// and milliseconds were given.
+///
+/// https://html.spec.whatwg.org/multipage/#parse-a-time-component
+fn parse_time(value: &str) -> Option<([u32; 4], TimePrecision)> {
+    let digits = |field: &str, width| {
+        if field.len() == width && field.chars().all(|c| c.is_digit(10)) { field.parse().ok() } else { None }
+    };
+    let (time, millisecond) = match value.find('.') {
+        Some(dot) if dot == 8 && value.len() > 9 && value.len() <= 12 => {
+            let fraction = &value[dot + 1..];
+            let millisecond: u32 = digits(fraction, fraction.len())?;
+            (&value[..dot], Some(millisecond * 10u32.pow(3 - fraction.len() as u32)))
+        },
+        Some(_) => return None,
+        None => (value, None),
+    };
+    let mut components = time.split(':');
+    let hour = digits(components.next()?, 2)?;
+    let minute = digits(components.next()?, 2)?;
+    let second = match components.next() {
+        Some(second) => Some(digits(second, 2)?),
+        None => None,
+    };
+    if components.next().is_some() || hour > 23 || minute > 59 || second.map_or(false, |second| second > 59) {
+        return None;
+    }
+    let precision = match (second, millisecond) {
+        (_, Some(_)) => TimePrecision::Millisecond,
+        (Some(_), None) => TimePrecision::Second,
+        (None, None) => TimePrecision::Minute,
+    };
+    Some(([hour, minute, second.unwrap_or(0), millisecond.unwrap_or(0)], precision))
+}
+
+/// The number of milliseconds after midnight of a valid time string.
+pub fn time_in_milliseconds(value: &str) -> Option<f64> {
+    parse_time(value).map(|([hour, minute, second, millisecond], _)| {
+        (((hour * 60 + minute) * 60 + second) * 1000 + millisecond) as f64
+    })
+}
+
+/// How the fields of a time are shown, showing at least the fields of `precision` and those
+/// that the value was given with, and on a twelve hour clock if `twelve_hour`. An empty or
+/// invalid value shows placeholders.
+pub fn time_display(value: &str, precision: TimePrecision, twelve_hour: bool) -> String {
+    let (fields, given): (Vec<_>, _) = match parse_time(value) {
+        Some((fields, given)) => (fields.iter().map(|&field| Some(field)).collect(), given),
+        None => (vec!(None; 4), TimePrecision::Minute),
+    };
+    let precision = if given > precision { given } else { precision };
+    let hour = match fields[0] {
+        Some(hour) if twelve_hour && hour % 12 == 0 => Some(12),
+        Some(hour) if twelve_hour => Some(hour % 12),
+        hour => hour,
+    };
+    let mut shown = format!("{}:{}", SegmentKind::Hour.format(hour), SegmentKind::Minute.format(fields[1]));
+    if precision >= TimePrecision::Second {
+        shown.push(':');
+        shown.push_str(&SegmentKind::Second.format(fields[2]));
+    }
+    if precision == TimePrecision::Millisecond {
+        shown.push('.');
+        shown.push_str(&SegmentKind::Millisecond.format(fields[3]));
+    }
+    if twelve_hour {
+        shown.push(' ');
+        shown.push_str(&SegmentKind::Meridiem.format(fields[0].map(|hour| hour / 12)));
+    }
+    shown
+}
+
+/// The time whose fields are shown as given, or the empty string until all of them have a
+/// value. The value has seconds and milliseconds whenever they are shown, so that showing it
+/// again gives the same fields.
+///
+/// https://html.spec.whatwg.org/multipage/#valid-time-string
+pub fn time_value(display: &str) -> String {
+    // A value that is not shown yet, such as one being sanitized, is already a time.
+    if parse_time(display).is_some() {
+        return display.to_owned();
+    }
+    let layout = layout(display);
+    if layout[0].0 != SegmentKind::Hour {
+        return String::new();
+    }
+    let mut fields = vec!();
+    for &(kind, ref range) in &layout {
+        match kind.parse(&display[range.clone()]) {
+            Some(value) => fields.push(value),
+            None => return String::new(),
+        }
+    }
+    let fields: Vec<_> = layout.iter().map(|&(kind, _)| kind).zip(fields).collect();
+    let value_of = |kind| fields.iter().find(|&&(field, _)| field == kind).map(|&(_, value)| value);
+    let hour = match value_of(SegmentKind::Meridiem) {
+        Some(meridiem) => value_of(SegmentKind::Hour).unwrap_or(0) % 12 + meridiem * 12,
+        None => value_of(SegmentKind::Hour).unwrap_or(0),
+    };
+    let mut value = format!("{:02}:{:02}", hour, value_of(SegmentKind::Minute).unwrap_or(0));
+    if let Some(second) = value_of(SegmentKind::Second) {
+        value.push_str(&format!(":{:02}", second));
+    }
+    if let Some(millisecond) = value_of(SegmentKind::Millisecond) {
+        value.push_str(&format!(".{:03}", millisecond));
+    }
+    value
+}
+
+/// The year, month and day of a valid date string.
+///
+/// https://html.spec.whatwg.org/multipage/#parse-a-date-component
+fn parse_date(value: &str) -> Option<[u32; 3]> {
+    let mut components = value.split('-');
+    let year = components.next()?;
+    let month = components.next()?;
+    let day = components.next()?;
+    let digits = |field: &str| field.len() >= 2 && field.chars().all(|c| c.is_digit(10));
+    if components.next().is_some() || year.len() < 4 || !digits(year) || month.len() != 2 || !digits(month) ||
+       day.len() != 2 || !digits(day) {
+        return None;
+    }
+    let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    if year == 0 || month == 0 || month > 12 || day == 0 || day > days_in_month(Some(year), Some(month)) {
+        return None;
+    }
+    Some([year, month, day])
+}
+
+/// The number of milliseconds between the Unix epoch and midnight UTC of a valid date string.
+pub fn date_in_milliseconds(value: &str) -> Option<f64> {
+    parse_date(value).map(|[year, month, day]| days_since_epoch(year, month, day) as f64 * 86400000.0)
+}
+
+/// The valid date string of the day that the given number of milliseconds after the Unix epoch
+/// falls on, or the empty string if it is out of the range of a date input.
+pub fn date_from_milliseconds(milliseconds: f64) -> String {
+    match date_from_days_since_epoch((milliseconds / 86400000.0).floor() as i64) {
+        Some([year, month, day]) => format!("{:04}-{:02}-{:02}", year, month, day),
+        None => String::new(),
+    }
+}
+
+/// The valid time string of the given number of milliseconds after midnight, or the empty
+/// string if it is not within a day.
+pub fn time_from_milliseconds(milliseconds: f64) -> String {
+    if milliseconds < 0.0 || milliseconds >= 86400000.0 {
+        return String::new();
+    }
+    let milliseconds = milliseconds as u32;
+    shortest_time([milliseconds / 3600000, milliseconds / 60000 % 60, milliseconds / 1000 % 60, milliseconds % 1000])
+}
+
+/// The year and month of a valid month string.
+///
+/// https://html.spec.whatwg.org/multipage/#parse-a-month-component
+fn parse_month(value: &str) -> Option<[u32; 2]> {
+    parse_date(&format!("{}-01", value)).map(|[year, month, _]| [year, month])
+}
+
+/// The number of months between January 1970 and a valid month string.
+pub fn month_in_months(value: &str) -> Option<f64> {
+    parse_month(value).map(|[year, month]| (year as f64 - 1970.0) * 12.0 + month as f64 - 1.0)
+}
+
+/// The valid month string of the given number of months after January 1970, or the empty
+/// string if it is out of the range of a month input.
+pub fn month_from_months(months: f64) -> String {
+    let months = months.floor();
+    let year = 1970.0 + (months / 12.0).floor();
+    if year < 1.0 || year > MAX_YEAR as f64 {
+        return String::new();
+    }
+    format!("{:04}-{:02}", year as u32, (months - (year - 1970.0) * 12.0) as u32 + 1)
+}
+
+/// The year and week of a valid week string.
+///
+/// https://html.spec.whatwg.org/multipage/#parse-a-week-string
+fn parse_week(value: &str) -> Option<[u32; 2]> {
+    let separator = value.find("-W")?;
+    let (year, week) = (&value[..separator], &value[separator + 2..]);
+    let digits = |field: &str| field.chars().all(|c| c.is_digit(10));
+    if year.len() < 4 || !digits(year) || week.len() != 2 || !digits(week) {
+        return None;
+    }
+    let (year, week) = (year.parse().ok()?, week.parse().ok()?);
+    if year == 0 || week == 0 || week > weeks_in_year(Some(year)) {
+        return None;
+    }
+    Some([year, week])
+}
+
+/// The day that the first week of the given year starts on, counted as `days_since_epoch`
+/// counts it: the Monday of the week that has the year's first Thursday.
+fn first_monday(year: u32) -> i64 {
+    let fourth_of_january = days_since_epoch(year, 1, 4);
+    // 1970-01-01 was a Thursday, three days after a Monday.
+    fourth_of_january - ((fourth_of_january + 3) % 7 + 7) % 7
+}
+
+/// The number of milliseconds between the Unix epoch and midnight UTC of the Monday that a
+/// valid week string starts on.
+pub fn week_in_milliseconds(value: &str) -> Option<f64> {
+    parse_week(value).map(|[year, week]| (first_monday(year) + (week as i64 - 1) * 7) as f64 * 86400000.0)
+}
+
+/// The valid week string of the week that the given number of milliseconds after the Unix
+/// epoch falls in, or the empty string if it is out of the range of a week input.
+pub fn week_from_milliseconds(milliseconds: f64) -> String {
+    let day = (milliseconds / 86400000.0).floor() as i64;
+    let monday = day - ((day + 3) % 7 + 7) % 7;
+    // A week belongs to the year that its Thursday is in.
+    match date_from_days_since_epoch(monday + 3) {
+        Some([year, _, _]) => format!("{:04}-W{:02}", year, (monday - first_monday(year)) / 7 + 1),
+        None => String::new(),
+    }
+}
+
+/// The date and the time of a valid local date and time string, which has either a `T` or a
+/// space between them.
+///
+/// https://html.spec.whatwg.org/multipage/#parse-a-local-date-and-time-string
+fn parse_local_date_and_time(value: &str) -> Option<([u32; 3], [u32; 4], TimePrecision)> {
+    let separator = value.find(|c| c == 'T' || c == ' ')?;
+    let date = parse_date(&value[..separator])?;
+    let (time, given) = parse_time(&value[separator + 1..])?;
+    Some((date, time, given))
+}
+
+/// The number of milliseconds between the Unix epoch and a valid local date and time string,
+/// taken as UTC.
+pub fn local_date_and_time_in_milliseconds(value: &str) -> Option<f64> {
+    parse_local_date_and_time(value).map(|([year, month, day], [hour, minute, second, millisecond], _)| {
+        let time = ((hour * 60 + minute) * 60 + second) * 1000 + millisecond;
+        days_since_epoch(year, month, day) as f64 * 86400000.0 + time as f64
+    })
+}
+
+/// The number of days between 1970-01-01 and the given day.
+fn days_since_epoch(year: u32, month: u32, day: u32) -> i64 {
+    // The number of days since 0000-03-01, counting years from March so that the leap day
+    // comes last.
+    let (year, month) = if month > 2 {
+        (year as i64, month as i64 - 3)
+    } else {
+        (year as i64 - 1, month as i64 + 9)
+    };
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + (month * 153 + 2) / 5 + day as i64 - 1;
+    // 0000-03-01 is 719468 days before 1970-01-01.
+    days - 719468
+}
+
+/// The valid normalized local date and time string of the given number of milliseconds after
+/// the Unix epoch, taken as UTC, or the empty string if it is out of the range of a date input.
+pub fn local_date_and_time_from_milliseconds(milliseconds: f64) -> String {
+    let date = date_from_milliseconds(milliseconds);
+    if date.is_empty() {
+        return date;
+    }
+    let time = milliseconds - (milliseconds / 86400000.0).floor() * 86400000.0;
+    format!("{}T{}", date, time_from_milliseconds(time))
+}
+
+/// The year, month and day of the given number of days after 1970-01-01, or `None` if it is
+/// out of the range of a date input. The inverse of `days_since_epoch`.
+fn date_from_days_since_epoch(days: i64) -> Option<[u32; 3]> {
+    // The number of days since 0000-03-01, in 400 year cycles of 146097 days.
+    let days = days + 719468;
+    if days < 0 {
+        return None;
+    }
+    let (cycle, day_of_cycle) = (days / 146097, days % 146097);
+    let year_of_cycle = (day_of_cycle - day_of_cycle / 1460 + day_of_cycle / 36524 - day_of_cycle / 146096) / 365;
+    let day_of_year = day_of_cycle - (year_of_cycle * 365 + year_of_cycle / 4 - year_of_cycle / 100);
+    // Months are counted from March, as in `days_since_epoch`.
+    let month = (day_of_year * 5 + 2) / 153;
+    let day = day_of_year - (month * 153 + 2) / 5 + 1;
+    let (year, month) = if month < 10 {
+        (cycle * 400 + year_of_cycle, month + 3)
+    } else {
+        (cycle * 400 + year_of_cycle + 1, month - 9)
+    };
+    if year < 1 || year > MAX_YEAR as i64 {
+        return None;
+    }
+    Some([year as u32, month as u32, day as u32])
+}
+
+/// How the fields of a local date and time are shown: the date, a space, and the time as
+/// `time_display` shows it.
+pub fn local_date_and_time_display(value: &str, precision: TimePrecision, twelve_hour: bool) -> String {
+    match parse_local_date_and_time(value) {
+        Some(([year, month, day], time, _)) => {
+            let time = time_display(&shortest_time(time), precision, twelve_hour);
+            format!("{:04}-{:02}-{:02} {}", year, month, day, time)
+        },
+        None => format!("{} {}", EMPTY_DATE, time_display("", precision, twelve_hour)),
+    }
+}
+
+/// The local date and time whose fields are shown as given, as a valid normalized local date
+/// and time string, or the empty string until all of them have a value.
+///
+/// https://html.spec.whatwg.org/multipage/#valid-normalized-local-date-and-time-string
+pub fn local_date_and_time_value(display: &str) -> String {
+    let (date, time) = match parse_local_date_and_time(display) {
+        // A value that is not shown yet, such as one being sanitized, is normalized.
+        Some((date, time, _)) => (date, time),
+        None => {
+            let layout = layout(display);
+            if layout.len() < 5 || layout[3].0 != SegmentKind::Hour {
+                return String::new();
+            }
+            let date = parse_date(&display[..layout[2].1.end]);
+            let time = parse_time(&time_value(&display[layout[3].1.start..]));
+            match (date, time) {
+                (Some(date), Some((time, _))) => (date, time),
+                _ => return String::new(),
+            }
+        },
+    };
+    format!("{:04}-{:02}-{:02}T{}", date[0], date[1], date[2], shortest_time(time))
+}
+
+/// The shortest valid time string for the given hour, minute, second and millisecond: without
+/// its seconds if they are zero, and without the trailing zeros of its milliseconds.
+fn shortest_time([hour, minute, second, millisecond]: [u32; 4]) -> String {
+    let mut time = format!("{:02}:{:02}", hour, minute);
+    if second != 0 || millisecond != 0 {
+        time.push_str(&format!(":{:02}", second));
+    }
+    if millisecond != 0 {
+        time.push_str(format!(".{:03}", millisecond).trim_right_matches('0'));
+    }
+    time
+}
+
+/// The number of days in the given month, or the most it may have if it is not known.
+///
+/// https://html.spec.whatwg.org/multipage/#number-of-days-in-month-month-of-year-year
+pub fn days_in_month(year: Option<u32>, month: Option<u32>) -> u32 {
+    match month {
+        Some(4) | Some(6) | Some(9) | Some(11) => 30,
+        Some(2) => match year {
+            Some(year) if year % 400 == 0 || (year % 4 == 0 && year % 100 != 0) => 29,
+            Some(_) => 28,
+            None => 29,
+        },
+        _ => 31,
+    }
+}
+
+/// The number of weeks in the given year, or the most a year may have if it is not known. A
+/// year has 53 weeks if it starts on a Thursday, or is a leap year that starts on a Wednesday.
+///
+/// https://html.spec.whatwg.org/multipage/#week-number-of-the-last-day
+pub fn weeks_in_year(year: Option<u32>) -> u32 {
+    let year = match year {
+        Some(year) => year,
+        None => return 53,
+    };
+    // 1970-01-01 was a Thursday, the fourth day of the week.
+    let weekday = (days_since_epoch(year, 1, 1) % 7 + 10) % 7;
+    if weekday == 3 || (weekday == 2 && days_in_month(Some(year), Some(2)) == 29) { 53 } else { 52 }
+}
@@ -0,0 +1,67 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::AutocorrectWord;
+use script_traits::{ScriptToConstellationChan, ScriptMsg};
+
+pub trait SpellCheckProvider {
+    // ask which of the given words are misspelled in the given language, the language of the
+    // text control they were typed into; a flag for each word, in the same order, is sent to
+    // the given channel once they are checked, which must not block
+    fn request_spell_check(&mut self, words: Vec<String>, lang: String, sender: IpcSender<Vec<bool>>);
+    // offer a word the user just finished typing to be corrected; the text to replace it with,
+    // or None to keep it, is sent to the given channel
+    fn request_autocorrect(&mut self, word: AutocorrectWord, sender: IpcSender<Option<String>>);
+}
+
+impl SpellCheckProvider for ScriptToConstellationChan {
+    fn request_spell_check(&mut self, words: Vec<String>, lang: String, sender: IpcSender<Vec<bool>>) {
+        self.send(ScriptMsg::CheckSpelling(words, lang, sender)).unwrap();
+    }
+
+    fn request_autocorrect(&mut self, word: AutocorrectWord, sender: IpcSender<Option<String>>) {
+        self.send(ScriptMsg::Autocorrect(word, sender)).unwrap();
+    }
+}
+
+/// A spell checker that knows only the misspellings and corrections it was given, whatever the
+/// language.
+#[derive(Clone)]
+pub struct DummySpellCheckProvider {
+    misspellings: Vec<String>,
+    corrections: Vec<(String, String)>,
+    /// The words offered for correction, most recent last.
+    pub offered: Vec<AutocorrectWord>,
+}
+
+impl DummySpellCheckProvider {
+    pub fn new(misspellings: &[&str]) -> DummySpellCheckProvider {
+        DummySpellCheckProvider {
+            misspellings: misspellings.iter().map(|&word| word.to_owned()).collect(),
+            corrections: vec![],
+            offered: vec![],
+        }
+    }
+
+    /// Correct each of the given words to the text paired with it.
+    pub fn with_corrections(mut self, corrections: &[(&str, &str)]) -> DummySpellCheckProvider {
+        self.corrections = corrections.iter().map(|&(word, correction)| {
+            (word.to_owned(), correction.to_owned())
+        }).collect();
+        self
+    }
+}
+
+impl SpellCheckProvider for DummySpellCheckProvider {
+    fn request_spell_check(&mut self, words: Vec<String>, _lang: String, sender: IpcSender<Vec<bool>>) {
+        let _ = sender.send(words.iter().map(|word| self.misspellings.contains(word)).collect());
+    }
+
+    fn request_autocorrect(&mut self, word: AutocorrectWord, sender: IpcSender<Option<String>>) {
+        let correction = self.corrections.iter().find(|&&(ref misspelling, _)| *misspelling == word.word);
+        let _ = sender.send(correction.map(|&(_, ref correction)| correction.clone()));
+        self.offered.push(word);
+    }
+}
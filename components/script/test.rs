@@ -15,6 +15,23 @@ pub mod area {
     pub use dom::htmlareaelement::{Area, Shape};
 }
 
+pub mod contenteditable {
+    pub use dom::contenteditable::{ContentEditableState, Deletion, InsertionPoint, LeafKind, plan_change};
+}
+
+pub mod textcontrol {
+    pub use dom::textcontrol::{parse_autocomplete, parse_enter_key_hint, parse_input_mode};
+}
+
+pub mod input {
+    pub use dom::htmlinputelement::{InputType, StepScale, ValueDisplay, local_date_and_time_validity, parse_number};
+    pub use dom::htmlinputelement::{step_number, step_scale};
+    pub use dom::htmlinputelement::{caps_lock_state, matching_suggestions};
+    pub use dom::htmlinputelement::{email_validity, is_valid_email_address, range_steps_for_key, sanitize_email};
+    pub use dom::htmlinputelement::{pattern_validity, tel_char_filter, time_validity, url_validity};
+    pub use dom::validitystate::ValidationFlags;
+}
+
 pub mod sizes {
     pub use dom::htmlimageelement::{parse_a_sizes_attribute, Size};
 }
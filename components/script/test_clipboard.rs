@@ -0,0 +1,192 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! An in-process clipboard for tests, which stands in for the system clipboard once it is
+//! installed, so that tests involving the clipboard can run headless and deterministically.
+//! Its contents can be set and inspected by the test, and it keeps a log of the calls made to
+//! it. Only built with the `test_clipboard` feature.
+
+use clipboard_provider::ClipboardProvider;
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor, clipboard_text_chunk};
+use script_traits::ScriptToConstellationChan;
+use std::borrow::ToOwned;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    static ref INSTALLED: Mutex<Option<TestClipboardProvider>> = Mutex::new(None);
+}
+
+/// Make `clipboard` stand in for the system clipboard in this process, or stop any test
+/// clipboard from doing so if it is `None`.
+pub fn install(clipboard: Option<TestClipboardProvider>) {
+    *INSTALLED.lock().unwrap() = clipboard;
+}
+
+/// The test clipboard standing in for the system clipboard, if one is installed.
+pub fn installed() -> Option<TestClipboardProvider> {
+    INSTALLED.lock().unwrap().clone()
+}
+
+/// The test clipboard standing in for the system clipboard, which is installed if there was
+/// none yet.
+pub fn install_if_needed() -> TestClipboardProvider {
+    INSTALLED.lock().unwrap().get_or_insert_with(TestClipboardProvider::new).clone()
+}
+
+/// A call made to a `TestClipboardProvider` through the `ClipboardProvider` trait.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ClipboardCall {
+    GetFlavors,
+    GetContents(ClipboardFlavor),
+    GetLength(ClipboardFlavor),
+    GetChunk(ClipboardFlavor, usize, usize),
+    SetContents(ClipboardContents),
+}
+
+impl fmt::Display for ClipboardCall {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClipboardCall::GetFlavors => write!(f, "get-flavors"),
+            ClipboardCall::GetContents(flavor) => write!(f, "get-contents {}", flavor.mime_type()),
+            ClipboardCall::GetLength(flavor) => write!(f, "get-length {}", flavor.mime_type()),
+            ClipboardCall::GetChunk(flavor, start, max_length) => {
+                write!(f, "get-chunk {} {} {}", flavor.mime_type(), start, max_length)
+            },
+            ClipboardCall::SetContents(ref contents) => {
+                write!(f, "set-contents")?;
+                for flavor in contents.flavors() {
+                    write!(f, " {}", flavor.mime_type())?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct TestClipboard {
+    contents: ClipboardContents,
+    log: Vec<ClipboardCall>,
+}
+
+/// A clipboard held in memory, which answers requests straight away. Clones share the same
+/// contents and log.
+#[derive(Clone, Default)]
+pub struct TestClipboardProvider {
+    clipboard: Arc<Mutex<TestClipboard>>,
+}
+
+impl TestClipboardProvider {
+    pub fn new() -> TestClipboardProvider {
+        Default::default()
+    }
+
+    /// The current contents, as a test sees them, which isn't logged.
+    pub fn contents(&self) -> ClipboardContents {
+        self.clipboard.lock().unwrap().contents.clone()
+    }
+
+    /// Replace the contents, as a test does, which isn't logged.
+    pub fn set_contents(&self, contents: ClipboardContents) {
+        self.clipboard.lock().unwrap().contents = contents;
+    }
+
+    /// The calls made so far, oldest first.
+    pub fn log(&self) -> Vec<ClipboardCall> {
+        self.clipboard.lock().unwrap().log.clone()
+    }
+
+    /// The calls made so far, oldest first, which are then forgotten.
+    pub fn take_log(&self) -> Vec<ClipboardCall> {
+        let mut clipboard = self.clipboard.lock().unwrap();
+        clipboard.log.drain(..).collect()
+    }
+
+    fn record<R, F: FnOnce(&mut ClipboardContents) -> R>(&self, call: ClipboardCall, f: F) -> R {
+        let mut clipboard = self.clipboard.lock().unwrap();
+        clipboard.log.push(call);
+        f(&mut clipboard.contents)
+    }
+}
+
+impl ClipboardProvider for TestClipboardProvider {
+    fn request_clipboard_flavors(&mut self, sender: IpcSender<Vec<ClipboardFlavor>>) {
+        let flavors = self.record(ClipboardCall::GetFlavors, |contents| contents.flavors());
+        let _ = sender.send(flavors);
+    }
+    fn request_clipboard_contents(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<String>>) {
+        let data = self.record(ClipboardCall::GetContents(flavor), |contents| {
+            contents.get(flavor).map(ToOwned::to_owned)
+        });
+        let _ = sender.send(data);
+    }
+    fn request_clipboard_length(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<usize>>) {
+        let length = self.record(ClipboardCall::GetLength(flavor), |contents| contents.get(flavor).map(str::len));
+        let _ = sender.send(length);
+    }
+    fn request_clipboard_chunk(&mut self, flavor: ClipboardFlavor, start: usize, max_length: usize,
+                               sender: IpcSender<Option<String>>) {
+        let chunk = self.record(ClipboardCall::GetChunk(flavor, start, max_length), |contents| {
+            contents.get(flavor).map(|text| clipboard_text_chunk(text, start, max_length).to_owned())
+        });
+        let _ = sender.send(chunk);
+    }
+    fn set_clipboard_contents(&mut self, contents: ClipboardContents) {
+        self.record(ClipboardCall::SetContents(contents.clone()), |old_contents| *old_contents = contents);
+    }
+}
+
+/// The clipboard of text controls with the `test_clipboard` feature, which is the test
+/// clipboard while one is installed, and the system clipboard otherwise. Which one is decided
+/// on each request, so that a test can install its clipboard after the controls are created.
+#[derive(Clone)]
+pub struct TestableClipboardProvider {
+    system_clipboard: ScriptToConstellationChan,
+}
+
+unsafe_no_jsmanaged_fields!(TestableClipboardProvider);
+
+impl TestableClipboardProvider {
+    pub fn new(system_clipboard: ScriptToConstellationChan) -> TestableClipboardProvider {
+        TestableClipboardProvider {
+            system_clipboard: system_clipboard,
+        }
+    }
+}
+
+impl ClipboardProvider for TestableClipboardProvider {
+    fn request_clipboard_flavors(&mut self, sender: IpcSender<Vec<ClipboardFlavor>>) {
+        match installed() {
+            Some(mut clipboard) => clipboard.request_clipboard_flavors(sender),
+            None => self.system_clipboard.request_clipboard_flavors(sender),
+        }
+    }
+    fn request_clipboard_contents(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<String>>) {
+        match installed() {
+            Some(mut clipboard) => clipboard.request_clipboard_contents(flavor, sender),
+            None => self.system_clipboard.request_clipboard_contents(flavor, sender),
+        }
+    }
+    fn request_clipboard_length(&mut self, flavor: ClipboardFlavor, sender: IpcSender<Option<usize>>) {
+        match installed() {
+            Some(mut clipboard) => clipboard.request_clipboard_length(flavor, sender),
+            None => self.system_clipboard.request_clipboard_length(flavor, sender),
+        }
+    }
+    fn request_clipboard_chunk(&mut self, flavor: ClipboardFlavor, start: usize, max_length: usize,
+                               sender: IpcSender<Option<String>>) {
+        match installed() {
+            Some(mut clipboard) => clipboard.request_clipboard_chunk(flavor, start, max_length, sender),
+            None => self.system_clipboard.request_clipboard_chunk(flavor, start, max_length, sender),
+        }
+    }
+    fn set_clipboard_contents(&mut self, contents: ClipboardContents) {
+        match installed() {
+            Some(mut clipboard) => clipboard.set_clipboard_contents(contents),
+            None => self.system_clipboard.set_clipboard_contents(contents),
+        }
+    }
+}
@@ -0,0 +1,159 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Storage for the lines of a text input control.
+
+use dom::bindings::str::DOMString;
+use std::ops::{Index, IndexMut, Range};
+
+/// The lines of a text input control, without their trailing '\n'.
+///
+/// A buffer always holds at least one line once it has been filled by `TextInput`.
+pub trait TextBuffer: Index<usize, Output = DOMString> + IndexMut<usize> {
+    /// A buffer holding the given lines.
+    fn from_lines(lines: Vec<DOMString>) -> Self;
+
+    /// The number of lines.
+    fn len(&self) -> usize;
+
+    /// Replace the lines in `range` with `lines`.
+    fn replace_lines(&mut self, range: Range<usize>, lines: Vec<DOMString>);
+
+    /// The line at `index`, if there is one.
+    fn get(&self, index: usize) -> Option<&DOMString> where Self: Sized {
+        if index < self.len() {
+            Some(&self[index])
+        } else {
+            None
+        }
+    }
+
+    /// Iterate over all the lines.
+    fn iter(&self) -> TextBufferIter<Self> where Self: Sized {
+        self.iter_range(0..self.len())
+    }
+
+    /// Iterate over the lines in `range`.
+    fn iter_range(&self, range: Range<usize>) -> TextBufferIter<Self> where Self: Sized {
+        TextBufferIter {
+            buffer: self,
+            range: range,
+        }
+    }
+}
+
+/// An iterator over some of the lines of a `TextBuffer`.
+pub struct TextBufferIter<'a, B: 'a> {
+    buffer: &'a B,
+    range: Range<usize>,
+}
+
+impl<'a, B: TextBuffer> Iterator for TextBufferIter<'a, B> {
+    type Item = &'a DOMString;
+
+    fn next(&mut self) -> Option<&'a DOMString> {
+        self.range.next().map(|i| &self.buffer[i])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl<'a, B: TextBuffer> DoubleEndedIterator for TextBufferIter<'a, B> {
+    fn next_back(&mut self) -> Option<&'a DOMString> {
+        self.range.next_back().map(|i| &self.buffer[i])
+    }
+}
+
+impl<'a, B: TextBuffer> ExactSizeIterator for TextBufferIter<'a, B> {}
+
+/// The plain storage, which copies every line after an edit into place.
+impl TextBuffer for Vec<DOMString> {
+    fn from_lines(lines: Vec<DOMString>) -> Vec<DOMString> {
+        lines
+    }
+
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn replace_lines(&mut self, range: Range<usize>, lines: Vec<DOMString>) {
+        self.splice(range, lines);
+    }
+}
+
+/// A gap buffer of lines, for controls that may hold a large number of them.
+///
+/// The lines before the gap are kept in order in `before`, and those after it in reverse order
+/// in `after`, so an edit only moves the lines between the gap and the edited range. As edits
+/// are usually made around the editing point, the cost of typing doesn't grow with the number
+/// of lines. The gap is only between lines, though: each line is a string of its own, and an
+/// edit inside it still copies the rest of the line, so a single very long line costs as much
+/// to edit as it does in a `Vec<DOMString>`.
+#[derive(Clone, Debug, Default, JSTraceable, MallocSizeOf)]
+pub struct GapBuffer {
+    before: Vec<DOMString>,
+    after: Vec<DOMString>,
+}
+
+impl GapBuffer {
+    /// Move the gap so that it starts before the line at `index`.
+    fn move_gap(&mut self, index: usize) {
+        while self.before.len() > index {
+            let line = self.before.pop().unwrap();
+            self.after.push(line);
+        }
+        while self.before.len() < index {
+            let line = self.after.pop().expect("Moved the gap past the last line");
+            self.before.push(line);
+        }
+    }
+}
+
+impl Index<usize> for GapBuffer {
+    type Output = DOMString;
+
+    fn index(&self, index: usize) -> &DOMString {
+        if index < self.before.len() {
+            &self.before[index]
+        } else {
+            let reversed = self.after.len() - 1 - (index - self.before.len());
+            &self.after[reversed]
+        }
+    }
+}
+
+impl IndexMut<usize> for GapBuffer {
+    fn index_mut(&mut self, index: usize) -> &mut DOMString {
+        if index < self.before.len() {
+            &mut self.before[index]
+        } else {
+            let reversed = self.after.len() - 1 - (index - self.before.len());
+            &mut self.after[reversed]
+        }
+    }
+}
+
+impl TextBuffer for GapBuffer {
+    fn from_lines(lines: Vec<DOMString>) -> GapBuffer {
+        GapBuffer {
+            before: lines,
+            after: vec!(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.before.len() + self.after.len()
+    }
+
+    fn replace_lines(&mut self, range: Range<usize>, lines: Vec<DOMString>) {
+        assert!(range.end <= self.len());
+        self.move_gap(range.start);
+        for _ in range {
+            self.after.pop();
+        }
+        self.before.extend(lines);
+    }
+}
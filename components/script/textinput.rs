@@ -4,23 +4,44 @@
 
 //! Common handling of keyboard input and state management for text input controls
 
-use clipboard_provider::ClipboardProvider;
+use clipboard_provider::{CLIPBOARD_CHUNK_SIZE, ClipboardChunkReader, ClipboardProvider};
+use compose::ComposeState;
 use dom::bindings::str::DOMString;
 use dom::keyboardevent::KeyboardEvent;
-use msg::constellation_msg::{Key, KeyModifiers};
+use keybindings::{EditCommand, KeyBindings};
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor, ImeSurroundingText, Key, KeyModifiers};
+use servo_config::prefs::PREFS;
 use std::borrow::ToOwned;
+use std::cell::{Cell, Ref, RefCell};
 use std::cmp::{max, min};
 use std::default::Default;
 use std::ops::Range;
+use std::rc::Rc;
+use std::time::Duration;
 use std::usize;
+use text_buffer::TextBuffer;
+use unicode_bidi::{BidiClass, bidi_class};
+use unicode_normalization::UnicodeNormalization;
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+use word_segmentation::{WordBreakRules, WordSelectionPolicy, is_word, word_bound_indices};
+use word_segmentation::word_selection_range;
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Selection {
     Selected,
     NotSelected
 }
 
+/// The unit in which dragging extends a selection. After a double click, for instance, the
+/// selection grows a word at a time.
+#[derive(Clone, Copy, Debug, Eq, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum SelectionGranularity {
+    Character,
+    Word,
+    Line,
+}
+
 #[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
 pub enum SelectionDirection {
     Forward,
@@ -48,6 +69,64 @@ impl From<SelectionDirection> for DOMString {
     }
 }
 
+/// The directionality of text, as the `dir` attribute gives it.
+///
+/// https://html.spec.whatwg.org/multipage/#the-directionality
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TextDirection {
+    Ltr,
+    Rtl,
+}
+
+impl From<TextDirection> for DOMString {
+    fn from(direction: TextDirection) -> DOMString {
+        match direction {
+            TextDirection::Ltr => DOMString::from("ltr"),
+            TextDirection::Rtl => DOMString::from("rtl"),
+        }
+    }
+}
+
+/// The direction of the first character of the text that has a strong direction, if any, by
+/// which `dir=auto` chooses the directionality of the text.
+pub fn first_strong_direction(text: &str) -> Option<TextDirection> {
+    text.chars().filter_map(|c| match bidi_class(c) {
+        BidiClass::L => Some(TextDirection::Ltr),
+        BidiClass::R | BidiClass::AL => Some(TextDirection::Rtl),
+        _ => None,
+    }).next()
+}
+
+/// The unit in which `max_length` and `min_length` measure the content.
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum LengthUnit {
+    /// UTF-16 code units, as required by the HTML specification.
+    Utf16CodeUnits,
+    CodePoints,
+    /// Extended grapheme clusters, i.e. user-perceived characters.
+    GraphemeClusters,
+}
+
+impl LengthUnit {
+    /// The unit selected by the `dom.textinput.length_unit` preference.
+    pub fn from_prefs() -> LengthUnit {
+        match PREFS.get("dom.textinput.length_unit").as_string() {
+            Some("code-points") => LengthUnit::CodePoints,
+            Some("grapheme-clusters") => LengthUnit::GraphemeClusters,
+            _ => LengthUnit::Utf16CodeUnits,
+        }
+    }
+
+    /// The length of a string in this unit.
+    pub fn length_of(self, text: &str) -> usize {
+        match self {
+            LengthUnit::Utf16CodeUnits => text.chars().map(char::len_utf16).sum(),
+            LengthUnit::CodePoints => text.chars().count(),
+            LengthUnit::GraphemeClusters => text.graphemes(true).count(),
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq, PartialOrd)]
 pub struct TextPoint {
     /// 0-based line number
@@ -56,6 +135,16 @@ pub struct TextPoint {
     pub index: usize,
 }
 
+/// A position in the content in user-perceived characters, for callers that should not have to
+/// segment the text themselves. See `TextInput::text_point_to_grapheme_point`.
+#[derive(Clone, Copy, Debug, Default, Eq, Ord, PartialEq, PartialOrd)]
+pub struct GraphemePoint {
+    /// 0-based line number
+    pub line: usize,
+    /// 0-based column number in grapheme clusters
+    pub grapheme: usize,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub struct SelectionState {
     start: TextPoint,
@@ -63,11 +152,86 @@ pub struct SelectionState {
     direction: SelectionDirection,
 }
 
+/// How text pasted from the clipboard is cleaned up before it is inserted.
+#[derive(Clone, Copy, Debug, Default, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct PasteSanitization {
+    /// Remove C0 and C1 control characters. Line breaks and tabs are kept in multiline controls.
+    pub strip_control_characters: bool,
+    /// Enclose pasted text containing bidi embedding, override or isolate characters in an
+    /// isolate, so that they cannot reorder the text around it.
+    pub isolate_bidi_controls: bool,
+    /// Remove ASCII whitespace from the start and end of the pasted text, as a URL control does
+    /// with its value.
+    pub trim_whitespace: bool,
+}
+
+/// Whether a character starts or ends a bidi embedding, override or isolate.
+fn is_bidi_control(c: char) -> bool {
+    match c {
+        '\u{202A}'...'\u{202E}' | '\u{2066}'...'\u{2069}' => true,
+        _ => false,
+    }
+}
+
+/// U+2068 FIRST STRONG ISOLATE
+const FIRST_STRONG_ISOLATE: char = '\u{2068}';
+
+/// U+2069 POP DIRECTIONAL ISOLATE
+const POP_DIRECTIONAL_ISOLATE: char = '\u{2069}';
+
+/// A filter applied to characters typed or pasted into a control. It returns the character to
+/// insert in place of the given one, or `None` to reject it.
+pub type CharFilter = fn(char) -> Option<char>;
+
+unsafe_no_jsmanaged_fields!(CharFilter);
+
+/// A handler given the keys pressed in a control before its editing commands. It returns the
+/// reaction to a key it handles in place of them, or `None` to leave the key to them.
+pub type KeyHook = fn(Key, KeyModifiers) -> Option<KeyReaction>;
+
+unsafe_no_jsmanaged_fields!(KeyHook);
+
+/// Values derived from the lines of a text input, computed when first needed after an edit.
+#[derive(Default)]
+struct ContentCache {
+    /// The byte offset of the start of each line.
+    line_offsets: RefCell<Option<Vec<usize>>>,
+    /// The lines joined by '\n'.
+    content: RefCell<Option<DOMString>>,
+    /// The length of the content in UTF-16 code units.
+    utf16_len: Cell<Option<usize>>,
+}
+
+unsafe_no_jsmanaged_fields!(ContentCache);
+
+/// The words of a text input found to be misspelled, and the part of it still to be checked.
+#[derive(Default, MallocSizeOf)]
+struct Spelling {
+    /// The byte ranges of the misspelled words, in order.
+    misspellings: Vec<Range<usize>>,
+    /// The byte range covering the text edited since the last check, if any.
+    unchecked: Option<Range<usize>>,
+}
+
+unsafe_no_jsmanaged_fields!(Spelling);
+
+/// The words of a text input sent to the spell checker, which only hold for the content they
+/// were taken from.
+#[derive(Debug)]
+pub struct SpellingRequest {
+    /// The content when the request was made.
+    content: DOMString,
+    /// The byte ranges of the words, in order.
+    words: Vec<Range<usize>>,
+    /// The text of each word, to send to the spell checker.
+    pub texts: Vec<String>,
+}
+
 /// Encapsulated state for handling keyboard input in a single or multiline text input control.
 #[derive(JSTraceable, MallocSizeOf)]
-pub struct TextInput<T: ClipboardProvider> {
+pub struct TextInput<T: ClipboardProvider, B: TextBuffer = Vec<DOMString>> {
     /// Current text input content, split across lines without trailing '\n'
-    lines: Vec<DOMString>,
+    lines: B,
     /// Current cursor input point
     pub edit_point: TextPoint,
     /// The current selection goes from the selection_origin until the edit_point. Note that the
@@ -77,22 +241,297 @@ pub struct TextInput<T: ClipboardProvider> {
     multiline: bool,
     #[ignore_malloc_size_of = "Can't easily measure this generic type"]
     clipboard_provider: T,
-    /// The maximum number of UTF-16 code units this text input is allowed to hold.
+    /// The maximum length, in `length_unit`, this text input is allowed to hold.
     ///
     /// <https://html.spec.whatwg.org/multipage/#attr-fe-maxlength>
     pub max_length: Option<usize>,
     pub min_length: Option<usize>,
+    /// The unit in which `max_length` and `min_length` are counted.
+    length_unit: LengthUnit,
     pub selection_direction: SelectionDirection,
+    /// The key combinations that trigger editing commands in this control.
+    #[ignore_malloc_size_of = "Defined in msg"]
+    keybindings: KeyBindings,
+    /// The column, in display width, that consecutive vertical movements try to return to.
+    /// Reset by horizontal movement and edits.
+    preferred_column: Option<usize>,
+    /// The number of rows the owner displays at once, used as the distance of a page movement.
+    visible_rows: Option<usize>,
+    /// The language-specific word boundary rules used for word movement and selection.
+    word_break_rules: WordBreakRules,
+    /// The character separating the items of a list, if the content is one. Word movement and
+    /// selection then take each item as a word, rather than following `word_break_rules`.
+    list_separator: Option<char>,
+    /// How `select_word_at` extends the selection around a word.
+    word_selection_policy: WordSelectionPolicy,
+    /// The unit in which `extend_selection_to_point` extends the selection.
+    selection_granularity: SelectionGranularity,
+    /// The unit that was selected first, e.g. the word that was double-clicked, which stays
+    /// selected whichever way the selection is extended.
+    granularity_anchor: Option<(TextPoint, TextPoint)>,
+    /// Whether Tab inserts a tab character into a multiline control, rather than being left
+    /// to the owner for focus navigation.
+    inserts_tabs: bool,
+    /// Whether Return is left to the owner to perform an action, rather than inserting a
+    /// newline into a multiline control.
+    enter_triggers_action: bool,
+    /// Whether edits are left to the owner to announce with a `beforeinput` event and then
+    /// carry out with `apply_edit`, rather than being carried out straight away.
+    defers_edits: bool,
+    /// Whether the content may only be changed by the owner, and not by editing commands.
+    read_only: bool,
+    /// Whether the content is hidden from the user, as in a password field.
+    obscured: bool,
+    /// The filter applied to typed and pasted characters before they are inserted.
+    #[ignore_malloc_size_of = "Function pointer"]
+    char_filter: Option<CharFilter>,
+    /// The handler given keys before the editing commands.
+    #[ignore_malloc_size_of = "Function pointer"]
+    key_hook: Option<KeyHook>,
+    /// The clean-up applied to pasted text.
+    paste_sanitization: PasteSanitization,
+    /// Whether a paste is waiting for the clipboard contents.
+    awaiting_paste: bool,
+    /// How long, in milliseconds, a paste waits for each answer of the clipboard before it is
+    /// abandoned.
+    paste_timeout_ms: u64,
+    /// Whether inserted text is normalized to NFC.
+    normalizes_insertions: bool,
+    /// The dead key or compose sequence waiting to be combined with the next character typed.
+    compose: ComposeState,
+    /// Whether Escape clears the content, once there is nothing else for it to undo.
+    clears_on_escape: bool,
+    /// The text an input method is composing, if any.
+    composition: Option<Composition>,
+    /// The changes that `undo` can take back, most recent last.
+    undo_stack: Vec<UndoEntry>,
+    /// The changes that `undo` took back, which `redo` can make again, most recent last.
+    redo_stack: Vec<UndoEntry>,
+    /// Speeds up conversions between byte offsets and `TextPoint`s, and reading the content.
+    /// Cleared whenever the lines change.
+    #[ignore_malloc_size_of = "Cache"]
+    cache: ContentCache,
+    /// The misspelled words found by `check_spelling`, kept up to date as the content changes.
+    spelling: Spelling,
+}
+
+/// A change made to the content of a text input by an edit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextChange {
+    /// The UTF-8 byte range of the replaced text in the content before the edit.
+    pub range: Range<usize>,
+    /// The text that was removed.
+    pub old_text: DOMString,
+    /// The text that was inserted in its place.
+    pub new_text: DOMString,
+}
+
+/// A change to the content asked for by the user, which `apply_edit` carries out.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    /// Insert typed text in place of the selection.
+    InsertText(String),
+    /// Insert a tab character in place of the selection.
+    InsertTab,
+    /// Insert a newline in place of the selection.
+    InsertLineBreak,
+    /// Insert pasted text in place of the selection.
+    InsertFromPaste(String),
+    /// Replace the whole content with text the user picked, such as a suggestion.
+    InsertReplacementText(String),
+    /// Replace a word the user typed with a correction, as autocorrection does.
+    InsertCorrection(WordCorrection),
+    /// Delete the selection, or else the character before the caret.
+    DeleteBackward,
+    /// Delete the selection, or else the character after the caret.
+    DeleteForward,
+    /// Delete the selection, or else everything from the start of the line to the caret.
+    DeleteToLineStart,
+    /// Delete the selection, once it has been cut to the clipboard.
+    DeleteByCut,
+    /// Take back the last change, with `undo`.
+    HistoryUndo,
+    /// Make the last change taken back again, with `redo`.
+    HistoryRedo,
+}
+
+impl Edit {
+    /// The `inputType` of the `beforeinput` and `input` events for this edit.
+    ///
+    /// https://w3c.github.io/input-events/#interface-InputEvent-Attributes
+    pub fn input_type(&self) -> &'static str {
+        match *self {
+            Edit::InsertText(_) | Edit::InsertTab => "insertText",
+            Edit::InsertLineBreak => "insertLineBreak",
+            Edit::InsertFromPaste(_) => "insertFromPaste",
+            Edit::InsertReplacementText(_) | Edit::InsertCorrection(_) => "insertReplacementText",
+            Edit::DeleteBackward => "deleteContentBackward",
+            Edit::DeleteForward => "deleteContentForward",
+            Edit::DeleteToLineStart => "deleteHardLineBackward",
+            Edit::DeleteByCut => "deleteByCut",
+            Edit::HistoryUndo => "historyUndo",
+            Edit::HistoryRedo => "historyRedo",
+        }
+    }
+
+    /// Whether this is typing, which is undone a word at a time rather than an edit at a time.
+    fn is_typing(&self) -> bool {
+        match *self {
+            Edit::InsertText(_) |
+            Edit::InsertTab |
+            Edit::InsertLineBreak |
+            Edit::DeleteBackward |
+            Edit::DeleteForward => true,
+            _ => false,
+        }
+    }
+
+    /// The `data` of the `beforeinput` and `input` events for this edit, which is given for
+    /// inserted text, but not for line breaks or deletions.
+    pub fn data(&self) -> Option<&str> {
+        match *self {
+            Edit::InsertText(ref text) |
+            Edit::InsertFromPaste(ref text) |
+            Edit::InsertReplacementText(ref text) => Some(text.as_str()),
+            Edit::InsertCorrection(ref correction) => Some(correction.correction.as_str()),
+            Edit::InsertTab => Some("\t"),
+            _ => None,
+        }
+    }
+}
+
+/// A word the user typed, and the correction that an `Edit::InsertCorrection` puts in its place.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordCorrection {
+    /// The UTF-8 byte range of the word in the content.
+    pub range: Range<usize>,
+    /// The word as it was typed.
+    pub word: String,
+    /// The text that replaces the word.
+    pub correction: String,
 }
 
 /// Resulting action to be taken by the owner of a text input that is handling an event.
+#[derive(Debug, PartialEq)]
 pub enum KeyReaction {
+    /// Return was pressed in a multiline control that performs an action for it rather than
+    /// inserting a line break.
     TriggerDefaultAction,
-    DispatchInput,
+    /// Return was pressed in a single-line control. The owner should submit its form
+    /// implicitly, if it has one.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#implicit-submission>
+    ImplicitSubmission,
+    /// The content may have changed, as described by the `TextChange` if it did.
+    DispatchInput(Option<TextChange>),
+    /// The user asked for an edit, which was deferred. The owner may let the page cancel it
+    /// with a `beforeinput` event, and otherwise carry it out with `apply_edit`.
+    BeforeInput(Edit),
+    /// The selection changed, but the caret stayed where it was.
     RedrawSelection,
+    /// The caret moved, and the owner should scroll it into view.
+    ScrollCaretIntoView,
+    /// The caret moved, collapsing a selection that was not empty.
+    SelectionCleared,
+    /// The key would have edited the content, but the control did not accept the edit, e.g.
+    /// because it is read-only or full. The owner may alert the user.
+    Rejected,
+    /// Tab was pressed without being inserted; the owner may move the focus in this direction.
+    FocusNavigation(Direction),
+    /// A copy was asked for. The owner may let the page handle it with a `copy` event, and
+    /// otherwise copy the selection with `copy_selection`.
+    CopyRequested,
+    /// A cut was asked for. The owner may let the page handle it with a `cut` event, and
+    /// otherwise cut the selection with `cut_selection`, or with `copy_selection` and an
+    /// `Edit::DeleteByCut` if edits are deferred.
+    CutRequested,
+    /// A paste is waiting for the clipboard contents. The owner should read them with a
+    /// `paste_reader` and insert them with `finish_paste`, or with `take_paste_request` and
+    /// `paste` if the page may handle the paste with a `paste` event.
+    PasteRequested,
+    /// The user asked for the value to be stepped up by the given number of steps, or down if
+    /// it is negative, as the arrow keys do in a number input.
+    StepRequested(i32),
+    /// Escape was pressed, with the given effect on the control. If it had none, the owner may
+    /// use the key itself, e.g. to close a popup or give up the focus.
+    Escape(EscapeOutcome),
+    /// Escape abandoned the text an input method was composing, putting back the text it
+    /// replaced, as described by the `TextChange` if the content changed. The owner should end
+    /// the composition, with a `compositionend` event.
+    CompositionCancelled(Option<TextChange>),
+    Nothing,
+}
+
+/// What pressing Escape did to a text input. Each press undoes one thing, in this order.
+#[derive(Debug, PartialEq)]
+pub enum EscapeOutcome {
+    /// A dead key or compose sequence was abandoned.
+    CompositionCancelled,
+    /// The selection was collapsed to the caret.
+    SelectionCleared,
+    /// The content was cleared, as a search field does.
+    ContentCleared(TextChange),
     Nothing,
 }
 
+/// A clause of the text being composed by an input method, which the user converts as a unit,
+/// e.g. a word of Japanese text in a sentence that is being converted to kanji.
+#[derive(Clone, Copy, Debug, Eq, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct CompositionClause {
+    /// The UTF-8 byte offset of the start of the clause in the composed text.
+    pub start: usize,
+    /// The UTF-8 byte offset of the end of the clause in the composed text.
+    pub end: usize,
+    /// Whether this is the clause the input method is currently converting.
+    pub selected: bool,
+}
+
+/// Text that an input method is composing. It is part of the content until it is committed or
+/// cancelled, but the input method may still replace it.
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub struct Composition {
+    /// The UTF-8 byte offset of the composed text in the content.
+    start: usize,
+    /// The composed text, as it appears in the content.
+    text: DOMString,
+    clauses: Vec<CompositionClause>,
+    /// The selection that the composition replaced, put back if it is cancelled.
+    replaced_text: DOMString,
+    replaced_direction: SelectionDirection,
+}
+
+impl Composition {
+    /// The UTF-8 byte range of the composed text in the content.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.text.len()
+    }
+
+    /// The composed text, also called the preedit string.
+    pub fn text(&self) -> &DOMString {
+        &self.text
+    }
+
+    /// The clauses of the composed text, in order.
+    pub fn clauses(&self) -> &[CompositionClause] {
+        &self.clauses
+    }
+}
+
+/// A change to the content that `undo` can take back and `redo` can make again.
+#[derive(Clone, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+struct UndoEntry {
+    /// The UTF-8 byte offset in the content at which the change was made.
+    start: usize,
+    old_text: DOMString,
+    new_text: DOMString,
+    /// The selection before the change, as UTF-8 byte offsets, which undoing it puts back.
+    selection_start: usize,
+    selection_end: usize,
+    /// Whether further typing within the inserted text is merged into this change, so that
+    /// a word typed a character at a time is undone at once.
+    open: bool,
+}
+
 impl Default for TextPoint {
     fn default() -> TextPoint {
         TextPoint {
@@ -103,40 +542,55 @@ impl Default for TextPoint {
 }
 
 /// Control whether this control should allow multiple lines.
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Lines {
     Single,
     Multiple,
 }
 
 /// The direction in which to delete a character.
-#[derive(Clone, Copy, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Direction {
     Forward,
     Backward
 }
 
 
-/// Was the keyboard event accompanied by the standard control modifier,
-/// i.e. cmd on Mac OS or ctrl on other platforms.
-#[cfg(target_os = "macos")]
-fn is_control_key(mods: KeyModifiers) -> bool {
-    mods.contains(KeyModifiers::SUPER) && !mods.contains(KeyModifiers::CONTROL | KeyModifiers::ALT)
+/// Whether typing the given character after a word finishes it, for autocorrection. Apostrophes
+/// and hyphens don't, as they may be part of the word.
+fn is_word_terminator(c: char) -> bool {
+    c.is_whitespace() || (c.is_ascii_punctuation() && c != '\'' && c != '-')
 }
 
-#[cfg(not(target_os = "macos"))]
-fn is_control_key(mods: KeyModifiers) -> bool {
-    mods.contains(KeyModifiers::CONTROL) && !mods.contains(KeyModifiers::SUPER | KeyModifiers::ALT)
-}
+/// The number of lines moved by PageUp and PageDown when the owner has not said how many rows
+/// it displays.
+const DEFAULT_PAGE_ROWS: usize = 28;
 
-/// The length in bytes of the first n characters in a UTF-8 string.
+/// The number of changes that `undo` can take back.
+const MAX_UNDO_ENTRIES: usize = 100;
+
+/// The character displayed for each grapheme of an obscured control's content.
+pub const PASSWORD_REPLACEMENT_CHAR: char = '●';
+
+/// The length in bytes of the graphemes of a UTF-8 string that come closest to filling the
+/// given display width, where East Asian wide characters take two columns.
 ///
-/// If the string has fewer than n characters, returns the length of the whole string.
-fn len_of_first_n_chars(text: &str, n: usize) -> usize {
-    match text.char_indices().take(n).last() {
-        Some((index, ch)) => index + ch.len_utf8(),
-        None => 0
+/// If the string is narrower than the width, returns the length of the whole string.
+fn len_of_display_width(text: &str, width: usize) -> usize {
+    let mut column = 0;
+    for (index, grapheme) in text.grapheme_indices(true) {
+        let next_column = column + grapheme.width();
+        if next_column > width {
+            // Stop on whichever side of this grapheme is closer to the requested width.
+            return if width - column <= next_column - width {
+                index
+            } else {
+                index + grapheme.len()
+            };
+        }
+        column = next_column;
     }
+    text.len()
 }
 
 /// The length in bytes of the first n code units a string when encoded in UTF-16.
@@ -155,45 +609,370 @@ fn len_of_first_n_code_units(text: &str, n: usize) -> usize {
     utf8_len
 }
 
+/// The length in bytes of the first n units of a string, in the given unit.
+///
+/// If the string is shorter than n units, returns the length of the whole string.
+fn len_of_first_n_units(text: &str, n: usize, unit: LengthUnit) -> usize {
+    match unit {
+        LengthUnit::Utf16CodeUnits => len_of_first_n_code_units(text, n),
+        LengthUnit::CodePoints => text.char_indices().nth(n).map_or(text.len(), |(i, _)| i),
+        LengthUnit::GraphemeClusters => text.grapheme_indices(true).nth(n).map_or(text.len(), |(i, _)| i),
+    }
+}
+
 impl<T: ClipboardProvider> TextInput<T> {
     /// Instantiate a new text input control
     pub fn new(lines: Lines, initial: DOMString,
                clipboard_provider: T, max_length: Option<usize>,
                min_length: Option<usize>,
                selection_direction: SelectionDirection) -> TextInput<T> {
+        TextInput::with_buffer(lines, initial, clipboard_provider, max_length, min_length, selection_direction)
+    }
+}
+
+impl<T: ClipboardProvider, B: TextBuffer> TextInput<T, B> {
+    /// Instantiate a new text input control that keeps its content in the given kind of buffer.
+    pub fn with_buffer(lines: Lines, initial: DOMString,
+                       clipboard_provider: T, max_length: Option<usize>,
+                       min_length: Option<usize>,
+                       selection_direction: SelectionDirection) -> TextInput<T, B> {
         let mut i = TextInput {
-            lines: vec!(),
+            lines: B::from_lines(vec!()),
             edit_point: Default::default(),
             selection_origin: None,
             multiline: lines == Lines::Multiple,
             clipboard_provider: clipboard_provider,
             max_length: max_length,
             min_length: min_length,
+            length_unit: LengthUnit::from_prefs(),
             selection_direction: selection_direction,
+            keybindings: KeyBindings::from_prefs(),
+            preferred_column: None,
+            visible_rows: None,
+            word_break_rules: WordBreakRules::Default,
+            list_separator: None,
+            word_selection_policy: WordSelectionPolicy::current(),
+            selection_granularity: SelectionGranularity::Character,
+            granularity_anchor: None,
+            inserts_tabs: false,
+            enter_triggers_action: false,
+            defers_edits: false,
+            read_only: false,
+            obscured: false,
+            char_filter: None,
+            key_hook: None,
+            paste_sanitization: Default::default(),
+            awaiting_paste: false,
+            paste_timeout_ms: PREFS.get("dom.textinput.paste_timeout_ms").as_u64().unwrap_or(1000),
+            normalizes_insertions: PREFS.get("dom.textinput.nfc_normalization.enabled")
+                                        .as_boolean().unwrap_or(false),
+            compose: Default::default(),
+            clears_on_escape: false,
+            composition: None,
+            undo_stack: vec!(),
+            redo_stack: vec!(),
+            cache: Default::default(),
+            spelling: Default::default(),
         };
         i.set_content(initial, false);
         i
     }
 
+    /// Replace the key combinations that trigger editing commands in this control.
+    pub fn set_keybindings(&mut self, keybindings: KeyBindings) {
+        self.keybindings = keybindings;
+    }
+
+    /// Set the number of rows the owner displays at once, which determines how far PageUp and
+    /// PageDown move the editing point. `None` falls back to a default distance.
+    pub fn set_visible_rows(&mut self, rows: Option<usize>) {
+        self.visible_rows = rows;
+    }
+
+    /// Set the language of the content, as given by the owner's `lang` attribute, which
+    /// determines where word movement and selection stop.
+    pub fn set_language(&mut self, language: &str) {
+        self.word_break_rules = WordBreakRules::for_language(language);
+    }
+
+    /// Set the character that separates the items of the content, if it is a list such as the
+    /// addresses of an email input, so that word movement and selection go an item at a time.
+    pub fn set_list_separator(&mut self, separator: Option<char>) {
+        self.list_separator = separator;
+    }
+
+    /// The rules that word movement and selection follow.
+    fn word_rules(&self) -> WordBreakRules {
+        match self.list_separator {
+            Some(separator) => WordBreakRules::List(separator),
+            None => self.word_break_rules,
+        }
+    }
+
+    /// Set how `select_word_at` extends the selection around a word, e.g. to follow the
+    /// conventions of another platform.
+    pub fn set_word_selection_policy(&mut self, policy: WordSelectionPolicy) {
+        self.word_selection_policy = policy;
+    }
+
+    /// Set whether Tab inserts a tab character. This only has an effect on multiline controls.
+    pub fn set_inserts_tabs(&mut self, inserts_tabs: bool) {
+        self.inserts_tabs = inserts_tabs;
+    }
+
+    /// Set whether Return triggers the owner's default action, e.g. the one hinted by
+    /// `enterkeyhint`, instead of inserting a newline. Single-line controls always trigger it.
+    pub fn set_enter_triggers_action(&mut self, enter_triggers_action: bool) {
+        self.enter_triggers_action = enter_triggers_action;
+    }
+
+    /// Set whether edits are deferred, for the owner to announce with a `beforeinput` event
+    /// before carrying them out with `apply_edit`.
+    pub fn set_defers_edits(&mut self, defers_edits: bool) {
+        self.defers_edits = defers_edits;
+    }
+
+    /// Set how long, in milliseconds, a paste waits for each answer of the clipboard while it
+    /// reads the contents.
+    pub fn set_paste_timeout(&mut self, timeout_ms: u64) {
+        self.paste_timeout_ms = timeout_ms;
+    }
+
+    /// A reader for the contents that the paste requested by the last
+    /// `KeyReaction::PasteRequested` is waiting for, which reads them in chunks so that huge
+    /// contents aren't sent in a single message. Text controls take plain text, or else a list
+    /// of URLs, whatever else the clipboard offers. The read is abandoned if the clipboard takes
+    /// longer than the paste timeout to answer any of its requests, however long the whole read
+    /// takes.
+    pub fn paste_reader(&self) -> ClipboardChunkReader<T> where T: Clone {
+        let mut reader = ClipboardChunkReader::new(self.clipboard_provider.clone(),
+                                                   vec!(ClipboardFlavor::PlainText, ClipboardFlavor::UriList),
+                                                   CLIPBOARD_CHUNK_SIZE);
+        reader.set_timeout(Duration::from_millis(self.paste_timeout_ms));
+        reader
+    }
+
+    /// Stop waiting for the clipboard contents, and return whether a paste was still waiting
+    /// for them. Contents that arrive after another editing command or a change of content
+    /// overtook the paste are dropped.
+    pub fn take_paste_request(&mut self) -> bool {
+        let awaiting_paste = self.awaiting_paste;
+        self.awaiting_paste = false;
+        awaiting_paste
+    }
+
+    /// Insert the clipboard contents for the paste that is waiting for them, like typed text.
+    pub fn finish_paste(&mut self, contents: ClipboardContents) -> KeyReaction {
+        if !self.take_paste_request() {
+            return KeyReaction::Nothing;
+        }
+        self.paste(contents)
+    }
+
+    /// Insert pasted clipboard contents, like typed text, once they have been cleaned up. The
+    /// pasted text is only copied out of the contents if they are shared, e.g. with the events
+    /// that carry them.
+    pub fn paste<C: Into<Rc<ClipboardContents>>>(&mut self, contents: C) -> KeyReaction {
+        if self.read_only {
+            return KeyReaction::Rejected;
+        }
+        let contents = self.pasted_text(contents.into());
+        let contents = self.sanitize_paste(contents);
+        let contents = self.filter_text(contents);
+        if contents.is_empty() {
+            KeyReaction::Nothing
+        } else {
+            self.edit_reaction(Edit::InsertFromPaste(contents))
+        }
+    }
+
+    /// Copy the selection to the clipboard, and return whether there was anything to copy.
+    /// The text of an obscured control is never copied.
+    pub fn copy_selection(&mut self) -> bool {
+        match self.get_selection_text() {
+            Some(text) => {
+                self.clipboard_provider.set_clipboard_contents(ClipboardContents::from_plain_text(text));
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Move the selection to the clipboard, as described by the `TextChange` if the content
+    /// changed.
+    pub fn cut_selection(&mut self) -> Option<TextChange> {
+        if self.read_only || !self.copy_selection() {
+            return None;
+        }
+        self.replace_selection(DOMString::new())
+    }
+
+    /// Put contents that the page chose on the clipboard, in place of the selection.
+    pub fn write_clipboard(&mut self, contents: ClipboardContents) {
+        self.clipboard_provider.set_clipboard_contents(contents);
+    }
+
+    /// Set whether Escape clears the content, as it does in a search field.
+    pub fn set_clears_on_escape(&mut self, clears_on_escape: bool) {
+        self.clears_on_escape = clears_on_escape;
+    }
+
+    /// Set whether editing commands are blocked. Caret movement, selection and copying remain
+    /// available, and the owner can still replace the content with `set_content`.
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Whether editing commands are blocked.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Set whether the content is hidden from the user. An obscured control does not give out
+    /// its selected text, so it cannot be copied out of the control.
+    pub fn set_obscured(&mut self, obscured: bool) {
+        self.obscured = obscured;
+    }
+
+    /// Set the filter applied to characters typed or pasted by the user. Content set by the
+    /// owner is not filtered.
+    pub fn set_char_filter(&mut self, filter: Option<CharFilter>) {
+        self.char_filter = filter;
+    }
+
+    /// Set the handler given keys before the editing commands, e.g. to use the arrow keys for
+    /// something other than moving the caret.
+    pub fn set_key_hook(&mut self, hook: Option<KeyHook>) {
+        self.key_hook = hook;
+    }
+
+    /// Set whether text inserted with `insert_string` and `insert_char` is normalized to NFC,
+    /// so that the content compares equal however its characters were entered.
+    pub fn set_normalizes_insertions(&mut self, normalizes: bool) {
+        self.normalizes_insertions = normalizes;
+    }
+
+    /// Set how text pasted from the clipboard is cleaned up before insertion.
+    pub fn set_paste_sanitization(&mut self, sanitization: PasteSanitization) {
+        self.paste_sanitization = sanitization;
+    }
+
+    /// The text that pasting the given clipboard contents inserts: their plain text, or else
+    /// the URLs in their `text/uri-list`, one per line, or only the first one in a single-line
+    /// control.
+    fn pasted_text(&self, contents: Rc<ClipboardContents>) -> String {
+        let has_plain_text = contents.get(ClipboardFlavor::PlainText).map_or(false, |text| !text.is_empty());
+        if has_plain_text {
+            return match Rc::try_unwrap(contents) {
+                Ok(contents) => contents.into_data(ClipboardFlavor::PlainText).unwrap_or_default(),
+                Err(contents) => contents.get(ClipboardFlavor::PlainText).unwrap_or("").to_owned(),
+            };
+        }
+        // https://tools.ietf.org/html/rfc2483#section-5
+        let uri_list = contents.get(ClipboardFlavor::UriList).unwrap_or("");
+        let mut urls = uri_list.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+        if self.multiline {
+            urls.collect::<Vec<_>>().join("\n")
+        } else {
+            urls.next().unwrap_or("").to_owned()
+        }
+    }
+
+    /// Clean up text about to be pasted, as configured by `set_paste_sanitization`. The text is
+    /// only copied if the clean-up changes it, as pasted text can be huge.
+    fn sanitize_paste(&self, mut text: String) -> String {
+        let multiline = self.multiline;
+        let stripped = |c: char| c.is_control() && !(multiline && (c == '\n' || c == '\t'));
+        if self.paste_sanitization.strip_control_characters && text.chars().any(&stripped) {
+            text = text.chars().filter(|&c| !stripped(c)).collect();
+        }
+        if self.paste_sanitization.trim_whitespace {
+            let end = text.trim_right_matches(|c: char| c.is_ascii_whitespace()).len();
+            text.truncate(end);
+            let start = text.len() - text.trim_left_matches(|c: char| c.is_ascii_whitespace()).len();
+            text.drain(..start);
+        }
+        if self.paste_sanitization.isolate_bidi_controls && text.chars().any(is_bidi_control) {
+            text.insert(0, FIRST_STRONG_ISOLATE);
+            text.push(POP_DIRECTIONAL_ISOLATE);
+        }
+        text
+    }
+
+    /// Apply the character filter to text about to be inserted by the user.
+    fn filter_text(&self, text: String) -> String {
+        match self.char_filter {
+            Some(filter) if text.chars().any(|c| filter(c) != Some(c)) => text.chars().filter_map(filter).collect(),
+            _ => text,
+        }
+    }
+
+    /// Set the unit in which `max_length` and `min_length` are counted.
+    pub fn set_length_unit(&mut self, unit: LengthUnit) {
+        self.length_unit = unit;
+    }
+
+    /// The number of lines moved by a page movement.
+    fn page_rows(&self) -> isize {
+        max(1, self.visible_rows.unwrap_or(DEFAULT_PAGE_ROWS)) as isize
+    }
+
     /// Remove a character at the current editing point
-    pub fn delete_char(&mut self, dir: Direction) {
+    pub fn delete_char(&mut self, dir: Direction) -> Option<TextChange> {
         if self.selection_origin.is_none() || self.selection_origin == Some(self.edit_point) {
             self.adjust_horizontal_by_one(dir, Selection::Selected);
         }
-        self.replace_selection(DOMString::new());
+        self.replace_selection(DOMString::new())
+    }
+
+    /// Remove the selection, or else everything between the editing point and the start of the
+    /// current line
+    pub fn delete_to_line_start(&mut self) -> Option<TextChange> {
+        if self.selection_origin.is_none() || self.selection_origin == Some(self.edit_point) {
+            self.clear_selection();
+            self.adjust_horizontal_to_line_end(Direction::Backward, Selection::Selected);
+        }
+        self.replace_selection(DOMString::new())
     }
 
     /// Insert a character at the current editing point
-    pub fn insert_char(&mut self, ch: char) {
-        self.insert_string(ch.to_string());
+    pub fn insert_char(&mut self, ch: char) -> Option<TextChange> {
+        self.insert_string(ch.to_string())
     }
 
     /// Insert a string at the current editing point
-    pub fn insert_string<S: Into<String>>(&mut self, s: S) {
+    pub fn insert_string<S: Into<String>>(&mut self, s: S) -> Option<TextChange> {
         if self.selection_origin.is_none() {
             self.selection_origin = Some(self.edit_point);
         }
-        self.replace_selection(DOMString::from(s.into()));
+        let mut text = s.into();
+        if self.normalizes_insertions {
+            text = self.normalize_insertion(text);
+        }
+        self.replace_selection(DOMString::from(text))
+    }
+
+    /// Normalize text about to replace the selection to NFC. If the text starts with characters
+    /// that compose with the grapheme before the selection, such as a combining accent or a
+    /// Hangul vowel, the selection is extended over that grapheme, which is returned composed
+    /// with the text.
+    fn normalize_insertion(&mut self, text: String) -> String {
+        let (start, end) = self.sorted_selection_bounds();
+        let previous = self.lines[start.line][..start.index].grapheme_indices(true).next_back()
+            .map(|(index, grapheme)| (index, grapheme.to_owned()));
+        let (index, previous) = match previous {
+            Some(previous) => previous,
+            None => return text.nfc().collect(),
+        };
+
+        let combined: String = previous.chars().chain(text.chars()).nfc().collect();
+        if combined.starts_with(&*previous) {
+            return combined[previous.len()..].to_owned();
+        }
+        self.selection_origin = Some(TextPoint { line: start.line, index: index });
+        self.edit_point = end;
+        self.selection_direction = SelectionDirection::Forward;
+        combined
     }
 
     /// The selection origin, or the edit point if there is no selection. Note that the selection
@@ -230,6 +1009,16 @@ impl<T: ClipboardProvider> TextInput<T> {
         self.text_point_to_offset(&self.selection_end())
     }
 
+    /// The UTF-16 code unit offset of the selection_start(), as used by the DOM
+    pub fn selection_start_utf16_offset(&self) -> usize {
+        self.text_point_to_utf16_offset(&self.selection_start())
+    }
+
+    /// The UTF-16 code unit offset of the selection_end(), as used by the DOM
+    pub fn selection_end_utf16_offset(&self) -> usize {
+        self.text_point_to_utf16_offset(&self.selection_end())
+    }
+
     /// Whether or not there is an active selection (the selection may be zero-length)
     #[inline]
     pub fn has_selection(&self) -> bool {
@@ -249,6 +1038,34 @@ impl<T: ClipboardProvider> TextInput<T> {
         self.selection_start_offset() .. self.selection_end_offset()
     }
 
+    /// The content as displayed by an obscured control, with every grapheme replaced by
+    /// `PASSWORD_REPLACEMENT_CHAR`.
+    pub fn masked_content(&self) -> DOMString {
+        let mut content = String::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                content.push('\n');
+            }
+            content.extend(line.graphemes(true).map(|_| PASSWORD_REPLACEMENT_CHAR));
+        }
+        DOMString::from(content)
+    }
+
+    /// The sorted selection bounds as byte offsets into `masked_content`.
+    pub fn masked_selection_offsets_range(&self) -> Range<usize> {
+        let (start, end) = self.sorted_selection_bounds();
+        self.masked_offset(start) .. self.masked_offset(end)
+    }
+
+    fn masked_offset(&self, point: TextPoint) -> usize {
+        let bytes_per_grapheme = PASSWORD_REPLACEMENT_CHAR.len_utf8();
+        let preceding_lines = self.lines.iter_range(0..point.line)
+            .map(|line| line.graphemes(true).count() * bytes_per_grapheme + 1)
+            .sum::<usize>();
+        let preceding_graphemes = self.lines[point.line][..point.index].graphemes(true).count();
+        preceding_lines + preceding_graphemes * bytes_per_grapheme
+    }
+
     /// The state of the current selection. Can be used to compare whether selection state has changed.
     pub fn selection_state(&self) -> SelectionState {
         SelectionState {
@@ -258,28 +1075,41 @@ impl<T: ClipboardProvider> TextInput<T> {
         }
     }
 
-    // Check that the selection is valid.
-    fn assert_ok_selection(&self) {
-        if let Some(begin) = self.selection_origin {
-            debug_assert!(begin.line < self.lines.len());
-            debug_assert!(begin.index <= self.lines[begin.line].len());
+    // Check that the selection is valid, and repair it if it is not. An invalid selection is a
+    // bug, but it must not lead to slicing panics later on.
+    fn validate_selection(&mut self) {
+        let edit_point = self.clamp_text_point(self.edit_point);
+        if edit_point != self.edit_point {
+            warn!("Edit point {:?} is not a valid position, moving it to {:?}", self.edit_point, edit_point);
+            self.edit_point = edit_point;
+        }
 
-            match self.selection_direction {
-                SelectionDirection::None | SelectionDirection::Forward => {
-                    debug_assert!(begin <= self.edit_point)
-                },
+        if let Some(origin) = self.selection_origin {
+            let begin = self.clamp_text_point(origin);
+            if begin != origin {
+                warn!("Selection origin {:?} is not a valid position, moving it to {:?}", origin, begin);
+                self.selection_origin = Some(begin);
+            }
 
-                SelectionDirection::Backward => {
-                    debug_assert!(self.edit_point <= begin)
-                },
+            let ordered = match self.selection_direction {
+                SelectionDirection::None | SelectionDirection::Forward => begin <= edit_point,
+                SelectionDirection::Backward => edit_point <= begin,
+            };
+            if !ordered {
+                warn!("Selection direction {:?} does not match the selection", self.selection_direction);
+                self.selection_direction = if begin <= edit_point {
+                    SelectionDirection::Forward
+                } else {
+                    SelectionDirection::Backward
+                };
             }
         }
-
-        debug_assert!(self.edit_point.line < self.lines.len());
-        debug_assert!(self.edit_point.index <= self.lines[self.edit_point.line].len());
     }
 
     pub fn get_selection_text(&self) -> Option<String> {
+        if self.obscured {
+            return None
+        }
         let text = self.fold_selection_slices(String::new(), |s, slice| s.push_str(slice));
         if text.is_empty() {
             return None
@@ -287,16 +1117,16 @@ impl<T: ClipboardProvider> TextInput<T> {
         Some(text)
     }
 
-    /// The length of the selected text in UTF-16 code units.
-    fn selection_utf16_len(&self) -> usize {
-        self.fold_selection_slices(0usize,
-            |len, slice| *len += slice.chars().map(char::len_utf16).sum::<usize>())
+    /// The length of the selected text in `length_unit`.
+    fn selection_length(&self) -> usize {
+        let unit = self.length_unit;
+        self.fold_selection_slices(0usize, |len, slice| *len += unit.length_of(slice))
     }
 
     /// Run the callback on a series of slices that, concatenated, make up the selected text.
     ///
     /// The accumulator `acc` can be mutated by the callback, and will be returned at the end.
-    fn fold_selection_slices<B, F: FnMut(&mut B, &str)>(&self, mut acc: B, mut f: F) -> B {
+    fn fold_selection_slices<A, F: FnMut(&mut A, &str)>(&self, mut acc: A, mut f: F) -> A {
         if self.has_selection() {
             let (start, end) = self.sorted_selection_bounds();
 
@@ -304,7 +1134,7 @@ impl<T: ClipboardProvider> TextInput<T> {
                 f(&mut acc, &self.lines[start.line][start.index..end.index])
             } else {
                 f(&mut acc, &self.lines[start.line][start.index..]);
-                for line in &self.lines[start.line + 1 .. end.line] {
+                for line in self.lines.iter_range(start.line + 1 .. end.line) {
                     f(&mut acc, "\n");
                     f(&mut acc, line);
                 }
@@ -316,19 +1146,43 @@ impl<T: ClipboardProvider> TextInput<T> {
         acc
     }
 
-    pub fn replace_selection(&mut self, insert: DOMString) {
+    /// Replace the selection with the given text, as far as `max_length` allows, and return the
+    /// change made to the content, if any, which `undo` can take back.
+    pub fn replace_selection(&mut self, insert: DOMString) -> Option<TextChange> {
+        self.validate_selection();
+        let selection = self.sorted_selection_offsets_range();
+        let change = self.replace_selection_unrecorded(insert);
+        if let Some(ref change) = change {
+            self.record_undo(change, selection);
+        }
+        change
+    }
+
+    /// Replace the selection with the given text in full, as `replace_selection` does but without
+    /// regard to `max_length`, which only limits what the user types, e.g. for `setRangeText`.
+    pub fn replace_selection_ignoring_max_length(&mut self, insert: DOMString) -> Option<TextChange> {
+        let max_length = self.max_length.take();
+        let change = self.replace_selection(insert);
+        self.max_length = max_length;
+        change
+    }
+
+    /// Replace the selection, as `replace_selection` does, without adding the change to the
+    /// undo history.
+    fn replace_selection_unrecorded(&mut self, insert: DOMString) -> Option<TextChange> {
+        self.validate_selection();
         if !self.has_selection() {
-            return
+            return None
         }
 
         let (start, end) = self.sorted_selection_bounds();
 
         let allowed_to_insert_count = if let Some(max_length) = self.max_length {
-            let len_after_selection_replaced = self.utf16_len() - self.selection_utf16_len();
+            let len_after_selection_replaced = self.length() - self.selection_length();
             if len_after_selection_replaced >= max_length {
                 // If, after deleting the selection, the len is still greater than the max
                 // length, then don't delete/insert anything
-                return
+                return None
             }
 
             max_length - len_after_selection_replaced
@@ -336,45 +1190,52 @@ impl<T: ClipboardProvider> TextInput<T> {
             usize::MAX
         };
 
-        let last_char_index = len_of_first_n_code_units(&*insert, allowed_to_insert_count);
+        let last_char_index = len_of_first_n_units(&*insert, allowed_to_insert_count, self.length_unit);
         let chars_to_insert = &insert[..last_char_index];
 
-        self.clear_selection();
-
-        let new_lines = {
-            let prefix = &self.lines[start.line][..start.index];
-            let suffix = &self.lines[end.line][end.index..];
-            let lines_prefix = &self.lines[..start.line];
-            let lines_suffix = &self.lines[end.line + 1..];
+        let old_text = self.fold_selection_slices(String::new(), |s, slice| s.push_str(slice));
+        let change = if old_text.is_empty() && chars_to_insert.is_empty() {
+            None
+        } else {
+            let offset = self.text_point_to_offset(&start);
+            Some(TextChange {
+                range: offset..offset + old_text.len(),
+                old_text: DOMString::from(old_text),
+                new_text: DOMString::from(chars_to_insert),
+            })
+        };
 
-            let mut insert_lines = if self.multiline {
-                chars_to_insert.split('\n').map(|s| DOMString::from(s)).collect()
-            } else {
-                vec!(DOMString::from(chars_to_insert))
-            };
+        self.clear_selection();
 
-            // FIXME(ajeffrey): effecient append for DOMStrings
-            let mut new_line = prefix.to_owned();
+        let mut insert_lines = if self.multiline {
+            chars_to_insert.split('\n').map(|s| DOMString::from(s)).collect()
+        } else {
+            vec!(DOMString::from(chars_to_insert))
+        };
 
-            new_line.push_str(&insert_lines[0]);
-            insert_lines[0] = DOMString::from(new_line);
+        // FIXME(ajeffrey): effecient append for DOMStrings
+        let mut new_line = self.lines[start.line][..start.index].to_owned();
 
-            let last_insert_lines_index = insert_lines.len() - 1;
-            self.edit_point.index = insert_lines[last_insert_lines_index].len();
-            self.edit_point.line = start.line + last_insert_lines_index;
+        new_line.push_str(&insert_lines[0]);
+        insert_lines[0] = DOMString::from(new_line);
 
-            // FIXME(ajeffrey): effecient append for DOMStrings
-            insert_lines[last_insert_lines_index].push_str(suffix);
+        let last_insert_lines_index = insert_lines.len() - 1;
+        self.edit_point.index = insert_lines[last_insert_lines_index].len();
+        self.edit_point.line = start.line + last_insert_lines_index;
 
-            let mut new_lines = vec!();
-            new_lines.extend_from_slice(lines_prefix);
-            new_lines.extend_from_slice(&insert_lines);
-            new_lines.extend_from_slice(lines_suffix);
-            new_lines
-        };
+        // FIXME(ajeffrey): effecient append for DOMStrings
+        insert_lines[last_insert_lines_index].push_str(&self.lines[end.line][end.index..]);
 
-        self.lines = new_lines;
-        self.assert_ok_selection();
+        // Only the lines covered by the selection are replaced, so typing does not copy the
+        // rest of the content.
+        self.lines.replace_lines(start.line..end.line + 1, insert_lines);
+        self.invalidate_cache();
+        if let Some(ref change) = change {
+            self.update_spelling(change);
+        }
+        self.preferred_column = None;
+        self.validate_selection();
+        change
     }
 
     /// Return the length in UTF-8 bytes of the current line under the editing point.
@@ -383,7 +1244,9 @@ impl<T: ClipboardProvider> TextInput<T> {
     }
 
     /// Adjust the editing point position by a given of lines. The resulting column is
-    /// as close to the original column position as possible.
+    /// as close to the original column position as possible, measured in display width so
+    /// that full-width characters count as two columns. Consecutive vertical movements
+    /// aim for the column where the first one started, even if they pass through shorter lines.
     pub fn adjust_vertical(&mut self, adjust: isize, select: Selection) {
         if !self.multiline {
             return;
@@ -399,22 +1262,34 @@ impl<T: ClipboardProvider> TextInput<T> {
 
         assert!(self.edit_point.line < self.lines.len());
 
+        let col = match self.preferred_column {
+            Some(col) => col,
+            None => self.lines[self.edit_point.line][..self.edit_point.index].width(),
+        };
+        self.preferred_column = Some(col);
+
         let target_line: isize = self.edit_point.line as isize + adjust;
 
         if target_line < 0 {
             self.edit_point.index = 0;
             self.edit_point.line = 0;
-            return;
         } else if target_line as usize >= self.lines.len() {
             self.edit_point.line = self.lines.len() - 1;
             self.edit_point.index = self.current_line_length();
-            return;
+        } else {
+            self.edit_point.line = target_line as usize;
+            self.edit_point.index = len_of_display_width(&self.lines[self.edit_point.line], col);
         }
 
-        let col = self.lines[self.edit_point.line][..self.edit_point.index].chars().count();
-        self.edit_point.line = target_line as usize;
-        self.edit_point.index = len_of_first_n_chars(&self.lines[self.edit_point.line], col);
-        self.assert_ok_selection();
+        // Moving past the selection origin turns the selection around.
+        if let Some(origin) = self.selection_origin {
+            self.selection_direction = if self.edit_point < origin {
+                SelectionDirection::Backward
+            } else {
+                SelectionDirection::Forward
+            };
+        }
+        self.validate_selection();
     }
 
     /// Adjust the editing point position by a given number of bytes. If the adjustment
@@ -455,6 +1330,7 @@ impl<T: ClipboardProvider> TextInput<T> {
     /// Return whether to cancel the caret move
     fn adjust_selection_for_horizontal_change(&mut self, adjust: Direction, select: Selection)
                                               -> bool {
+        self.preferred_column = None;
         if select == Selection::Selected {
             if self.selection_origin.is_none() {
                 self.selection_origin = Some(self.edit_point);
@@ -499,44 +1375,352 @@ impl<T: ClipboardProvider> TextInput<T> {
                                             self.edit_point.index + adjust as usize);
             }
         }
-        self.assert_ok_selection();
+        // Crossing lines goes through adjust_vertical, which must not leave a goal column behind.
+        self.preferred_column = None;
+        self.validate_selection();
     }
 
     /// Deal with a newline input.
     pub fn handle_return(&mut self) -> KeyReaction {
         if !self.multiline {
+            KeyReaction::ImplicitSubmission
+        } else if self.enter_triggers_action {
             KeyReaction::TriggerDefaultAction
+        } else if self.read_only {
+            KeyReaction::Rejected
         } else {
-            self.insert_char('\n');
-            KeyReaction::DispatchInput
+            self.edit_reaction(Edit::InsertLineBreak)
         }
     }
 
-    /// Select all text in the input control.
-    pub fn select_all(&mut self) {
-        self.selection_origin = Some(TextPoint {
-            line: 0,
-            index: 0,
-        });
-        let last_line = self.lines.len() - 1;
-        self.edit_point.line = last_line;
-        self.edit_point.index = self.lines[last_line].len();
-        self.assert_ok_selection();
+    /// Carry out an edit, or leave it to the owner if edits are deferred.
+    fn edit_reaction(&mut self, edit: Edit) -> KeyReaction {
+        if self.defers_edits {
+            KeyReaction::BeforeInput(edit)
+        } else {
+            self.apply_edit(edit)
+        }
     }
 
-    /// Remove the current selection.
-    pub fn clear_selection(&mut self) {
-        self.selection_origin = None;
-        self.selection_direction = SelectionDirection::None;
+    /// Ask for an edit on behalf of the page, e.g. with `document.execCommand`, which is
+    /// undone on its own. Inserted text is filtered like typed text.
+    pub fn request_edit(&mut self, edit: Edit) -> KeyReaction {
+        if self.read_only {
+            return KeyReaction::Rejected;
+        }
+        self.composition = None;
+        self.awaiting_paste = false;
+        self.close_undo_group();
+        let edit = match edit {
+            Edit::InsertText(text) => Edit::InsertText(self.filter_requested_text(text)),
+            Edit::InsertReplacementText(text) => Edit::InsertReplacementText(self.filter_requested_text(text)),
+            Edit::InsertCorrection(correction) => Edit::InsertCorrection(WordCorrection {
+                correction: self.filter_requested_text(correction.correction),
+                ..correction
+            }),
+            edit => edit,
+        };
+        self.edit_reaction(edit)
     }
 
-    /// Remove the current selection and set the edit point to the end of the content.
-    pub fn clear_selection_to_limit(&mut self, direction: Direction, update_text_cursor: bool) {
-        self.clear_selection();
-        self.adjust_horizontal_to_limit(direction, Selection::NotSelected, update_text_cursor);
+    /// Filter text that an edit on behalf of the page inserts, like typed text.
+    fn filter_requested_text(&self, text: String) -> String {
+        // A single-line control can't hold line breaks, which typing never inserts.
+        let text = if self.multiline {
+            text
+        } else {
+            text.chars().filter(|&c| c != '\n' && c != '\r').collect()
+        };
+        self.filter_text(text)
     }
 
-    pub fn adjust_horizontal_by_word(&mut self, direction: Direction, select: Selection) {
+    /// Carry out an edit that the user asked for. It is rejected if the control became
+    /// read-only in the meantime.
+    pub fn apply_edit(&mut self, edit: Edit) -> KeyReaction {
+        if self.read_only {
+            return KeyReaction::Rejected;
+        }
+        let typing = edit.is_typing();
+        let reaction = match edit {
+            Edit::InsertText(text) |
+            Edit::InsertFromPaste(text) => self.insertion_reaction(|textinput| textinput.insert_string(text)),
+            Edit::InsertReplacementText(text) => {
+                self.select_all();
+                self.insertion_reaction(|textinput| textinput.replace_selection(DOMString::from(text)))
+            },
+            Edit::InsertCorrection(correction) => {
+                self.insertion_reaction(|textinput| textinput.correct_word(correction))
+            },
+            Edit::InsertTab => KeyReaction::DispatchInput(self.insert_char('\t')),
+            Edit::InsertLineBreak => self.insertion_reaction(|textinput| textinput.insert_char('\n')),
+            Edit::DeleteBackward => KeyReaction::DispatchInput(self.delete_char(Direction::Backward)),
+            Edit::DeleteForward => KeyReaction::DispatchInput(self.delete_char(Direction::Forward)),
+            Edit::DeleteToLineStart => KeyReaction::DispatchInput(self.delete_to_line_start()),
+            Edit::DeleteByCut => KeyReaction::DispatchInput(self.replace_selection(DOMString::new())),
+            Edit::HistoryUndo => KeyReaction::DispatchInput(self.undo()),
+            Edit::HistoryRedo => KeyReaction::DispatchInput(self.redo()),
+        };
+        if typing {
+            if let KeyReaction::DispatchInput(Some(_)) = reaction {
+                self.open_undo_group();
+            }
+        }
+        reaction
+    }
+
+    /// Make sure that the next change is undone on its own, rather than with the typing
+    /// before it.
+    pub fn close_undo_group(&mut self) {
+        if let Some(last) = self.undo_stack.last_mut() {
+            last.open = false;
+        }
+    }
+
+    /// Let further typing within the text inserted by the last change be undone with it.
+    fn open_undo_group(&mut self) {
+        if let Some(last) = self.undo_stack.last_mut() {
+            last.open = true;
+        }
+    }
+
+    /// Whether there is a change for `undo` to take back.
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    /// Whether there is a change for `redo` to make again.
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Take back the last change to the content, putting back the selection from before it.
+    pub fn undo(&mut self) -> Option<TextChange> {
+        if self.read_only {
+            return None
+        }
+        let entry = self.undo_stack.pop()?;
+        self.composition = None;
+        self.awaiting_paste = false;
+        self.select_offsets(entry.start..entry.start + entry.new_text.len());
+        let change = self.replace_selection_unrecorded(entry.old_text.clone());
+        if entry.selection_start == entry.selection_end {
+            self.set_edit_point_from_offset(entry.selection_start, Selection::NotSelected);
+        } else {
+            self.select_offsets(entry.selection_start..entry.selection_end);
+        }
+        self.push_redo(entry, &change);
+        change
+    }
+
+    /// Make the last change that `undo` took back again, putting the caret after it.
+    pub fn redo(&mut self) -> Option<TextChange> {
+        if self.read_only {
+            return None
+        }
+        let mut entry = self.redo_stack.pop()?;
+        self.composition = None;
+        self.awaiting_paste = false;
+        self.select_offsets(entry.start..entry.start + entry.old_text.len());
+        let change = self.replace_selection_unrecorded(entry.new_text.clone());
+        self.set_edit_point_from_offset(entry.start + entry.new_text.len(), Selection::NotSelected);
+        entry.open = false;
+        if change.as_ref().map_or(false, |change| change.new_text == entry.new_text) {
+            self.undo_stack.push(entry);
+        } else {
+            self.clear_undo_history();
+        }
+        change
+    }
+
+    /// Keep an entry that `undo` took back for `redo`, unless `max_length` stopped all of its
+    /// text from being put back, as the history would no longer match the content.
+    fn push_redo(&mut self, entry: UndoEntry, change: &Option<TextChange>) {
+        self.close_undo_group();
+        if change.as_ref().map_or(false, |change| change.new_text == entry.old_text) {
+            self.redo_stack.push(entry);
+        } else {
+            self.clear_undo_history();
+        }
+    }
+
+    /// Forget the changes that `undo` and `redo` could take back or make again.
+    pub fn clear_undo_history(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+    }
+
+    /// Add a change made to the selection at the given offsets to the undo history, merging
+    /// it into the last change if that is open and the change is within the text it inserted.
+    fn record_undo(&mut self, change: &TextChange, selection: Range<usize>) {
+        self.redo_stack.clear();
+        let mut merged = false;
+        if let Some(last) = self.undo_stack.last_mut() {
+            let inserted_end = last.start + last.new_text.len();
+            if last.open && change.range.start >= last.start && change.range.end <= inserted_end {
+                let mut new_text = String::from(&last.new_text[..change.range.start - last.start]);
+                new_text.push_str(&change.new_text);
+                new_text.push_str(&last.new_text[change.range.end - last.start..]);
+                last.new_text = DOMString::from(new_text);
+                merged = true;
+            }
+        }
+        if merged {
+            // Typing something and deleting it again leaves nothing to undo.
+            if self.undo_stack.last().map_or(false, |last| last.old_text.is_empty() && last.new_text.is_empty()) {
+                self.undo_stack.pop();
+            }
+            return;
+        }
+        self.close_undo_group();
+        self.undo_stack.push(UndoEntry {
+            start: change.range.start,
+            old_text: change.old_text.clone(),
+            new_text: change.new_text.clone(),
+            selection_start: selection.start,
+            selection_end: selection.end,
+            open: false,
+        });
+        if self.undo_stack.len() > MAX_UNDO_ENTRIES {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Perform an insertion, which is rejected if it changes nothing.
+    fn insertion_reaction<F>(&mut self, insert: F) -> KeyReaction
+        where F: FnOnce(&mut Self) -> Option<TextChange>
+    {
+        match insert(self) {
+            Some(change) => KeyReaction::DispatchInput(Some(change)),
+            None => KeyReaction::Rejected,
+        }
+    }
+
+    /// The reaction to a command that moved the caret.
+    fn caret_movement_reaction(&self, had_selection: bool) -> KeyReaction {
+        if had_selection && !self.has_selection() {
+            KeyReaction::SelectionCleared
+        } else {
+            KeyReaction::ScrollCaretIntoView
+        }
+    }
+
+    /// Select all text in the input control.
+    pub fn select_all(&mut self) {
+        self.selection_origin = Some(TextPoint {
+            line: 0,
+            index: 0,
+        });
+        let last_line = self.lines.len() - 1;
+        self.edit_point.line = last_line;
+        self.edit_point.index = self.lines[last_line].len();
+        self.preferred_column = None;
+        self.set_selection_granularity(SelectionGranularity::Character);
+        self.validate_selection();
+    }
+
+    /// Select the word around the given point, as determined by the word boundary rules for the
+    /// control's language and the word selection policy. If the point is between two words, the
+    /// word following it is selected.
+    pub fn select_word_at(&mut self, point: TextPoint) {
+        let point = self.clamp_text_point(point);
+        // The end of a line belongs to the last word on it.
+        let (start, end) = self.word_range_at(point);
+        self.selection_origin = Some(TextPoint { line: point.line, index: start });
+        self.edit_point = TextPoint { line: point.line, index: end };
+        self.selection_direction = SelectionDirection::Forward;
+        self.preferred_column = None;
+        self.validate_selection();
+        self.set_selection_granularity(SelectionGranularity::Word);
+    }
+
+    /// The byte range in its line of the word that `select_word_at` selects around `point`.
+    fn word_range_at(&self, point: TextPoint) -> (usize, usize) {
+        word_selection_range(&self.lines[point.line], point.index, self.word_rules(), self.word_selection_policy)
+    }
+
+    /// The unit of the given granularity around a point.
+    fn unit_at(&self, point: TextPoint, granularity: SelectionGranularity) -> (TextPoint, TextPoint) {
+        match granularity {
+            SelectionGranularity::Character => (point, point),
+            SelectionGranularity::Word => {
+                let (start, end) = self.word_range_at(point);
+                (TextPoint { line: point.line, index: start }, TextPoint { line: point.line, index: end })
+            },
+            SelectionGranularity::Line => {
+                (TextPoint { line: point.line, index: 0 },
+                 TextPoint { line: point.line, index: self.lines[point.line].len() })
+            },
+        }
+    }
+
+    /// Set the unit in which `extend_selection_to_point` extends the selection from now on,
+    /// keeping the current selection as the unit selected first.
+    pub fn set_selection_granularity(&mut self, granularity: SelectionGranularity) {
+        self.selection_granularity = granularity;
+        self.granularity_anchor = match granularity {
+            SelectionGranularity::Character => None,
+            _ => Some(self.sorted_selection_bounds()),
+        };
+    }
+
+    /// Select the whole line containing the given point, not including its line break.
+    pub fn select_line_at(&mut self, point: TextPoint) {
+        let line = self.clamp_text_point(point).line;
+        self.select_lines(line, line);
+    }
+
+    /// Select the paragraph containing the given point, i.e. the run of non-empty lines around
+    /// it. In a single line control, this is the same as `select_line_at`.
+    pub fn select_paragraph_at(&mut self, point: TextPoint) {
+        let line = self.clamp_text_point(point).line;
+        if self.lines[line].is_empty() {
+            self.select_lines(line, line);
+            return
+        }
+        let first = self.lines.iter_range(0..line)
+            .rposition(|l| l.is_empty())
+            .map_or(0, |empty| empty + 1);
+        let last = self.lines.iter_range(line..self.lines.len())
+            .position(|l| l.is_empty())
+            .map_or(self.lines.len() - 1, |empty| line + empty - 1);
+        self.select_lines(first, last);
+    }
+
+    /// Select from the start of `first` to the end of `last`.
+    fn select_lines(&mut self, first: usize, last: usize) {
+        self.selection_origin = Some(TextPoint { line: first, index: 0 });
+        self.edit_point = TextPoint { line: last, index: self.lines[last].len() };
+        self.selection_direction = SelectionDirection::Forward;
+        self.preferred_column = None;
+        self.validate_selection();
+        self.set_selection_granularity(SelectionGranularity::Line);
+    }
+
+    /// Restrict a point to the content, moving it back to a character boundary if it falls
+    /// within a character.
+    fn clamp_text_point(&self, point: TextPoint) -> TextPoint {
+        let line = min(point.line, self.lines.len() - 1);
+        let mut index = min(point.index, self.lines[line].len());
+        while !self.lines[line].is_char_boundary(index) {
+            index -= 1;
+        }
+        TextPoint { line: line, index: index }
+    }
+
+    /// Remove the current selection.
+    pub fn clear_selection(&mut self) {
+        self.selection_origin = None;
+        self.selection_direction = SelectionDirection::None;
+        self.set_selection_granularity(SelectionGranularity::Character);
+    }
+
+    /// Remove the current selection and set the edit point to the end of the content.
+    pub fn clear_selection_to_limit(&mut self, direction: Direction, update_text_cursor: bool) {
+        self.clear_selection();
+        self.adjust_horizontal_to_limit(direction, Selection::NotSelected, update_text_cursor);
+    }
+
+    pub fn adjust_horizontal_by_word(&mut self, direction: Direction, select: Selection) {
         if self.adjust_selection_for_horizontal_change(direction, select) {
             return
         }
@@ -557,14 +1741,14 @@ impl<T: ClipboardProvider> TextInput<T> {
                             [..remaining];
                     }
 
-                    let mut iter = input.split_word_bounds().rev();
+                    let mut iter = word_bound_indices(input, self.word_rules()).into_iter().rev();
                     let mut shift_temp: isize = 0;
                     loop {
                         match iter.next() {
                             None => break,
-                            Some(x) => {
+                            Some((_, x)) => {
                                 shift_temp += - (x.len() as isize);
-                                if x.chars().any(|x| x.is_alphabetic() || x.is_numeric()) {
+                                if is_word(x) {
                                     break;
                                 }
                             }
@@ -586,14 +1770,14 @@ impl<T: ClipboardProvider> TextInput<T> {
                             [self.edit_point.index..];
                     }
 
-                    let mut iter = input.split_word_bounds();
+                    let mut iter = word_bound_indices(input, self.word_rules()).into_iter();
                     let mut shift_temp: isize = 0;
                     loop {
                         match iter.next() {
                             None => break,
-                            Some(x) => {
+                            Some((_, x)) => {
                                 shift_temp += x.len() as isize;
-                                if x.chars().any(|x| x.is_alphabetic() || x.is_numeric()) {
+                                if is_word(x) {
                                     break;
                                 }
                             }
@@ -656,136 +1840,353 @@ impl<T: ClipboardProvider> TextInput<T> {
                               printable: Option<char>,
                               key: Key,
                               mods: KeyModifiers) -> KeyReaction {
+        if let Some(reaction) = self.key_hook.and_then(|hook| hook(key, mods)) {
+            return reaction;
+        }
+
         let maybe_select = if mods.contains(KeyModifiers::SHIFT) {
                 Selection::Selected
             } else {
                 Selection::NotSelected
         };
 
-        match (printable, key) {
-            (_, Key::B) if mods.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
-                self.adjust_horizontal_by_word(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
-            },
-            (_, Key::F) if mods.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
-                self.adjust_horizontal_by_word(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
-            },
-            (_, Key::A) if mods.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
-                self.adjust_horizontal_to_line_end(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
-            },
-            (_, Key::E) if mods.contains(KeyModifiers::CONTROL | KeyModifiers::ALT) => {
-                self.adjust_horizontal_to_line_end(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
-            },
-            #[cfg(target_os = "macos")]
-            (None, Key::A) if mods == KeyModifiers::CONTROL => {
-                self.adjust_horizontal_to_line_end(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
-            },
-            #[cfg(target_os = "macos")]
-            (None, Key::E) if mods == KeyModifiers::CONTROL => {
-                self.adjust_horizontal_to_line_end(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
+        match key {
+            // The embedder sends the accent of a dead key as its printable character.
+            Key::Dead => return match printable {
+                Some(_) if self.read_only => KeyReaction::Rejected,
+                Some(accent) => {
+                    let text = self.compose.press_dead_key(accent);
+                    self.insert_typed_text(text)
+                },
+                None => KeyReaction::Nothing,
             },
-            (_, Key::A) if is_control_key(mods) => {
-                self.select_all();
-                KeyReaction::RedrawSelection
+            Key::Compose => {
+                self.compose.press_compose();
+                return KeyReaction::Nothing
             },
-            (_, Key::C) if is_control_key(mods) => {
-                if let Some(text) = self.get_selection_text() {
-                    self.clipboard_provider.set_clipboard_contents(text);
+            _ => (),
+        }
+
+        match (printable, self.keybindings.lookup_event(printable, key, mods)) {
+            (_, Some(command)) => {
+                if command != EditCommand::Cancel {
+                    self.compose.cancel();
                 }
-                KeyReaction::DispatchInput
+                self.execute_command(command, maybe_select)
             },
-            (_, Key::V) if is_control_key(mods) => {
-                let contents = self.clipboard_provider.clipboard_contents();
-                self.insert_string(contents);
-                KeyReaction::DispatchInput
+            (Some(_), None) if self.read_only => KeyReaction::Rejected,
+            (Some(c), None) => {
+                let text = self.compose.press_char(c);
+                self.insert_typed_text(text)
             },
-            (Some(c), _) => {
-                self.insert_char(c);
-                KeyReaction::DispatchInput
+            (None, None) => KeyReaction::Nothing,
+        }
+    }
+
+    /// Insert text typed by the user, which may be nothing while a composition is unfinished.
+    fn insert_typed_text(&mut self, text: Option<String>) -> KeyReaction {
+        let text = match text {
+            Some(text) => self.filter_text(text),
+            None => return KeyReaction::Nothing,
+        };
+        if text.is_empty() {
+            return KeyReaction::Rejected
+        }
+        self.edit_reaction(Edit::InsertText(text))
+    }
+
+    /// Perform an editing command, extending the selection if `select` is `Selection::Selected`.
+    pub fn execute_command(&mut self, command: EditCommand, select: Selection) -> KeyReaction {
+        // The selection fields are public, so make sure nobody left them out of bounds.
+        self.validate_selection();
+        // Keyboard selection goes a character at a time, whatever the mouse selected before.
+        self.set_selection_granularity(SelectionGranularity::Character);
+        // Escape gives up the composition; other editing commands end it, leaving the text
+        // composed so far in place.
+        if command == EditCommand::Cancel && self.composition.is_some() {
+            return KeyReaction::CompositionCancelled(self.cancel_composition());
+        }
+        self.composition = None;
+        // A paste that is still waiting for the clipboard is overtaken by any later command.
+        self.awaiting_paste = false;
+        let had_selection = self.selection_start() != self.selection_end();
+        match command {
+            EditCommand::Cut |
+            EditCommand::Paste |
+            EditCommand::DeleteBackward |
+            EditCommand::DeleteForward |
+            EditCommand::DeleteToLineStart if self.read_only => KeyReaction::Rejected,
+            EditCommand::MoveCharBackward => {
+                self.adjust_horizontal_by_one(Direction::Backward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Delete) => {
-                self.delete_char(Direction::Forward);
-                KeyReaction::DispatchInput
+            EditCommand::MoveCharForward => {
+                self.adjust_horizontal_by_one(Direction::Forward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Backspace) => {
-                self.delete_char(Direction::Backward);
-                KeyReaction::DispatchInput
+            EditCommand::MoveWordBackward => {
+                self.adjust_horizontal_by_word(Direction::Backward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            #[cfg(target_os = "macos")]
-            (None, Key::Left) if mods.contains(KeyModifiers::SUPER) => {
-                self.adjust_horizontal_to_line_end(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveWordForward => {
+                self.adjust_horizontal_by_word(Direction::Forward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            #[cfg(target_os = "macos")]
-            (None, Key::Right) if mods.contains(KeyModifiers::SUPER) => {
-                self.adjust_horizontal_to_line_end(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveLineStart => {
+                self.adjust_horizontal_to_line_end(Direction::Backward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            #[cfg(target_os = "macos")]
-            (None, Key::Up) if mods.contains(KeyModifiers::SUPER) => {
-                self.adjust_horizontal_to_limit(Direction::Backward, maybe_select, true);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveLineEnd => {
+                self.adjust_horizontal_to_line_end(Direction::Forward, select);
+                self.caret_movement_reaction(had_selection)
             },
-            #[cfg(target_os = "macos")]
-            (None, Key::Down) if mods.contains(KeyModifiers::SUPER) => {
-                self.adjust_horizontal_to_limit(Direction::Forward, maybe_select, true);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveUp => {
+                self.adjust_vertical(-1, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Left) if mods.contains(KeyModifiers::ALT) => {
-                self.adjust_horizontal_by_word(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveDown => {
+                self.adjust_vertical(1, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Right) if mods.contains(KeyModifiers::ALT) => {
-                self.adjust_horizontal_by_word(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MovePageUp => {
+                let rows = self.page_rows();
+                self.adjust_vertical(-rows, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Left) => {
-                self.adjust_horizontal_by_one(Direction::Backward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MovePageDown => {
+                let rows = self.page_rows();
+                self.adjust_vertical(rows, select);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Right) => {
-                self.adjust_horizontal_by_one(Direction::Forward, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveDocumentStart => {
+                self.adjust_horizontal_to_limit(Direction::Backward, select, true);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Up) => {
-                self.adjust_vertical(-1, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::MoveDocumentEnd => {
+                self.adjust_horizontal_to_limit(Direction::Forward, select, true);
+                self.caret_movement_reaction(had_selection)
             },
-            (None, Key::Down) => {
-                self.adjust_vertical(1, maybe_select);
+            EditCommand::SelectAll => {
+                self.select_all();
                 KeyReaction::RedrawSelection
             },
-            (None, Key::Enter) | (None, Key::KpEnter) => self.handle_return(),
-            (None, Key::Home) => {
-                #[cfg(not(target_os = "macos"))]
-                {
-                    self.edit_point.index = 0;
-                }
-                KeyReaction::RedrawSelection
+            EditCommand::Copy => KeyReaction::CopyRequested,
+            EditCommand::Cut => KeyReaction::CutRequested,
+            EditCommand::Paste => {
+                self.awaiting_paste = true;
+                KeyReaction::PasteRequested
             },
-            (None, Key::End) => {
-                #[cfg(not(target_os = "macos"))]
-                {
-                    self.edit_point.index = self.current_line_length();
-                    self.assert_ok_selection();
+            EditCommand::DeleteBackward => self.edit_reaction(Edit::DeleteBackward),
+            EditCommand::DeleteForward => self.edit_reaction(Edit::DeleteForward),
+            EditCommand::DeleteToLineStart => self.edit_reaction(Edit::DeleteToLineStart),
+            EditCommand::InsertLineBreak => self.handle_return(),
+            EditCommand::InsertTab => {
+                if self.inserts_tabs && self.multiline && !self.read_only && select == Selection::NotSelected {
+                    self.edit_reaction(Edit::InsertTab)
+                } else if select == Selection::Selected {
+                    // Shift+Tab moves the focus backwards.
+                    KeyReaction::FocusNavigation(Direction::Backward)
+                } else {
+                    KeyReaction::FocusNavigation(Direction::Forward)
                 }
-                KeyReaction::RedrawSelection
             },
-            (None, Key::PageUp) => {
-                self.adjust_vertical(-28, maybe_select);
-                KeyReaction::RedrawSelection
+            EditCommand::Cancel => KeyReaction::Escape(self.escape()),
+        }
+    }
+
+    /// Undo the first of: an unfinished composition, a selection, or the content of a control
+    /// that clears on Escape.
+    pub fn escape(&mut self) -> EscapeOutcome {
+        if self.compose.is_pending() {
+            self.compose.cancel();
+            return EscapeOutcome::CompositionCancelled;
+        }
+        if self.selection_start() != self.selection_end() {
+            self.clear_selection();
+            return EscapeOutcome::SelectionCleared;
+        }
+        if self.clears_on_escape && !self.read_only {
+            self.select_all();
+            if let Some(change) = self.replace_selection(DOMString::new()) {
+                return EscapeOutcome::ContentCleared(change);
+            }
+        }
+        self.clear_selection();
+        EscapeOutcome::Nothing
+    }
+
+    /// The text an input method is composing, if any.
+    pub fn composition(&self) -> Option<&Composition> {
+        self.composition.as_ref()
+    }
+
+    /// Whether an input method is composing text.
+    pub fn is_composing(&self) -> bool {
+        self.composition.is_some()
+    }
+
+    /// Show the text an input method is composing, with the caret at the given byte offset in
+    /// it. The first call replaces the selection, and later ones replace the text composed so
+    /// far, until the composition is committed or cancelled. Clauses that are out of the text
+    /// or not on character boundaries are ignored.
+    pub fn set_composition(&mut self, text: DOMString, clauses: Vec<CompositionClause>, caret: usize)
+                           -> Option<TextChange> {
+        if self.read_only {
+            return None
+        }
+        self.compose.cancel();
+        let (replaced_text, replaced_direction) = match self.composition.take() {
+            Some(composition) => {
+                self.select_offsets(composition.range());
+                (composition.replaced_text, composition.replaced_direction)
             },
-            (None, Key::PageDown) => {
-                self.adjust_vertical(28, maybe_select);
-                KeyReaction::RedrawSelection
+            None => {
+                let selected = self.fold_selection_slices(String::new(), |s, slice| s.push_str(slice));
+                (DOMString::from(selected), self.selection_direction)
+            },
+        };
+
+        let start = self.selection_start_offset();
+        if self.selection_origin.is_none() {
+            self.selection_origin = Some(self.edit_point);
+        }
+        let change = self.replace_selection(text);
+
+        // The text may have been cut short to fit `max_length`.
+        let end = self.text_point_to_offset(&self.edit_point);
+        let text = DOMString::from(self.slice_offsets(start..end));
+        let clauses = clauses.into_iter().filter(|clause| {
+            clause.start < clause.end && clause.end <= text.len() &&
+                text.is_char_boundary(clause.start) && text.is_char_boundary(clause.end)
+        }).collect();
+        let mut caret = min(caret, text.len());
+        while !text.is_char_boundary(caret) {
+            caret -= 1;
+        }
+        self.set_edit_point_from_offset(start + caret, Selection::NotSelected);
+        self.composition = Some(Composition {
+            start: start,
+            text: text,
+            clauses: clauses,
+            replaced_text: replaced_text,
+            replaced_direction: replaced_direction,
+        });
+        // The text is undone at once, however many updates it took to compose.
+        if change.is_some() {
+            self.open_undo_group();
+        }
+        change
+    }
+
+    /// End the composition, replacing the composed text with `text`, or keeping it if `text` is
+    /// `None`, and put the caret after it. The committed text is filtered like typed text.
+    pub fn commit_composition(&mut self, text: Option<DOMString>) -> Option<TextChange> {
+        let composition = self.composition.take()?;
+        let text = match text {
+            Some(text) => String::from(text),
+            None => String::from(composition.text.clone()),
+        };
+        let text = self.filter_text(text);
+        self.select_offsets(composition.range());
+        if *text == *composition.text {
+            self.set_edit_point_from_offset(composition.range().end, Selection::NotSelected);
+            return None
+        }
+        self.replace_selection(DOMString::from(text))
+    }
+
+    /// End the composition, removing the composed text and restoring the selection it
+    /// replaced.
+    pub fn cancel_composition(&mut self) -> Option<TextChange> {
+        let composition = self.composition.take()?;
+        self.select_offsets(composition.range());
+        let change = self.replace_selection(composition.replaced_text.clone());
+        let restored = composition.start..composition.start + composition.replaced_text.len();
+        self.set_selection_range(restored.start as u32, restored.end as u32, composition.replaced_direction);
+        change
+    }
+
+    /// End the composition, leaving the composed text in the content as it is, e.g. because the
+    /// owner is about to replace the content.
+    pub fn abandon_composition(&mut self) -> Option<Composition> {
+        self.composition.take()
+    }
+
+    /// The text around the selection, with up to `before` characters before it and `after`
+    /// characters after it, for input methods. The text being composed is left out, as the input
+    /// method already knows it, and an obscured control has no text to give.
+    pub fn surrounding_text(&self, before: usize, after: usize) -> Option<ImeSurroundingText> {
+        if self.obscured {
+            return None
+        }
+        let mut content = String::from(self.get_content());
+        let origin = self.text_point_to_offset(&self.selection_origin_or_edit_point());
+        let (cursor, anchor) = match self.composition {
+            Some(ref composition) => {
+                content.drain(composition.range());
+                (composition.start, composition.start)
             },
-            _ => KeyReaction::Nothing,
+            None => (self.text_point_to_offset(&self.edit_point), origin),
+        };
+
+        let (selection_start, selection_end) = (min(cursor, anchor), max(cursor, anchor));
+        let start = content[..selection_start].char_indices().rev().take(before).last()
+                                              .map_or(selection_start, |(index, _)| index);
+        let end = content[selection_end..].char_indices().nth(after)
+                                          .map_or(content.len(), |(index, _)| selection_end + index);
+        Some(ImeSurroundingText {
+            text: content[start..end].to_owned(),
+            cursor: cursor - start,
+            anchor: anchor - start,
+        })
+    }
+
+    /// Delete up to `before` characters before the selection and `after` characters after it, for
+    /// input methods, and return the change as a single edit. The selection, and any text being
+    /// composed, are kept; the characters are counted around the composition, as in
+    /// `surrounding_text`.
+    pub fn delete_surrounding_text(&mut self, before: usize, after: usize) -> Option<TextChange> {
+        if self.read_only {
+            return None
         }
+        let (start, end) = match self.composition {
+            Some(ref composition) => (composition.range().start, composition.range().end),
+            None => (self.selection_start_offset(), self.selection_end_offset()),
+        };
+        let (delete_start, delete_end, kept) = {
+            let content = self.get_content();
+            let delete_start = content[..start].char_indices().rev().take(before).last()
+                                               .map_or(start, |(index, _)| index);
+            let delete_end = content[end..].char_indices().nth(after)
+                                           .map_or(content.len(), |(index, _)| end + index);
+            (delete_start, delete_end, DOMString::from(&content[start..end]))
+        };
+        if delete_start == start && delete_end == end {
+            return None
+        }
+
+        let (selection_start, selection_end) = (self.selection_start_offset(), self.selection_end_offset());
+        let direction = self.selection_direction;
+        self.select_offsets(delete_start..delete_end);
+        // Deleting text can only shorten the content, so `max_length` has nothing to say.
+        let change = self.replace_selection_ignoring_max_length(kept);
+
+        let shift = start - delete_start;
+        if let Some(ref mut composition) = self.composition {
+            composition.start -= shift;
+        }
+        self.set_selection_range((selection_start - shift) as u32, (selection_end - shift) as u32, direction);
+        change
+    }
+
+    /// Select the content in the given UTF-8 byte range.
+    fn select_offsets(&mut self, range: Range<usize>) {
+        self.set_selection_range(range.start as u32, range.end as u32, SelectionDirection::Forward);
+    }
+
+    /// The content in the given UTF-8 byte range.
+    fn slice_offsets(&self, range: Range<usize>) -> String {
+        self.get_content()[range].to_owned()
     }
 
     /// Whether the content is empty.
@@ -793,20 +2194,59 @@ impl<T: ClipboardProvider> TextInput<T> {
         self.lines.len() <= 1 && self.lines.get(0).map_or(true, |line| line.is_empty())
     }
 
+    /// The direction of the first character of the content that has a strong direction, if any.
+    pub fn first_strong_direction(&self) -> Option<TextDirection> {
+        self.lines().filter_map(first_strong_direction).next()
+    }
+
+    /// Whether the placeholder of the control is shown in place of its content: there is no
+    /// content, and no input method has started composing any, even if it has composed nothing
+    /// yet.
+    pub fn shows_placeholder(&self) -> bool {
+        self.is_empty() && !self.is_composing()
+    }
+
     /// The length of the content in bytes.
     pub fn len(&self) -> usize {
-        self.lines.iter().fold(0, |m, l| {
-            m + l.len() + 1 // + 1 for the '\n'
-        }) - 1
+        let last_line = self.lines.len() - 1;
+        self.line_offsets()[last_line] + self.lines[last_line].len()
     }
 
-    /// The length of the content in bytes.
+    /// The length of the content in UTF-16 code units, the unit of offsets in the DOM.
     pub fn utf16_len(&self) -> usize {
-        self.lines.iter().fold(0, |m, l| {
+        if let Some(len) = self.cache.utf16_len.get() {
+            return len;
+        }
+        let len = self.lines.iter().fold(0, |m, l| {
             m + l.chars().map(char::len_utf16).sum::<usize>() + 1 // + 1 for the '\n'
+        }) - 1;
+        self.cache.utf16_len.set(Some(len));
+        len
+    }
+
+    /// The length of the content in `length_unit`, the unit `max_length` and `min_length` are
+    /// compared with.
+    pub fn length(&self) -> usize {
+        let unit = self.length_unit;
+        self.lines.iter().fold(0, |m, l| {
+            m + unit.length_of(l) + 1 // + 1 for the '\n'
         }) - 1
     }
 
+    /// Whether the content is longer than `max_length`.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#suffering-from-being-too-long
+    pub fn is_too_long(&self) -> bool {
+        self.max_length.map_or(false, |max_length| self.length() > max_length)
+    }
+
+    /// Whether the content is shorter than `min_length`, without being empty.
+    ///
+    /// https://html.spec.whatwg.org/multipage/#suffering-from-being-too-short
+    pub fn is_too_short(&self) -> bool {
+        self.min_length.map_or(false, |min_length| !self.is_empty() && self.length() < min_length)
+    }
+
     /// The length of the content in chars.
     pub fn char_count(&self) -> usize {
         self.lines.iter().fold(0, |m, l| {
@@ -814,18 +2254,105 @@ impl<T: ClipboardProvider> TextInput<T> {
         }) - 1
     }
 
+    /// The number of lines in the content. This is always at least one.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The line at the given index, without its trailing '\n'.
+    pub fn line(&self, index: usize) -> Option<&str> {
+        self.lines.get(index).map(|line| &**line)
+    }
+
+    /// Iterate over the lines of the content without joining them, each without its trailing '\n'.
+    pub fn lines<'a>(&'a self) -> impl Iterator<Item=&'a str> + 'a {
+        self.lines.iter().map(|line| &**line)
+    }
+
     /// Get the current contents of the text input. Multiple lines are joined by \n.
     pub fn get_content(&self) -> DOMString {
-        let mut content = "".to_owned();
-        for (i, line) in self.lines.iter().enumerate() {
-            content.push_str(&line);
-            if i < self.lines.len() - 1 {
-                content.push('\n');
+        if self.cache.content.borrow().is_none() {
+            let mut content = "".to_owned();
+            for (i, line) in self.lines.iter().enumerate() {
+                content.push_str(&line);
+                if i < self.lines.len() - 1 {
+                    content.push('\n');
+                }
+            }
+            *self.cache.content.borrow_mut() = Some(DOMString::from(content));
+        }
+        self.cache.content.borrow().clone().unwrap()
+    }
+
+    /// Get the current contents of the text input with lines joined by CRLF, as in the value of
+    /// a textarea used for form submission.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#textarea-wrapping-transformation>
+    pub fn get_content_with_crlf(&self) -> DOMString {
+        DOMString::from(self.lines().collect::<Vec<_>>().join("\r\n"))
+    }
+
+    /// Like `get_content_with_crlf`, but also breaking lines with a CRLF at each of the given
+    /// points, as the wrap=hard transformation of a textarea requires. Points at the start or
+    /// end of a line, or inside a character, are ignored.
+    pub fn get_content_with_hard_wraps(&self, wrap_points: &[TextPoint]) -> DOMString {
+        let mut wrap_points = wrap_points.to_vec();
+        wrap_points.sort_by_key(|point| (point.line, point.index));
+
+        let mut content = String::new();
+        for (i, line) in self.lines().enumerate() {
+            if i > 0 {
+                content.push_str("\r\n");
+            }
+            let mut start = 0;
+            for index in wrap_points.iter().filter(|point| point.line == i).map(|point| point.index) {
+                if index > start && index < line.len() && line.is_char_boundary(index) {
+                    content.push_str(&line[start..index]);
+                    content.push_str("\r\n");
+                    start = index;
+                }
             }
+            content.push_str(&line[start..]);
         }
         DOMString::from(content)
     }
 
+    /// The points where the lines of the content wrap when laid out `cols` columns wide, as a
+    /// textarea's are: after the spaces following the last word that fits in a row, or inside a
+    /// word too wide for a row of its own. Spaces at the end of a row hang past its edge rather
+    /// than wrap.
+    pub fn soft_wrap_points(&self, cols: usize) -> Vec<TextPoint> {
+        let cols = max(cols, 1);
+        let mut wrap_points = vec![];
+        for (line, text) in self.lines().enumerate() {
+            let mut row_start = 0;
+            let mut column = 0;
+            // The start of the last word in the row after the first, and the column it is at.
+            let mut last_break = None;
+            let mut after_space = false;
+            for (index, grapheme) in text.grapheme_indices(true) {
+                let width = grapheme.width();
+                if grapheme.chars().all(char::is_whitespace) {
+                    after_space = true;
+                    column += width;
+                    continue;
+                }
+                if after_space && index > row_start {
+                    last_break = Some((index, column));
+                }
+                after_space = false;
+                if column + width > cols && index > row_start {
+                    let (index, break_column) = last_break.take().unwrap_or((index, column));
+                    wrap_points.push(TextPoint { line: line, index: index });
+                    row_start = index;
+                    column -= break_column;
+                }
+                column += width;
+            }
+        }
+        wrap_points
+    }
+
     /// Get a reference to the contents of a single-line text input. Panics if self is a multiline input.
     pub fn single_line_content(&self) -> &DOMString {
         assert!(!self.multiline);
@@ -835,13 +2362,18 @@ impl<T: ClipboardProvider> TextInput<T> {
     /// Get a mutable reference to the contents of a single-line text input. Panics if self is a multiline input.
     pub fn single_line_content_mut(&mut self) -> &mut DOMString {
         assert!(!self.multiline);
+        self.invalidate_cache();
+        self.respell_content();
         &mut self.lines[0]
     }
 
     /// Set the current contents of the text input. If this is control supports multiple lines,
     /// any \n encountered will be stripped and force a new logical line.
+    ///
+    /// Setting the content the control already holds keeps the caret and the selection, so that
+    /// pages which assign the value on every input event do not disturb the user.
     pub fn set_content(&mut self, content: DOMString, update_text_cursor: bool) {
-        self.lines = if self.multiline {
+        let lines: Vec<DOMString> = if self.multiline {
             // https://html.spec.whatwg.org/multipage/#textarea-line-break-normalisation-transformation
             content.replace("\r\n", "\n")
                    .split(|c| c == '\n' || c == '\r')
@@ -850,53 +2382,289 @@ impl<T: ClipboardProvider> TextInput<T> {
         } else {
             vec!(content)
         };
+        if lines.len() == self.lines.len() && lines.iter().eq(self.lines.iter()) {
+            return;
+        }
+
+        // The composed text was part of the old content, which a waiting paste was meant for,
+        // and the undo history describes.
+        self.composition = None;
+        self.awaiting_paste = false;
+        self.clear_undo_history();
+
+        self.invalidate_cache();
+        self.lines = B::from_lines(lines);
+        self.respell_content();
         if update_text_cursor {
             self.edit_point.line = min(self.edit_point.line, self.lines.len() - 1);
             self.edit_point.index = min(self.edit_point.index, self.current_line_length());
         }
         self.selection_origin = None;
-        self.assert_ok_selection();
+        self.preferred_column = None;
+        self.validate_selection();
     }
 
-    /// Convert a TextPoint into a byte offset from the start of the content.
-    fn text_point_to_offset(&self, text_point: &TextPoint) -> usize {
-        self.lines.iter().enumerate().fold(0, |acc, (i, val)| {
-            if i < text_point.line {
-                acc + val.len() + 1 // +1 for the \n
-            } else {
-                acc
+    /// Put back the default content, as a form reset does. Unlike `set_content`, the caret goes
+    /// to the end with nothing selected, and the undo history is cleared, even if the content is
+    /// unchanged, as the control starts over.
+    pub fn reset_content(&mut self, content: DOMString) {
+        self.set_content(content, true);
+        self.composition = None;
+        self.awaiting_paste = false;
+        self.clear_undo_history();
+        self.clear_selection_to_limit(Direction::Forward, true);
+    }
+
+    /// Set the content, as `set_content` does, cut short to fit `max_length` as typed text is,
+    /// for a value filled in on the user's behalf.
+    pub fn set_content_within_max_length(&mut self, content: DOMString) {
+        let content = match self.max_length {
+            Some(max_length) => {
+                let end = len_of_first_n_units(&content, max_length, self.length_unit);
+                DOMString::from(&content[..end])
+            },
+            None => content,
+        };
+        self.set_content(content, true);
+    }
+
+    /// Take the words in the text edited since the last check, for the spell checker to check,
+    /// or None if there are none. The word at the caret is left unchecked while it may still be
+    /// being typed, unless `include_word_at_caret` is set, e.g. when the owner loses focus.
+    pub fn spelling_request(&mut self, include_word_at_caret: bool) -> Option<SpellingRequest> {
+        let unchecked = self.spelling.unchecked.take()?;
+        let content = self.get_content();
+        let unchecked = min(unchecked.start, content.len())..min(unchecked.end, content.len());
+        // No word goes past the end of a line, so the lines around the edited text hold every
+        // word the edits may have changed.
+        let start = content[..unchecked.start].rfind('\n').map_or(0, |index| index + 1);
+        let end = content[unchecked.end..].find('\n').map_or(content.len(), |index| unchecked.end + index);
+
+        let selection = self.sorted_selection_offsets_range();
+        let caret = if selection.start == selection.end { Some(selection.start) } else { None };
+        let mut words = vec!();
+        let mut word_at_caret = None;
+        for (offset, segment) in word_bound_indices(&content[start..end], self.word_rules()) {
+            let word = start + offset..start + offset + segment.len();
+            if !is_word(segment) || word.end < unchecked.start || word.start > unchecked.end {
+                continue;
             }
-        }) + text_point.index
-    }
-
-    /// Convert a byte offset from the start of the content into a TextPoint.
-    fn offset_to_text_point(&self, abs_point: usize) -> TextPoint {
-        let mut index = abs_point;
-        let mut line = 0;
-        let last_line_idx = self.lines.len() - 1;
-        self.lines.iter().enumerate().fold(0, |acc, (i, val)| {
-            if i != last_line_idx {
-                let line_end = val.len();
-                let new_acc = acc + line_end + 1;
-                if abs_point >= new_acc && index > line_end {
-                    index -= line_end + 1;
-                    line += 1;
-                }
-                new_acc
+            if !include_word_at_caret && caret.map_or(false, |caret| word.start < caret && caret <= word.end) {
+                word_at_caret = Some(word);
+                continue;
+            }
+            words.push(word);
+        }
+        self.spelling.unchecked = word_at_caret;
+        if words.is_empty() {
+            return None;
+        }
+        let texts = words.iter().map(|word| content[word.clone()].to_owned()).collect();
+        Some(SpellingRequest {
+            content: content,
+            words: words,
+            texts: texts,
+        })
+    }
+
+    /// Update the misspelled words with the spell checker's answer to `request`, a flag for each
+    /// of its words. If the content changed while the request was out, the words may no longer
+    /// be where they were, so the answer is dropped and all of the content is marked to be
+    /// checked again instead. Returns whether the answer was applied.
+    pub fn receive_misspellings(&mut self, request: SpellingRequest, misspelled: Vec<bool>) -> bool {
+        if self.get_content() != request.content {
+            self.spelling.unchecked = Some(0..usize::MAX);
+            return false;
+        }
+        let words = request.words;
+        // A spell checker that can't tell leaves every word as it is.
+        let misspelled = if misspelled.len() == words.len() { misspelled } else { vec![false; words.len()] };
+        self.spelling.misspellings.retain(|misspelling| {
+            !words.iter().any(|word| word.start < misspelling.end && misspelling.start < word.end)
+        });
+        self.spelling.misspellings.extend(
+            words.into_iter().zip(misspelled).filter(|&(_, misspelled)| misspelled).map(|(word, _)| word));
+        self.spelling.misspellings.sort_by_key(|misspelling| misspelling.start);
+        true
+    }
+
+    /// The word that the given change finished, if it typed a space or punctuation right after
+    /// one, as the word's byte range and text. It is the word that autocorrection offers to
+    /// correct.
+    pub fn word_finished_by(&self, change: &TextChange) -> Option<(Range<usize>, String)> {
+        let mut chars = change.new_text.chars();
+        let finishes_word = match (chars.next(), chars.next()) {
+            (Some(c), None) => is_word_terminator(c),
+            _ => false,
+        };
+        if !finishes_word || !change.old_text.is_empty() || self.obscured {
+            return None;
+        }
+
+        // The text before the change is as it was, and no word goes past the start of a line.
+        let content = self.get_content();
+        let end = change.range.start;
+        let line_start = content[..end].rfind('\n').map_or(0, |index| index + 1);
+        let segments = word_bound_indices(&content[line_start..end], self.word_rules());
+        match segments.last() {
+            Some(&(offset, segment)) if is_word(segment) => {
+                Some((line_start + offset..end, segment.to_owned()))
+            },
+            _ => None,
+        }
+    }
+
+    /// Replace a word with its correction, keeping the caret and the selection where they were
+    /// in the text around it. Nothing is replaced if the word is no longer at its range, e.g.
+    /// because the page changed the content in the meantime.
+    fn correct_word(&mut self, correction: WordCorrection) -> Option<TextChange> {
+        let range = correction.range;
+        if self.get_content().get(range.clone()) != Some(&*correction.word) {
+            return None;
+        }
+        let edit_point = self.text_point_to_offset(&self.edit_point);
+        let selection_origin = self.selection_origin.map(|origin| self.text_point_to_offset(&origin));
+        let selection_direction = self.selection_direction;
+
+        self.select_offsets(range.clone());
+        let change = self.replace_selection(DOMString::from(correction.correction));
+
+        let new_end = self.text_point_to_offset(&self.edit_point);
+        let moved = |offset: usize| if offset >= range.end { offset - range.end + new_end } else { offset };
+        self.edit_point = self.offset_to_text_point(moved(edit_point));
+        self.selection_origin = selection_origin.map(|origin| self.offset_to_text_point(moved(origin)));
+        self.selection_direction = selection_direction;
+        change
+    }
+
+    /// The byte ranges of the words found to be misspelled, in order.
+    pub fn misspellings(&self) -> &[Range<usize>] {
+        &self.spelling.misspellings
+    }
+
+    /// Forget the misspelled words, e.g. when the owner no longer checks its spelling, and check
+    /// all of the content again next time.
+    pub fn clear_misspellings(&mut self) {
+        self.respell_content();
+    }
+
+    /// Mark all of the content as needing to be checked, as when it is replaced.
+    fn respell_content(&mut self) {
+        self.spelling.misspellings.clear();
+        self.spelling.unchecked = Some(0..usize::MAX);
+    }
+
+    /// Move the misspelled words after an edit to follow the text they were found in, and mark
+    /// the edited text to be checked. A misspelled word the edit touched may have become another
+    /// word, so it is dropped until it is checked again.
+    fn update_spelling(&mut self, change: &TextChange) {
+        let start = change.range.start;
+        let old_end = change.range.end;
+        let new_end = start + change.new_text.len();
+        let moved = |offset: usize| {
+            if offset >= old_end {
+                // Saturating, as content marked to be checked up to its end may end at usize::MAX.
+                (offset - old_end).saturating_add(new_end)
+            } else if offset > start {
+                new_end
             } else {
-                acc
+                offset
             }
+        };
+
+        self.spelling.misspellings.retain(|misspelling| misspelling.end < start || misspelling.start > old_end);
+        for misspelling in &mut self.spelling.misspellings {
+            *misspelling = moved(misspelling.start)..moved(misspelling.end);
+        }
+        self.spelling.unchecked = Some(match self.spelling.unchecked.take() {
+            Some(unchecked) => min(moved(unchecked.start), start)..max(moved(unchecked.end), new_end),
+            None => start..new_end,
         });
+    }
 
-        TextPoint {
-            line: line, index: index
+    /// The byte offset of the start of each line.
+    fn line_offsets(&self) -> Ref<Vec<usize>> {
+        if self.cache.line_offsets.borrow().is_none() {
+            let mut offset = 0;
+            let offsets = self.lines.iter().map(|line| {
+                let start = offset;
+                offset += line.len() + 1; // +1 for the \n
+                start
+            }).collect();
+            *self.cache.line_offsets.borrow_mut() = Some(offsets);
         }
+        Ref::map(self.cache.line_offsets.borrow(), |offsets| offsets.as_ref().unwrap())
+    }
+
+    fn invalidate_cache(&self) {
+        *self.cache.line_offsets.borrow_mut() = None;
+        *self.cache.content.borrow_mut() = None;
+        self.cache.utf16_len.set(None);
+    }
+
+    /// Convert a TextPoint into a byte offset from the start of the content. A point past the
+    /// end of its line or of the content, or inside a character, is moved back to the nearest
+    /// character boundary first.
+    pub fn text_point_to_offset(&self, text_point: &TextPoint) -> usize {
+        let text_point = self.clamp_text_point(*text_point);
+        self.line_offsets()[text_point.line] + text_point.index
+    }
+
+    /// Convert a TextPoint into a UTF-16 code unit offset from the start of the content, moving
+    /// it back to a character boundary like `text_point_to_offset`.
+    pub fn text_point_to_utf16_offset(&self, text_point: &TextPoint) -> usize {
+        let text_point = self.clamp_text_point(*text_point);
+        let utf16_len = |text: &str| text.chars().map(char::len_utf16).sum::<usize>();
+        self.lines.iter_range(0..text_point.line).fold(0, |acc, line| {
+            acc + utf16_len(line) + 1 // +1 for the \n
+        }) + utf16_len(&self.lines[text_point.line][..text_point.index])
+    }
+
+    /// Convert a UTF-16 code unit offset from the start of the content into a byte offset.
+    /// An offset between the two halves of a surrogate pair is moved back to the start of the
+    /// pair, and one past the end of the content is moved back to the end.
+    pub fn utf16_offset_to_offset(&self, utf16_offset: usize) -> usize {
+        let mut remaining = utf16_offset;
+        let mut offset = 0;
+        for line in self.lines.iter() {
+            let line_utf16_len = line.chars().map(char::len_utf16).sum::<usize>();
+            if remaining <= line_utf16_len {
+                return offset + len_of_first_n_code_units(line, remaining);
+            }
+            remaining -= line_utf16_len + 1; // +1 for the \n
+            offset += line.len() + 1;
+        }
+        self.len()
+    }
+
+    /// Convert a byte offset from the start of the content into a TextPoint. An offset past the
+    /// end of the content or inside a character is moved back to the nearest character boundary.
+    pub fn offset_to_text_point(&self, abs_point: usize) -> TextPoint {
+        let abs_point = min(abs_point, self.len());
+        let point = {
+            let line_offsets = self.line_offsets();
+            let line = match line_offsets.binary_search(&abs_point) {
+                Ok(line) => line,
+                Err(next_line) => next_line - 1,
+            };
+            TextPoint {
+                line: line, index: abs_point - line_offsets[line]
+            }
+        };
+        self.clamp_text_point(point)
+    }
+
+    /// Convert a UTF-16 code unit offset from the start of the content into a TextPoint, moving
+    /// it back to a character boundary like `utf16_offset_to_offset`.
+    pub fn utf16_offset_to_text_point(&self, utf16_offset: usize) -> TextPoint {
+        let offset = self.utf16_offset_to_offset(utf16_offset);
+        self.offset_to_text_point(offset)
     }
 
     pub fn set_selection_range(&mut self, start: u32, end: u32, direction: SelectionDirection) {
         let mut start = start as usize;
         let mut end = end as usize;
-        let text_end = self.get_content().len();
+        let text_end = self.len();
 
         if end > text_end {
             end = text_end;
@@ -906,6 +2674,7 @@ impl<T: ClipboardProvider> TextInput<T> {
         }
 
         self.selection_direction = direction;
+        self.set_selection_granularity(SelectionGranularity::Character);
 
         match direction {
             SelectionDirection::None |
@@ -918,14 +2687,148 @@ impl<T: ClipboardProvider> TextInput<T> {
                 self.edit_point = self.offset_to_text_point(start);
             }
         }
-        self.assert_ok_selection();
+        self.preferred_column = None;
+        self.validate_selection();
+    }
+
+    /// Like `set_selection_range`, but with the offsets in UTF-16 code units, as they are given
+    /// by the DOM.
+    pub fn set_selection_range_utf16(&mut self, start: u32, end: u32, direction: SelectionDirection) {
+        let start = self.utf16_offset_to_offset(start as usize);
+        let end = self.utf16_offset_to_offset(end as usize);
+        self.set_selection_range(start as u32, end as u32, direction);
+    }
+
+    /// Move the editing point to the given point, e.g. in response to a click. The point is
+    /// restricted to the content and moved back to the nearest grapheme boundary. If `select` is
+    /// `Selection::Selected` the selection is extended to the new point, otherwise it is
+    /// removed.
+    pub fn set_edit_point(&mut self, point: TextPoint, select: Selection) {
+        let mut point = self.clamp_text_point(point);
+        point.index = {
+            let line = &self.lines[point.line];
+            line.grapheme_indices(true)
+                .map(|(index, _)| index)
+                .chain(Some(line.len()))
+                .take_while(|&index| index <= point.index)
+                .last()
+                .unwrap_or(0)
+        };
+
+        if select == Selection::Selected {
+            let origin = self.selection_origin_or_edit_point();
+            self.selection_origin = Some(origin);
+            self.selection_direction = if point < origin {
+                SelectionDirection::Backward
+            } else {
+                SelectionDirection::Forward
+            };
+        } else {
+            self.clear_selection();
+        }
+        self.edit_point = point;
+        self.preferred_column = None;
+        self.validate_selection();
+    }
+
+    /// Move the editing point to the given point while keeping the selection origin where it is,
+    /// e.g. while the mouse is dragged or on Shift+click. If there is no selection, one is
+    /// anchored at the current editing point. The selection direction follows the new point.
+    ///
+    /// With a granularity other than `SelectionGranularity::Character`, the selection grows a
+    /// whole unit at a time, and always includes the unit selected first.
+    pub fn extend_selection_to_point(&mut self, point: TextPoint) {
+        let (anchor_start, anchor_end) = match self.granularity_anchor {
+            Some(anchor) if self.has_selection() => anchor,
+            _ => return self.set_edit_point(point, Selection::Selected),
+        };
+        let point = self.clamp_text_point(point);
+        let (unit_start, unit_end) = self.unit_at(point, self.selection_granularity);
+        if unit_start < anchor_start {
+            self.selection_origin = Some(anchor_end);
+            self.edit_point = unit_start;
+            self.selection_direction = SelectionDirection::Backward;
+        } else {
+            self.selection_origin = Some(anchor_start);
+            self.edit_point = if unit_end > anchor_end { unit_end } else { anchor_end };
+            self.selection_direction = SelectionDirection::Forward;
+        }
+        self.preferred_column = None;
+        self.validate_selection();
+    }
+
+    /// Move the editing point to the given UTF-8 byte offset from the start of the content.
+    /// See `set_edit_point`.
+    pub fn set_edit_point_from_offset(&mut self, offset: usize, select: Selection) {
+        let offset = min(offset, self.len());
+        let point = self.offset_to_text_point(offset);
+        self.set_edit_point(point, select);
     }
 
     pub fn set_edit_point_index(&mut self, index: usize) {
-        let byte_size = self.lines[self.edit_point.line]
+        self.edit_point.index = self.grapheme_index_to_byte_index(index);
+        self.preferred_column = None;
+    }
+
+    /// Extend the selection to the given grapheme index in the current line, as for a
+    /// Shift+click. See `extend_selection_to_point`.
+    pub fn extend_selection_to_index(&mut self, index: usize) {
+        let point = TextPoint {
+            line: self.edit_point.line,
+            index: self.grapheme_index_to_byte_index(index),
+        };
+        self.extend_selection_to_point(point);
+    }
+
+    /// The byte offset of the given grapheme index in the current line.
+    fn grapheme_index_to_byte_index(&self, index: usize) -> usize {
+        self.grapheme_point_to_text_point(GraphemePoint { line: self.edit_point.line, grapheme: index }).index
+    }
+
+    /// Convert a `TextPoint` into the equivalent `GraphemePoint`. A point inside a grapheme
+    /// cluster is counted as being at its start.
+    pub fn text_point_to_grapheme_point(&self, point: TextPoint) -> GraphemePoint {
+        let point = self.clamp_text_point(point);
+        let grapheme = self.lines[point.line]
+            .grapheme_indices(true)
+            .take_while(|&(i, g)| i + g.len() <= point.index)
+            .count();
+        GraphemePoint { line: point.line, grapheme: grapheme }
+    }
+
+    /// Convert an index counting grapheme clusters from the start of the content, with each line
+    /// break counting as one, into a `TextPoint`. An index past the end is moved back to the end.
+    pub fn grapheme_offset_to_text_point(&self, offset: usize) -> TextPoint {
+        let mut remaining = offset;
+        for (line, text) in self.lines.iter().enumerate() {
+            let graphemes = text.graphemes(true).count();
+            if remaining <= graphemes {
+                return self.grapheme_point_to_text_point(GraphemePoint { line: line, grapheme: remaining });
+            }
+            remaining -= graphemes + 1; // +1 for the \n
+        }
+        let last_line = self.lines.len() - 1;
+        TextPoint { line: last_line, index: self.lines[last_line].len() }
+    }
+
+    /// Select the grapheme clusters from `start` to `end`, counted from the start of the content
+    /// as for `grapheme_offset_to_text_point`, i.e. in the characters the user sees.
+    pub fn select_grapheme_range(&mut self, start: usize, end: usize, direction: SelectionDirection) {
+        let start = self.grapheme_offset_to_text_point(start);
+        let end = self.grapheme_offset_to_text_point(end);
+        let start = self.text_point_to_offset(&start);
+        let end = self.text_point_to_offset(&end);
+        self.set_selection_range(start as u32, end as u32, direction);
+    }
+
+    /// Convert a `GraphemePoint` into the equivalent `TextPoint`. Points past the end of a line
+    /// or of the content are moved back to its end.
+    pub fn grapheme_point_to_text_point(&self, point: GraphemePoint) -> TextPoint {
+        let line = min(point.line, self.lines.len() - 1);
+        let index = self.lines[line]
             .graphemes(true)
-            .take(index)
+            .take(point.grapheme)
             .fold(0, |acc, x| acc + x.len());
-        self.edit_point.index = byte_size;
+        TextPoint { line: line, index: index }
     }
 }
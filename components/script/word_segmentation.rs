@@ -0,0 +1,220 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Word segmentation for caret movement and word selection in text controls.
+//!
+//! This starts from the Unicode word boundaries of UAX #29, which split every Han and Hiragana
+//! character into a word of its own. Runs of characters from these scripts are joined back
+//! together, and for Japanese text a run of Han characters also takes in the Hiragana that
+//! follows it, since that is how inflected words are written.
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The language-specific rules to apply on top of the Unicode word boundaries.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, PartialEq)]
+pub enum WordBreakRules {
+    Default,
+    Japanese,
+    /// Each item of a list separated by the given character is a word of its own, whatever it
+    /// contains, as are the addresses of an email input that accepts several.
+    List(char),
+}
+
+unsafe_no_jsmanaged_fields!(WordBreakRules);
+
+impl WordBreakRules {
+    /// The rules to use for text in the given language, as found in a `lang` attribute.
+    pub fn for_language(language: &str) -> WordBreakRules {
+        let primary = language.split('-').next().unwrap_or("");
+        if primary.eq_ignore_ascii_case("ja") {
+            WordBreakRules::Japanese
+        } else {
+            WordBreakRules::Default
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Script {
+    Han,
+    Hiragana,
+    Katakana,
+    Other,
+}
+
+fn script_of(segment: &str) -> Script {
+    let mut chars = segment.chars();
+    let script = match chars.next() {
+        Some(c) => script_of_char(c),
+        None => return Script::Other,
+    };
+    if chars.all(|c| script_of_char(c) == script) {
+        script
+    } else {
+        Script::Other
+    }
+}
+
+fn script_of_char(c: char) -> Script {
+    match c as u32 {
+        0x3400...0x4DBF | 0x4E00...0x9FFF | 0xF900...0xFAFF | 0x20000...0x2FA1F => Script::Han,
+        // U+3005 IDEOGRAPHIC ITERATION MARK repeats the preceding ideograph.
+        0x3005 => Script::Han,
+        0x3041...0x309F => Script::Hiragana,
+        0x30A0...0x30FF | 0x31F0...0x31FF | 0xFF66...0xFF9F => Script::Katakana,
+        _ => Script::Other,
+    }
+}
+
+/// Whether two adjacent segments belong to the same word.
+fn joins(rules: WordBreakRules, previous: Script, next: Script) -> bool {
+    match (previous, next) {
+        (Script::Other, _) | (_, Script::Other) => false,
+        (Script::Han, Script::Hiragana) => rules == WordBreakRules::Japanese,
+        (previous, next) => previous == next,
+    }
+}
+
+/// Split `text` into words and the runs of spaces and punctuation between them. Each item is
+/// the byte offset of the segment and the segment itself; together they cover the whole text.
+pub fn word_bound_indices(text: &str, rules: WordBreakRules) -> Vec<(usize, &str)> {
+    if let WordBreakRules::List(separator) = rules {
+        return list_item_bound_indices(text, separator);
+    }
+    let mut segments: Vec<(usize, &str, Script)> = vec!();
+    for (index, segment) in text.split_word_bound_indices() {
+        let script = script_of(segment);
+        if let Some(last) = segments.last_mut() {
+            if joins(rules, last.2, script) {
+                last.1 = &text[last.0..index + segment.len()];
+                // Once a Han run has taken in Hiragana, only more Hiragana can follow.
+                last.2 = script;
+                continue;
+            }
+        }
+        segments.push((index, segment, script));
+    }
+    segments.into_iter().map(|(index, segment, _)| (index, segment)).collect()
+}
+
+/// Split `text` into the items of a list and the runs of separators and spaces between them.
+fn list_item_bound_indices(text: &str, separator: char) -> Vec<(usize, &str)> {
+    let is_separator = |c: char| c == separator || c.is_whitespace();
+    let mut segments = vec!();
+    let mut start = 0;
+    let mut previous = None;
+    for (index, c) in text.char_indices() {
+        if previous.map_or(false, |previous| is_separator(previous) != is_separator(c)) {
+            segments.push((start, &text[start..index]));
+            start = index;
+        }
+        previous = Some(c);
+    }
+    if start < text.len() {
+        segments.push((start, &text[start..]));
+    }
+    segments
+}
+
+/// Whether a segment returned by `word_bound_indices` is a word, as opposed to spaces or
+/// punctuation that caret movement skips over.
+pub fn is_word(segment: &str) -> bool {
+    segment.chars().any(|c| c.is_alphabetic() || c.is_numeric())
+}
+
+/// How double-clicking selects a word, which differs between platforms.
+#[derive(Clone, Copy, Debug, Eq, MallocSizeOf, PartialEq)]
+pub struct WordSelectionPolicy {
+    /// Whether the spaces after a word are selected along with it.
+    pub include_trailing_whitespace: bool,
+    /// Whether a run of punctuation is selected as a whole, rather than one mark at a time.
+    pub group_punctuation: bool,
+}
+
+unsafe_no_jsmanaged_fields!(WordSelectionPolicy);
+
+impl WordSelectionPolicy {
+    pub fn windows() -> WordSelectionPolicy {
+        WordSelectionPolicy {
+            include_trailing_whitespace: true,
+            group_punctuation: true,
+        }
+    }
+
+    pub fn mac() -> WordSelectionPolicy {
+        WordSelectionPolicy {
+            include_trailing_whitespace: false,
+            group_punctuation: false,
+        }
+    }
+
+    pub fn linux() -> WordSelectionPolicy {
+        WordSelectionPolicy {
+            include_trailing_whitespace: false,
+            group_punctuation: true,
+        }
+    }
+
+    /// The policy of the platform Servo was built for.
+    pub fn current() -> WordSelectionPolicy {
+        if cfg!(target_os = "windows") {
+            WordSelectionPolicy::windows()
+        } else if cfg!(target_os = "macos") {
+            WordSelectionPolicy::mac()
+        } else {
+            WordSelectionPolicy::linux()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum SegmentKind {
+    Word,
+    Whitespace,
+    Punctuation,
+}
+
+fn segment_kind(segment: &str) -> SegmentKind {
+    if is_word(segment) {
+        SegmentKind::Word
+    } else if segment.chars().all(char::is_whitespace) {
+        SegmentKind::Whitespace
+    } else {
+        SegmentKind::Punctuation
+    }
+}
+
+/// The byte range that double-clicking at `index` selects in `text`. This is the word, run of
+/// spaces or punctuation containing `index`, or the last one if `index` is at the end.
+pub fn word_selection_range(text: &str, index: usize, rules: WordBreakRules, policy: WordSelectionPolicy)
+                            -> (usize, usize) {
+    let segments: Vec<(usize, usize, SegmentKind)> = word_bound_indices(text, rules).into_iter()
+        .map(|(start, segment)| (start, start + segment.len(), segment_kind(segment)))
+        .collect();
+    let position = match segments.iter().position(|&(_, end, _)| index < end) {
+        Some(position) => position,
+        None if segments.is_empty() => return (index, index),
+        None => segments.len() - 1,
+    };
+
+    let kind = segments[position].2;
+    let groups = |other: SegmentKind| {
+        other == kind && (kind == SegmentKind::Whitespace ||
+                          (kind == SegmentKind::Punctuation && policy.group_punctuation))
+    };
+    let mut first = position;
+    while first > 0 && groups(segments[first - 1].2) {
+        first -= 1;
+    }
+    let mut last = position;
+    while last + 1 < segments.len() && groups(segments[last + 1].2) {
+        last += 1;
+    }
+    if kind == SegmentKind::Word && policy.include_trailing_whitespace {
+        while last + 1 < segments.len() && segments[last + 1].2 == SegmentKind::Whitespace {
+            last += 1;
+        }
+    }
+    (segments[first].0, segments[last].1)
+}
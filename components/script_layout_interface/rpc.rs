@@ -23,6 +23,12 @@ pub trait LayoutRPC {
     fn content_box(&self) -> ContentBoxResponse;
     /// Requests the dimensions of all the content boxes, as in the `getClientRects()` call.
     fn content_boxes(&self) -> ContentBoxesResponse;
+    /// Requests the bounds of the caret of a text control, e.g. to place the candidate window
+    /// of an input method next to it.
+    fn caret_rect(&self) -> CaretRectResponse;
+    /// Requests the points where the lines of a text control's content wrap, for the wrap=hard
+    /// transformation of a textarea.
+    fn text_wrap_points(&self) -> TextWrapPointsResponse;
     /// Requests the geometry of this node. Used by APIs such as `clientTop`.
     fn node_geometry(&self) -> NodeGeometryResponse;
     /// Requests the scroll geometry of this node. Used by APIs such as `scrollTop`.
@@ -46,6 +52,11 @@ pub struct ContentBoxResponse(pub Option<Rect<Au>>);
 
 pub struct ContentBoxesResponse(pub Vec<Rect<Au>>);
 
+pub struct CaretRectResponse(pub Option<Rect<Au>>);
+
+/// Each wrap point is a line of the content and the byte offset in it where a row starts.
+pub struct TextWrapPointsResponse(pub Option<Vec<(usize, usize)>>);
+
 pub struct NodeGeometryResponse {
     pub client_rect: Rect<i32>,
 }
@@ -246,6 +246,10 @@ pub trait ThreadSafeLayoutNode: Clone + Copy + Debug + GetLayoutData + NodeInfo
     /// If the insertion point is within this node, returns it. Otherwise, returns `None`.
     fn selection(&self) -> Option<Range<ByteIndex>>;
 
+    /// Returns the ranges of the words of this node's text that are misspelled, if it is a text
+    /// control whose spelling is checked.
+    fn misspellings(&self) -> Vec<Range<ByteIndex>>;
+
     /// If this is an image element, returns its URL. If this is not an image element, fails.
     fn image_url(&self) -> Option<ServoUrl>;
 
@@ -41,14 +41,15 @@ pub mod webdriver_msg;
 use bluetooth_traits::BluetoothRequest;
 use canvas_traits::webgl::WebGLPipeline;
 use devtools_traits::{DevtoolScriptControlMsg, ScriptToDevtoolsControlMsg, WorkerId};
-use euclid::{Size2D, Length, Point2D, Vector2D, Rect, TypedScale, TypedSize2D};
+use euclid::{Size2D, Length, Point2D, Vector2D, Rect, TypedRect, TypedScale, TypedSize2D};
 use gfx_traits::Epoch;
 use hyper::header::Headers;
 use hyper::method::Method;
 use ipc_channel::{Error as IpcError};
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
 use libc::c_void;
-use msg::constellation_msg::{BrowsingContextId, TopLevelBrowsingContextId, Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{BrowsingContextId, TopLevelBrowsingContextId, ImeEvent, Key, KeyModifiers, KeyState};
+use msg::constellation_msg::ImeSurroundingText;
 use msg::constellation_msg::{PipelineId, PipelineNamespaceId, TraversalDirection};
 use net_traits::{FetchResponseMsg, ReferrerPolicy, ResourceThreads};
 use net_traits::image::base::Image;
@@ -274,6 +275,18 @@ pub enum ConstellationControlMsg {
     SetScrollState(PipelineId, Vec<(UntrustedNodeAddress, Vector2D<f32>)>),
     /// Requests that the script thread immediately send the constellation the title of a pipeline.
     GetTitle(PipelineId),
+    /// Requests the text around the caret of the focused text control of a pipeline, with up to
+    /// the given numbers of characters before and after it.
+    GetImeSurroundingText(PipelineId, usize, usize, IpcSender<Option<ImeSurroundingText>>),
+    /// Notifies the script thread that the virtual keyboard now covers the given area of the
+    /// viewport of a pipeline.
+    VirtualKeyboardGeometryChange(PipelineId, TypedRect<f32, CSSPixel>),
+    /// Shows or hides the value of the focused password field of a pipeline.
+    RevealPassword(PipelineId, bool),
+    /// Fills in the focused text control of a pipeline on the user's behalf.
+    Autofill(PipelineId, String),
+    /// Commits the suggestion that the user picked for the focused input of a pipeline.
+    ChooseSuggestion(PipelineId, String),
     /// Notifies script thread of a change to one of its document's activity
     SetDocumentActivity(PipelineId, DocumentActivity),
     /// Notifies script thread whether frame is visible
@@ -337,6 +350,11 @@ impl fmt::Debug for ConstellationControlMsg {
             Viewport(..) => "Viewport",
             SetScrollState(..) => "SetScrollState",
             GetTitle(..) => "GetTitle",
+            GetImeSurroundingText(..) => "GetImeSurroundingText",
+            VirtualKeyboardGeometryChange(..) => "VirtualKeyboardGeometryChange",
+            RevealPassword(..) => "RevealPassword",
+            Autofill(..) => "Autofill",
+            ChooseSuggestion(..) => "ChooseSuggestion",
             SetDocumentActivity(..) => "SetDocumentActivity",
             ChangeFrameVisibilityStatus(..) => "ChangeFrameVisibilityStatus",
             NotifyVisibilityChange(..) => "NotifyVisibilityChange",
@@ -442,6 +460,8 @@ pub enum CompositorEvent {
     TouchEvent(TouchEventType, TouchId, Point2D<f32>, Option<UntrustedNodeAddress>),
     /// A key was pressed.
     KeyEvent(Option<char>, Key, KeyState, KeyModifiers),
+    /// The input method editor composed or committed text.
+    ImeEvent(ImeEvent),
 }
 
 /// Requests a TimerEvent-Message be sent after the given duration.
@@ -673,6 +693,22 @@ pub enum ConstellationMsg {
     IsReadyToSaveImage(HashMap<PipelineId, Epoch>),
     /// Inform the constellation of a key event.
     KeyEvent(Option<char>, Key, KeyState, KeyModifiers),
+    /// Inform the constellation of an update from the input method editor.
+    ImeEvent(ImeEvent),
+    /// Request the text around the caret of the focused text control, with up to the given
+    /// numbers of characters before and after it.
+    GetImeSurroundingText(usize, usize, IpcSender<Option<ImeSurroundingText>>),
+    /// Inform the constellation that the virtual keyboard now covers the given area of the
+    /// viewport.
+    VirtualKeyboardGeometryChange(TypedRect<f32, CSSPixel>),
+    /// Inform the constellation that the user asked to show or hide the value of the focused
+    /// password field.
+    RevealPassword(bool),
+    /// Inform the constellation that the embedder filled in the focused text control on the
+    /// user's behalf.
+    Autofill(String),
+    /// Inform the constellation that the user picked a suggestion for the focused input.
+    ChooseSuggestion(String),
     /// Request to load a page.
     LoadUrl(TopLevelBrowsingContextId, ServoUrl),
     /// Request to traverse the joint session history of the provided browsing context.
@@ -13,11 +13,12 @@ use WorkerGlobalScopeInit;
 use WorkerScriptLoadOrigin;
 use canvas_traits::canvas::CanvasMsg;
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
-use euclid::{Point2D, Size2D, TypedSize2D};
+use euclid::{Point2D, Size2D, TypedRect, TypedSize2D};
 use gfx_traits::Epoch;
 use ipc_channel::ipc::{IpcReceiver, IpcSender};
-use msg::constellation_msg::{BrowsingContextId, PipelineId, TraversalDirection};
-use msg::constellation_msg::{Key, KeyModifiers, KeyState};
+use msg::constellation_msg::{AutocorrectWord, BrowsingContextId, ClipboardContents, ClipboardFlavor, PipelineId};
+use msg::constellation_msg::TraversalDirection;
+use msg::constellation_msg::{Key, KeyModifiers, KeyState, Suggestion, TextInputHints};
 use net_traits::CoreResourceMsg;
 use net_traits::request::RequestInit;
 use net_traits::storage_thread::StorageType;
@@ -83,8 +84,17 @@ pub enum ScriptMsg {
     Focus,
     /// Forward an event that was sent to the parent window.
     ForwardEvent(PipelineId, CompositorEvent),
-    /// Requests that the constellation retrieve the current contents of the clipboard
-    GetClipboardContents(IpcSender<String>),
+    /// Requests the flavors that the current contents of the clipboard are offered in
+    GetClipboardFlavors(IpcSender<Vec<ClipboardFlavor>>),
+    /// Requests that the constellation retrieve the current contents of the clipboard in the
+    /// given flavor, if they are offered in it
+    GetClipboardContents(ClipboardFlavor, IpcSender<Option<String>>),
+    /// Requests the length in UTF-8 bytes of the current contents of the clipboard in the given
+    /// flavor, if they are offered in it
+    GetClipboardLength(ClipboardFlavor, IpcSender<Option<usize>>),
+    /// Requests the part of the current contents of the clipboard in the given flavor that
+    /// starts at the given byte offset and is at most the given number of bytes long
+    GetClipboardChunk(ClipboardFlavor, usize, usize, IpcSender<Option<String>>),
     /// Get the browsing context id for a given pipeline.
     GetBrowsingContextId(PipelineId, IpcSender<Option<BrowsingContextId>>),
     /// Get the parent info for a given pipeline.
@@ -121,7 +131,7 @@ pub enum ScriptMsg {
     /// A load of the initial `about:blank` has been completed in an IFrame.
     ScriptNewIFrame(IFrameLoadInfo, IpcSender<LayoutControlMsg>),
     /// Requests that the constellation set the contents of the clipboard
-    SetClipboardContents(String),
+    SetClipboardContents(ClipboardContents),
     /// Mark a new document as active
     ActivateDocument,
     /// Set the document state for a pipeline (used by screenshot / reftests)
@@ -130,11 +140,35 @@ pub enum ScriptMsg {
     SetFinalUrl(ServoUrl),
     /// Check if an alert dialog box should be presented
     Alert(String, IpcSender<bool>),
+    /// Ask the embedder to let the user pick a color for a color input, starting at the given
+    /// color in `#rrggbb` form, and reply with the picked color, or `None` if there is none.
+    PickColor(String, IpcSender<Option<String>>),
+    /// Ask the embedder which of the given words, typed into a text control in the given
+    /// language, are misspelled, and reply with a flag for each word.
+    CheckSpelling(Vec<String>, String, IpcSender<Vec<bool>>),
+    /// Offer the embedder a word the user just finished typing to correct, and reply with the
+    /// text to replace it with, or `None` to keep it.
+    Autocorrect(AutocorrectWord, IpcSender<Option<String>>),
     /// Set title of current page
     /// <https://html.spec.whatwg.org/multipage/#document.title>
     SetTitle(Option<String>),
     /// Send a key event
     SendKeyEvent(Option<char>, Key, KeyState, KeyModifiers),
+    /// Tell the embedder where the caret of the focused text control is, relative to the
+    /// viewport, or that there is none.
+    SetImeCaretRect(Option<TypedRect<f32, CSSPixel>>),
+    /// Tell the embedder that the page cancelled the text that an input method was composing,
+    /// so that the input method stops composing it too.
+    CancelComposition,
+    /// Tell the embedder which kind of virtual keyboard suits the text control that gained the
+    /// focus, or that the focus left text controls.
+    SetTextInputHints(Option<TextInputHints>),
+    /// Tell the embedder whether to warn the user that Caps Lock is on while they type in the
+    /// focused password field.
+    SetCapsLockWarning(bool),
+    /// Tell the embedder which options of the datalist of the focused input match what the
+    /// user typed, if any.
+    SetSuggestions(Vec<Suggestion>),
     /// Get Window Informations size and position
     GetClientWindow(IpcSender<(Size2D<u32>, Point2D<i32>)>),
     /// Move the window to a point
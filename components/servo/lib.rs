@@ -318,6 +318,48 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
                 }
             }
 
+            WindowEvent::Ime(event) => {
+                let msg = ConstellationMsg::ImeEvent(event);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending input method event to constellation failed ({}).", e);
+                }
+            }
+
+            WindowEvent::GetImeSurroundingText(before, after, sender) => {
+                let msg = ConstellationMsg::GetImeSurroundingText(before, after, sender);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending surrounding text request to constellation failed ({}).", e);
+                }
+            }
+
+            WindowEvent::VirtualKeyboardGeometryChange(rect) => {
+                let msg = ConstellationMsg::VirtualKeyboardGeometryChange(rect);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending virtual keyboard geometry to constellation failed ({}).", e);
+                }
+            }
+
+            WindowEvent::RevealPassword(revealed) => {
+                let msg = ConstellationMsg::RevealPassword(revealed);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending password reveal to constellation failed ({}).", e);
+                }
+            }
+
+            WindowEvent::Autofill(value) => {
+                let msg = ConstellationMsg::Autofill(value);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending autofill to constellation failed ({}).", e);
+                }
+            }
+
+            WindowEvent::ChooseSuggestion(value) => {
+                let msg = ConstellationMsg::ChooseSuggestion(value);
+                if let Err(e) = self.constellation_chan.send(msg) {
+                    warn!("Sending suggestion choice to constellation failed ({}).", e);
+                }
+            }
+
             WindowEvent::Quit => {
                 self.compositor.maybe_start_shutting_down();
             }
@@ -429,6 +471,46 @@ impl<Window> Servo<Window> where Window: WindowMethods + 'static {
                     self.compositor.window.set_cursor(cursor)
                 },
 
+                (EmbedderMsg::SetImeCaretRect(top_level_browsing_context, rect),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.set_ime_caret_rect(top_level_browsing_context, rect);
+                },
+
+                (EmbedderMsg::CancelComposition(top_level_browsing_context),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.cancel_composition(top_level_browsing_context);
+                },
+
+                (EmbedderMsg::SetTextInputHints(top_level_browsing_context, hints),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.set_text_input_hints(top_level_browsing_context, hints);
+                },
+
+                (EmbedderMsg::SetCapsLockWarning(top_level_browsing_context, warn),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.set_caps_lock_warning(top_level_browsing_context, warn);
+                },
+
+                (EmbedderMsg::SetSuggestions(top_level_browsing_context, suggestions),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.set_suggestions(top_level_browsing_context, suggestions);
+                },
+
+                (EmbedderMsg::PickColor(top_level_browsing_context, color, response_chan),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.pick_color(top_level_browsing_context, color, response_chan);
+                },
+
+                (EmbedderMsg::CheckSpelling(top_level_browsing_context, words, lang, response_chan),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.check_spelling(top_level_browsing_context, words, lang, response_chan);
+                },
+
+                (EmbedderMsg::Autocorrect(top_level_browsing_context, word, response_chan),
+                 ShutdownState::NotShuttingDown) => {
+                    self.compositor.window.autocorrect(top_level_browsing_context, word, response_chan);
+                },
+
                 (EmbedderMsg::NewFavicon(top_level_browsing_context, url), ShutdownState::NotShuttingDown) => {
                     self.compositor.window.set_favicon(top_level_browsing_context, url);
                 },
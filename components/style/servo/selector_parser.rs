@@ -290,6 +290,7 @@ pub type PseudoClassStringArg = Box<str>;
 pub enum NonTSPseudoClass {
     Active,
     AnyLink,
+    Autofill,
     Checked,
     Disabled,
     Enabled,
@@ -328,6 +329,7 @@ impl ToCss for NonTSPseudoClass {
         dest.write_str(match *self {
             Active => ":active",
             AnyLink => ":any-link",
+            Autofill => ":autofill",
             Checked => ":checked",
             Disabled => ":disabled",
             Enabled => ":enabled",
@@ -376,6 +378,7 @@ impl NonTSPseudoClass {
             Indeterminate => ElementState::IN_INDETERMINATE_STATE,
             ReadOnly | ReadWrite => ElementState::IN_READ_WRITE_STATE,
             PlaceholderShown => ElementState::IN_PLACEHOLDER_SHOWN_STATE,
+            Autofill => ElementState::IN_AUTOFILL_STATE,
             Target => ElementState::IN_TARGET_STATE,
 
             AnyLink |
@@ -441,6 +444,7 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
         let pseudo_class = match_ignore_ascii_case! { &name,
             "active" => Active,
             "any-link" => AnyLink,
+            "autofill" | "-webkit-autofill" => Autofill,
             "checked" => Checked,
             "disabled" => Disabled,
             "enabled" => Enabled,
@@ -0,0 +1,42 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::test::contenteditable::{ContentEditableState, Deletion, InsertionPoint, LeafKind, plan_change};
+
+#[test]
+fn test_content_editable_state() {
+    assert_eq!(ContentEditableState::from_attribute(Some("")), ContentEditableState::True);
+    assert_eq!(ContentEditableState::from_attribute(Some("TRUE")), ContentEditableState::True);
+    assert_eq!(ContentEditableState::from_attribute(Some("false")), ContentEditableState::False);
+    assert_eq!(ContentEditableState::from_attribute(Some("plaintext")), ContentEditableState::Inherit);
+    assert_eq!(ContentEditableState::from_attribute(None), ContentEditableState::Inherit);
+}
+
+#[test]
+fn test_plan_change() {
+    // "hello", <br>, "foo"
+    let leaves = [(LeafKind::Text, 5), (LeafKind::LineBreak, 1), (LeafKind::Text, 3)];
+
+    // Typing goes into the text before the caret, or else the text after it.
+    assert_eq!(plan_change(&leaves, 5..5), (vec!(), InsertionPoint::InText(0, 5)));
+    assert_eq!(plan_change(&leaves, 0..0), (vec!(), InsertionPoint::InText(0, 0)));
+    assert_eq!(plan_change(&leaves, 6..6), (vec!(), InsertionPoint::InText(2, 0)));
+
+    // Deleting the line break joins the lines.
+    assert_eq!(plan_change(&leaves, 5..6), (vec!(Deletion::Remove(1)), InsertionPoint::InText(0, 5)));
+
+    assert_eq!(plan_change(&leaves, 2..8),
+               (vec!(Deletion::Trim(0, 2..5), Deletion::Remove(1), Deletion::Trim(2, 0..2)),
+                InsertionPoint::InText(0, 2)));
+    assert_eq!(plan_change(&leaves, 0..9),
+               (vec!(Deletion::Remove(0), Deletion::Remove(1), Deletion::Remove(2)), InsertionPoint::End));
+
+    let leaves = [(LeafKind::LineBreak, 1), (LeafKind::Text, 3)];
+    assert_eq!(plan_change(&leaves, 0..0), (vec!(), InsertionPoint::Before(0)));
+    assert_eq!(plan_change(&leaves, 0..1), (vec!(Deletion::Remove(0)), InsertionPoint::InText(1, 0)));
+
+    let leaves = [(LeafKind::LineBreak, 1)];
+    assert_eq!(plan_change(&leaves, 1..1), (vec!(), InsertionPoint::After(0)));
+    assert_eq!(plan_change(&[], 0..0), (vec!(), InsertionPoint::End));
+}
@@ -0,0 +1,273 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::{Key, Suggestion};
+use script::test::input::{InputType, StepScale, ValidationFlags, ValueDisplay, local_date_and_time_validity};
+use script::test::input::{parse_number, step_number, step_scale};
+use script::test::input::{caps_lock_state, matching_suggestions};
+use script::test::input::{email_validity, is_valid_email_address, range_steps_for_key, sanitize_email};
+use script::test::input::{pattern_validity, tel_char_filter, time_validity, url_validity};
+
+#[test]
+fn test_parse_number() {
+    assert_eq!(parse_number("1.5"), Some(1.5));
+    assert_eq!(parse_number("-2e3"), Some(-2000.0));
+    assert_eq!(parse_number("+1"), None);
+    assert_eq!(parse_number("1."), None);
+    assert_eq!(parse_number(" 1"), None);
+    assert_eq!(parse_number("any"), None);
+    assert_eq!(parse_number(""), None);
+}
+
+#[test]
+fn test_step_number() {
+    assert_eq!(step_number(5.0, 0.0, 1.0, None, None, 1), Some(6.0));
+    assert_eq!(step_number(5.0, 0.0, 1.0, None, None, -10), Some(-5.0));
+
+    // Steps are counted from the base, and a value between steps goes to the next one first.
+    assert_eq!(step_number(4.0, 1.0, 2.0, None, None, 1), Some(5.0));
+    assert_eq!(step_number(4.0, 1.0, 2.0, None, None, -1), Some(3.0));
+
+    // Rounding errors don't show in the value.
+    assert_eq!(step_number(0.2, 0.0, 0.1, None, None, 1), Some(0.3));
+    assert_eq!(step_number(0.3, 0.0, 0.1, None, None, 1), Some(0.4));
+
+    // The value is kept within the range, at a whole number of steps.
+    assert_eq!(step_number(9.0, 0.0, 1.0, None, Some(10.0), 100), Some(10.0));
+    assert_eq!(step_number(10.0, 0.0, 1.0, None, Some(10.0), 1), None);
+    assert_eq!(step_number(9.0, 0.0, 3.0, None, Some(10.0), 1), None);
+    assert_eq!(step_number(5.0, 2.0, 1.0, Some(2.0), None, -10), Some(2.0));
+
+    // A value out of range is brought into it, but not moved further away.
+    assert_eq!(step_number(50.0, 0.0, 1.0, None, Some(10.0), -1), Some(10.0));
+    assert_eq!(step_number(50.0, 0.0, 1.0, None, Some(10.0), 1), None);
+}
+
+#[test]
+fn test_step_scale() {
+    assert_eq!(step_scale(InputType::Month), Some(StepScale {
+        default_step: 1.0,
+        scale: 1.0,
+        default_base: 0.0,
+    }));
+    assert_eq!(step_scale(InputType::Week), Some(StepScale {
+        default_step: 1.0,
+        scale: 604800000.0,
+        default_base: -259200000.0,
+    }));
+    assert_eq!(step_scale(InputType::Text), None);
+
+    // Without a min or value, weeks step from Monday to Monday, as 1970-01-01 is a Thursday.
+    let week = step_scale(InputType::Week).unwrap();
+    assert_eq!(step_number(0.0, week.default_base, week.scale, None, None, 1), Some(345600000.0));
+    // A month input steps whole months, counted from 1970-01.
+    let month = step_scale(InputType::Month).unwrap();
+    assert_eq!(step_number(577.0, month.default_base, month.scale * 3.0, None, None, 1), Some(579.0));
+}
+
+#[test]
+fn test_value_display() {
+    assert_eq!(ValueDisplay::for_number("de"), ValueDisplay::DecimalComma);
+    assert_eq!(ValueDisplay::for_number("pt-BR"), ValueDisplay::DecimalComma);
+    assert_eq!(ValueDisplay::for_number("FR"), ValueDisplay::DecimalComma);
+    assert_eq!(ValueDisplay::for_number("en-US"), ValueDisplay::AsIs);
+    assert_eq!(ValueDisplay::for_number(""), ValueDisplay::AsIs);
+
+    let display = ValueDisplay::DecimalComma;
+    assert_eq!(display.to_display("-1.5e3"), "-1,5e3");
+    assert_eq!(display.to_value("-1,5e3"), "-1.5e3");
+    // A dot may be typed as well.
+    assert_eq!(display.to_value("2.25"), "2.25");
+    assert_eq!(ValueDisplay::AsIs.to_value("1,5"), "1,5");
+
+    assert_eq!(ValueDisplay::Month.to_display(""), "yyyy-mm");
+    assert_eq!(ValueDisplay::Month.to_value("2018-mm"), "");
+    assert_eq!(ValueDisplay::Week.to_display("2018-W05"), "2018-W05");
+    assert_eq!(ValueDisplay::Week.to_value("2018-W53"), "");
+}
+
+#[test]
+fn test_time_value_display() {
+    let display = ValueDisplay::for_time("en-US", "");
+    assert_eq!(display.to_display(""), "hh:mm --");
+    assert_eq!(display.to_display("00:05"), "12:05 AM");
+    assert_eq!(display.to_display("13:30:15"), "01:30:15 PM");
+    assert_eq!(display.to_value("12:05 PM"), "12:05");
+    assert_eq!(display.to_value("12:05 --"), "");
+
+    // The fields that the step can change are shown, and are part of the value.
+    let display = ValueDisplay::for_time("de", "1");
+    assert_eq!(display.to_display("08:00"), "08:00:00");
+    assert_eq!(display.to_value("08:00:00"), "08:00:00");
+    assert_eq!(ValueDisplay::for_time("de", "0.5").to_display("08:00:30.5"), "08:00:30.500");
+    assert_eq!(ValueDisplay::for_time("de", "120").to_display("08:00"), "08:00");
+}
+
+#[test]
+fn test_time_validity() {
+    let fails = |value, min, max, step| time_validity(value, min, max, step, "");
+    assert_eq!(fails("", "10:00", "", ""), ValidationFlags::empty());
+    assert_eq!(fails("09:00", "10:00", "", ""), ValidationFlags::RANGE_UNDERFLOW);
+    assert_eq!(fails("18:00", "", "17:00", ""), ValidationFlags::RANGE_OVERFLOW);
+
+    // A reversed range wraps around midnight.
+    assert_eq!(fails("23:00", "22:00", "06:00", ""), ValidationFlags::empty());
+    assert_eq!(fails("12:00", "22:00", "06:00", ""),
+               ValidationFlags::RANGE_UNDERFLOW | ValidationFlags::RANGE_OVERFLOW);
+
+    // The default step is a minute, counted from the min.
+    assert_eq!(fails("10:00:30", "", "", ""), ValidationFlags::STEP_MISMATCH);
+    assert_eq!(fails("10:00:30", "", "", "any"), ValidationFlags::empty());
+    assert_eq!(fails("10:20", "10:05", "", "900"), ValidationFlags::empty());
+    assert_eq!(fails("10:15", "10:05", "", "900"), ValidationFlags::STEP_MISMATCH);
+}
+
+#[test]
+fn test_local_date_and_time_validity() {
+    let fails = |value, min, max, step| local_date_and_time_validity(value, min, max, step, "");
+    assert_eq!(fails("2018-03-07T10:00", "2018-03-07T09:00", "2018-03-08T09:00", ""), ValidationFlags::empty());
+    assert_eq!(fails("2018-03-06T10:00", "2018-03-07T09:00", "", ""), ValidationFlags::RANGE_UNDERFLOW);
+
+    // The range does not wrap around.
+    assert_eq!(fails("2018-03-07T10:00", "2018-03-08T09:00", "2018-03-07T09:00", ""),
+               ValidationFlags::RANGE_UNDERFLOW | ValidationFlags::RANGE_OVERFLOW);
+
+    // Steps are counted across days.
+    assert_eq!(fails("2018-03-08T09:00", "2018-03-07T09:00", "", "86400"), ValidationFlags::empty());
+    assert_eq!(fails("2018-03-08T10:00", "2018-03-07T09:00", "", "86400"), ValidationFlags::STEP_MISMATCH);
+    assert_eq!(fails("2018-03-08T10:00:01", "", "", ""), ValidationFlags::STEP_MISMATCH);
+}
+
+#[test]
+fn test_range_steps_for_key() {
+    // The value a key moves the thumb to, as the range control steps it from its minimum.
+    let range_value_for_key = |key, value, min: f64, max: f64, step| {
+        range_steps_for_key(key, max - min, step).map(|steps| {
+            step_number(value, min, step, Some(min), Some(max), steps).unwrap_or(value)
+        })
+    };
+    assert_eq!(range_value_for_key(Key::Right, 50.0, 0.0, 100.0, 1.0), Some(51.0));
+    assert_eq!(range_value_for_key(Key::Down, 50.0, 0.0, 100.0, 1.0), Some(49.0));
+    assert_eq!(range_value_for_key(Key::Right, 0.5, 0.0, 1.0, 0.1), Some(0.6));
+
+    // Page Up and Page Down move a tenth of the range, and stop at its ends.
+    assert_eq!(range_steps_for_key(Key::PageUp, 100.0, 1.0), Some(10));
+    assert_eq!(range_steps_for_key(Key::PageDown, 1.0, 0.1), Some(-1));
+    assert_eq!(range_value_for_key(Key::PageUp, 50.0, 0.0, 100.0, 1.0), Some(60.0));
+    assert_eq!(range_value_for_key(Key::PageDown, 5.0, 0.0, 100.0, 1.0), Some(0.0));
+    assert_eq!(range_value_for_key(Key::Up, 100.0, 0.0, 100.0, 1.0), Some(100.0));
+
+    // Home and End go to the first and last steps of the range.
+    assert_eq!(range_value_for_key(Key::Home, 50.0, 10.0, 100.0, 3.0), Some(10.0));
+    assert_eq!(range_value_for_key(Key::End, 50.0, 10.0, 100.0, 3.0), Some(100.0));
+    assert_eq!(range_value_for_key(Key::End, 50.0, 0.0, 100.0, 3.0), Some(99.0));
+
+    assert_eq!(range_steps_for_key(Key::A, 100.0, 1.0), None);
+}
+
+#[test]
+fn test_sanitize_email() {
+    assert_eq!(sanitize_email(" a@b.c\n ", false), "a@b.c");
+    assert_eq!(sanitize_email(" a@b.c , d@e.f,", false), "a@b.c , d@e.f,");
+    assert_eq!(sanitize_email(" a@b.c , d@e.f,", true), "a@b.c,d@e.f,");
+    assert_eq!(sanitize_email("", true), "");
+}
+
+#[test]
+fn test_email_validity() {
+    assert!(is_valid_email_address("first.last+tag@example.com"));
+    assert!(is_valid_email_address("a@localhost"));
+    assert!(!is_valid_email_address("a@-example.com"));
+    assert!(!is_valid_email_address("a@example..com"));
+    assert!(!is_valid_email_address("@example.com"));
+    assert!(!is_valid_email_address("a b@example.com"));
+    assert!(!is_valid_email_address("example.com"));
+
+    assert_eq!(email_validity("", false), ValidationFlags::empty());
+    assert_eq!(email_validity("a@b.c,d@e.f", true), ValidationFlags::empty());
+    assert_eq!(email_validity("a@b.c,d@e.f", false), ValidationFlags::TYPE_MISMATCH);
+    assert_eq!(email_validity("a@b.c,", true), ValidationFlags::TYPE_MISMATCH);
+}
+
+#[test]
+fn test_url_validity() {
+    assert_eq!(url_validity(""), ValidationFlags::empty());
+    assert_eq!(url_validity("https://example.com/a?b#c"), ValidationFlags::empty());
+    assert_eq!(url_validity("mailto:a@example.com"), ValidationFlags::empty());
+    assert_eq!(url_validity("example.com"), ValidationFlags::TYPE_MISMATCH);
+    assert_eq!(url_validity("/relative/path"), ValidationFlags::TYPE_MISMATCH);
+    assert_eq!(url_validity("http://exa mple.com"), ValidationFlags::TYPE_MISMATCH);
+}
+
+#[test]
+fn test_pattern_validity() {
+    assert_eq!(pattern_validity("[a-z]+", "", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("[a-z]+", "abc", false), ValidationFlags::empty());
+    // The pattern must match the whole value.
+    assert_eq!(pattern_validity("[a-z]+", "abc1", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity("a|b", "ab", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity(".", "\u{1F600}", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("[a-z]+@x", "a@x,b@x", true), ValidationFlags::empty());
+    assert_eq!(pattern_validity("[a-z]+@x", "a@x,b@y", true), ValidationFlags::PATTERN_MISMATCH);
+    // An invalid pattern sets no constraint.
+    assert_eq!(pattern_validity("(", "abc", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("a{", "a{", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("\\a", "b", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("(?i)a", "b", false), ValidationFlags::empty());
+}
+
+#[test]
+fn test_pattern_validity_ecmascript_syntax() {
+    // Character classes only cover ASCII, as in ECMAScript.
+    assert_eq!(pattern_validity("\\d", "3", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("\\d", "\u{663}", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity("\\w+", "abc_1", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("\\w+", "caf\u{E9}", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity("[^\\W]", "\u{E9}", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity("a\\b", "a", false), ValidationFlags::empty());
+    // `.` matches neither line terminators nor nothing at all.
+    assert_eq!(pattern_validity("a.b", "a\rb", false), ValidationFlags::PATTERN_MISMATCH);
+    assert_eq!(pattern_validity("\\u{1F600}", "\u{1F600}", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("\\uD83D\\uDE00", "\u{1F600}", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("(?<word>[a-z]+)", "abc", false), ValidationFlags::empty());
+    // Lookarounds and backreferences can not be checked, and set no constraint.
+    assert_eq!(pattern_validity("(?=a)b", "b", false), ValidationFlags::empty());
+    assert_eq!(pattern_validity("(a)\\1", "ab", false), ValidationFlags::empty());
+}
+
+#[test]
+fn test_tel_char_filter() {
+    let filter = |text: &str| text.chars().filter_map(tel_char_filter).collect::<String>();
+    assert_eq!(filter("+1 (555) 010-9999"), "+1 (555) 010-9999");
+    assert_eq!(filter("*#31#"), "*#31#");
+    assert_eq!(filter("call 555\n0199"), " 5550199");
+    assert_eq!(filter("０９０"), "090");
+}
+
+#[test]
+fn test_caps_lock_state() {
+    assert_eq!(caps_lock_state(Some('a'), false), Some(false));
+    assert_eq!(caps_lock_state(Some('A'), true), Some(false));
+    assert_eq!(caps_lock_state(Some('A'), false), Some(true));
+    assert_eq!(caps_lock_state(Some('é'), true), Some(true));
+
+    // Keys that type no letter, or a letter without case, tell nothing.
+    assert_eq!(caps_lock_state(Some('1'), false), None);
+    assert_eq!(caps_lock_state(Some('あ'), false), None);
+    assert_eq!(caps_lock_state(None, true), None);
+}
+
+#[test]
+fn test_matching_suggestions() {
+    let suggestion = |value: &str, label: &str| Suggestion { value: value.to_owned(), label: label.to_owned() };
+    let options = || vec![suggestion("Amsterdam", ""), suggestion("AMS", "Schiphol"), suggestion("Berlin", "")];
+
+    assert_eq!(matching_suggestions(options(), ""), options());
+    assert_eq!(matching_suggestions(options(), "am"),
+               vec![suggestion("Amsterdam", ""), suggestion("AMS", "Schiphol")]);
+    assert_eq!(matching_suggestions(options(), "schip"), vec![suggestion("AMS", "Schiphol")]);
+    // The option the user already typed in full is not offered again.
+    assert_eq!(matching_suggestions(options(), "ams"), vec![suggestion("Amsterdam", "")]);
+    assert_eq!(matching_suggestions(options(), "paris"), vec![]);
+}
@@ -0,0 +1,100 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::{Key, KeyModifiers};
+use script::keybindings::{EditCommand, KeyBindings, Platform};
+
+#[test]
+fn test_default_bindings_follow_platform_conventions() {
+    let mac = KeyBindings::for_platform(Platform::Mac);
+    assert_eq!(mac.lookup(Key::A, KeyModifiers::SUPER), Some(EditCommand::SelectAll));
+    assert_eq!(mac.lookup(Key::A, KeyModifiers::CONTROL), Some(EditCommand::MoveLineStart));
+    assert_eq!(mac.lookup(Key::Up, KeyModifiers::SUPER), Some(EditCommand::MoveDocumentStart));
+    assert_eq!(mac.lookup(Key::Home, KeyModifiers::empty()), Some(EditCommand::MoveDocumentStart));
+    assert_eq!(mac.lookup(Key::End, KeyModifiers::SHIFT), Some(EditCommand::MoveDocumentEnd));
+
+    let other = KeyBindings::for_platform(Platform::Other);
+    assert_eq!(other.lookup(Key::A, KeyModifiers::CONTROL), Some(EditCommand::SelectAll));
+    assert_eq!(other.lookup(Key::A, KeyModifiers::SUPER), None);
+    assert_eq!(other.lookup(Key::Left, KeyModifiers::CONTROL), Some(EditCommand::MoveWordBackward));
+    assert_eq!(other.lookup(Key::Home, KeyModifiers::empty()), Some(EditCommand::MoveLineStart));
+}
+
+#[test]
+fn test_cua_clipboard_bindings() {
+    let other = KeyBindings::for_platform(Platform::Other);
+    assert_eq!(other.lookup(Key::Insert, KeyModifiers::CONTROL), Some(EditCommand::Copy));
+    assert_eq!(other.lookup(Key::Delete, KeyModifiers::SHIFT), Some(EditCommand::Cut));
+    assert_eq!(other.lookup(Key::Insert, KeyModifiers::SHIFT), Some(EditCommand::Paste));
+    assert_eq!(other.lookup(Key::Delete, KeyModifiers::empty()), Some(EditCommand::DeleteForward));
+    // The keypad keys act as Insert and Delete when NumLock is off.
+    assert_eq!(other.lookup_event(None, Key::Kp0, KeyModifiers::SHIFT), Some(EditCommand::Paste));
+    assert_eq!(other.lookup_event(None, Key::KpDecimal, KeyModifiers::SHIFT), Some(EditCommand::Cut));
+
+    let mac = KeyBindings::for_platform(Platform::Mac);
+    assert_eq!(mac.lookup(Key::Insert, KeyModifiers::SHIFT), None);
+    assert_eq!(mac.lookup(Key::Delete, KeyModifiers::SHIFT), Some(EditCommand::DeleteForward));
+}
+
+#[test]
+fn test_shift_falls_back_to_unshifted_binding() {
+    let mut bindings = KeyBindings::for_platform(Platform::Other);
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::SHIFT), Some(EditCommand::MoveCharBackward));
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::SHIFT | KeyModifiers::ALT),
+               Some(EditCommand::MoveWordBackward));
+
+    bindings.bind(Key::Left, KeyModifiers::SHIFT, Some(EditCommand::MoveLineStart));
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::SHIFT), Some(EditCommand::MoveLineStart));
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::empty()), Some(EditCommand::MoveCharBackward));
+}
+
+#[test]
+fn test_apply_overrides() {
+    let mut bindings = KeyBindings::for_platform(Platform::Other);
+    bindings.apply_overrides("Control+k=delete-to-line-start  Alt+ArrowLeft=none\n\
+                              Ctrl+Shift++=select-all Hyper+x=copy Control+y=yank");
+
+    assert_eq!(bindings.lookup(Key::K, KeyModifiers::CONTROL), Some(EditCommand::DeleteToLineStart));
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::ALT), None);
+    assert_eq!(bindings.lookup(Key::Equal, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+               Some(EditCommand::SelectAll));
+    assert_eq!(bindings.lookup(Key::X, KeyModifiers::empty()), None);
+    assert_eq!(bindings.lookup(Key::Y, KeyModifiers::CONTROL), None);
+
+    // Bindings that were not overridden are untouched.
+    assert_eq!(bindings.lookup(Key::Left, KeyModifiers::empty()), Some(EditCommand::MoveCharBackward));
+}
+
+#[test]
+fn test_altgr_characters_are_not_shortcuts() {
+    let bindings = KeyBindings::for_platform(Platform::Other);
+    let altgr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    assert_eq!(bindings.lookup_event(Some('\u{20AC}'), Key::E, altgr), None);
+    assert_eq!(bindings.lookup_event(Some('@'), Key::Q, altgr), None);
+    assert_eq!(bindings.lookup_event(Some('{'), Key::B, altgr), None);
+    assert_eq!(bindings.lookup_event(Some('}'), Key::F, altgr | KeyModifiers::SHIFT), None);
+    // The key's own character, a control character or none at all is a shortcut.
+    assert_eq!(bindings.lookup_event(Some('b'), Key::B, altgr), Some(EditCommand::MoveWordBackward));
+    assert_eq!(bindings.lookup_event(Some('\u{6}'), Key::F, altgr), Some(EditCommand::MoveWordForward));
+    assert_eq!(bindings.lookup_event(None, Key::E, altgr), Some(EditCommand::MoveLineEnd));
+    // Other modifiers are left alone.
+    assert_eq!(bindings.lookup_event(Some('a'), Key::A, KeyModifiers::CONTROL), Some(EditCommand::SelectAll));
+}
+
+#[test]
+fn test_keypad_navigation_without_numlock() {
+    let bindings = KeyBindings::for_platform(Platform::Other);
+    assert_eq!(bindings.lookup_event(None, Key::Kp4, KeyModifiers::empty()), Some(EditCommand::MoveCharBackward));
+    assert_eq!(bindings.lookup_event(None, Key::Kp6, KeyModifiers::CONTROL), Some(EditCommand::MoveWordForward));
+    assert_eq!(bindings.lookup_event(None, Key::Kp8, KeyModifiers::SHIFT), Some(EditCommand::MoveUp));
+    assert_eq!(bindings.lookup_event(None, Key::Kp7, KeyModifiers::empty()), Some(EditCommand::MoveLineStart));
+    assert_eq!(bindings.lookup_event(None, Key::Kp3, KeyModifiers::empty()), Some(EditCommand::MovePageDown));
+    assert_eq!(bindings.lookup_event(None, Key::KpDecimal, KeyModifiers::empty()), Some(EditCommand::DeleteForward));
+    assert_eq!(bindings.lookup_event(None, Key::Kp5, KeyModifiers::empty()), None);
+    // With NumLock on, the keys type digits.
+    assert_eq!(bindings.lookup_event(Some('4'), Key::Kp4, KeyModifiers::empty()), None);
+
+    let mac = KeyBindings::for_platform(Platform::Mac);
+    assert_eq!(mac.lookup_event(None, Key::Kp1, KeyModifiers::empty()), Some(EditCommand::MoveDocumentEnd));
+}
@@ -3,17 +3,27 @@
  * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
 
 #[cfg(test)] extern crate euclid;
+#[cfg(test)] extern crate ipc_channel;
 #[cfg(test)] extern crate msg;
 #[cfg(test)] extern crate script;
 #[cfg(test)] extern crate servo_url;
 #[cfg(test)] extern crate style;
 
+#[cfg(test)] mod contenteditable;
+#[cfg(test)] mod keybindings;
 #[cfg(test)] mod origin;
+#[cfg(test)] mod segmented_input;
 #[cfg(all(test, target_pointer_width = "64"))] mod size_of;
+#[cfg(test)] mod test_clipboard;
+#[cfg(test)] mod text_buffer;
 #[cfg(test)] mod textinput;
+#[cfg(test)] mod textinput_invariants;
+#[cfg(test)] mod word_segmentation;
 #[cfg(test)] mod headers;
 #[cfg(test)] mod htmlareaelement;
 #[cfg(test)] mod htmlimageelement;
+#[cfg(test)] mod htmlinputelement;
+#[cfg(test)] mod textcontrol;
 
 /**
 ```compile_fail,E0277
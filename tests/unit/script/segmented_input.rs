@@ -0,0 +1,274 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::{Key, KeyModifiers};
+use script::segmented_input::{EMPTY_DATE, EMPTY_MONTH, EMPTY_WEEK, SegmentReaction, SegmentedInput, TimePrecision};
+use script::segmented_input::{date_from_milliseconds, date_in_milliseconds, days_in_month, weeks_in_year};
+use script::segmented_input::{local_date_and_time_display, local_date_and_time_from_milliseconds};
+use script::segmented_input::{local_date_and_time_in_milliseconds, local_date_and_time_value, time_display};
+use script::segmented_input::{month_from_months, month_in_months, time_from_milliseconds, time_in_milliseconds};
+use script::segmented_input::{time_value, week_from_milliseconds, week_in_milliseconds};
+use script::textinput::Direction;
+
+/// Press the key for each character of `keys` in turn, keeping the fields as they are shown.
+fn type_keys(input: &mut SegmentedInput, display: &mut String, keys: &str) {
+    for c in keys.chars() {
+        let reaction = input.handle_keydown_aux(display, Some(c), Key::A, KeyModifiers::empty());
+        if let SegmentReaction::Changed(shown) = reaction {
+            *display = shown;
+        }
+    }
+}
+
+fn press(input: &mut SegmentedInput, display: &mut String, key: Key, mods: KeyModifiers) -> SegmentReaction {
+    let reaction = input.handle_keydown_aux(display, None, key, mods);
+    if let SegmentReaction::Changed(ref shown) = reaction {
+        *display = shown.clone();
+    }
+    reaction
+}
+
+#[test]
+fn test_segmented_input_typing() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = EMPTY_DATE.to_owned();
+
+    // Each field moves on to the next once no more digits fit.
+    type_keys(&mut input, &mut display, "2018");
+    assert_eq!(display, "2018-mm-dd");
+    assert_eq!(input.focused(), 1);
+    type_keys(&mut input, &mut display, "3");
+    assert_eq!(display, "2018-03-dd");
+    assert_eq!(input.focused(), 2);
+    type_keys(&mut input, &mut display, "07");
+    assert_eq!(display, "2018-03-07");
+    assert_eq!(input.focused_range(&display), 8..10);
+
+    // The last field starts over.
+    type_keys(&mut input, &mut display, "4");
+    assert_eq!(display, "2018-03-04");
+
+    // Digits overwrite the field, and a separator finishes it.
+    input.focus(1);
+    type_keys(&mut input, &mut display, "1/");
+    assert_eq!(display, "2018-01-04");
+    assert_eq!(input.focused(), 2);
+
+    // A field is cleared, and other keys are rejected.
+    assert_eq!(press(&mut input, &mut display, Key::Backspace, KeyModifiers::empty()),
+               SegmentReaction::Changed("2018-01-dd".to_owned()));
+    assert_eq!(input.handle_keydown_aux(&display, Some('x'), Key::X, KeyModifiers::empty()),
+               SegmentReaction::Rejected);
+}
+
+#[test]
+fn test_segmented_input_stepping() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = EMPTY_DATE.to_owned();
+
+    // An empty year starts at the reference year.
+    press(&mut input, &mut display, Key::Up, KeyModifiers::empty());
+    assert_eq!(display, "2018-mm-dd");
+
+    input.focus(1);
+    press(&mut input, &mut display, Key::Down, KeyModifiers::empty());
+    assert_eq!(display, "2018-12-dd");
+    press(&mut input, &mut display, Key::Up, KeyModifiers::empty());
+    assert_eq!(display, "2018-01-dd");
+
+    // Days wrap around at the end of the month, and are kept within it.
+    input.focus(2);
+    press(&mut input, &mut display, Key::Down, KeyModifiers::empty());
+    assert_eq!(display, "2018-01-31");
+    input.focus(1);
+    press(&mut input, &mut display, Key::Up, KeyModifiers::empty());
+    assert_eq!(display, "2018-02-28");
+}
+
+#[test]
+fn test_segmented_input_focus() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = "2018-03-07".to_owned();
+
+    assert_eq!(press(&mut input, &mut display, Key::Left, KeyModifiers::empty()), SegmentReaction::Nothing);
+    assert_eq!(press(&mut input, &mut display, Key::Right, KeyModifiers::empty()), SegmentReaction::SegmentFocused);
+    assert_eq!(press(&mut input, &mut display, Key::Tab, KeyModifiers::empty()), SegmentReaction::SegmentFocused);
+    assert_eq!(input.focused(), 2);
+
+    // Tab leaves the control from the last field.
+    assert_eq!(press(&mut input, &mut display, Key::Tab, KeyModifiers::empty()),
+               SegmentReaction::FocusNavigation(Direction::Forward));
+    assert_eq!(press(&mut input, &mut display, Key::Tab, KeyModifiers::SHIFT), SegmentReaction::SegmentFocused);
+    assert_eq!(input.focused(), 1);
+
+    input.focus_at(&display, 9);
+    assert_eq!(input.focused(), 2);
+    input.focus_at(&display, 0);
+    assert_eq!(input.focused(), 0);
+}
+
+#[test]
+fn test_segmented_input_month_and_week() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = EMPTY_MONTH.to_owned();
+    type_keys(&mut input, &mut display, "20189");
+    assert_eq!(display, "2018-09");
+    assert_eq!(input.focused_range(&display), 5..7);
+
+    // The week follows its W, and is kept within the weeks of the year.
+    let mut input = SegmentedInput::new(2018);
+    let mut display = EMPTY_WEEK.to_owned();
+    type_keys(&mut input, &mut display, "2015");
+    assert_eq!(input.focused_range(&display), 6..8);
+    press(&mut input, &mut display, Key::Down, KeyModifiers::empty());
+    assert_eq!(display, "2015-W53");
+    input.focus(0);
+    press(&mut input, &mut display, Key::Up, KeyModifiers::empty());
+    assert_eq!(display, "2016-W52");
+    input.focus_at(&display, 7);
+    assert_eq!(input.focused(), 1);
+}
+
+#[test]
+fn test_segmented_input_time() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = time_display("", TimePrecision::Second, true);
+    assert_eq!(display, "hh:mm:ss --");
+
+    // An hour on a twelve hour clock is from 1 to 12, and AM or PM is typed as a letter.
+    type_keys(&mut input, &mut display, "2");
+    assert_eq!(display, "02:mm:ss --");
+    assert_eq!(input.focused(), 1);
+    type_keys(&mut input, &mut display, "7");
+    assert_eq!(display, "02:07:ss --");
+    type_keys(&mut input, &mut display, "45p");
+    assert_eq!(display, "02:07:45 PM");
+    assert_eq!(input.focused_range(&display), 9..11);
+    assert_eq!(time_value(&display), "14:07:45");
+
+    press(&mut input, &mut display, Key::Up, KeyModifiers::empty());
+    assert_eq!(display, "02:07:45 AM");
+    input.focus(0);
+    press(&mut input, &mut display, Key::Down, KeyModifiers::empty());
+    assert_eq!(display, "01:07:45 AM");
+    assert_eq!(time_value(&display), "01:07:45");
+
+    // An hour on a 24 hour clock is from 0 to 23.
+    let mut display = time_display("", TimePrecision::Minute, false);
+    input.focus(0);
+    press(&mut input, &mut display, Key::Down, KeyModifiers::empty());
+    assert_eq!(display, "23:mm");
+    assert_eq!(time_value(&display), "");
+    input.focus(0);
+    type_keys(&mut input, &mut display, "0930");
+    assert_eq!(display, "09:30");
+    assert_eq!(time_value(&display), "09:30");
+}
+
+#[test]
+fn test_time_display() {
+    assert_eq!(time_display("13:05", TimePrecision::Minute, false), "13:05");
+    assert_eq!(time_display("13:05", TimePrecision::Minute, true), "01:05 PM");
+    assert_eq!(time_display("12:00", TimePrecision::Minute, true), "12:00 PM");
+    assert_eq!(time_display("13:05:09.25", TimePrecision::Minute, false), "13:05:09.250");
+    assert_eq!(time_display("13:60", TimePrecision::Minute, false), "hh:mm");
+
+    assert_eq!(time_value("12:30 AM"), "00:30");
+    assert_eq!(time_value("13:05:09.25"), "13:05:09.25");
+    assert_eq!(time_in_milliseconds("01:00:01.5"), Some(3601500.0));
+    assert_eq!(time_in_milliseconds("1:00"), None);
+}
+
+#[test]
+fn test_segmented_input_local_date_and_time() {
+    let mut input = SegmentedInput::new(2018);
+    let mut display = local_date_and_time_display("", TimePrecision::Minute, true);
+    assert_eq!(display, "yyyy-mm-dd hh:mm --");
+
+    // Typing moves on from the date to the time.
+    type_keys(&mut input, &mut display, "20180307");
+    assert_eq!(display, "2018-03-07 hh:mm --");
+    assert_eq!(input.focused(), 3);
+    type_keys(&mut input, &mut display, "1205a");
+    assert_eq!(display, "2018-03-07 12:05 AM");
+    assert_eq!(input.focused_range(&display), 17..19);
+    assert_eq!(local_date_and_time_value(&display), "2018-03-07T00:05");
+
+    input.focus_at(&display, 12);
+    assert_eq!(input.focused(), 3);
+    press(&mut input, &mut display, Key::Backspace, KeyModifiers::empty());
+    assert_eq!(local_date_and_time_value(&display), "");
+}
+
+#[test]
+fn test_local_date_and_time_display() {
+    assert_eq!(local_date_and_time_display("2018-03-07T13:05", TimePrecision::Minute, false), "2018-03-07 13:05");
+    assert_eq!(local_date_and_time_display("2018-03-07 13:05:00", TimePrecision::Second, true),
+               "2018-03-07 01:05:00 PM");
+    assert_eq!(local_date_and_time_display("2018-02-30T13:05", TimePrecision::Minute, false),
+               "yyyy-mm-dd hh:mm");
+
+    // The value is normalized.
+    assert_eq!(local_date_and_time_value("2018-03-07 13:05:00.500"), "2018-03-07T13:05:00.5");
+    assert_eq!(local_date_and_time_value("2018-03-07 01:05:00 PM"), "2018-03-07T13:05");
+    assert_eq!(local_date_and_time_value("2018-03-07"), "");
+
+    assert_eq!(local_date_and_time_in_milliseconds("1970-01-01T00:00"), Some(0.0));
+    assert_eq!(local_date_and_time_in_milliseconds("2000-03-01T00:00:01"), Some(951868801000.0));
+    assert_eq!(local_date_and_time_in_milliseconds("1969-12-31T23:59"), Some(-60000.0));
+}
+
+#[test]
+fn test_milliseconds_to_values() {
+    assert_eq!(date_in_milliseconds("1970-01-02"), Some(86400000.0));
+    assert_eq!(date_from_milliseconds(86400000.0), "1970-01-02");
+    assert_eq!(date_from_milliseconds(-1.0), "1969-12-31");
+    assert_eq!(date_from_milliseconds(date_in_milliseconds("2000-02-29").unwrap()), "2000-02-29");
+    // Before the year 1, a date input has no value.
+    assert_eq!(date_from_milliseconds(-62135596800001.0), "");
+
+    assert_eq!(time_from_milliseconds(3601500.0), "01:00:01.5");
+    assert_eq!(time_from_milliseconds(43260000.0), "12:01");
+    assert_eq!(time_from_milliseconds(86400000.0), "");
+
+    assert_eq!(local_date_and_time_from_milliseconds(951868801000.0), "2000-03-01T00:00:01");
+    assert_eq!(local_date_and_time_from_milliseconds(-60000.0), "1969-12-31T23:59");
+}
+
+#[test]
+fn test_months_and_weeks() {
+    assert_eq!(month_in_months("1970-01"), Some(0.0));
+    assert_eq!(month_in_months("2018-03"), Some(578.0));
+    assert_eq!(month_in_months("2018-13"), None);
+    assert_eq!(month_from_months(578.0), "2018-03");
+    assert_eq!(month_from_months(-1.0), "1969-12");
+
+    // The first week of 1970 started on Monday 1969-12-29.
+    assert_eq!(week_in_milliseconds("1970-W01"), Some(-259200000.0));
+    assert_eq!(week_in_milliseconds("2015-W53"), Some(1451260800000.0));
+    assert_eq!(week_in_milliseconds("2018-W53"), None);
+    assert_eq!(week_from_milliseconds(0.0), "1970-W01");
+    // 2016-01-01 was a Friday, in the last week of 2015.
+    assert_eq!(week_from_milliseconds(1451606400000.0), "2015-W53");
+}
+
+#[test]
+fn test_days_in_month() {
+    assert_eq!(days_in_month(Some(2018), Some(2)), 28);
+    assert_eq!(days_in_month(Some(2000), Some(2)), 29);
+    assert_eq!(days_in_month(Some(1900), Some(2)), 28);
+    assert_eq!(days_in_month(None, Some(2)), 29);
+    assert_eq!(days_in_month(None, Some(9)), 30);
+    assert_eq!(days_in_month(None, None), 31);
+}
+
+#[test]
+fn test_weeks_in_year() {
+    assert_eq!(weeks_in_year(Some(2015)), 53);
+    assert_eq!(weeks_in_year(Some(2018)), 52);
+    assert_eq!(weeks_in_year(Some(2020)), 53);
+    assert_eq!(weeks_in_year(Some(1908)), 53);
+    assert_eq!(weeks_in_year(Some(1909)), 52);
+    assert_eq!(weeks_in_year(None), 53);
+}
@@ -0,0 +1,36 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor};
+use script::clipboard_provider::ClipboardProvider;
+use script::test_clipboard::{ClipboardCall, TestClipboardProvider};
+
+#[test]
+fn test_clipboard_calls_are_logged() {
+    let clipboard = TestClipboardProvider::new();
+    clipboard.set_contents(ClipboardContents::from_plain_text("abc".to_owned()));
+
+    // Clones share the contents and the log.
+    let mut provider = clipboard.clone();
+    let (sender, receiver) = ipc::channel().unwrap();
+    provider.request_clipboard_contents(ClipboardFlavor::PlainText, sender);
+    assert_eq!(receiver.recv().unwrap(), Some("abc".to_owned()));
+    let (sender, receiver) = ipc::channel().unwrap();
+    provider.request_clipboard_chunk(ClipboardFlavor::Html, 0, 2, sender);
+    assert_eq!(receiver.recv().unwrap(), None);
+
+    let mut contents = ClipboardContents::from_plain_text("x".to_owned());
+    contents.set(ClipboardFlavor::Html, "<b>x</b>".to_owned());
+    provider.set_clipboard_contents(contents.clone());
+    assert_eq!(clipboard.contents(), contents);
+
+    let log = clipboard.take_log();
+    assert_eq!(log, vec!(ClipboardCall::GetContents(ClipboardFlavor::PlainText),
+                         ClipboardCall::GetChunk(ClipboardFlavor::Html, 0, 2),
+                         ClipboardCall::SetContents(contents)));
+    let log: Vec<String> = log.iter().map(ToString::to_string).collect();
+    assert_eq!(log, vec!("get-contents text/plain", "get-chunk text/html 0 2", "set-contents text/plain text/html"));
+    assert!(clipboard.log().is_empty());
+}
@@ -0,0 +1,50 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::test::DOMString;
+use script::text_buffer::{GapBuffer, TextBuffer};
+
+fn lines(strings: &[&str]) -> Vec<DOMString> {
+    strings.iter().map(|s| DOMString::from(*s)).collect()
+}
+
+fn contents<B: TextBuffer>(buffer: &B) -> Vec<String> {
+    buffer.iter().map(|line| String::from(line.clone())).collect()
+}
+
+#[test]
+fn test_gap_buffer_replace_lines() {
+    let mut buffer = GapBuffer::from_lines(lines(&["a", "b", "c", "d"]));
+    assert_eq!(buffer.len(), 4);
+
+    buffer.replace_lines(1..3, lines(&["x", "y", "z"]));
+    assert_eq!(contents(&buffer), ["a", "x", "y", "z", "d"]);
+
+    // Edits before and after the gap.
+    buffer.replace_lines(0..1, lines(&["A"]));
+    assert_eq!(contents(&buffer), ["A", "x", "y", "z", "d"]);
+    buffer.replace_lines(4..5, vec!());
+    assert_eq!(contents(&buffer), ["A", "x", "y", "z"]);
+    buffer.replace_lines(2..2, lines(&["new"]));
+    assert_eq!(contents(&buffer), ["A", "x", "new", "y", "z"]);
+
+    assert_eq!(&*buffer[3], "y");
+    assert_eq!(buffer.get(5), None);
+    buffer[3].push_str("!");
+    assert_eq!(contents(&buffer), ["A", "x", "new", "y!", "z"]);
+}
+
+#[test]
+fn test_gap_buffer_matches_vec() {
+    let mut gap = GapBuffer::from_lines(lines(&["one", "two", "three"]));
+    let mut vec = lines(&["one", "two", "three"]);
+    let edits = [(0, 0, 2), (3, 5, 1), (2, 3, 0), (1, 2, 3), (4, 5, 1)];
+    for &(start, end, count) in edits.iter() {
+        let insert: Vec<DOMString> = (0..count).map(|i| DOMString::from(format!("{}-{}", start, i))).collect();
+        gap.replace_lines(start..end, insert.clone());
+        TextBuffer::replace_lines(&mut vec, start..end, insert);
+        assert_eq!(contents(&gap), contents(&vec));
+        assert_eq!(gap.iter_range(1..3).rev().collect::<Vec<_>>(), vec[1..3].iter().rev().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,48 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use msg::constellation_msg::{AutofillHint, EnterKeyHint, InputMode};
+use script::test::textcontrol::{parse_autocomplete, parse_enter_key_hint, parse_input_mode};
+
+#[test]
+fn test_parse_input_mode() {
+    assert_eq!(parse_input_mode("numeric"), Some(InputMode::Numeric));
+    assert_eq!(parse_input_mode("none"), Some(InputMode::None));
+    assert_eq!(parse_input_mode("EMail"), Some(InputMode::Email));
+    assert_eq!(parse_input_mode(" tel"), None);
+    assert_eq!(parse_input_mode("latin"), None);
+    assert_eq!(parse_input_mode(""), None);
+}
+
+#[test]
+fn test_parse_enter_key_hint() {
+    assert_eq!(parse_enter_key_hint("send"), Some(EnterKeyHint::Send));
+    assert_eq!(parse_enter_key_hint("Previous"), Some(EnterKeyHint::Previous));
+    assert_eq!(parse_enter_key_hint("return"), None);
+    assert_eq!(parse_enter_key_hint(""), None);
+}
+
+#[test]
+fn test_parse_autocomplete() {
+    let field = |name: &str| parse_autocomplete(name).map(|hint| hint.field);
+    assert_eq!(field("username"), Some("username".to_owned()));
+    assert_eq!(field(" One-Time-Code "), Some("one-time-code".to_owned()));
+    assert_eq!(field("off"), Some("off".to_owned()));
+
+    assert_eq!(parse_autocomplete("section-login shipping work email webauthn"), Some(AutofillHint {
+        field: "email".to_owned(),
+        section: Some("section-login".to_owned()),
+        address_type: Some("shipping".to_owned()),
+        contact_type: Some("work".to_owned()),
+        webauthn: true,
+    }));
+
+    // Contact types only go with contact fields, and the tokens must be in order.
+    assert_eq!(parse_autocomplete("work username"), None);
+    assert_eq!(parse_autocomplete("billing section-a street-address"), None);
+    // `on` and `off` stand alone.
+    assert_eq!(parse_autocomplete("shipping off"), None);
+    assert_eq!(parse_autocomplete("password"), None);
+    assert_eq!(parse_autocomplete(""), None);
+}
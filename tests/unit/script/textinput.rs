@@ -7,10 +7,21 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use msg::constellation_msg::{Key, KeyModifiers};
-use script::clipboard_provider::DummyClipboardContext;
+use ipc_channel::ipc;
+use msg::constellation_msg::{ClipboardContents, ClipboardFlavor, ImeSurroundingText, Key, KeyModifiers};
+use msg::constellation_msg::clipboard_text_chunk;
+use script::clipboard_provider::{CLIPBOARD_CHUNK_SIZE, ClipboardChunkReader, ClipboardProvider, DummyClipboardContext};
+use script::key_sequence::KeySequence;
+use script::keybindings::{EditCommand, KeyBindings, Platform};
+use script::spellcheck_provider::{DummySpellCheckProvider, SpellCheckProvider};
 use script::test::DOMString;
-use script::textinput::{TextInput, TextPoint, Selection, Lines, Direction, SelectionDirection};
+use script::textinput::{TextInput, TextPoint, Selection, Lines, Direction, SelectionDirection, KeyReaction};
+use script::text_buffer::{GapBuffer, TextBuffer};
+use script::word_segmentation::WordSelectionPolicy;
+use script::textinput::{EscapeOutcome, GraphemePoint, LengthUnit, PasteSanitization, SelectionGranularity};
+use script::textinput::{CompositionClause, Edit, TextChange, TextDirection, WordCorrection, first_strong_direction};
+use std::thread;
+use std::time::Duration;
 
 fn text_input(lines: Lines, s: &str) -> TextInput<DummyClipboardContext> {
     TextInput::new(lines,
@@ -21,6 +32,49 @@ fn text_input(lines: Lines, s: &str) -> TextInput<DummyClipboardContext> {
                    SelectionDirection::None)
 }
 
+/// Read the contents that a paste is waiting for, answering each request for them straight away.
+fn read_paste<B: TextBuffer>(textinput: &TextInput<DummyClipboardContext, B>) -> ClipboardContents {
+    read_paste_slowly(textinput, Duration::from_millis(0))
+}
+
+/// Read the contents that a paste is waiting for, answering each request for them after `delay`,
+/// as a slow clipboard does.
+fn read_paste_slowly<B: TextBuffer>(textinput: &TextInput<DummyClipboardContext, B>,
+                                    delay: Duration) -> ClipboardContents {
+    let mut reader = textinput.paste_reader();
+    let (length_sender, length_receiver) = ipc::channel().unwrap();
+    let (chunk_sender, chunk_receiver) = ipc::channel().unwrap();
+    reader.request_length(length_sender);
+    thread::sleep(delay);
+    if reader.receive_length(length_receiver.recv().unwrap()) {
+        while reader.request_next_chunk(chunk_sender.clone()) {
+            thread::sleep(delay);
+            if !reader.receive_chunk(chunk_receiver.recv().unwrap()) {
+                break;
+            }
+        }
+    }
+    reader.into_contents()
+}
+
+/// Paste the clipboard contents, answering the request for them straight away.
+fn paste_from_clipboard<B: TextBuffer>(textinput: &mut TextInput<DummyClipboardContext, B>) -> KeyReaction {
+    assert_eq!(textinput.execute_command(EditCommand::Paste, Selection::NotSelected), KeyReaction::PasteRequested);
+    let contents = read_paste(textinput);
+    textinput.finish_paste(contents)
+}
+
+/// Check the spelling of the text edited since the last check, answering the request straight
+/// away.
+fn check_spelling(textinput: &mut TextInput<DummyClipboardContext>, spellchecker: &mut DummySpellCheckProvider,
+                  include_word_at_caret: bool) {
+    if let Some(request) = textinput.spelling_request(include_word_at_caret) {
+        let (sender, receiver) = ipc::channel().unwrap();
+        spellchecker.request_spell_check(request.texts.clone(), "en".to_owned(), sender);
+        assert!(textinput.receive_misspellings(request, receiver.recv().unwrap()));
+    }
+}
+
 #[test]
 fn test_set_content_ignores_max_length() {
     let mut textinput = TextInput::new(
@@ -31,6 +85,63 @@ fn test_set_content_ignores_max_length() {
     assert_eq!(textinput.get_content(), DOMString::from("mozilla rocks"));
 }
 
+#[test]
+fn test_set_content_within_max_length() {
+    let mut textinput = TextInput::new(
+        Lines::Single, DOMString::from(""), DummyClipboardContext::new(""), Some(7), None, SelectionDirection::None
+    );
+
+    textinput.set_content_within_max_length(DOMString::from("mozilla rocks"));
+    assert_eq!(textinput.get_content(), DOMString::from("mozilla"));
+
+    textinput.max_length = None;
+    textinput.set_content_within_max_length(DOMString::from("mozilla rocks"));
+    assert_eq!(textinput.get_content(), DOMString::from("mozilla rocks"));
+}
+
+#[test]
+fn test_max_length_only_limits_user_insertions() {
+    let mut textinput = TextInput::new(
+        Lines::Single, DOMString::from(""), DummyClipboardContext::new(""), Some(7), None, SelectionDirection::None
+    );
+
+    // Values set by script or by the value attribute are kept in full.
+    textinput.set_content(DOMString::from("mozilla rocks"), true);
+    assert_eq!(textinput.get_content(), DOMString::from("mozilla rocks"));
+    assert!(textinput.is_too_long());
+
+    textinput.select_all();
+    textinput.replace_selection_ignoring_max_length(DOMString::from("servo rocks"));
+    assert_eq!(textinput.get_content(), DOMString::from("servo rocks"));
+
+    // Typing into an over-long value inserts nothing.
+    textinput.insert_char('!');
+    assert_eq!(textinput.get_content(), DOMString::from("servo rocks"));
+}
+
+#[test]
+fn test_too_long_and_too_short() {
+    let mut textinput = TextInput::new(
+        Lines::Single, DOMString::from(""), DummyClipboardContext::new(""), Some(5), Some(3), SelectionDirection::None
+    );
+    // An empty value is never too short.
+    assert!(!textinput.is_too_long());
+    assert!(!textinput.is_too_short());
+
+    textinput.set_content(DOMString::from("ab"), true);
+    assert!(textinput.is_too_short());
+
+    textinput.set_content(DOMString::from("abc"), true);
+    assert!(!textinput.is_too_short());
+
+    textinput.set_content(DOMString::from("abcdef"), true);
+    assert!(textinput.is_too_long());
+    assert!(!textinput.is_too_short());
+
+    textinput.max_length = None;
+    assert!(!textinput.is_too_long());
+}
+
 #[test]
 fn test_textinput_when_inserting_multiple_lines_over_a_selection_respects_max_length() {
     let mut textinput = TextInput::new(
@@ -303,9 +414,10 @@ fn test_textinput_adjust_vertical() {
     assert_eq!(textinput.edit_point.line, 1);
     assert_eq!(textinput.edit_point.index, 2);
 
+    // Moving back up returns to the original column, even though line 1 was shorter.
     textinput.adjust_vertical(-1, Selection::NotSelected);
     assert_eq!(textinput.edit_point.line, 0);
-    assert_eq!(textinput.edit_point.index, 2);
+    assert_eq!(textinput.edit_point.index, 3);
 
     textinput.adjust_vertical(2, Selection::NotSelected);
     assert_eq!(textinput.edit_point.line, 2);
@@ -458,13 +570,20 @@ fn test_navigation_keyboard_shortcuts() {
 fn test_textinput_handle_return() {
     let mut single_line_textinput = text_input(Lines::Single, "abcdef");
     single_line_textinput.adjust_horizontal(3, Selection::NotSelected);
-    single_line_textinput.handle_return();
+    assert_eq!(single_line_textinput.handle_return(), KeyReaction::ImplicitSubmission);
     assert_eq!(single_line_textinput.get_content(), "abcdef");
 
+    single_line_textinput.set_enter_triggers_action(true);
+    assert_eq!(single_line_textinput.handle_return(), KeyReaction::ImplicitSubmission);
+
     let mut multi_line_textinput = text_input(Lines::Multiple, "abcdef");
     multi_line_textinput.adjust_horizontal(3, Selection::NotSelected);
     multi_line_textinput.handle_return();
     assert_eq!(multi_line_textinput.get_content(), "abc\ndef");
+
+    multi_line_textinput.set_enter_triggers_action(true);
+    assert_eq!(multi_line_textinput.handle_return(), KeyReaction::TriggerDefaultAction);
+    assert_eq!(multi_line_textinput.get_content(), "abc\ndef");
 }
 
 #[test]
@@ -521,7 +640,10 @@ fn test_clipboard_paste() {
                                        SelectionDirection::None);
     assert_eq!(textinput.get_content(), "defg");
     assert_eq!(textinput.edit_point.index, 0);
-    textinput.handle_keydown_aux(Some('v'), Key::V, MODIFIERS);
+    assert_eq!(textinput.handle_keydown_aux(Some('v'), Key::V, MODIFIERS), KeyReaction::PasteRequested);
+    assert_eq!(textinput.get_content(), "defg");
+    let contents = read_paste(&textinput);
+    textinput.finish_paste(contents);
     assert_eq!(textinput.get_content(), "abcdefg");
 }
 
@@ -662,3 +784,1664 @@ fn test_selection_bounds() {
     assert_eq!(TextPoint { line: 1, index: 0 }, textinput.selection_end());
 
 }
+
+#[test]
+fn test_textinput_delete_to_line_start() {
+    let mut textinput = text_input(Lines::Single, "abcdef");
+    textinput.adjust_horizontal(4, Selection::NotSelected);
+    textinput.handle_keydown_aux(None, Key::U, KeyModifiers::CONTROL);
+    assert_eq!(textinput.get_content(), "ef");
+    assert_eq!(textinput.edit_point.index, 0);
+
+    let mut textinput = text_input(Lines::Multiple, "abc\ndef\ng");
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    textinput.adjust_horizontal(2, Selection::NotSelected);
+    textinput.delete_to_line_start();
+    assert_eq!(textinput.get_content(), "abc\nf\ng");
+    assert_eq!(textinput.edit_point.line, 1);
+    assert_eq!(textinput.edit_point.index, 0);
+
+    // At the start of a line, nothing is removed.
+    textinput.delete_to_line_start();
+    assert_eq!(textinput.get_content(), "abc\nf\ng");
+
+    // A selection is removed instead of the start of the line.
+    let mut textinput = text_input(Lines::Single, "abcdef");
+    textinput.adjust_horizontal(3, Selection::NotSelected);
+    textinput.adjust_horizontal(2, Selection::Selected);
+    textinput.handle_keydown_aux(None, Key::U, KeyModifiers::CONTROL);
+    assert_eq!(textinput.get_content(), "abcf");
+    assert_eq!(textinput.edit_point.index, 3);
+}
+
+#[test]
+fn test_textinput_custom_keybindings() {
+    let mut textinput = text_input(Lines::Single, "abc def");
+    let mut bindings = KeyBindings::empty();
+    bindings.bind(Key::E, KeyModifiers::CONTROL, Some(EditCommand::MoveLineEnd));
+    textinput.set_keybindings(bindings);
+
+    textinput.handle_keydown_aux(None, Key::E, KeyModifiers::CONTROL);
+    assert_eq!(textinput.edit_point.index, 7);
+
+    // Unbound keys no longer move the caret.
+    textinput.handle_keydown_aux(None, Key::Left, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point.index, 7);
+
+    textinput.handle_keydown_aux(Some('x'), Key::X, KeyModifiers::empty());
+    assert_eq!(textinput.get_content(), "abc defx");
+}
+
+#[test]
+fn test_textinput_adjust_vertical_keeps_preferred_column() {
+    let mut textinput = text_input(Lines::Multiple, "abcdef\nab\n\nabcdefgh");
+    textinput.adjust_horizontal(5, Selection::NotSelected);
+
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 2 });
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 0 });
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 3, index: 5 });
+    textinput.adjust_vertical(-3, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 5 });
+
+    // Horizontal movement resets the preferred column.
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    textinput.adjust_horizontal_by_one(Direction::Backward, Selection::NotSelected);
+    textinput.adjust_vertical(-1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 1 });
+
+    // So do edits.
+    textinput.adjust_horizontal(4, Selection::NotSelected);
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    textinput.insert_char('x');
+    textinput.adjust_vertical(-1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 3 });
+}
+
+#[test]
+fn test_textinput_page_movement_uses_visible_rows() {
+    let content = (0..40).map(|i| i.to_string()).collect::<Vec<_>>().join("\n");
+    let mut textinput = text_input(Lines::Multiple, &content);
+
+    textinput.handle_keydown_aux(None, Key::PageDown, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point.line, 28);
+
+    textinput.set_visible_rows(Some(5));
+    textinput.handle_keydown_aux(None, Key::PageUp, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point.line, 23);
+    textinput.handle_keydown_aux(None, Key::PageDown, KeyModifiers::SHIFT);
+    assert_eq!(textinput.edit_point.line, 28);
+    assert_eq!(textinput.selection_start().line, 23);
+
+    textinput.set_visible_rows(Some(0));
+    textinput.handle_keydown_aux(None, Key::PageDown, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point.line, 29);
+}
+
+#[test]
+fn test_textinput_home_end_on_mac() {
+    let mut textinput = text_input(Lines::Multiple, "abc\nde\nf");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Mac));
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    textinput.adjust_horizontal(1, Selection::NotSelected);
+
+    textinput.handle_keydown_aux(None, Key::End, KeyModifiers::SHIFT);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 1 });
+    assert_eq!(textinput.get_selection_text(), Some("e\nf".to_owned()));
+
+    textinput.clear_selection();
+    textinput.handle_keydown_aux(None, Key::Home, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 0 });
+    assert!(!textinput.has_selection());
+}
+
+#[test]
+fn test_textinput_select_word_at() {
+    let mut textinput = text_input(Lines::Multiple, "hello wörld\nfoo, bar");
+    textinput.set_word_selection_policy(WordSelectionPolicy::linux());
+    textinput.select_word_at(TextPoint { line: 0, index: 2 });
+    assert_eq!(textinput.get_selection_text(), Some("hello".to_owned()));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Forward);
+
+    // Between two words, the following word is selected.
+    textinput.select_word_at(TextPoint { line: 0, index: 6 });
+    assert_eq!(textinput.get_selection_text(), Some("wörld".to_owned()));
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 12 });
+
+    // The end of a line belongs to the last word.
+    textinput.select_word_at(TextPoint { line: 1, index: 8 });
+    assert_eq!(textinput.get_selection_text(), Some("bar".to_owned()));
+
+    textinput.select_word_at(TextPoint { line: 1, index: 4 });
+    assert_eq!(textinput.get_selection_text(), Some(" ".to_owned()));
+
+    // Points beyond the content are clamped.
+    textinput.select_word_at(TextPoint { line: 7, index: 0 });
+    assert_eq!(textinput.get_selection_text(), Some("foo".to_owned()));
+
+    let mut textinput = text_input(Lines::Single, "");
+    textinput.select_word_at(TextPoint { line: 0, index: 0 });
+    assert_eq!(textinput.get_selection_text(), None);
+}
+
+#[test]
+fn test_textinput_select_line_and_paragraph_at() {
+    let mut textinput = text_input(Lines::Multiple, "one\ntwo\n\nthree\nfour\n");
+    textinput.select_line_at(TextPoint { line: 1, index: 1 });
+    assert_eq!(textinput.get_selection_text(), Some("two".to_owned()));
+    assert_eq!(textinput.selection_start(), TextPoint { line: 1, index: 0 });
+    assert_eq!(textinput.selection_end(), TextPoint { line: 1, index: 3 });
+
+    textinput.select_paragraph_at(TextPoint { line: 1, index: 1 });
+    assert_eq!(textinput.get_selection_text(), Some("one\ntwo".to_owned()));
+
+    textinput.select_paragraph_at(TextPoint { line: 4, index: 0 });
+    assert_eq!(textinput.get_selection_text(), Some("three\nfour".to_owned()));
+
+    textinput.select_paragraph_at(TextPoint { line: 2, index: 0 });
+    assert_eq!(textinput.selection_start(), TextPoint { line: 2, index: 0 });
+    assert_eq!(textinput.selection_end(), TextPoint { line: 2, index: 0 });
+
+    let mut textinput = text_input(Lines::Single, "abc def");
+    textinput.select_paragraph_at(TextPoint { line: 0, index: 5 });
+    assert_eq!(textinput.get_selection_text(), Some("abc def".to_owned()));
+}
+
+#[test]
+fn test_textinput_set_edit_point() {
+    let mut textinput = text_input(Lines::Multiple, "abc\nde\u{301}f\n🌠");
+
+    textinput.set_edit_point(TextPoint { line: 1, index: 1 }, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 1 });
+    assert!(!textinput.has_selection());
+
+    // Positions inside a grapheme cluster snap back to its start.
+    textinput.set_edit_point(TextPoint { line: 1, index: 3 }, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 1 });
+    textinput.set_edit_point(TextPoint { line: 2, index: 2 }, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 0 });
+
+    // Positions past the end are clamped.
+    textinput.set_edit_point(TextPoint { line: 1, index: 40 }, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 5 });
+    textinput.set_edit_point(TextPoint { line: 9, index: 0 }, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 0 });
+
+    textinput.set_edit_point_from_offset(2, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 2 });
+    textinput.set_edit_point_from_offset(5, Selection::Selected);
+    assert_eq!(textinput.get_selection_text(), Some("c\nd".to_owned()));
+    textinput.set_edit_point_from_offset(1, Selection::Selected);
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+    assert_eq!(textinput.get_selection_text(), Some("b".to_owned()));
+
+    textinput.set_edit_point_from_offset(100, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 4 });
+    assert!(!textinput.has_selection());
+}
+
+#[test]
+fn test_textinput_extend_selection_to_point() {
+    let mut textinput = text_input(Lines::Multiple, "abc\ndef");
+    textinput.set_edit_point(TextPoint { line: 0, index: 2 }, Selection::NotSelected);
+
+    textinput.extend_selection_to_point(TextPoint { line: 1, index: 1 });
+    assert_eq!(textinput.selection_origin, Some(TextPoint { line: 0, index: 2 }));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Forward);
+    assert_eq!(textinput.get_selection_text(), Some("c\nd".to_owned()));
+
+    // Dragging back past the origin reverses the selection.
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 0 });
+    assert_eq!(textinput.selection_origin, Some(TextPoint { line: 0, index: 2 }));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+    assert_eq!(textinput.get_selection_text(), Some("ab".to_owned()));
+    assert_eq!(textinput.sorted_selection_offsets_range(), 0..2);
+
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 2 });
+    assert_eq!(textinput.get_selection_text(), None);
+}
+
+#[test]
+fn test_textinput_shift_click_extends_selection() {
+    let mut textinput = text_input(Lines::Single, "abcdéf");
+
+    // Without a selection, the selection is anchored at the caret.
+    textinput.set_edit_point_index(1);
+    textinput.extend_selection_to_index(5);
+    assert_eq!(textinput.get_selection_text(), Some("bcdé".to_owned()));
+
+    // An existing selection keeps its origin.
+    textinput.set_selection_range(2, 4, SelectionDirection::Forward);
+    textinput.extend_selection_to_index(0);
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+    assert_eq!(textinput.get_selection_text(), Some("ab".to_owned()));
+}
+
+#[test]
+fn test_textinput_adjust_horizontal_by_word_uses_language() {
+    let mut textinput = text_input(Lines::Single, "日本語を食べる");
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 9);
+
+    textinput.set_language("ja-JP");
+    textinput.adjust_horizontal_by_word(Direction::Backward, Selection::NotSelected);
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 12);
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 21);
+}
+
+#[test]
+fn test_textinput_adjust_horizontal_by_word_in_list() {
+    let mut textinput = text_input(Lines::Single, "a.b@example.com, c@d.org");
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 1);
+
+    // Each address of a list is a single word.
+    textinput.set_list_separator(Some(','));
+    textinput.adjust_horizontal_by_word(Direction::Backward, Selection::NotSelected);
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 15);
+    textinput.adjust_horizontal_by_word(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 24);
+    textinput.adjust_horizontal_by_word(Direction::Backward, Selection::NotSelected);
+    assert_eq!(textinput.edit_point.index, 17);
+}
+
+#[test]
+fn test_textinput_adjust_vertical_east_asian_width() {
+    let mut textinput = text_input(Lines::Multiple, "日本語入力\nabcdefghij\nａｂｃ");
+
+    // Two full-width characters span four columns of Latin text.
+    textinput.adjust_horizontal_by_one(Direction::Forward, Selection::NotSelected);
+    textinput.adjust_horizontal_by_one(Direction::Forward, Selection::NotSelected);
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 4 });
+    textinput.adjust_vertical(1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 2, index: 6 });
+
+    // A column in the middle of a wide character lands on its nearest edge.
+    textinput.set_edit_point(TextPoint { line: 1, index: 3 }, Selection::NotSelected);
+    textinput.adjust_vertical(-1, Selection::NotSelected);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 3 });
+}
+
+#[test]
+fn test_tab_insertion() {
+    let mut textinput = text_input(Lines::Multiple, "abc");
+    textinput.adjust_horizontal_to_line_end(Direction::Forward, Selection::NotSelected);
+    assert_eq!(textinput.handle_keydown_aux(Some('\t'), Key::Tab, KeyModifiers::empty()),
+               KeyReaction::FocusNavigation(Direction::Forward));
+    assert_eq!(textinput.get_content(), "abc");
+
+    textinput.set_inserts_tabs(true);
+    assert_eq!(textinput.handle_keydown_aux(Some('\t'), Key::Tab, KeyModifiers::empty()),
+               KeyReaction::DispatchInput(Some(TextChange {
+                   range: 3..3,
+                   old_text: DOMString::new(),
+                   new_text: DOMString::from("\t"),
+               })));
+    assert_eq!(textinput.get_content(), "abc\t");
+    assert_eq!(textinput.handle_keydown_aux(Some('\t'), Key::Tab, KeyModifiers::SHIFT),
+               KeyReaction::FocusNavigation(Direction::Backward));
+    assert_eq!(textinput.get_content(), "abc\t");
+
+    // Single-line controls never take the tab character.
+    let mut textinput = text_input(Lines::Single, "abc");
+    textinput.set_inserts_tabs(true);
+    assert_eq!(textinput.handle_keydown_aux(Some('\t'), Key::Tab, KeyModifiers::empty()),
+               KeyReaction::FocusNavigation(Direction::Forward));
+    assert_eq!(textinput.get_content(), "abc");
+}
+
+#[test]
+fn test_read_only() {
+    let mut textinput = TextInput::new(Lines::Multiple,
+                                       DOMString::from("abc\ndef"),
+                                       DummyClipboardContext::new("xyz"),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    textinput.set_read_only(true);
+    textinput.set_inserts_tabs(true);
+
+    assert_eq!(textinput.handle_keydown_aux(Some('x'), Key::X, KeyModifiers::empty()), KeyReaction::Rejected);
+    assert_eq!(textinput.handle_keydown_aux(None, Key::Backspace, KeyModifiers::empty()), KeyReaction::Rejected);
+    assert_eq!(textinput.handle_keydown_aux(None, Key::Delete, KeyModifiers::empty()), KeyReaction::Rejected);
+    assert_eq!(textinput.handle_keydown_aux(None, Key::Enter, KeyModifiers::empty()), KeyReaction::Rejected);
+    assert_eq!(textinput.handle_keydown_aux(Some('\t'), Key::Tab, KeyModifiers::empty()),
+               KeyReaction::FocusNavigation(Direction::Forward));
+    assert_eq!(textinput.execute_command(EditCommand::Paste, Selection::NotSelected), KeyReaction::Rejected);
+    assert_eq!(textinput.get_content(), "abc\ndef");
+
+    // The caret can still move and select, and the selection can be copied.
+    textinput.adjust_horizontal(2, Selection::Selected);
+    assert_eq!(textinput.get_selection_text(), Some("ab".to_owned()));
+    textinput.copy_selection();
+    textinput.set_read_only(false);
+    textinput.clear_selection_to_limit(Direction::Forward, true);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "abc\ndefab");
+}
+
+#[test]
+fn test_obscured() {
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from("pa\u{301}ss"),
+                                       DummyClipboardContext::new(""),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    textinput.set_obscured(true);
+    assert_eq!(textinput.masked_content(), "●●●●");
+
+    textinput.set_edit_point_index(1);
+    textinput.extend_selection_to_index(3);
+    assert_eq!(textinput.masked_selection_offsets_range(), 3..9);
+    assert_eq!(textinput.get_selection_text(), None);
+
+    // Copying does not put the password on the clipboard.
+    textinput.copy_selection();
+    textinput.clear_selection_to_limit(Direction::Forward, true);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "pa\u{301}ss");
+
+    textinput.set_obscured(false);
+    assert_eq!(textinput.get_selection_text(), None);
+    textinput.select_all();
+    assert_eq!(textinput.get_selection_text(), Some("pa\u{301}ss".to_owned()));
+}
+
+#[test]
+fn test_char_filter() {
+    fn digits_only(c: char) -> Option<char> {
+        match c {
+            '0'...'9' => Some(c),
+            'o' => Some('0'),
+            _ => None,
+        }
+    }
+
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from("1"),
+                                       DummyClipboardContext::new("2a3"),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    textinput.set_char_filter(Some(digits_only));
+    textinput.adjust_horizontal_to_line_end(Direction::Forward, Selection::NotSelected);
+
+    assert_eq!(textinput.handle_keydown_aux(Some('x'), Key::X, KeyModifiers::empty()), KeyReaction::Rejected);
+    assert_eq!(textinput.handle_keydown_aux(Some('o'), Key::O, KeyModifiers::empty()),
+               KeyReaction::DispatchInput(Some(TextChange {
+                   range: 1..1,
+                   old_text: DOMString::new(),
+                   new_text: DOMString::from("0"),
+               })));
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "1023");
+    assert_eq!(textinput.edit_point.index, 4);
+
+    // Content set by the owner is not filtered.
+    textinput.set_content(DOMString::from("abc"), true);
+    assert_eq!(textinput.get_content(), "abc");
+}
+
+#[test]
+fn test_max_length_units() {
+    // A single grapheme of 3 UTF-16 code units and 2 code points.
+    let smiley = "\u{1F600}\u{301}";
+
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::new(""),
+                                       Some(4),
+                                       None,
+                                       SelectionDirection::None);
+    textinput.insert_string(smiley);
+    textinput.insert_string(smiley);
+    assert_eq!(textinput.get_content(), smiley);
+    assert_eq!(textinput.length(), 3);
+
+    textinput.set_content(DOMString::new(), true);
+    textinput.set_length_unit(LengthUnit::CodePoints);
+    textinput.insert_string(smiley);
+    textinput.insert_string(smiley);
+    assert_eq!(textinput.length(), 4);
+
+    textinput.set_content(DOMString::new(), true);
+    textinput.set_length_unit(LengthUnit::GraphemeClusters);
+    for _ in 0..5 {
+        textinput.insert_string(smiley);
+    }
+    assert_eq!(textinput.length(), 4);
+    assert_eq!(textinput.utf16_len(), 12);
+}
+
+#[test]
+fn test_utf16_len_follows_edits() {
+    let mut textinput = text_input(Lines::Multiple, "a\u{1F600}\nb");
+    assert_eq!(textinput.utf16_len(), 5);
+
+    // The cached length is dropped with each change of content.
+    textinput.insert_char('\u{1F600}');
+    assert_eq!(textinput.utf16_len(), 7);
+    textinput.delete_char(Direction::Backward);
+    assert_eq!(textinput.utf16_len(), 5);
+    textinput.set_content(DOMString::from("\u{E9}"), true);
+    assert_eq!(textinput.utf16_len(), 1);
+}
+
+#[test]
+fn test_replace_selection_keeps_other_lines() {
+    let mut textinput = text_input(Lines::Multiple, "abc\ndef\nghi\njkl");
+    textinput.set_edit_point(TextPoint { line: 1, index: 1 }, Selection::NotSelected);
+    textinput.extend_selection_to_point(TextPoint { line: 2, index: 2 });
+    textinput.insert_string("x\ny\nz");
+    assert_eq!(textinput.get_content(), "abc\ndx\ny\nzi\njkl");
+    assert_eq!(textinput.edit_point, TextPoint { line: 3, index: 1 });
+
+    textinput.set_edit_point(TextPoint { line: 0, index: 3 }, Selection::NotSelected);
+    textinput.extend_selection_to_point(TextPoint { line: 4, index: 0 });
+    textinput.insert_char('-');
+    assert_eq!(textinput.get_content(), "abc-jkl");
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 4 });
+}
+
+#[test]
+fn test_textinput_with_gap_buffer() {
+    let mut textinput: TextInput<_, GapBuffer> = TextInput::with_buffer(Lines::Multiple,
+                                                                        DOMString::from("abc\ndef\nghi"),
+                                                                        DummyClipboardContext::new(""),
+                                                                        None,
+                                                                        None,
+                                                                        SelectionDirection::None);
+    textinput.adjust_vertical(2, Selection::NotSelected);
+    textinput.insert_string("x\ny");
+    textinput.adjust_vertical(-3, Selection::NotSelected);
+    textinput.insert_char('z');
+    textinput.set_edit_point(TextPoint { line: 1, index: 1 }, Selection::NotSelected);
+    textinput.extend_selection_to_point(TextPoint { line: 2, index: 0 });
+    textinput.delete_char(Direction::Backward);
+    assert_eq!(textinput.get_content(), "azbc\ndx\nyghi");
+    assert_eq!(textinput.len(), 12);
+}
+
+#[test]
+fn test_offsets_follow_edits() {
+    let mut textinput = text_input(Lines::Multiple, "abc\nde");
+    textinput.set_selection_range(5, 6, SelectionDirection::Forward);
+    assert_eq!(textinput.selection_start(), TextPoint { line: 1, index: 1 });
+
+    textinput.set_edit_point(TextPoint { line: 0, index: 1 }, Selection::NotSelected);
+    textinput.insert_string("x\ny");
+    assert_eq!(textinput.get_content(), "ax\nybc\nde");
+    assert_eq!(textinput.selection_start_offset(), 4);
+
+    textinput.set_selection_range(7, 8, SelectionDirection::Forward);
+    assert_eq!(textinput.selection_start(), TextPoint { line: 2, index: 0 });
+    assert_eq!(textinput.selection_end(), TextPoint { line: 2, index: 1 });
+
+    textinput.set_content(DOMString::from("a\nb\nc"), false);
+    textinput.set_selection_range(4, 5, SelectionDirection::Forward);
+    assert_eq!(textinput.selection_start(), TextPoint { line: 2, index: 0 });
+    assert_eq!(textinput.sorted_selection_offsets_range(), 4..5);
+}
+
+#[test]
+fn test_utf16_selection_offsets() {
+    // U+1F600 takes 4 bytes in UTF-8 and 2 code units in UTF-16; é takes 2 bytes and 1 unit.
+    let mut textinput = text_input(Lines::Multiple, "a\u{1F600}b\né");
+    textinput.set_selection_range_utf16(3, 6, SelectionDirection::Forward);
+    assert_eq!(textinput.selection_start(), TextPoint { line: 0, index: 5 });
+    assert_eq!(textinput.selection_end(), TextPoint { line: 1, index: 2 });
+    assert_eq!(textinput.selection_start_utf16_offset(), 3);
+    assert_eq!(textinput.selection_end_utf16_offset(), 6);
+    assert_eq!(textinput.selection_start_offset(), 5);
+    assert_eq!(textinput.selection_end_offset(), 9);
+
+    // An offset inside the surrogate pair moves to its start, and one past the end is clamped.
+    textinput.set_selection_range_utf16(2, 100, SelectionDirection::Backward);
+    assert_eq!(textinput.selection_start(), TextPoint { line: 0, index: 1 });
+    assert_eq!(textinput.selection_end(), TextPoint { line: 1, index: 2 });
+    assert_eq!(textinput.selection_start_utf16_offset(), 1);
+}
+
+#[test]
+fn test_grapheme_points() {
+    let textinput = text_input(Lines::Multiple, "ae\u{301}b\nc\u{1F600}d");
+    assert_eq!(textinput.text_point_to_grapheme_point(TextPoint { line: 0, index: 4 }),
+               GraphemePoint { line: 0, grapheme: 2 });
+    assert_eq!(textinput.text_point_to_grapheme_point(TextPoint { line: 1, index: 5 }),
+               GraphemePoint { line: 1, grapheme: 2 });
+    // Points inside a grapheme cluster count from its start.
+    assert_eq!(textinput.text_point_to_grapheme_point(TextPoint { line: 0, index: 2 }),
+               GraphemePoint { line: 0, grapheme: 1 });
+    assert_eq!(textinput.text_point_to_grapheme_point(TextPoint { line: 1, index: 3 }),
+               GraphemePoint { line: 1, grapheme: 1 });
+
+    assert_eq!(textinput.grapheme_point_to_text_point(GraphemePoint { line: 0, grapheme: 2 }),
+               TextPoint { line: 0, index: 4 });
+    assert_eq!(textinput.grapheme_point_to_text_point(GraphemePoint { line: 1, grapheme: 3 }),
+               TextPoint { line: 1, index: 6 });
+    assert_eq!(textinput.grapheme_point_to_text_point(GraphemePoint { line: 1, grapheme: 10 }),
+               TextPoint { line: 1, index: 6 });
+    assert_eq!(textinput.grapheme_point_to_text_point(GraphemePoint { line: 5, grapheme: 1 }),
+               TextPoint { line: 1, index: 1 });
+}
+
+#[test]
+fn test_text_changes() {
+    let mut textinput = text_input(Lines::Multiple, "ab\ncd");
+    textinput.set_edit_point(TextPoint { line: 0, index: 1 }, Selection::NotSelected);
+    textinput.extend_selection_to_point(TextPoint { line: 1, index: 1 });
+    assert_eq!(textinput.insert_string("XY"), Some(TextChange {
+        range: 1..4,
+        old_text: DOMString::from("b\nc"),
+        new_text: DOMString::from("XY"),
+    }));
+    assert_eq!(textinput.get_content(), "aXYd");
+
+    assert_eq!(textinput.delete_char(Direction::Forward), Some(TextChange {
+        range: 3..4,
+        old_text: DOMString::from("d"),
+        new_text: DOMString::new(),
+    }));
+    // Nothing left to delete.
+    assert_eq!(textinput.delete_char(Direction::Forward), None);
+    assert_eq!(textinput.handle_keydown_aux(None, Key::Delete, KeyModifiers::empty()),
+               KeyReaction::DispatchInput(None));
+
+    // Insertions cut short by the maximum length report what was actually inserted.
+    textinput.max_length = Some(5);
+    assert_eq!(textinput.insert_string("123"), Some(TextChange {
+        range: 3..3,
+        old_text: DOMString::new(),
+        new_text: DOMString::from("12"),
+    }));
+    assert_eq!(textinput.insert_string("3"), None);
+}
+
+#[test]
+fn test_line_accessors() {
+    let textinput = text_input(Lines::Multiple, "abc\n\ndé");
+    assert_eq!(textinput.line_count(), 3);
+    assert_eq!(textinput.line(0), Some("abc"));
+    assert_eq!(textinput.line(1), Some(""));
+    assert_eq!(textinput.line(2), Some("dé"));
+    assert_eq!(textinput.line(3), None);
+    assert_eq!(textinput.lines().collect::<Vec<_>>(), ["abc", "", "dé"]);
+
+    let textinput = text_input(Lines::Single, "");
+    assert_eq!(textinput.line_count(), 1);
+    assert_eq!(textinput.lines().collect::<Vec<_>>(), [""]);
+}
+
+#[test]
+fn test_nfc_normalization() {
+    let mut textinput = text_input(Lines::Single, "");
+    textinput.insert_string("cafe\u{301}");
+    assert_eq!(textinput.get_content(), "cafe\u{301}");
+
+    let mut textinput = text_input(Lines::Single, "");
+    textinput.set_normalizes_insertions(true);
+    textinput.insert_string("cafe\u{301}");
+    assert_eq!(textinput.get_content(), "caf\u{E9}");
+
+    // A combining mark typed on its own composes with the preceding character.
+    textinput.insert_char('e');
+    assert_eq!(textinput.insert_char('\u{301}'), Some(TextChange {
+        range: 5..6,
+        old_text: DOMString::from("e"),
+        new_text: DOMString::from("\u{E9}"),
+    }));
+    assert_eq!(textinput.get_content(), "caf\u{E9}\u{E9}");
+    assert_eq!(textinput.edit_point.index, 7);
+
+    // A combining mark typed over a backward selection composes with the character before it.
+    let mut textinput = text_input(Lines::Single, "cafex");
+    textinput.set_normalizes_insertions(true);
+    textinput.set_selection_range(4, 5, SelectionDirection::Backward);
+    assert_eq!(textinput.insert_char('\u{301}'), Some(TextChange {
+        range: 3..5,
+        old_text: DOMString::from("ex"),
+        new_text: DOMString::from("\u{E9}"),
+    }));
+    assert_eq!(textinput.get_content(), "caf\u{E9}");
+    assert_eq!(textinput.edit_point.index, 5);
+
+    // Conjoining Hangul jamo typed one by one compose into a syllable.
+    let mut textinput = text_input(Lines::Single, "");
+    textinput.set_normalizes_insertions(true);
+    textinput.insert_char('\u{1112}');
+    textinput.insert_char('\u{1161}');
+    textinput.insert_char('\u{11AB}');
+    assert_eq!(textinput.get_content(), "\u{D55C}");
+    textinput.insert_char('\u{1100}');
+    assert_eq!(textinput.get_content(), "\u{D55C}\u{1100}");
+}
+
+#[test]
+fn test_paste_sanitization() {
+    fn paste(lines: Lines, clipboard: &str, sanitization: PasteSanitization) -> DOMString {
+        let mut textinput = TextInput::new(lines,
+                                           DOMString::from(""),
+                                           DummyClipboardContext::new(clipboard),
+                                           None,
+                                           None,
+                                           SelectionDirection::None);
+        textinput.set_paste_sanitization(sanitization);
+        paste_from_clipboard(&mut textinput);
+        textinput.get_content()
+    }
+
+    let text = "a\u{0}b\r\nc\td\u{85}e";
+    assert_eq!(paste(Lines::Single, text, Default::default()), text);
+
+    let strip = PasteSanitization { strip_control_characters: true, ..Default::default() };
+    assert_eq!(paste(Lines::Single, text, strip), "abcde");
+    assert_eq!(paste(Lines::Multiple, text, strip), "ab\nc\tde");
+
+    let isolate = PasteSanitization { isolate_bidi_controls: true, ..Default::default() };
+    assert_eq!(paste(Lines::Single, "abc", isolate), "abc");
+    assert_eq!(paste(Lines::Single, "\u{202E}cba", isolate), "\u{2068}\u{202E}cba\u{2069}");
+
+    let trim = PasteSanitization { strip_control_characters: true, trim_whitespace: true, ..Default::default() };
+    assert_eq!(paste(Lines::Single, " \r\nhttp://example.com/a b\t\n", trim), "http://example.com/a b");
+}
+
+#[test]
+fn test_get_content_with_crlf() {
+    let textinput = text_input(Lines::Multiple, "a\r\nb\rc\n\nd");
+    assert_eq!(textinput.get_content(), "a\nb\nc\n\nd");
+    assert_eq!(textinput.get_content_with_crlf(), "a\r\nb\r\nc\r\n\r\nd");
+}
+
+#[test]
+fn test_get_content_with_hard_wraps() {
+    let textinput = text_input(Lines::Multiple, "one two three\nfé fi");
+    let wrap_points = [
+        TextPoint { line: 1, index: 4 },
+        TextPoint { line: 0, index: 8 },
+        TextPoint { line: 0, index: 4 },
+        // Ignored: at the end of a line, inside a character, and past the last line.
+        TextPoint { line: 0, index: 13 },
+        TextPoint { line: 1, index: 2 },
+        TextPoint { line: 2, index: 1 },
+    ];
+    assert_eq!(textinput.get_content_with_hard_wraps(&wrap_points), "one \r\ntwo \r\nthree\r\nfé \r\nfi");
+    assert_eq!(textinput.get_content_with_hard_wraps(&[]), textinput.get_content_with_crlf());
+    // The editing buffer keeps the logical lines.
+    assert_eq!(textinput.get_content(), "one two three\nfé fi");
+}
+
+#[test]
+fn test_soft_wrap_points() {
+    let textinput = text_input(Lines::Multiple, "one two three\nfé fi");
+    assert_eq!(textinput.soft_wrap_points(8), [TextPoint { line: 0, index: 8 }]);
+    assert_eq!(textinput.get_content_with_hard_wraps(&textinput.soft_wrap_points(5)),
+               "one \r\ntwo \r\nthree\r\nfé fi");
+    assert!(textinput.soft_wrap_points(20).is_empty());
+
+    // A word too wide for a row is broken, and spaces at the end of a row don't wrap.
+    let textinput = text_input(Lines::Single, "abcdefg   h");
+    assert_eq!(textinput.get_content_with_hard_wraps(&textinput.soft_wrap_points(3)), "abc\r\ndef\r\ng   \r\nh");
+}
+
+#[test]
+fn test_invalid_selection_is_clamped() {
+    let mut textinput = text_input(Lines::Multiple, "abc\nd\u{e9}f");
+    textinput.edit_point = TextPoint { line: 5, index: 10 };
+    textinput.selection_origin = Some(TextPoint { line: 1, index: 2 });
+    textinput.selection_direction = SelectionDirection::Backward;
+    textinput.execute_command(EditCommand::MoveCharForward, Selection::Selected);
+    // The edit point was moved to the end of the content, and the direction fixed to match.
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 4 });
+    assert_eq!(textinput.selection_origin, Some(TextPoint { line: 1, index: 1 }));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Forward);
+
+    textinput.selection_origin = Some(TextPoint { line: 0, index: 7 });
+    assert_eq!(textinput.replace_selection(DOMString::from("x")).map(|change| change.new_text),
+               Some(DOMString::from("x")));
+    assert_eq!(textinput.get_content(), "abcx");
+}
+
+#[test]
+fn test_key_sequence() {
+    let mut textinput = text_input(Lines::Multiple, "");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+    let reactions = KeySequence::new()
+        .type_text("Hello w\u{f6}rld\nbye")
+        .press_times("Shift+ArrowLeft", 2)
+        .press("Backspace")
+        .press("Control+a")
+        .send_to(&mut textinput);
+    assert_eq!(reactions.len(), 19);
+    assert_eq!(reactions[0], KeyReaction::DispatchInput(Some(TextChange {
+        range: 0..0,
+        old_text: DOMString::new(),
+        new_text: DOMString::from("H"),
+    })));
+    assert_eq!(textinput.get_content(), "Hello w\u{f6}rld\nb");
+    assert_eq!(textinput.get_selection_text(), Some(String::from("Hello w\u{f6}rld\nb")));
+}
+
+#[test]
+fn test_key_reactions() {
+    let mut textinput = TextInput::new(Lines::Multiple,
+                                       DOMString::from("ab\ncd"),
+                                       DummyClipboardContext::new(""),
+                                       Some(5),
+                                       None,
+                                       SelectionDirection::None);
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+
+    let reactions = KeySequence::new()
+        .press("ArrowRight")
+        .press("Shift+ArrowRight")
+        .press("Control+a")
+        .press("ArrowLeft")
+        .type_text("x")
+        .press("Enter")
+        .press("Control+v")
+        .send_to(&mut textinput);
+    assert_eq!(reactions, vec!(
+        KeyReaction::ScrollCaretIntoView,
+        KeyReaction::ScrollCaretIntoView,
+        KeyReaction::RedrawSelection,
+        KeyReaction::SelectionCleared,
+        // The content is as long as max_length allows.
+        KeyReaction::Rejected,
+        KeyReaction::Rejected,
+        // Pasting waits for the clipboard, however full the control is.
+        KeyReaction::PasteRequested,
+    ));
+}
+
+#[test]
+fn test_deferred_edits() {
+    let mut textinput = text_input(Lines::Multiple, "ab");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+    textinput.set_defers_edits(true);
+    textinput.edit_point.index = 2;
+
+    // Edits are left to the owner, which may not carry them out at all.
+    let reactions = KeySequence::new()
+        .type_text("x")
+        .press("Enter")
+        .press("Backspace")
+        .press("Delete")
+        .press("Control+u")
+        .send_to(&mut textinput);
+    assert_eq!(reactions, vec!(
+        KeyReaction::BeforeInput(Edit::InsertText("x".to_owned())),
+        KeyReaction::BeforeInput(Edit::InsertLineBreak),
+        KeyReaction::BeforeInput(Edit::DeleteBackward),
+        KeyReaction::BeforeInput(Edit::DeleteForward),
+        KeyReaction::BeforeInput(Edit::DeleteToLineStart),
+    ));
+    assert_eq!(textinput.get_content(), "ab");
+
+    assert_eq!(Edit::InsertText("x".to_owned()).input_type(), "insertText");
+    assert_eq!(Edit::InsertText("x".to_owned()).data(), Some("x"));
+    assert_eq!(Edit::DeleteBackward.input_type(), "deleteContentBackward");
+    assert_eq!(Edit::DeleteBackward.data(), None);
+    assert_eq!(Edit::InsertFromPaste("x".to_owned()).data(), Some("x"));
+    assert_eq!(Edit::InsertLineBreak.data(), None);
+
+    // Pasted text is deferred like typed text.
+    assert_eq!(textinput.paste(ClipboardContents::from_plain_text("cd".to_owned())),
+               KeyReaction::BeforeInput(Edit::InsertFromPaste("cd".to_owned())));
+
+    assert_eq!(textinput.apply_edit(Edit::InsertText("x".to_owned())),
+               KeyReaction::DispatchInput(Some(TextChange {
+                   range: 2..2,
+                   old_text: DOMString::new(),
+                   new_text: DOMString::from("x"),
+               })));
+    textinput.apply_edit(Edit::InsertLineBreak);
+    textinput.apply_edit(Edit::DeleteBackward);
+    assert_eq!(textinput.get_content(), "abx");
+
+    // An edit is rejected if the control became read-only before it was carried out.
+    textinput.set_read_only(true);
+    assert_eq!(textinput.apply_edit(Edit::DeleteBackward), KeyReaction::Rejected);
+    assert_eq!(textinput.get_content(), "abx");
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut textinput = text_input(Lines::Multiple, "");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+
+    // Typing is undone at once, including the characters deleted while typing.
+    KeySequence::new().type_text("helo").press("Backspace").type_text("lo").send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "hello");
+    assert!(textinput.can_undo());
+    assert!(textinput.undo().is_some());
+    assert_eq!(textinput.get_content(), "");
+    assert!(!textinput.can_undo());
+    assert!(textinput.redo().is_some());
+    assert_eq!(textinput.get_content(), "hello");
+    assert_eq!(textinput.edit_point.index, 5);
+
+    // Typing elsewhere starts another change, and a new change can't be redone past.
+    textinput.edit_point.index = 0;
+    KeySequence::new().type_text("oh ").send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "oh hello");
+    textinput.undo();
+    assert_eq!(textinput.get_content(), "hello");
+    assert_eq!(textinput.edit_point.index, 0);
+    KeySequence::new().press("Delete").send_to(&mut textinput);
+    assert!(!textinput.can_redo());
+    assert_eq!(textinput.redo(), None);
+
+    // Undoing a deletion puts the selection back.
+    textinput.set_content(DOMString::from("abcd"), true);
+    assert!(!textinput.can_undo());
+    textinput.set_selection_range(1, 3, SelectionDirection::Forward);
+    KeySequence::new().press("Backspace").send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "ad");
+    assert_eq!(textinput.undo(), Some(TextChange {
+        range: 1..1,
+        old_text: DOMString::new(),
+        new_text: DOMString::from("bc"),
+    }));
+    assert_eq!(textinput.get_content(), "abcd");
+    assert_eq!(textinput.sorted_selection_offsets_range(), 1..3);
+
+    // Edits asked for by the page are undone one at a time.
+    textinput.set_content(DOMString::from(""), true);
+    textinput.request_edit(Edit::InsertText("a".to_owned()));
+    textinput.request_edit(Edit::InsertText("b".to_owned()));
+    assert_eq!(textinput.request_edit(Edit::HistoryUndo),
+               KeyReaction::DispatchInput(Some(TextChange {
+                   range: 1..2,
+                   old_text: DOMString::from("b"),
+                   new_text: DOMString::new(),
+               })));
+    assert_eq!(textinput.get_content(), "a");
+    assert_eq!(Edit::HistoryUndo.input_type(), "historyUndo");
+
+    // A single-line control drops the line breaks of inserted text.
+    let mut textinput = text_input(Lines::Single, "");
+    textinput.request_edit(Edit::InsertText("a\nb".to_owned()));
+    assert_eq!(textinput.get_content(), "ab");
+
+    // Replacement text takes the place of the whole content, wherever the caret is.
+    textinput.clear_selection_to_limit(Direction::Backward, true);
+    textinput.request_edit(Edit::InsertReplacementText("c\nd".to_owned()));
+    assert_eq!(textinput.get_content(), "cd");
+    assert_eq!(Edit::InsertReplacementText("cd".to_owned()).data(), Some("cd"));
+}
+
+#[test]
+fn test_dead_keys_and_compose() {
+    let mut textinput = text_input(Lines::Single, "");
+    let reactions = KeySequence::new().dead_key('\u{B4}').type_text("e").send_to(&mut textinput);
+    assert_eq!(reactions[0], KeyReaction::Nothing);
+    assert_eq!(textinput.get_content(), "\u{E9}");
+
+    KeySequence::new()
+        .dead_key('`').type_text("a")
+        // Dead keys that do not combine type their accent.
+        .dead_key('\u{B4}').type_text("x")
+        .dead_key('\u{A8}').type_text(" ")
+        .dead_key('^').dead_key('^')
+        .send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "\u{E9}\u{E0}\u{B4}x\u{A8}^");
+
+    // A command cancels the dead key.
+    textinput.set_content(DOMString::from(""), true);
+    KeySequence::new().dead_key('\u{B4}').press("ArrowLeft").type_text("e").send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "e");
+
+    textinput.set_content(DOMString::from(""), true);
+    KeySequence::new()
+        .press("Compose").type_text("oc")
+        .press("Compose").type_text("'e")
+        .press("Compose").type_text("o\"")
+        // Unknown sequences type nothing.
+        .press("Compose").type_text("qq")
+        .send_to(&mut textinput);
+    assert_eq!(textinput.get_content(), "\u{A9}\u{E9}\u{F6}");
+
+    textinput.set_read_only(true);
+    assert_eq!(KeySequence::new().dead_key('\u{B4}').send_to(&mut textinput), vec!(KeyReaction::Rejected));
+}
+
+#[test]
+fn test_altgr_typing() {
+    let mut textinput = text_input(Lines::Single, "ab");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+    let altgr = KeyModifiers::CONTROL | KeyModifiers::ALT;
+    textinput.handle_keydown_aux(Some('@'), Key::Q, altgr);
+    textinput.handle_keydown_aux(Some('{'), Key::Num7, altgr);
+    assert_eq!(textinput.get_content(), "@{ab");
+    textinput.handle_keydown_aux(None, Key::E, altgr);
+    assert_eq!(textinput.edit_point.index, 4);
+}
+
+#[test]
+fn test_escape() {
+    let mut textinput = text_input(Lines::Single, "abc");
+    textinput.set_keybindings(KeyBindings::for_platform(Platform::Other));
+    textinput.set_clears_on_escape(true);
+
+    let reactions = KeySequence::new()
+        .press("Shift+ArrowRight")
+        .dead_key('`')
+        .press_times("Escape", 4)
+        .send_to(&mut textinput);
+    assert_eq!(reactions[2..], [
+        KeyReaction::Escape(EscapeOutcome::CompositionCancelled),
+        KeyReaction::Escape(EscapeOutcome::SelectionCleared),
+        KeyReaction::Escape(EscapeOutcome::ContentCleared(TextChange {
+            range: 0..3,
+            old_text: DOMString::from("abc"),
+            new_text: DOMString::new(),
+        })),
+        KeyReaction::Escape(EscapeOutcome::Nothing),
+    ]);
+    assert_eq!(textinput.get_content(), "");
+
+    textinput.set_content(DOMString::from("abc"), true);
+    textinput.set_clears_on_escape(false);
+    assert_eq!(textinput.escape(), EscapeOutcome::Nothing);
+    assert_eq!(textinput.get_content(), "abc");
+
+    // An input method's composition is cancelled first, putting back what it replaced.
+    textinput.set_selection_range(1, 2, SelectionDirection::Forward);
+    textinput.set_composition(DOMString::from("\u{304B}"), vec!(), 3);
+    let reactions = KeySequence::new().press_times("Escape", 2).send_to(&mut textinput);
+    assert_eq!(reactions, [
+        KeyReaction::CompositionCancelled(Some(TextChange {
+            range: 1..4,
+            old_text: DOMString::from("\u{304B}"),
+            new_text: DOMString::from("b"),
+        })),
+        KeyReaction::Escape(EscapeOutcome::SelectionCleared),
+    ]);
+    assert!(!textinput.is_composing());
+    assert_eq!(textinput.get_content(), "abc");
+}
+
+#[test]
+fn test_set_same_content_keeps_selection() {
+    let mut textinput = text_input(Lines::Multiple, "abc\r\ndef");
+    textinput.set_selection_range(1, 6, SelectionDirection::Backward);
+    textinput.set_content(DOMString::from("abc\ndef"), true);
+    assert_eq!(textinput.selection_start_offset(), 1);
+    assert_eq!(textinput.selection_end_offset(), 6);
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+
+    textinput.set_content(DOMString::from("abc\ndeg"), true);
+    assert!(!textinput.has_selection());
+}
+
+#[test]
+fn test_content_follows_edits() {
+    let mut textinput = text_input(Lines::Single, "abc");
+    assert_eq!(textinput.get_content(), "abc");
+    textinput.insert_char('x');
+    assert_eq!(textinput.get_content(), "xabc");
+    assert_eq!(textinput.len(), 4);
+    textinput.single_line_content_mut().push_str("yz");
+    assert_eq!(textinput.get_content(), "xabcyz");
+    assert_eq!(textinput.len(), 6);
+    textinput.set_content(DOMString::from("\u{e9}"), true);
+    assert_eq!(textinput.get_content(), "\u{e9}");
+    assert_eq!(textinput.len(), 2);
+}
+
+#[test]
+fn test_copy_and_cut() {
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from("abc"),
+                                       DummyClipboardContext::new("old"),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    assert_eq!(textinput.execute_command(EditCommand::Copy, Selection::NotSelected), KeyReaction::CopyRequested);
+    assert!(!textinput.copy_selection());
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "oldabc");
+
+    textinput.set_selection_range(3, 5, SelectionDirection::Forward);
+    assert!(textinput.copy_selection());
+    textinput.clear_selection_to_limit(Direction::Forward, true);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "oldabcab");
+
+    // Cutting moves the selection to the clipboard.
+    textinput.set_selection_range(0, 3, SelectionDirection::Forward);
+    assert_eq!(textinput.execute_command(EditCommand::Cut, Selection::NotSelected), KeyReaction::CutRequested);
+    assert_eq!(textinput.cut_selection(), Some(TextChange {
+        range: 0..3,
+        old_text: DOMString::from("old"),
+        new_text: DOMString::new(),
+    }));
+    assert_eq!(textinput.get_content(), "abcab");
+    assert_eq!(textinput.cut_selection(), None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "oldabcab");
+
+    // The page may put data of its own on the clipboard instead.
+    textinput.write_clipboard(ClipboardContents::from_plain_text("page".to_owned()));
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "oldpageabcab");
+
+    // A read-only control can be copied from, but not cut from.
+    textinput.set_read_only(true);
+    textinput.select_all();
+    assert_eq!(textinput.execute_command(EditCommand::Cut, Selection::NotSelected), KeyReaction::Rejected);
+    assert_eq!(textinput.cut_selection(), None);
+    assert!(textinput.copy_selection());
+    assert_eq!(textinput.get_content(), "oldpageabcab");
+}
+
+#[test]
+fn test_clipboard_flavors() {
+    assert_eq!(ClipboardFlavor::from_mime_type("text/plain"), Some(ClipboardFlavor::PlainText));
+    assert_eq!(ClipboardFlavor::from_mime_type("Text/HTML; charset=utf-8"), Some(ClipboardFlavor::Html));
+    assert_eq!(ClipboardFlavor::from_mime_type("text/uri-list"), Some(ClipboardFlavor::UriList));
+    assert_eq!(ClipboardFlavor::from_mime_type("image/png"), None);
+
+    let mut contents = ClipboardContents::new();
+    contents.set(ClipboardFlavor::Html, "<b>old</b>".to_owned());
+    contents.set(ClipboardFlavor::PlainText, "bold".to_owned());
+    contents.set(ClipboardFlavor::Html, "<b>bold</b>".to_owned());
+    assert_eq!(contents.flavors(), vec!(ClipboardFlavor::Html, ClipboardFlavor::PlainText));
+    assert_eq!(contents.get(ClipboardFlavor::Html), Some("<b>bold</b>"));
+
+    // Pasting takes the plain text, whatever else is offered.
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(contents),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "bold");
+
+    let mut html_only = ClipboardContents::new();
+    html_only.set(ClipboardFlavor::Html, "<i>x</i>".to_owned());
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(html_only),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    assert_eq!(paste_from_clipboard(&mut textinput), KeyReaction::Nothing);
+    assert_eq!(textinput.get_content(), "");
+}
+
+#[test]
+fn test_clipboard_chunks() {
+    // Chunks end at character boundaries, and hold at least one character.
+    let text = "a\u{e9}\u{3042}\u{1F600}b";
+    assert_eq!(clipboard_text_chunk(text, 0, 2), "a");
+    assert_eq!(clipboard_text_chunk(text, 1, 2), "\u{e9}");
+    assert_eq!(clipboard_text_chunk(text, 3, 2), "\u{3042}");
+    assert_eq!(clipboard_text_chunk(text, 6, 100), "\u{1F600}b");
+    assert_eq!(clipboard_text_chunk(text, 11, 2), "");
+
+    let mut clipboard = DummyClipboardContext::new(text);
+    let mut reader = ClipboardChunkReader::new(clipboard.clone(), vec!(ClipboardFlavor::PlainText), 3);
+    let (length_sender, length_receiver) = ipc::channel().unwrap();
+    let (chunk_sender, chunk_receiver) = ipc::channel().unwrap();
+    reader.request_length(length_sender);
+    assert!(reader.receive_length(length_receiver.recv().unwrap()));
+    let mut chunks = vec!();
+    while reader.request_next_chunk(chunk_sender.clone()) {
+        let chunk = chunk_receiver.recv().unwrap();
+        chunks.push(chunk.clone().unwrap());
+        reader.receive_chunk(chunk);
+    }
+    assert_eq!(chunks, vec!("a\u{e9}", "\u{3042}", "\u{1F600}", "b"));
+    assert_eq!(reader.into_contents(), ClipboardContents::from_plain_text(text.to_owned()));
+
+    // A read stops early when the clipboard no longer offers the flavor.
+    let mut reader = ClipboardChunkReader::new(clipboard.clone(), vec!(ClipboardFlavor::PlainText), 3);
+    let (length_sender, length_receiver) = ipc::channel().unwrap();
+    reader.request_length(length_sender);
+    assert!(reader.receive_length(length_receiver.recv().unwrap()));
+    assert!(reader.request_next_chunk(chunk_sender.clone()));
+    assert!(reader.receive_chunk(chunk_receiver.recv().unwrap()));
+    clipboard.set_clipboard_contents(ClipboardContents::new());
+    assert!(!reader.receive_chunk(None));
+    assert!(!reader.request_next_chunk(chunk_sender.clone()));
+    assert_eq!(reader.into_contents(), ClipboardContents::from_plain_text("a\u{e9}".to_owned()));
+
+    // There is nothing to read from an empty clipboard.
+    let mut reader = ClipboardChunkReader::new(clipboard, vec!(ClipboardFlavor::PlainText), 3);
+    let (length_sender, length_receiver) = ipc::channel().unwrap();
+    reader.request_length(length_sender);
+    assert!(!reader.receive_length(length_receiver.recv().unwrap()));
+    assert!(reader.into_contents().is_empty());
+}
+
+#[test]
+fn test_uri_list_paste() {
+    let mut uri_list = ClipboardContents::new();
+    uri_list.set(ClipboardFlavor::UriList,
+                 "# copied links\r\nhttps://servo.org/\r\n\r\nhttps://example.com/a b\r\n".to_owned());
+
+    // A list of URLs is pasted one per line, or only the first one in a single-line control.
+    let mut textinput = TextInput::new(Lines::Multiple,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(uri_list.clone()),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "https://servo.org/\nhttps://example.com/a b");
+
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(uri_list.clone()),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "https://servo.org/");
+
+    // Plain text is preferred, unless it is empty.
+    let mut both = uri_list.clone();
+    both.set(ClipboardFlavor::PlainText, "Servo".to_owned());
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(both.clone()),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "Servo");
+
+    both.set(ClipboardFlavor::PlainText, String::new());
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from(""),
+                                       DummyClipboardContext::with_contents(both),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    paste_from_clipboard(&mut textinput);
+    assert_eq!(textinput.get_content(), "https://servo.org/");
+}
+
+#[test]
+fn test_asynchronous_paste() {
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::from("abc"),
+                                       DummyClipboardContext::new("xy"),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    assert_eq!(textinput.finish_paste(ClipboardContents::from_plain_text("stray".to_owned())), KeyReaction::Nothing);
+
+    // Nothing is inserted until the contents arrive.
+    assert_eq!(textinput.execute_command(EditCommand::Paste, Selection::NotSelected), KeyReaction::PasteRequested);
+    let contents = read_paste(&textinput);
+    assert_eq!(textinput.get_content(), "abc");
+    assert_eq!(textinput.finish_paste(contents),
+               KeyReaction::DispatchInput(Some(TextChange {
+                   range: 0..0,
+                   old_text: DOMString::new(),
+                   new_text: DOMString::from("xy"),
+               })));
+    assert_eq!(textinput.get_content(), "xyabc");
+    assert_eq!(textinput.finish_paste(ClipboardContents::from_plain_text("xy".to_owned())), KeyReaction::Nothing);
+
+    // A paste overtaken by another command or a change of content is dropped.
+    textinput.execute_command(EditCommand::Paste, Selection::NotSelected);
+    textinput.execute_command(EditCommand::MoveLineEnd, Selection::NotSelected);
+    assert_eq!(textinput.finish_paste(ClipboardContents::from_plain_text("xy".to_owned())), KeyReaction::Nothing);
+    textinput.execute_command(EditCommand::Paste, Selection::NotSelected);
+    textinput.set_content(DOMString::from("def"), true);
+    assert_eq!(textinput.finish_paste(ClipboardContents::from_plain_text("xy".to_owned())), KeyReaction::Nothing);
+    assert_eq!(textinput.get_content(), "def");
+
+    // So is one for which the clipboard took too long to answer.
+    textinput.set_paste_timeout(0);
+    textinput.execute_command(EditCommand::Paste, Selection::NotSelected);
+    let contents = read_paste(&textinput);
+    assert!(contents.is_empty());
+    assert_eq!(textinput.finish_paste(contents), KeyReaction::Nothing);
+    assert_eq!(textinput.get_content(), "def");
+}
+
+#[test]
+fn test_slow_chunked_paste() {
+    let text = "a".repeat(3 * CLIPBOARD_CHUNK_SIZE);
+    let mut textinput = TextInput::new(Lines::Single,
+                                       DOMString::new(),
+                                       DummyClipboardContext::new(&text),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+
+    // The timeout applies to each answer of the clipboard, not to the whole read, which here
+    // takes a length and three chunks.
+    textinput.set_paste_timeout(200);
+    textinput.execute_command(EditCommand::Paste, Selection::NotSelected);
+    let contents = read_paste_slowly(&textinput, Duration::from_millis(100));
+    textinput.finish_paste(contents);
+    assert_eq!(textinput.get_content().len(), text.len());
+
+    // A clipboard that is slower than the timeout to answer any request is given up on.
+    textinput.set_content(DOMString::new(), true);
+    textinput.set_paste_timeout(50);
+    textinput.execute_command(EditCommand::Paste, Selection::NotSelected);
+    let contents = read_paste_slowly(&textinput, Duration::from_millis(100));
+    assert_eq!(textinput.finish_paste(contents), KeyReaction::Nothing);
+    assert_eq!(textinput.get_content(), "");
+}
+
+#[test]
+fn test_offset_conversions() {
+    let textinput = text_input(Lines::Multiple, "a\u{e9}\n\u{1F600}b");
+    assert_eq!(textinput.text_point_to_offset(&TextPoint { line: 1, index: 4 }), 8);
+    assert_eq!(textinput.text_point_to_utf16_offset(&TextPoint { line: 1, index: 4 }), 5);
+    assert_eq!(textinput.offset_to_text_point(8), TextPoint { line: 1, index: 4 });
+    assert_eq!(textinput.utf16_offset_to_text_point(5), TextPoint { line: 1, index: 4 });
+    assert_eq!(textinput.utf16_offset_to_offset(5), 8);
+
+    // Out of range and mid-character positions are clamped to a character boundary.
+    assert_eq!(textinput.text_point_to_offset(&TextPoint { line: 0, index: 2 }), 1);
+    assert_eq!(textinput.text_point_to_offset(&TextPoint { line: 0, index: 9 }), 3);
+    assert_eq!(textinput.text_point_to_offset(&TextPoint { line: 7, index: 0 }), 4);
+    assert_eq!(textinput.text_point_to_utf16_offset(&TextPoint { line: 1, index: 2 }), 3);
+    assert_eq!(textinput.offset_to_text_point(2), TextPoint { line: 0, index: 1 });
+    assert_eq!(textinput.offset_to_text_point(100), TextPoint { line: 1, index: 5 });
+    assert_eq!(textinput.utf16_offset_to_text_point(4), TextPoint { line: 1, index: 0 });
+    assert_eq!(textinput.utf16_offset_to_text_point(100), TextPoint { line: 1, index: 5 });
+}
+
+#[test]
+fn test_select_grapheme_range() {
+    let mut textinput = text_input(Lines::Multiple, "e\u{301}a\n\u{1F600}bc");
+    textinput.select_grapheme_range(1, 4, SelectionDirection::Forward);
+    assert_eq!(textinput.get_selection_text(), Some(String::from("a\n\u{1F600}")));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Forward);
+
+    textinput.select_grapheme_range(0, 1, SelectionDirection::Backward);
+    assert_eq!(textinput.get_selection_text(), Some(String::from("e\u{301}")));
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 0 });
+
+    // Indices past the end select up to the end, and reversed ranges are empty.
+    textinput.select_grapheme_range(5, 50, SelectionDirection::None);
+    assert_eq!(textinput.get_selection_text(), Some(String::from("c")));
+    textinput.select_grapheme_range(3, 1, SelectionDirection::None);
+    assert_eq!(textinput.get_selection_text(), None);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 3 });
+}
+
+#[test]
+fn test_drag_selection_granularity() {
+    let mut textinput = text_input(Lines::Multiple, "one two three\nfour five");
+    textinput.set_word_selection_policy(WordSelectionPolicy::linux());
+
+    // After selecting a word, dragging extends the selection by whole words, and the first
+    // word stays selected.
+    textinput.select_word_at(TextPoint { line: 0, index: 5 });
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 9 });
+    assert_eq!(textinput.get_selection_text(), Some("two three".to_owned()));
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 1 });
+    assert_eq!(textinput.get_selection_text(), Some("one two".to_owned()));
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 5 });
+    assert_eq!(textinput.get_selection_text(), Some("two".to_owned()));
+
+    textinput.select_line_at(TextPoint { line: 0, index: 0 });
+    textinput.extend_selection_to_point(TextPoint { line: 1, index: 1 });
+    assert_eq!(textinput.get_selection_text(), Some("one two three\nfour five".to_owned()));
+
+    // Keyboard selection goes back to characters.
+    textinput.select_word_at(TextPoint { line: 1, index: 0 });
+    textinput.execute_command(EditCommand::MoveCharForward, Selection::Selected);
+    textinput.extend_selection_to_point(TextPoint { line: 1, index: 7 });
+    assert_eq!(textinput.get_selection_text(), Some("four fi".to_owned()));
+
+    textinput.set_selection_range(0, 1, SelectionDirection::Forward);
+    textinput.set_selection_granularity(SelectionGranularity::Word);
+    textinput.extend_selection_to_point(TextPoint { line: 0, index: 5 });
+    assert_eq!(textinput.get_selection_text(), Some("one two".to_owned()));
+}
+
+#[test]
+fn test_first_strong_direction() {
+    assert_eq!(first_strong_direction("123 abc"), Some(TextDirection::Ltr));
+    assert_eq!(first_strong_direction("(\u{5E9}\u{5DC}\u{5D5}\u{5DD}) abc"), Some(TextDirection::Rtl));
+    assert_eq!(first_strong_direction("\u{627}\u{644}"), Some(TextDirection::Rtl));
+    assert_eq!(first_strong_direction("123 !?"), None);
+
+    let textinput = text_input(Lines::Multiple, "12\n\u{5E9}a");
+    assert_eq!(textinput.first_strong_direction(), Some(TextDirection::Rtl));
+}
+
+#[test]
+fn test_shows_placeholder() {
+    let mut textinput = text_input(Lines::Single, "");
+    assert!(textinput.shows_placeholder());
+
+    // The placeholder goes as soon as a composition starts, before anything is composed.
+    textinput.set_composition(DOMString::new(), vec!(), 0);
+    assert!(textinput.is_empty());
+    assert!(!textinput.shows_placeholder());
+    textinput.cancel_composition();
+    assert!(textinput.shows_placeholder());
+
+    textinput.set_content(DOMString::from("a"), true);
+    assert!(!textinput.shows_placeholder());
+}
+
+#[test]
+fn test_composition() {
+    let mut textinput = text_input(Lines::Single, "ab cd");
+    textinput.set_selection_range(3, 5, SelectionDirection::Backward);
+
+    // The composed text replaces the selection, and then itself.
+    textinput.set_composition(DOMString::from("\u{304B}"), vec!(), 3);
+    assert_eq!(textinput.get_content(), "ab \u{304B}");
+    assert_eq!(textinput.composition().unwrap().range(), 3..6);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 6 });
+
+    let clauses = vec!(
+        CompositionClause { start: 0, end: 3, selected: true },
+        CompositionClause { start: 3, end: 6, selected: false },
+        CompositionClause { start: 1, end: 6, selected: false },
+    );
+    let change = textinput.set_composition(DOMString::from("\u{6F22}\u{5B57}"), clauses, 3);
+    assert_eq!(change.unwrap().old_text, "\u{304B}");
+    assert_eq!(textinput.get_content(), "ab \u{6F22}\u{5B57}");
+    assert_eq!(textinput.composition().unwrap().text(), "\u{6F22}\u{5B57}");
+    assert_eq!(textinput.composition().unwrap().clauses().len(), 2);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 6 });
+
+    // Committing keeps the composed text, with the caret after it.
+    assert_eq!(textinput.commit_composition(None), None);
+    assert!(!textinput.is_composing());
+    assert_eq!(textinput.get_content(), "ab \u{6F22}\u{5B57}");
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 9 });
+    assert!(textinput.selection_start() == textinput.selection_end());
+
+    // Committing other text replaces it.
+    textinput.set_composition(DOMString::from("x"), vec!(), 1);
+    let change = textinput.commit_composition(Some(DOMString::from("yz"))).unwrap();
+    assert_eq!(change.range, 9..10);
+    assert_eq!(textinput.get_content(), "ab \u{6F22}\u{5B57}yz");
+
+    // Cancelling puts back the selection the composition replaced.
+    textinput.set_selection_range(0, 2, SelectionDirection::Backward);
+    textinput.set_composition(DOMString::from("q"), vec!(), 1);
+    textinput.set_composition(DOMString::from("qq"), vec!(), 2);
+    textinput.cancel_composition();
+    assert_eq!(textinput.get_content(), "ab \u{6F22}\u{5B57}yz");
+    assert_eq!(textinput.selection_start_offset(), 0);
+    assert_eq!(textinput.selection_end_offset(), 2);
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+    assert_eq!(textinput.cancel_composition(), None);
+
+    // Abandoning leaves the composed text where it is.
+    textinput.set_composition(DOMString::from("q"), vec!(), 1);
+    assert_eq!(textinput.abandon_composition().unwrap().text(), "q");
+    assert!(!textinput.is_composing());
+    assert_eq!(textinput.get_content(), "q \u{6F22}\u{5B57}yz");
+
+    // The composition is cut short by the maximum length.
+    let mut textinput = TextInput::new(
+        Lines::Single, DOMString::from("abc"), DummyClipboardContext::new(""), Some(5), None, SelectionDirection::None
+    );
+    textinput.set_composition(DOMString::from("defg"), vec!(), 4);
+    assert_eq!(textinput.get_content(), "deabc");
+    assert_eq!(textinput.composition().unwrap().range(), 0..2);
+}
+
+#[test]
+fn test_surrounding_text() {
+    let mut textinput = text_input(Lines::Multiple, "h\u{E9}llo\nw\u{F6}rld");
+    textinput.set_selection_range(3, 5, SelectionDirection::Backward);
+
+    // The window is counted in characters on either side of the selection.
+    assert_eq!(textinput.surrounding_text(1, 2), Some(ImeSurroundingText {
+        text: "\u{E9}llo\n".to_owned(),
+        cursor: 2,
+        anchor: 4,
+    }));
+    assert_eq!(textinput.surrounding_text(10, 10), Some(ImeSurroundingText {
+        text: "h\u{E9}llo\nw\u{F6}rld".to_owned(),
+        cursor: 3,
+        anchor: 5,
+    }));
+    assert_eq!(textinput.surrounding_text(0, 0), Some(ImeSurroundingText {
+        text: "ll".to_owned(),
+        cursor: 0,
+        anchor: 2,
+    }));
+
+    // The text being composed is left out.
+    textinput.set_composition(DOMString::from("\u{304B}"), vec!(), 3);
+    assert_eq!(textinput.surrounding_text(1, 1), Some(ImeSurroundingText {
+        text: "\u{E9}o".to_owned(),
+        cursor: 2,
+        anchor: 2,
+    }));
+
+    textinput.set_obscured(true);
+    assert_eq!(textinput.surrounding_text(1, 1), None);
+}
+
+#[test]
+fn test_delete_surrounding_text() {
+    let mut textinput = text_input(Lines::Single, "h\u{E9}llo w\u{F6}rld");
+    textinput.set_selection_range(3, 5, SelectionDirection::Backward);
+
+    // Both sides go in one change, and the selection is kept.
+    let change = textinput.delete_surrounding_text(1, 2);
+    assert_eq!(change, Some(TextChange {
+        range: 1..7,
+        old_text: DOMString::from("\u{E9}llo "),
+        new_text: DOMString::from("ll"),
+    }));
+    assert_eq!(textinput.get_content(), "hllw\u{F6}rld");
+    assert_eq!(textinput.selection_start_offset(), 1);
+    assert_eq!(textinput.selection_end_offset(), 3);
+    assert_eq!(textinput.selection_direction, SelectionDirection::Backward);
+
+    textinput.set_selection_range(0, 0, SelectionDirection::None);
+    assert_eq!(textinput.delete_surrounding_text(5, 0), None);
+    assert_eq!(textinput.delete_surrounding_text(0, 100).unwrap().range, 0..9);
+    assert_eq!(textinput.get_content(), "");
+
+    // Deleting is allowed when the content is already too long.
+    let mut textinput = TextInput::new(
+        Lines::Single, DOMString::from(""), DummyClipboardContext::new(""), Some(3), None, SelectionDirection::None
+    );
+    textinput.set_content(DOMString::from("abcdef"), true);
+    textinput.set_selection_range(6, 6, SelectionDirection::None);
+    assert!(textinput.delete_surrounding_text(1, 0).is_some());
+    assert_eq!(textinput.get_content(), "abcde");
+
+    // The characters are counted around the text being composed.
+    let mut textinput = text_input(Lines::Single, "ab cd");
+    textinput.set_selection_range(5, 5, SelectionDirection::None);
+    textinput.set_composition(DOMString::from("\u{304B}"), vec!(), 3);
+    assert!(textinput.delete_surrounding_text(2, 1).is_some());
+    assert_eq!(textinput.get_content(), "ab \u{304B}");
+    assert_eq!(textinput.composition().unwrap().range(), 3..6);
+    assert_eq!(textinput.edit_point, TextPoint { line: 0, index: 6 });
+}
+
+#[test]
+fn test_key_hook() {
+    fn hook(key: Key, _mods: KeyModifiers) -> Option<KeyReaction> {
+        match key {
+            Key::Up => Some(KeyReaction::StepRequested(1)),
+            _ => None,
+        }
+    }
+
+    let mut textinput = text_input(Lines::Single, "12");
+    textinput.set_key_hook(Some(hook));
+    assert_eq!(textinput.handle_keydown_aux(None, Key::Up, KeyModifiers::empty()),
+               KeyReaction::StepRequested(1));
+    assert_eq!(textinput.edit_point.index, 0);
+
+    // Other keys are still editing commands.
+    textinput.handle_keydown_aux(None, Key::End, KeyModifiers::empty());
+    assert_eq!(textinput.edit_point.index, 2);
+
+    textinput.set_key_hook(None);
+    assert!(textinput.handle_keydown_aux(None, Key::Up, KeyModifiers::empty()) != KeyReaction::StepRequested(1));
+}
+
+#[test]
+fn test_misspellings_follow_edits() {
+    let mut spellchecker = DummySpellCheckProvider::new(&["teh", "wrod"]);
+    let mut textinput = text_input(Lines::Multiple, "teh quick\nbrown wrod");
+    check_spelling(&mut textinput, &mut spellchecker, true);
+    assert_eq!(textinput.misspellings(), &[0..3, 16..20]);
+
+    // Words after an edit move with it, without being checked again.
+    textinput.edit_point = TextPoint { line: 1, index: 0 };
+    textinput.insert_string("the ");
+    assert_eq!(textinput.misspellings(), &[0..3, 20..24]);
+    check_spelling(&mut textinput, &mut spellchecker, false);
+    assert_eq!(textinput.misspellings(), &[0..3, 20..24]);
+
+    // A misspelled word that is edited is no longer marked.
+    textinput.set_selection_range_utf16(0, 3, SelectionDirection::None);
+    textinput.replace_selection(DOMString::from("the"));
+    assert_eq!(textinput.misspellings(), &[20..24]);
+
+    // Setting the content checks all of it again.
+    textinput.set_content(DOMString::from("wrod teh"), true);
+    assert!(textinput.misspellings().is_empty());
+    check_spelling(&mut textinput, &mut spellchecker, true);
+    assert_eq!(textinput.misspellings(), &[0..4, 5..8]);
+}
+
+#[test]
+fn test_misspellings_wait_for_word_at_caret() {
+    let mut spellchecker = DummySpellCheckProvider::new(&["helo", "wrold"]);
+    let mut textinput = text_input(Lines::Single, "");
+
+    // The word being typed is not marked until the user moves on from it.
+    textinput.insert_string("helo");
+    check_spelling(&mut textinput, &mut spellchecker, false);
+    assert!(textinput.misspellings().is_empty());
+    textinput.insert_char(' ');
+    check_spelling(&mut textinput, &mut spellchecker, false);
+    assert_eq!(textinput.misspellings(), &[0..4]);
+
+    // Unless the control is losing the focus.
+    textinput.insert_string("wrold");
+    check_spelling(&mut textinput, &mut spellchecker, false);
+    assert_eq!(textinput.misspellings(), &[0..4]);
+    check_spelling(&mut textinput, &mut spellchecker, true);
+    assert_eq!(textinput.misspellings(), &[0..4, 5..10]);
+
+    textinput.clear_misspellings();
+    assert!(textinput.misspellings().is_empty());
+}
+
+#[test]
+fn test_misspellings_for_changed_content() {
+    let mut spellchecker = DummySpellCheckProvider::new(&["teh"]);
+    let mut textinput = text_input(Lines::Single, "teh end");
+    let request = textinput.spelling_request(true).unwrap();
+    let (sender, receiver) = ipc::channel().unwrap();
+    spellchecker.request_spell_check(request.texts.clone(), "en".to_owned(), sender);
+
+    // The answer came for content that has changed since, so it is dropped, and all of the
+    // content is checked again.
+    textinput.edit_point.index = 0;
+    textinput.insert_string("at ");
+    assert!(!textinput.receive_misspellings(request, receiver.recv().unwrap()));
+    assert!(textinput.misspellings().is_empty());
+    check_spelling(&mut textinput, &mut spellchecker, true);
+    assert_eq!(textinput.misspellings(), &[3..6]);
+}
+
+#[test]
+fn test_word_finished_by_typing() {
+    let mut textinput = text_input(Lines::Multiple, "first\nteh");
+    textinput.edit_point = TextPoint { line: 1, index: 3 };
+    let change = textinput.insert_char(' ').unwrap();
+    assert_eq!(textinput.word_finished_by(&change), Some((6..9, "teh".to_owned())));
+
+    // A second space doesn't finish a word.
+    let change = textinput.insert_char(' ').unwrap();
+    assert_eq!(textinput.word_finished_by(&change), None);
+
+    // Neither do letters, nor an apostrophe, which may be part of the word.
+    let mut textinput = text_input(Lines::Single, "don");
+    textinput.edit_point.index = 3;
+    let change = textinput.insert_char('\'').unwrap();
+    assert_eq!(textinput.word_finished_by(&change), None);
+    let change = textinput.insert_char('t').unwrap();
+    assert_eq!(textinput.word_finished_by(&change), None);
+    let change = textinput.insert_char('.').unwrap();
+    assert_eq!(textinput.word_finished_by(&change), Some((0..5, "don't".to_owned())));
+}
+
+#[test]
+fn test_insert_correction() {
+    let mut textinput = text_input(Lines::Single, "wont go");
+    textinput.edit_point.index = 5;
+    let correction = WordCorrection {
+        range: 0..4,
+        word: "wont".to_owned(),
+        correction: "won't".to_owned(),
+    };
+    assert_eq!(Edit::InsertCorrection(correction.clone()).input_type(), "insertReplacementText");
+    assert_eq!(Edit::InsertCorrection(correction.clone()).data(), Some("won't"));
+
+    // The caret stays after the space that followed the word.
+    let reaction = textinput.apply_edit(Edit::InsertCorrection(correction.clone()));
+    assert_eq!(reaction, KeyReaction::DispatchInput(Some(TextChange {
+        range: 0..4,
+        old_text: DOMString::from("wont"),
+        new_text: DOMString::from("won't"),
+    })));
+    assert_eq!(textinput.get_content(), DOMString::from("won't go"));
+    assert_eq!(textinput.edit_point.index, 6);
+    assert!(!textinput.has_selection());
+
+    // The correction is undone on its own.
+    textinput.undo();
+    assert_eq!(textinput.get_content(), DOMString::from("wont go"));
+
+    // A word that is no longer where it was typed is left alone.
+    textinput.set_content(DOMString::from("it wont go"), true);
+    assert_eq!(textinput.apply_edit(Edit::InsertCorrection(correction)), KeyReaction::Rejected);
+    assert_eq!(textinput.get_content(), DOMString::from("it wont go"));
+}
+
+#[test]
+fn test_reset_content() {
+    let mut textinput = text_input(Lines::Multiple, "default");
+    textinput.edit_point.index = 7;
+    textinput.insert_string(" value\ntyped");
+    textinput.select_all();
+    assert!(textinput.can_undo());
+
+    // The caret goes to the end, and the edits can no longer be undone.
+    textinput.reset_content(DOMString::from("default\nvalue"));
+    assert_eq!(textinput.get_content(), DOMString::from("default\nvalue"));
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 5 });
+    assert!(!textinput.has_selection());
+    assert!(!textinput.can_undo());
+    assert!(!textinput.can_redo());
+
+    // Even when the content is already the default value.
+    textinput.insert_char('s');
+    textinput.undo();
+    assert!(textinput.can_redo());
+    textinput.edit_point.index = 0;
+    textinput.reset_content(DOMString::from("default\nvalue"));
+    assert_eq!(textinput.edit_point, TextPoint { line: 1, index: 5 });
+    assert!(!textinput.can_redo());
+}
@@ -0,0 +1,236 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+//! Randomized tests that run sequences of editing operations over multi-byte text, and check
+//! that the invariants of `TextInput` hold after each of them. A failure reports the seed and
+//! the operations that led to it, so it can be replayed with `check_sequence`.
+
+use ipc_channel::ipc;
+use script::clipboard_provider::DummyClipboardContext;
+use script::keybindings::EditCommand;
+use script::test::DOMString;
+use script::textinput::{Direction, KeyReaction, Lines, Selection, SelectionDirection, TextInput, TextPoint};
+
+/// Text fragments covering one to four byte UTF-8 sequences, surrogate pairs in UTF-16,
+/// combining marks and line breaks.
+const FRAGMENTS: &'static [&'static str] = &[
+    "a", "Z", " ", "\u{e9}", "e\u{301}", "\u{3042}", "\u{6f22}", "\u{1F600}", "\u{10348}",
+    "\n", "\r\n", "\u{200D}", "ab cd", "\u{442}\u{435}\u{441}\u{442}",
+];
+
+const COMMANDS: &'static [EditCommand] = &[
+    EditCommand::MoveCharBackward,
+    EditCommand::MoveCharForward,
+    EditCommand::MoveWordBackward,
+    EditCommand::MoveWordForward,
+    EditCommand::MoveLineStart,
+    EditCommand::MoveLineEnd,
+    EditCommand::MoveUp,
+    EditCommand::MoveDown,
+    EditCommand::MovePageUp,
+    EditCommand::MovePageDown,
+    EditCommand::MoveDocumentStart,
+    EditCommand::MoveDocumentEnd,
+    EditCommand::SelectAll,
+    EditCommand::Copy,
+    EditCommand::Cut,
+    EditCommand::Paste,
+    EditCommand::DeleteBackward,
+    EditCommand::DeleteForward,
+    EditCommand::DeleteToLineStart,
+    EditCommand::InsertLineBreak,
+    EditCommand::InsertTab,
+];
+
+/// A xorshift generator, so that a failing sequence can be reproduced from its seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn text(&mut self) -> String {
+        let count = self.below(6);
+        (0..count).map(|_| FRAGMENTS[self.below(FRAGMENTS.len())]).collect()
+    }
+
+    fn selection(&mut self) -> Selection {
+        if self.below(2) == 0 { Selection::Selected } else { Selection::NotSelected }
+    }
+}
+
+#[derive(Debug)]
+enum Operation {
+    Command(EditCommand, Selection),
+    Insert(String),
+    Delete(Direction),
+    SetContent(String, bool),
+    SetSelectionRange(u32, u32),
+    SetSelectionRangeUtf16(u32, u32),
+    SetEditPoint(usize, usize, Selection),
+}
+
+fn random_operation(rng: &mut Rng) -> Operation {
+    match rng.below(7) {
+        0 => {
+            let command = COMMANDS[rng.below(COMMANDS.len())];
+            Operation::Command(command, rng.selection())
+        },
+        1 => Operation::Insert(rng.text()),
+        2 => Operation::Delete(if rng.below(2) == 0 { Direction::Backward } else { Direction::Forward }),
+        3 => Operation::SetContent(rng.text(), rng.below(2) == 0),
+        4 => Operation::SetSelectionRange(rng.below(40) as u32, rng.below(40) as u32),
+        5 => Operation::SetSelectionRangeUtf16(rng.below(40) as u32, rng.below(40) as u32),
+        _ => Operation::SetEditPoint(rng.below(4), rng.below(20), rng.selection()),
+    }
+}
+
+fn apply(textinput: &mut TextInput<DummyClipboardContext>, operation: &Operation) {
+    match *operation {
+        Operation::Command(command, select) => {
+            // Clipboard commands are carried out straight away, as if no page handled them and
+            // the clipboard answered at once.
+            match textinput.execute_command(command, select) {
+                KeyReaction::CopyRequested => {
+                    textinput.copy_selection();
+                },
+                KeyReaction::CutRequested => {
+                    textinput.cut_selection();
+                },
+                KeyReaction::PasteRequested => {
+                    let mut reader = textinput.paste_reader();
+                    let (length_sender, length_receiver) = ipc::channel().unwrap();
+                    let (chunk_sender, chunk_receiver) = ipc::channel().unwrap();
+                    reader.request_length(length_sender);
+                    if reader.receive_length(length_receiver.recv().unwrap()) {
+                        while reader.request_next_chunk(chunk_sender.clone()) &&
+                              reader.receive_chunk(chunk_receiver.recv().unwrap()) {}
+                    }
+                    textinput.finish_paste(reader.into_contents());
+                },
+                _ => (),
+            }
+        },
+        Operation::Insert(ref text) => {
+            textinput.insert_string(text.clone());
+        },
+        Operation::Delete(direction) => {
+            textinput.delete_char(direction);
+        },
+        Operation::SetContent(ref text, update_text_cursor) => {
+            textinput.set_content(DOMString::from(text.clone()), update_text_cursor);
+        },
+        Operation::SetSelectionRange(start, end) => {
+            textinput.set_selection_range(start, end, SelectionDirection::None);
+        },
+        Operation::SetSelectionRangeUtf16(start, end) => {
+            textinput.set_selection_range_utf16(start, end, SelectionDirection::Forward);
+        },
+        Operation::SetEditPoint(line, index, select) => {
+            // Only ask for positions that exist, as the caller is expected to.
+            let line = line.min(textinput.line_count() - 1);
+            let text = textinput.line(line).unwrap().to_owned();
+            let mut index = index.min(text.len());
+            while !text.is_char_boundary(index) {
+                index -= 1;
+            }
+            textinput.set_edit_point(TextPoint { line: line, index: index }, select);
+        },
+    }
+}
+
+fn check_point(textinput: &TextInput<DummyClipboardContext>, point: TextPoint) -> Result<(), String> {
+    let line = textinput.line(point.line).ok_or(format!("{:?} is past the last line", point))?;
+    if !line.is_char_boundary(point.index) {
+        return Err(format!("{:?} is not on a character boundary of {:?}", point, line));
+    }
+    Ok(())
+}
+
+fn check_invariants(textinput: &TextInput<DummyClipboardContext>, lines: Lines) -> Result<(), String> {
+    check_point(textinput, textinput.edit_point)?;
+    if let Some(origin) = textinput.selection_origin {
+        check_point(textinput, origin)?;
+    }
+
+    let (start, end) = (textinput.selection_start(), textinput.selection_end());
+    if start > end {
+        return Err(format!("selection start {:?} is after its end {:?}", start, end));
+    }
+    let (start_offset, end_offset) = (textinput.selection_start_offset(), textinput.selection_end_offset());
+    if start_offset > end_offset || end_offset > textinput.len() {
+        return Err(format!("selection offsets {}..{} are out of order or bounds", start_offset, end_offset));
+    }
+    if textinput.selection_start_utf16_offset() > textinput.selection_end_utf16_offset() ||
+       textinput.selection_end_utf16_offset() > textinput.utf16_len() {
+        return Err("UTF-16 selection offsets are out of order or bounds".to_owned());
+    }
+
+    let content = String::from(textinput.get_content());
+    if textinput.len() != content.len() {
+        return Err(format!("len() is {} for {:?}", textinput.len(), content));
+    }
+    if textinput.utf16_len() != content.encode_utf16().count() {
+        return Err(format!("utf16_len() is {} for {:?}", textinput.utf16_len(), content));
+    }
+    if textinput.char_count() != content.chars().count() {
+        return Err(format!("char_count() is {} for {:?}", textinput.char_count(), content));
+    }
+    // A single-line input holds whatever it is given, as stripping line breaks is up to the
+    // element.
+    let line_count = if lines == Lines::Multiple { content.split('\n').count() } else { 1 };
+    if textinput.line_count() != line_count {
+        return Err(format!("{} lines for {:?}", textinput.line_count(), content));
+    }
+    Ok(())
+}
+
+/// Run a sequence of operations, and return the first invariant that fails, if any.
+fn check_sequence(lines: Lines, initial: &str, operations: &[Operation]) -> Result<(), String> {
+    let mut textinput = TextInput::new(lines,
+                                       DOMString::from(initial),
+                                       DummyClipboardContext::new("x\u{1F600}\ny\u{301}"),
+                                       None,
+                                       None,
+                                       SelectionDirection::None);
+    check_invariants(&textinput, lines)?;
+    for (i, operation) in operations.iter().enumerate() {
+        apply(&mut textinput, operation);
+        check_invariants(&textinput, lines).map_err(|error| format!("after operation {}: {}", i, error))?;
+    }
+    Ok(())
+}
+
+fn check_random_sequences(lines: Lines) {
+    for seed in 0..300 {
+        let mut rng = Rng::new(seed);
+        let initial = rng.text();
+        let operations: Vec<_> = (0..40).map(|_| random_operation(&mut rng)).collect();
+        if let Err(error) = check_sequence(lines, &initial, &operations) {
+            panic!("seed {}: {}\ninitial content: {:?}\noperations: {:#?}", seed, error, initial, operations);
+        }
+    }
+}
+
+#[test]
+fn test_random_edits_single_line() {
+    check_random_sequences(Lines::Single);
+}
+
+#[test]
+fn test_random_edits_multiline() {
+    check_random_sequences(Lines::Multiple);
+}
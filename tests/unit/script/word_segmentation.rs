@@ -0,0 +1,77 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at http://mozilla.org/MPL/2.0/. */
+
+use script::word_segmentation::{WordBreakRules, WordSelectionPolicy, is_word, word_bound_indices};
+use script::word_segmentation::word_selection_range;
+
+fn words(text: &str, rules: WordBreakRules) -> Vec<&str> {
+    word_bound_indices(text, rules).into_iter().map(|(_, segment)| segment).collect()
+}
+
+#[test]
+fn test_rules_for_language() {
+    assert_eq!(WordBreakRules::for_language("ja"), WordBreakRules::Japanese);
+    assert_eq!(WordBreakRules::for_language("JA-jp"), WordBreakRules::Japanese);
+    assert_eq!(WordBreakRules::for_language("jam"), WordBreakRules::Default);
+    assert_eq!(WordBreakRules::for_language(""), WordBreakRules::Default);
+}
+
+#[test]
+fn test_word_bound_indices_latin() {
+    assert_eq!(words("abc, def", WordBreakRules::Default), vec!["abc", ",", " ", "def"]);
+    assert_eq!(word_bound_indices("ab cd", WordBreakRules::Default), vec![(0, "ab"), (2, " "), (3, "cd")]);
+}
+
+#[test]
+fn test_word_bound_indices_joins_ideographs() {
+    assert_eq!(words("中文输入，测试", WordBreakRules::Default), vec!["中文输入", "，", "测试"]);
+    assert_eq!(words("ひらがなカタカナ", WordBreakRules::Default), vec!["ひらがな", "カタカナ"]);
+
+    // Only Japanese text joins Han with the Hiragana following it.
+    assert_eq!(words("日本語を食べる", WordBreakRules::Default), vec!["日本語", "を", "食", "べる"]);
+    assert_eq!(words("日本語を食べる", WordBreakRules::Japanese), vec!["日本語を", "食べる"]);
+}
+
+#[test]
+fn test_word_bound_indices_list() {
+    assert_eq!(words("a.b@c.d, e-f@g.h", WordBreakRules::List(',')), vec!["a.b@c.d", ", ", "e-f@g.h"]);
+    assert_eq!(words(" a ,b,", WordBreakRules::List(',')), vec![" ", "a", " ,", "b", ","]);
+}
+
+#[test]
+fn test_is_word() {
+    assert!(is_word("abc"));
+    assert!(is_word("中文"));
+    assert!(is_word("42"));
+    assert!(!is_word(" "));
+    assert!(!is_word("..."));
+}
+
+#[test]
+fn test_word_selection_policies() {
+    let text = "foo...  bar";
+    let select = |index, policy| {
+        let (start, end) = word_selection_range(text, index, WordBreakRules::Default, policy);
+        &text[start..end]
+    };
+
+    assert_eq!(select(1, WordSelectionPolicy::windows()), "foo");
+    assert_eq!(select(4, WordSelectionPolicy::windows()), "...");
+    assert_eq!(select(6, WordSelectionPolicy::windows()), "  ");
+    assert_eq!(select(9, WordSelectionPolicy::windows()), "bar");
+
+    assert_eq!(select(1, WordSelectionPolicy::mac()), "foo");
+    assert_eq!(select(4, WordSelectionPolicy::mac()), ".");
+    assert_eq!(select(7, WordSelectionPolicy::mac()), "  ");
+    assert_eq!(select(11, WordSelectionPolicy::mac()), "bar");
+
+    assert_eq!(select(5, WordSelectionPolicy::linux()), "...");
+
+    let text = "foo bar";
+    let (start, end) = word_selection_range(text, 0, WordBreakRules::Default, WordSelectionPolicy::windows());
+    assert_eq!(&text[start..end], "foo ");
+    let (start, end) = word_selection_range(text, 0, WordBreakRules::Default, WordSelectionPolicy::linux());
+    assert_eq!(&text[start..end], "foo");
+    assert_eq!(word_selection_range("", 0, WordBreakRules::Default, WordSelectionPolicy::mac()), (0, 0));
+}